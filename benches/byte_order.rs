@@ -1,24 +1,60 @@
 /// Benchmarks for the byte order reversal implementation.
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use reductionist::array::{build_array_mut_from_shape, get_shape, reverse_array_byte_order};
+use reductionist::array::{
+    build_array, build_array_mut_from_shape, get_shape, reverse_array_byte_order,
+};
 use reductionist::models::{DType, RequestData, Slice};
+use reductionist::operation::Operation;
+use reductionist::operations;
+use reductionist::types::NON_NATIVE_BYTE_ORDER;
 use url::Url;
+// Bring trait into scope to use as_bytes method.
+use zerocopy::AsBytes;
 
 fn get_test_request_data() -> RequestData {
     RequestData {
         source: Url::parse("http://example.com").unwrap(),
+        protocol: None,
+        presigned: None,
+        region: None,
         bucket: "bar".to_string(),
         object: "baz".to_string(),
+        session: None,
         dtype: DType::Int32,
         byte_order: None,
         offset: None,
+        skip_header_bytes: None,
         size: None,
+        units: None,
+        part_number: None,
+        ranges: None,
+        shard: None,
         shape: None,
+        stored_shape: None,
         order: None,
         selection: None,
+        indices: None,
+        hyperslab: None,
+        axis: None,
+        sparse_fetch: None,
         compression: None,
         filters: None,
         missing: None,
+        fill_value: None,
+        accumulate_dtype: None,
+        output_dtype: None,
+        output_order: None,
+        clip_min: None,
+        clip_max: None,
+        reject_nonfinite: None,
+        percentile: None,
+        trim_fraction: None,
+        weights_bucket: None,
+        weights_object: None,
+        weights_dtype: None,
+        mask_bucket: None,
+        mask_object: None,
+        expected_memory: None,
     }
 }
 
@@ -39,6 +75,33 @@ fn criterion_benchmark(c: &mut Criterion) {
             });
         }
     }
+
+    // Compare reversing the byte order of the whole array before a sum with reversing the byte
+    // order of each element as it is summed.
+    for size_k in [64, 256, 1024] {
+        let size = size_k * 1024;
+        let data: Vec<u32> = (0_u32..size).collect::<Vec<u32>>();
+        let data = data.as_bytes().to_vec();
+        let mut request_data = get_test_request_data();
+        request_data.dtype = DType::Uint32;
+        let mut non_native_request_data = request_data.clone();
+        non_native_request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        let name = format!("byte_order_sum_bulk({})", size);
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                let mut data = data.clone();
+                build_array::<u32>(&non_native_request_data, &mut data).unwrap();
+                operations::Sum::execute(&request_data, black_box(data)).unwrap();
+            })
+        });
+        let name = format!("byte_order_sum_lazy({})", size);
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                operations::Sum::execute(&non_native_request_data, black_box(data.clone()))
+                    .unwrap();
+            })
+        });
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -2,9 +2,10 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use reductionist::compression;
 use reductionist::models;
+use reductionist::resource_manager::ResourceManager;
 
 use axum::body::Bytes;
-use flate2::read::{GzEncoder, ZlibEncoder};
+use flate2::read::{DeflateEncoder, GzEncoder, ZlibEncoder};
 use flate2::Compression;
 use std::io::Read;
 // Bring trait into scope to use as_bytes method.
@@ -26,17 +27,79 @@ fn compress_zlib(data: &[u8]) -> Bytes {
     result.into()
 }
 
+fn compress_deflate(data: &[u8]) -> Bytes {
+    let mut result = Vec::<u8>::new();
+    let mut deflater = DeflateEncoder::new(data, Compression::fast());
+    deflater.read_to_end(&mut result).unwrap();
+    result.into()
+}
+
+fn compress_brotli(data: &[u8]) -> Bytes {
+    let mut result = Vec::<u8>::new();
+    let mut compressor = brotli::CompressorReader::new(data, 4096, 5, 22);
+    compressor.read_to_end(&mut result).unwrap();
+    result.into()
+}
+
+fn compress_snappy(data: &[u8], framed: bool) -> Bytes {
+    if framed {
+        let mut result = Vec::<u8>::new();
+        let mut compressor = snap::read::FrameEncoder::new(data);
+        compressor.read_to_end(&mut result).unwrap();
+        result.into()
+    } else {
+        snap::raw::Encoder::new().compress_vec(data).unwrap().into()
+    }
+}
+
 fn compress(compression: models::Compression, data: &[u8]) -> Bytes {
     match compression {
         models::Compression::Gzip => compress_gzip(data),
         models::Compression::Zlib => compress_zlib(data),
+        models::Compression::Deflate => compress_deflate(data),
+        models::Compression::Brotli => compress_brotli(data),
+        models::Compression::Snappy { framed } => compress_snappy(data, framed),
     }
 }
 
+/// Decompress and reduce data for a number of concurrent requests, limited to one task at a time
+/// as by `--thread-limit`. Unless decompression is offloaded to the blocking thread pool, as by
+/// `--compression-thread-pool`, the task permit is held for both decompression and reduction.
+async fn concurrent_requests(
+    resource_manager: &ResourceManager,
+    compression: models::Compression,
+    compressed: &Bytes,
+    requests: usize,
+    blocking: bool,
+) {
+    futures::future::join_all((0..requests).map(|_| async {
+        let decompress = {
+            let compressed = compressed.clone();
+            move || compression::decompress(compression, &compressed, None, None).unwrap()
+        };
+        let (data, _task_permit) = if blocking {
+            let data = tokio::task::spawn_blocking(decompress).await.unwrap();
+            (data, resource_manager.task().await.unwrap())
+        } else {
+            let task_permit = resource_manager.task().await.unwrap();
+            (decompress(), task_permit)
+        };
+        black_box(data.iter().map(|byte| u64::from(*byte)).sum::<u64>());
+    }))
+    .await;
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let compression_algs = [
         (models::Compression::Gzip, "gzip"),
         (models::Compression::Zlib, "zlib"),
+        (models::Compression::Deflate, "deflate"),
+        (models::Compression::Brotli, "brotli"),
+        (models::Compression::Snappy { framed: false }, "snappy"),
+        (
+            models::Compression::Snappy { framed: true },
+            "snappy-framed",
+        ),
     ];
     for (compression, name) in compression_algs {
         for size_k in [64, 256, 1024] {
@@ -47,11 +110,28 @@ fn criterion_benchmark(c: &mut Criterion) {
             let name = format!("decompress({}, {})", name, size);
             c.bench_function(&name, |b| {
                 b.iter(|| {
-                    compression::decompress(compression, black_box(&compressed)).unwrap();
+                    compression::decompress(compression, black_box(&compressed), None, None)
+                        .unwrap();
                 })
             });
         }
     }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .build()
+        .unwrap();
+    let resource_manager = ResourceManager::new(None, None, Some(1), None);
+    let compression = models::Compression::Gzip;
+    let data: Vec<i64> = (0_i64..1024 * 1024).map(|i| i % 256).collect::<Vec<i64>>();
+    let compressed = compress(compression, data.as_bytes());
+    for blocking in [false, true] {
+        let name = format!("concurrent_decompress(gzip, 8, blocking={})", blocking);
+        c.bench_function(&name, |b| {
+            b.to_async(&runtime).iter(|| {
+                concurrent_requests(&resource_manager, compression, &compressed, 8, blocking)
+            })
+        });
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -12,18 +12,47 @@ use zerocopy::AsBytes;
 fn get_test_request_data() -> RequestData {
     RequestData {
         source: Url::parse("http://example.com").unwrap(),
+        protocol: None,
+        presigned: None,
+        region: None,
         bucket: "bar".to_string(),
         object: "baz".to_string(),
+        session: None,
         dtype: DType::Int32,
         byte_order: None,
         offset: None,
+        skip_header_bytes: None,
         size: None,
+        units: None,
+        part_number: None,
+        ranges: None,
+        shard: None,
         shape: None,
+        stored_shape: None,
         order: None,
         selection: None,
+        indices: None,
+        hyperslab: None,
+        axis: None,
+        sparse_fetch: None,
         compression: None,
         filters: None,
         missing: None,
+        fill_value: None,
+        accumulate_dtype: None,
+        output_dtype: None,
+        output_order: None,
+        clip_min: None,
+        clip_max: None,
+        reject_nonfinite: None,
+        percentile: None,
+        trim_fraction: None,
+        weights_bucket: None,
+        weights_object: None,
+        weights_dtype: None,
+        mask_bucket: None,
+        mask_object: None,
+        expected_memory: None,
     }
 }
 
@@ -6,7 +6,9 @@ use aws_types::region::Region;
 use axum::body::Bytes;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use reductionist::resource_manager::ResourceManager;
-use reductionist::s3_client::{S3Client, S3ClientMap, S3Credentials};
+use reductionist::s3_client::{self, S3Client, S3ClientMap, S3Credentials};
+use std::num::NonZeroUsize;
+use std::time::Duration;
 use url::Url;
 // Bring trait into scope to use as_bytes method.
 use zerocopy::AsBytes;
@@ -43,8 +45,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     let credentials = S3Credentials::access_key(username, password);
     let bucket = "s3-client-bench";
     let runtime = tokio::runtime::Runtime::new().unwrap();
-    let map = S3ClientMap::new();
-    let resource_manager = ResourceManager::new(None, None, None);
+    let map = S3ClientMap::new(NonZeroUsize::new(1024).unwrap(), None, false, None);
+    let resource_manager = ResourceManager::new(None, None, None, None);
     for size_k in [64, 256, 1024] {
         let size: isize = size_k * 1024;
         let data: Vec<u32> = (0_u32..(size as u32)).collect::<Vec<u32>>();
@@ -54,9 +56,16 @@ fn criterion_benchmark(c: &mut Criterion) {
         let name = format!("s3_client({})", size);
         c.bench_function(&name, |b| {
             b.to_async(&runtime).iter(|| async {
-                let client = S3Client::new(&url, credentials.clone()).await;
+                let client = S3Client::new(&url, "us-east-1", credentials.clone(), None).await;
                 client
-                    .download_object(black_box(bucket), &key, None, &resource_manager, &mut None)
+                    .download_object(
+                        black_box(bucket),
+                        &key,
+                        None,
+                        None,
+                        &resource_manager,
+                        &mut None,
+                    )
                     .await
                     .unwrap();
             })
@@ -64,13 +73,48 @@ fn criterion_benchmark(c: &mut Criterion) {
         let name = format!("s3_client_map({})", size);
         c.bench_function(&name, |b| {
             b.to_async(&runtime).iter(|| async {
-                let client = map.get(&url, credentials.clone()).await;
+                let client = map.get(&url, "us-east-1", credentials.clone()).await;
                 client
-                    .download_object(black_box(bucket), &key, None, &resource_manager, &mut None)
+                    .download_object(
+                        black_box(bucket),
+                        &key,
+                        None,
+                        None,
+                        &resource_manager,
+                        &mut None,
+                    )
                     .await
                     .unwrap();
             })
         });
+        // Concurrent downloads, with connection pools of different sizes.
+        for pool_size in [1, 4, 16] {
+            let idle_timeout = Some(Duration::from_secs(90));
+            let http_client =
+                s3_client::http_client(None, false, Some(pool_size), idle_timeout, false).unwrap();
+            let map = S3ClientMap::new(NonZeroUsize::new(1024).unwrap(), http_client, false, None);
+            let name = format!("s3_client_map_concurrent({}, pool={})", size, pool_size);
+            c.bench_function(&name, |b| {
+                b.to_async(&runtime).iter(|| async {
+                    let client = map.get(&url, "us-east-1", credentials.clone()).await;
+                    let downloads = (0..16).map(|_| async {
+                        client
+                            .download_object(
+                                black_box(bucket),
+                                &key,
+                                None,
+                                None,
+                                &resource_manager,
+                                &mut None,
+                            )
+                            .await
+                    });
+                    for result in futures::future::join_all(downloads).await {
+                        result.unwrap();
+                    }
+                })
+            });
+        }
     }
 }
 
@@ -26,7 +26,10 @@ fn from_bytes<T: zerocopy::AsBytes + zerocopy::FromBytes>(
     Ok(layout.into_mut_slice())
 }
 
-/// Returns an [ndarray] Shape corresponding to the data in the request.
+/// Returns an [ndarray] Shape corresponding to the layout of the data in the request.
+///
+/// This is the stored shape if specified, in which case the array should be restricted to the
+/// shape of the request using [logical_region].
 ///
 /// # Arguments
 ///
@@ -36,8 +39,11 @@ pub fn get_shape(
     size: usize,
     request_data: &models::RequestData,
 ) -> ndarray::Shape<Dim<ndarray::IxDynImpl>> {
-    // Use the provided shape, or fall back to a 1D array.
-    let shape = request_data.shape.clone().unwrap_or_else(|| vec![size]);
+    // Use the provided layout shape, or fall back to a 1D array.
+    let shape = request_data
+        .layout_shape()
+        .clone()
+        .unwrap_or_else(|| vec![size]);
     // Convert the Vec into a Shape.
     let shape = shape.into_shape();
     match request_data.order {
@@ -58,7 +64,7 @@ pub fn get_shape(
 fn build_array_from_shape<T>(
     shape: ndarray::Shape<Dim<ndarray::IxDynImpl>>,
     data: &[T],
-) -> Result<ArrayViewD<T>, ActiveStorageError> {
+) -> Result<ArrayViewD<'_, T>, ActiveStorageError> {
     ArrayView::<T, _>::from_shape(shape, data).map_err(ActiveStorageError::ShapeInvalid)
 }
 
@@ -74,10 +80,38 @@ fn build_array_from_shape<T>(
 pub fn build_array_mut_from_shape<T>(
     shape: ndarray::Shape<Dim<ndarray::IxDynImpl>>,
     data: &mut [T],
-) -> Result<ArrayViewMutD<T>, ActiveStorageError> {
+) -> Result<ArrayViewMutD<'_, T>, ActiveStorageError> {
     ArrayViewMut::<T, _>::from_shape(shape, data).map_err(ActiveStorageError::ShapeInvalid)
 }
 
+/// Returns the region of an array laid out with the stored shape of the request that lies within
+/// the shape of the request, starting at the origin. Arrays are unchanged if the request has no
+/// stored shape.
+///
+/// # Arguments
+///
+/// * `array`: Array with the layout shape of the request
+/// * `request_data`: RequestData object for the request
+pub fn logical_region<S: ndarray::RawData>(
+    array: ArrayBase<S, IxDyn>,
+    request_data: &models::RequestData,
+) -> ArrayBase<S, IxDyn> {
+    let (Some(_), Some(shape)) = (&request_data.stored_shape, &request_data.shape) else {
+        return array;
+    };
+    let si: Vec<ndarray::SliceInfoElem> = shape
+        .iter()
+        .map(|length| ndarray::SliceInfoElem::Slice {
+            start: 0,
+            end: Some(*length as isize),
+            step: 1,
+        })
+        .collect();
+    let slice_info: ndarray::SliceInfo<_, IxDyn, IxDyn> =
+        ndarray::SliceInfo::try_from(si).expect("SliceInfo should not fail for IxDyn");
+    array.slice_move(slice_info)
+}
+
 /// Returns an array index in numpy semantics to an index with ndarray semantics.
 ///
 /// The resulting value will be clamped such that it is safe for indexing in ndarray.
@@ -106,10 +140,7 @@ fn to_ndarray_index(index: isize, length: usize, reverse: bool) -> isize {
 /// See [ndarray docs](https://docs.rs/ndarray/0.15.6/ndarray/macro.s.html#negative-step) for
 /// information about ndarray's handling of negative strides.
 fn to_ndarray_slice(slice: &models::Slice, length: usize) -> ndarray::SliceInfoElem {
-    let reverse = slice.stride < 0;
-    let start = to_ndarray_index(slice.start, length, reverse);
-    let end = to_ndarray_index(slice.end, length, reverse);
-    let (start, end) = if reverse { (end, start) } else { (start, end) };
+    let (start, end) = to_ndarray_bounds(slice, length);
     ndarray::SliceInfoElem::Slice {
         start,
         end: Some(end),
@@ -117,6 +148,271 @@ fn to_ndarray_slice(slice: &models::Slice, length: usize) -> ndarray::SliceInfoE
     }
 }
 
+/// Returns the start and end of a [crate::models::Slice] object with ndarray semantics.
+fn to_ndarray_bounds(slice: &models::Slice, length: usize) -> (isize, isize) {
+    let reverse = slice.stride < 0;
+    let start = to_ndarray_index(slice.start, length, reverse);
+    let end = to_ndarray_index(slice.end, length, reverse);
+    if reverse {
+        (end, start)
+    } else {
+        (start, end)
+    }
+}
+
+/// Returns the first index, number and step of the indices along an axis that are selected by a
+/// slice, in ascending order.
+///
+/// # Arguments
+///
+/// * `slice`: Selection of the axis
+/// * `length`: Length of the axis
+fn selected_range(slice: &models::Slice, length: usize) -> (usize, usize, usize) {
+    // Bounds are clamped to the axis, so are never negative.
+    let (start, end) = to_ndarray_bounds(slice, length);
+    let (start, end) = (start as usize, end as usize);
+    let step = slice.stride.unsigned_abs();
+    let count = end.saturating_sub(start).div_ceil(step);
+    if slice.stride > 0 || count == 0 {
+        (start, count, step)
+    } else {
+        // ndarray steps backwards from the end for negative strides.
+        (end - 1 - (count - 1) * step, count, step)
+    }
+}
+
+/// Returns the shape of a selection of an array.
+///
+/// # Arguments
+///
+/// * `shape`: Shape of the array
+/// * `selection`: Optional selection of the array, with one slice per axis
+pub fn selection_shape(shape: &[usize], selection: &Option<Vec<models::Slice>>) -> Vec<usize> {
+    match selection {
+        Some(selection) => std::iter::zip(selection, shape)
+            .map(|(slice, length)| selected_range(slice, *length).1)
+            .collect(),
+        None => shape.to_vec(),
+    }
+}
+
+/// Returns the index in an array of an element of a selection of the array.
+///
+/// # Arguments
+///
+/// * `shape`: Shape of the array
+/// * `selection`: Optional selection of the array, with one slice per axis
+/// * `index`: Index of the element within the selection
+pub fn unselected_index(
+    shape: &[usize],
+    selection: &Option<Vec<models::Slice>>,
+    index: &[usize],
+) -> Vec<usize> {
+    let Some(selection) = selection else {
+        return index.to_vec();
+    };
+    std::iter::zip(std::iter::zip(selection, shape), index)
+        .map(|((slice, length), position)| {
+            // Bounds are clamped to the axis, so are never negative.
+            let (start, end) = to_ndarray_bounds(slice, *length);
+            let offset = position * slice.stride.unsigned_abs();
+            if slice.stride > 0 {
+                start as usize + offset
+            } else {
+                // ndarray steps backwards from the end for negative strides.
+                end as usize - 1 - offset
+            }
+        })
+        .collect()
+}
+
+/// Returns the shape of a selection and the byte ranges of the array data that contain the
+/// selected elements.
+///
+/// Adjacent elements are merged into a single range. The data in the ranges forms an array with
+/// the shape of the selection and the same order as the original array, except that axes with a
+/// negative stride are in ascending rather than selection order.
+///
+/// Returns `None` if more than `max_ranges` ranges would be required.
+///
+/// # Arguments
+///
+/// * `shape`: Shape of the array
+/// * `order`: Order of the array
+/// * `selection`: Selection of the array, with one slice per axis
+/// * `element_size`: Size of each element in bytes
+/// * `max_ranges`: Maximum number of ranges
+pub fn selection_byte_ranges(
+    shape: &[usize],
+    order: Option<models::Order>,
+    selection: &[models::Slice],
+    element_size: usize,
+    max_ranges: usize,
+) -> Option<(Vec<usize>, Vec<models::ByteRange>)> {
+    let axis_ranges: Vec<(usize, usize, usize)> = std::iter::zip(selection, shape)
+        .map(|(slice, length)| selected_range(slice, *length))
+        .collect();
+    let selection_shape: Vec<usize> = axis_ranges.iter().map(|(_, count, _)| *count).collect();
+    let mut ranges: Vec<models::ByteRange> = vec![];
+    if selection_shape.contains(&0) {
+        return Some((selection_shape, ranges));
+    }
+    // Axes from the slowest to the fastest varying in memory.
+    let mut axes: Vec<usize> = (0..shape.len()).collect();
+    if let Some(models::Order::F) = order {
+        axes.reverse();
+    }
+    // Distance in elements between consecutive indices of each axis.
+    let mut strides = vec![0; shape.len()];
+    let mut stride = 1;
+    for &axis in axes.iter().rev() {
+        strides[axis] = stride;
+        stride *= shape[axis];
+    }
+    // The selected elements form contiguous runs over the fastest varying axes that are selected
+    // in full, and the next axis if its selected indices are consecutive. The remaining outer axes
+    // start a new run at each of their selected indices.
+    let mut outer = axes.len();
+    let mut run = 1;
+    while outer > 0 {
+        let axis = axes[outer - 1];
+        let (_, count, step) = axis_ranges[axis];
+        if step != 1 && count > 1 {
+            break;
+        }
+        run *= count;
+        outer -= 1;
+        if count != shape[axis] {
+            break;
+        }
+    }
+    let outer_axes = &axes[..outer];
+    let runs = outer_axes.iter().try_fold(1_usize, |runs, &axis| {
+        runs.checked_mul(selection_shape[axis])
+    })?;
+    if runs > max_ranges {
+        return None;
+    }
+    let first: usize = (0..shape.len())
+        .map(|axis| axis_ranges[axis].0 * strides[axis])
+        .sum();
+    // Visit the first element of each run in memory order.
+    let mut counters = vec![0; shape.len()];
+    loop {
+        let element = first
+            + outer_axes
+                .iter()
+                .map(|&axis| counters[axis] * axis_ranges[axis].2 * strides[axis])
+                .sum::<usize>();
+        ranges.push(models::ByteRange::new(
+            element * element_size,
+            run * element_size,
+        ));
+        let mut done = true;
+        for &axis in outer_axes.iter().rev() {
+            counters[axis] += 1;
+            if counters[axis] < selection_shape[axis] {
+                done = false;
+                break;
+            }
+            counters[axis] = 0;
+        }
+        if done {
+            return Some((selection_shape, ranges));
+        }
+    }
+}
+
+/// Returns the byte range of the array data containing a hyperslab, and the shape and selection
+/// of the hyperslab within the data in that range.
+///
+/// The range is the smallest contiguous one that forms an array: it starts at the first element
+/// of the hyperslab and spans whole subarrays of the first axis, from slowest to fastest varying
+/// in memory, with a count greater than one. Axes that vary more slowly have a length of one in
+/// the returned shape, so the selection has the same dimensions as the hyperslab.
+///
+/// # Arguments
+///
+/// * `shape`: Shape of the array
+/// * `order`: Order of the array
+/// * `hyperslab`: Hyperslab of the array, with the same number of dimensions as the shape
+/// * `element_size`: Size of each element in bytes
+pub fn hyperslab_byte_range(
+    shape: &[usize],
+    order: Option<models::Order>,
+    hyperslab: &models::Hyperslab,
+    element_size: usize,
+) -> (models::ByteRange, Vec<usize>, Vec<models::Slice>) {
+    // Axes from the slowest to the fastest varying in memory.
+    let mut axes: Vec<usize> = (0..shape.len()).collect();
+    if let Some(models::Order::F) = order {
+        axes.reverse();
+    }
+    let position = axes
+        .iter()
+        .position(|&axis| hyperslab.count[axis] > 1)
+        .unwrap_or(axes.len() - 1);
+    let outer = axes[position];
+    // Number of elements in each subarray of the outer axis.
+    let inner: usize = axes[position + 1..]
+        .iter()
+        .map(|&axis| shape[axis])
+        .product();
+    let mut stride = inner * shape[outer];
+    let mut start = hyperslab.start[outer] * inner;
+    for &axis in axes[..position].iter().rev() {
+        start += hyperslab.start[axis] * stride;
+        stride *= shape[axis];
+    }
+    let range = models::ByteRange::new(
+        start * element_size,
+        hyperslab.count[outer] * inner * element_size,
+    );
+    let mut range_shape = shape.to_vec();
+    let mut selection = vec![];
+    for axis in 0..shape.len() {
+        let (start, count) = (hyperslab.start[axis], hyperslab.count[axis]);
+        let (length, slice) = match axes.iter().position(|&a| a == axis) {
+            Some(p) if p < position => (1, models::Slice::new(0, 1, 1)),
+            Some(p) if p == position => (count, models::Slice::new(0, count as isize, 1)),
+            _ => (
+                shape[axis],
+                models::Slice::new(start as isize, (start + count) as isize, 1),
+            ),
+        };
+        range_shape[axis] = length;
+        selection.push(slice);
+    }
+    (range, range_shape, selection)
+}
+
+/// Returns the offset in elements within the data of an array of the element with a flat index.
+///
+/// # Arguments
+///
+/// * `index`: Flat index of the element, in C order (row major) of `shape`
+/// * `shape`: Shape of the array
+/// * `layout_shape`: Shape with which the array is laid out, which may be larger than `shape`
+/// * `order`: Order in which the array is laid out
+pub fn flat_index_offset(
+    index: usize,
+    shape: &[usize],
+    layout_shape: &[usize],
+    order: Option<models::Order>,
+) -> usize {
+    let mut position = vec![0; shape.len()];
+    let mut remainder = index;
+    for (axis, length) in shape.iter().enumerate().rev() {
+        position[axis] = remainder % length;
+        remainder /= length;
+    }
+    let offset = |offset, axis: usize| offset * layout_shape[axis] + position[axis];
+    match order {
+        Some(models::Order::F) => (0..shape.len()).rev().fold(0, offset),
+        _ => (0..shape.len()).fold(0, offset),
+    }
+}
+
 /// Returns an [ndarray] SliceInfo object corresponding to the selection.
 pub fn build_slice_info<T>(
     selection: &Option<Vec<models::Slice>>,
@@ -146,11 +442,9 @@ pub fn build_slice_info<T>(
 /// Reverse the byte order of an array element.
 fn reverse_byte_order<T>(element: &mut T)
 where
-    T: Copy
-        + num_traits::FromBytes<Bytes = <T as num_traits::ToBytes>::Bytes>
-        + num_traits::ToBytes,
+    T: zerocopy::AsBytes + zerocopy::FromBytes,
 {
-    *element = T::from_be_bytes(&element.to_le_bytes());
+    element.as_bytes_mut().reverse();
 }
 
 /// Reverse the byte order of an array.
@@ -163,9 +457,7 @@ pub fn reverse_array_byte_order<T>(
     array: &mut ArrayViewMutD<T>,
     selection: &Option<Vec<models::Slice>>,
 ) where
-    T: Copy
-        + num_traits::FromBytes<Bytes = <T as num_traits::ToBytes>::Bytes>
-        + num_traits::ToBytes,
+    T: Copy + zerocopy::AsBytes + zerocopy::FromBytes,
 {
     if selection.is_some() {
         let slice_info = build_slice_info::<T>(selection, array.shape());
@@ -190,21 +482,63 @@ pub fn build_array<'a, T>(
     data: &'a mut [u8],
 ) -> Result<ArrayViewD<'a, T>, ActiveStorageError>
 where
-    T: Copy
-        + num_traits::FromBytes<Bytes = <T as num_traits::ToBytes>::Bytes>
-        + num_traits::ToBytes
-        + zerocopy::AsBytes
-        + zerocopy::FromBytes,
+    T: Copy + zerocopy::AsBytes + zerocopy::FromBytes,
 {
     let data = from_bytes::<T>(data)?;
     if let Some(NON_NATIVE_BYTE_ORDER) = request_data.byte_order {
         // Create a mutable array to change the byte order.
         let shape = get_shape(data.len(), request_data);
-        let mut array = build_array_mut_from_shape(shape, data)?;
+        let array = build_array_mut_from_shape(shape, data)?;
+        let mut array = logical_region(array, request_data);
         reverse_array_byte_order(&mut array, &request_data.selection);
     }
     let shape = get_shape(data.len(), request_data);
-    build_array_from_shape(shape, data)
+    Ok(logical_region(
+        build_array_from_shape(shape, data)?,
+        request_data,
+    ))
+}
+
+/// Build an [ndarray::ArrayView] object corresponding to the request and data bytes, without
+/// converting the data to native byte order.
+///
+/// Elements in non-native byte order must be converted using [reversed_byte_order] as they are
+/// read.
+/// For operations that read each selected element once, this avoids a separate pass over the
+/// data to reverse its byte order.
+///
+/// # Arguments
+///
+/// * `data`: Slice of bytes containing data for the array. Must be at least as aligned as an
+///   instance of `T`.
+/// * `request_data`: RequestData object for the request
+pub fn build_array_unconverted<'a, T>(
+    request_data: &'a models::RequestData,
+    data: &'a mut [u8],
+) -> Result<ArrayViewD<'a, T>, ActiveStorageError>
+where
+    T: Copy + zerocopy::AsBytes + zerocopy::FromBytes,
+{
+    let data = from_bytes::<T>(data)?;
+    let shape = get_shape(data.len(), request_data);
+    Ok(logical_region(
+        build_array_from_shape(shape, data)?,
+        request_data,
+    ))
+}
+
+/// Returns whether the data in the request is in native byte order.
+pub fn is_native_byte_order(request_data: &models::RequestData) -> bool {
+    request_data.byte_order != Some(NON_NATIVE_BYTE_ORDER)
+}
+
+/// Returns an array element with its byte order reversed.
+pub fn reversed_byte_order<T>(mut element: T) -> T
+where
+    T: zerocopy::AsBytes + zerocopy::FromBytes,
+{
+    reverse_byte_order(&mut element);
+    element
 }
 
 #[cfg(test)]
@@ -212,6 +546,7 @@ mod tests {
     use super::*;
     use crate::test_utils;
     use num_traits::Float;
+    use zerocopy::AsBytes;
 
     #[test]
     fn from_bytes_u32() {
@@ -483,6 +818,210 @@ mod tests {
         );
     }
 
+    #[test]
+    fn selected_range_positive_stride() {
+        let slice = models::Slice::new(1, 8, 3);
+        assert_eq!((1, 3, 3), selected_range(&slice, 10));
+    }
+
+    #[test]
+    fn selected_range_negative_stride() {
+        // NumPy's [8:1:-3] selects 8, 5, 2.
+        let slice = models::Slice::new(8, 1, -3);
+        assert_eq!((2, 3, 3), selected_range(&slice, 10));
+    }
+
+    #[test]
+    fn selected_range_empty() {
+        let slice = models::Slice::new(5, 2, 1);
+        assert_eq!(0, selected_range(&slice, 10).1);
+    }
+
+    #[test]
+    fn selection_shape_strides() {
+        let selection = Some(vec![
+            models::Slice::new(1, 8, 3),
+            models::Slice::new(8, 1, -3),
+            models::Slice::new(5, 2, 1),
+        ]);
+        assert_eq!(vec![3, 3, 0], selection_shape(&[10, 10, 10], &selection));
+        assert_eq!(vec![2, 4], selection_shape(&[2, 4], &None));
+    }
+
+    #[test]
+    fn unselected_index_strides() {
+        let selection = Some(vec![
+            models::Slice::new(1, 8, 3),
+            models::Slice::new(8, 1, -3),
+        ]);
+        assert_eq!(vec![4, 2], unselected_index(&[10, 10], &selection, &[1, 2]));
+        assert_eq!(vec![1, 8], unselected_index(&[10, 10], &selection, &[0, 0]));
+        assert_eq!(vec![1, 2], unselected_index(&[10, 10], &None, &[1, 2]));
+    }
+
+    #[test]
+    fn flat_index_offset_c_order() {
+        assert_eq!(7, flat_index_offset(7, &[2, 4], &[2, 4], None));
+        // Element [1, 2] of a 2x3 region of a 2x4 array.
+        assert_eq!(6, flat_index_offset(5, &[2, 3], &[2, 4], None));
+    }
+
+    #[test]
+    fn flat_index_offset_f_order() {
+        // Element [1, 2] of a 2x4 array.
+        assert_eq!(
+            5,
+            flat_index_offset(6, &[2, 4], &[2, 4], Some(models::Order::F))
+        );
+        // Element [1, 2] of a 2x3 region of a 3x4 array.
+        assert_eq!(
+            7,
+            flat_index_offset(5, &[2, 3], &[3, 4], Some(models::Order::F))
+        );
+    }
+
+    #[test]
+    fn selection_byte_ranges_2d() {
+        let selection = [models::Slice::new(0, 4, 2), models::Slice::new(1, 3, 1)];
+        let (shape, ranges) = selection_byte_ranges(&[4, 4], None, &selection, 4, 8).unwrap();
+        assert_eq!(vec![2, 2], shape);
+        assert_eq!(
+            vec![models::ByteRange::new(4, 8), models::ByteRange::new(36, 8)],
+            ranges
+        );
+    }
+
+    #[test]
+    fn selection_byte_ranges_2d_merged() {
+        // Whole rows are contiguous, so are merged into a single range.
+        let selection = [models::Slice::new(1, 3, 1), models::Slice::new(0, 4, 1)];
+        let (shape, ranges) = selection_byte_ranges(&[4, 4], None, &selection, 4, 8).unwrap();
+        assert_eq!(vec![2, 4], shape);
+        assert_eq!(vec![models::ByteRange::new(16, 32)], ranges);
+    }
+
+    #[test]
+    fn selection_byte_ranges_2d_f_order() {
+        let selection = [models::Slice::new(0, 4, 2), models::Slice::new(1, 3, 1)];
+        let (shape, ranges) =
+            selection_byte_ranges(&[4, 4], Some(models::Order::F), &selection, 4, 8).unwrap();
+        assert_eq!(vec![2, 2], shape);
+        assert_eq!(
+            vec![
+                models::ByteRange::new(16, 4),
+                models::ByteRange::new(24, 4),
+                models::ByteRange::new(32, 4),
+                models::ByteRange::new(40, 4)
+            ],
+            ranges
+        );
+    }
+
+    #[test]
+    fn selection_byte_ranges_matches_slice() {
+        // Compare the selected elements with those of an ndarray slice of the element indices.
+        let shape = [3, 4, 5];
+        let selection = [
+            models::Slice::new(-1, -4, -2),
+            models::Slice::new(1, 4, 2),
+            models::Slice::new(0, 5, 3),
+        ];
+        let elements = Array::from_iter(0..60).into_shape(shape.to_vec()).unwrap();
+        let slice_info = build_slice_info::<u32>(&Some(selection.to_vec()), &shape);
+        let mut expected: Vec<usize> = elements.slice(slice_info).iter().copied().collect();
+        expected.sort();
+        let (selection_shape, ranges) =
+            selection_byte_ranges(&shape, None, &selection, 1, 60).unwrap();
+        assert_eq!(vec![2, 2, 2], selection_shape);
+        let selected: Vec<usize> = ranges
+            .iter()
+            .flat_map(|range| range.offset..range.offset + range.size)
+            .collect();
+        assert_eq!(expected, selected);
+    }
+
+    #[test]
+    fn selection_byte_ranges_empty() {
+        let selection = [models::Slice::new(2, 2, 1)];
+        let (shape, ranges) = selection_byte_ranges(&[4], None, &selection, 4, 8).unwrap();
+        assert_eq!(vec![0], shape);
+        assert!(ranges.is_empty());
+    }
+
+    #[test]
+    fn selection_byte_ranges_too_many() {
+        let selection = [models::Slice::new(0, 8, 2)];
+        assert!(selection_byte_ranges(&[8], None, &selection, 4, 3).is_none());
+        assert!(selection_byte_ranges(&[8], None, &selection, 4, 4).is_some());
+    }
+
+    #[test]
+    fn selection_byte_ranges_huge() {
+        // Runs are computed without visiting each selected element.
+        let length = 1 << 40;
+        let selection = [models::Slice::new(0, length as isize, 1)];
+        let (shape, ranges) = selection_byte_ranges(&[length], None, &selection, 4, 1).unwrap();
+        assert_eq!(vec![length], shape);
+        assert_eq!(vec![models::ByteRange::new(0, length * 4)], ranges);
+        let selection = [models::Slice::new(0, length as isize, 2)];
+        assert!(selection_byte_ranges(&[length], None, &selection, 4, 64).is_none());
+    }
+
+    #[test]
+    fn hyperslab_byte_range_2d() {
+        // Rows 1 and 2 are downloaded in full, then columns 2 and 3 selected.
+        let hyperslab = models::Hyperslab::new(vec![1, 2], vec![2, 2]);
+        let (range, shape, selection) = hyperslab_byte_range(&[4, 5], None, &hyperslab, 4);
+        assert_eq!(models::ByteRange::new(20, 40), range);
+        assert_eq!(vec![2, 5], shape);
+        assert_eq!(
+            vec![models::Slice::new(0, 2, 1), models::Slice::new(2, 4, 1)],
+            selection
+        );
+    }
+
+    #[test]
+    fn hyperslab_byte_range_2d_single_row() {
+        let hyperslab = models::Hyperslab::new(vec![1, 2], vec![1, 3]);
+        let (range, shape, selection) = hyperslab_byte_range(&[4, 5], None, &hyperslab, 4);
+        assert_eq!(models::ByteRange::new(28, 12), range);
+        assert_eq!(vec![1, 3], shape);
+        assert_eq!(
+            vec![models::Slice::new(0, 1, 1), models::Slice::new(0, 3, 1)],
+            selection
+        );
+    }
+
+    #[test]
+    fn hyperslab_byte_range_2d_f_order() {
+        let hyperslab = models::Hyperslab::new(vec![1, 2], vec![2, 2]);
+        let (range, shape, selection) =
+            hyperslab_byte_range(&[4, 5], Some(models::Order::F), &hyperslab, 4);
+        assert_eq!(models::ByteRange::new(32, 32), range);
+        assert_eq!(vec![4, 2], shape);
+        assert_eq!(
+            vec![models::Slice::new(1, 3, 1), models::Slice::new(0, 2, 1)],
+            selection
+        );
+    }
+
+    #[test]
+    fn hyperslab_byte_range_matches_slice() {
+        // Compare the selected elements with those of an ndarray slice of the element indices.
+        let shape = [3, 4, 5];
+        let hyperslab = models::Hyperslab::new(vec![2, 1, 1], vec![1, 2, 3]);
+        let elements = Array::from_iter(0_usize..60).into_shape(shape).unwrap();
+        let (range, range_shape, selection) = hyperslab_byte_range(&shape, None, &hyperslab, 1);
+        assert_eq!(models::ByteRange::new(45, 10), range);
+        let range_elements = Array::from_iter(range.offset..range.offset + range.size)
+            .into_shape(range_shape.clone())
+            .unwrap();
+        let slice_info = build_slice_info::<u32>(&Some(selection), &range_shape);
+        let selected = range_elements.slice(slice_info);
+        let expected = elements.slice(ndarray::s![2..3, 1..3, 1..4]).into_dyn();
+        assert_eq!(expected, selected);
+    }
+
     #[test]
     fn reverse_array_byte_order_u32() {
         let mut data = [0, 42, u32::max_value()];
@@ -514,6 +1053,73 @@ mod tests {
         assert_eq!(array![[0x04030201_i64], [0x08070605_i64]].into_dyn(), array);
     }
 
+    #[test]
+    fn build_array_unconverted_non_native() {
+        let mut data = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        let array = build_array_unconverted::<u32>(&request_data, &mut data).unwrap();
+        assert_eq!(array![0x04030201_u32, 0x08070605_u32].into_dyn(), array);
+    }
+
+    #[test]
+    fn build_array_stored_shape() {
+        // A 2x3 array padded to a 3x4 chunk.
+        let mut values: Vec<u32> = (0..12).collect();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![3, 4]);
+        let array = build_array::<u32>(&request_data, values.as_bytes_mut()).unwrap();
+        assert_eq!(array![[0_u32, 1, 2], [4, 5, 6]].into_dyn(), array);
+        let array = build_array_unconverted::<u32>(&request_data, values.as_bytes_mut()).unwrap();
+        assert_eq!(array![[0_u32, 1, 2], [4, 5, 6]].into_dyn(), array);
+    }
+
+    #[test]
+    fn build_array_stored_shape_fortran() {
+        let mut values: Vec<u32> = (0..12).collect();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![3, 4]);
+        request_data.order = Some(models::Order::F);
+        let array = build_array::<u32>(&request_data, values.as_bytes_mut()).unwrap();
+        assert_eq!(array![[0_u32, 3, 6], [1, 4, 7]].into_dyn(), array);
+    }
+
+    #[test]
+    fn build_array_stored_shape_non_native() {
+        let mut values: Vec<u32> = (0..6).map(|i: u32| i.swap_bytes()).collect();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        request_data.shape = Some(vec![2, 2]);
+        request_data.stored_shape = Some(vec![2, 3]);
+        let array = build_array::<u32>(&request_data, values.as_bytes_mut()).unwrap();
+        assert_eq!(array![[0_u32, 1], [3, 4]].into_dyn(), array);
+        // Padding is not converted.
+        assert_eq!(
+            [0, 1, 2_u32.swap_bytes(), 3, 4, 5_u32.swap_bytes()],
+            values[..]
+        );
+    }
+
+    #[test]
+    fn is_native_byte_order_default() {
+        let mut request_data = test_utils::get_test_request_data();
+        assert!(is_native_byte_order(&request_data));
+        request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        assert!(!is_native_byte_order(&request_data));
+    }
+
+    #[test]
+    fn reversed_byte_order_u32() {
+        assert_eq!(42_u32 << 24, reversed_byte_order(42_u32));
+        assert_eq!(42_i64, reversed_byte_order(42_i64.swap_bytes()));
+    }
+
     // Helper function for tests that slice an array using a selection.
     fn test_selection(slice: models::Slice, expected: Array1<u32>) {
         let mut data = [1, 2, 3, 4, 5, 6, 7, 8];
@@ -0,0 +1,149 @@
+//! Heuristic check of the byte order of floating point data
+
+use crate::array;
+use crate::models::{self, DType};
+
+use num_traits::Float;
+use std::num::FpCategory;
+use zerocopy::{AsBytes, FromBytes};
+
+/// Maximum number of elements examined by [byte_order_suspicious].
+const MAX_CHECKED_ELEMENTS: usize = 4096;
+
+/// Returns whether the byte order of floating point data in the request looks wrong.
+///
+/// The server cannot know the true byte order of the data, but floating point data interpreted
+/// using the wrong byte order typically contains many NaN, infinite or subnormal values, since
+/// the low-order bytes of the mantissa end up in the exponent. The data looks wrong if it has
+/// more such values when interpreted using the byte order of the request than when byte-swapped,
+/// and at least twice as many.
+///
+/// Only the first `MAX_CHECKED_ELEMENTS` elements are examined. Integer data is not checked.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `data`: Decoded data, in the byte order of the request
+pub fn byte_order_suspicious(request_data: &models::RequestData, data: &[u8]) -> bool {
+    let native = array::is_native_byte_order(request_data);
+    let (requested, swapped) = match request_data.dtype {
+        DType::Float16 => count_suspicious::<half::f16>(data, native),
+        DType::Bfloat16 => count_suspicious::<half::bf16>(data, native),
+        DType::Float32 => count_suspicious::<f32>(data, native),
+        DType::Float64 => count_suspicious::<f64>(data, native),
+        DType::Int32 | DType::Int64 | DType::Uint32 | DType::Uint64 => return false,
+    };
+    requested > 0 && requested >= 2 * swapped
+}
+
+/// Returns the number of NaN, infinite or subnormal values of type `T` in some data when
+/// interpreted using the byte order of the request and when byte-swapped.
+fn count_suspicious<T>(data: &[u8], native: bool) -> (usize, usize)
+where
+    T: AsBytes + FromBytes + Float,
+{
+    data.chunks_exact(std::mem::size_of::<T>())
+        .take(MAX_CHECKED_ELEMENTS)
+        .fold((0, 0), |(requested, swapped), bytes| {
+            let value = T::read_from(bytes).expect("chunk is the size of T");
+            let (value, swapped_value) = if native {
+                (value, array::reversed_byte_order(value))
+            } else {
+                (array::reversed_byte_order(value), value)
+            };
+            (
+                requested + is_suspicious(value) as usize,
+                swapped + is_suspicious(swapped_value) as usize,
+            )
+        })
+}
+
+/// Returns whether a floating point value is NaN, infinite or subnormal.
+fn is_suspicious<T: Float>(value: T) -> bool {
+    matches!(
+        value.classify(),
+        FpCategory::Nan | FpCategory::Infinite | FpCategory::Subnormal
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::NON_NATIVE_BYTE_ORDER;
+
+    fn f32_data(values: &[f32], swap: bool) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| {
+                let value = if swap {
+                    array::reversed_byte_order(*value)
+                } else {
+                    *value
+                };
+                value.to_ne_bytes()
+            })
+            .collect()
+    }
+
+    const VALUES: [f32; 6] = [1.0, 2.5, 273.15, -40.0, 0.0, 1e6];
+
+    #[test]
+    fn correct_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        assert!(!byte_order_suspicious(
+            &request_data,
+            &f32_data(&VALUES, false)
+        ));
+        request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        assert!(!byte_order_suspicious(
+            &request_data,
+            &f32_data(&VALUES, true)
+        ));
+    }
+
+    #[test]
+    fn wrong_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        assert!(byte_order_suspicious(
+            &request_data,
+            &f32_data(&VALUES, true)
+        ));
+        request_data.byte_order = Some(NON_NATIVE_BYTE_ORDER);
+        assert!(byte_order_suspicious(
+            &request_data,
+            &f32_data(&VALUES, false)
+        ));
+    }
+
+    #[test]
+    fn wrong_byte_order_f64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        let data: Vec<u8> = [1.0_f64, 0.1, 300.0]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        request_data.byte_order = Some(crate::types::ByteOrder::Little);
+        assert!(byte_order_suspicious(&request_data, &data));
+        request_data.byte_order = Some(crate::types::ByteOrder::Big);
+        assert!(!byte_order_suspicious(&request_data, &data));
+    }
+
+    #[test]
+    fn integer_not_checked() {
+        let request_data = test_utils::get_test_request_data();
+        assert!(!byte_order_suspicious(&request_data, &[0xff; 16]));
+    }
+
+    #[test]
+    fn nan_both_orders() {
+        // Data that is NaN in either byte order is not suspicious, since the other order is no
+        // better.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        assert!(!byte_order_suspicious(&request_data, &[0xff; 16]));
+    }
+}
@@ -1,6 +1,14 @@
 //! Command Line Interface (CLI) arguments.
 
-use clap::Parser;
+use crate::operation::FloatSumStrategy;
+use crate::tracing::AccessLogRotation;
+use crate::types::ByteOrder;
+
+use clap::{CommandFactory, Parser};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::PathBuf;
+use tracing_subscriber::EnvFilter;
+use url::Url;
 
 /// Reductionist command line interface
 #[derive(Clone, Debug, Parser)]
@@ -34,22 +42,519 @@ pub struct CommandLineArgs {
     /// Whether to enable sending traces to Jaeger.
     #[arg(long, default_value_t = false, env = "REDUCTIONIST_ENABLE_JAEGER")]
     pub enable_jaeger: bool,
+    /// Comma-separated directives filtering the log by module and level, in the format of the
+    /// `RUST_LOG` environment variable, e.g. `reductionist::s3_client=debug,tower_http=info`.
+    /// Takes precedence over `RUST_LOG`. Default is to use `RUST_LOG` if set, or otherwise debug
+    /// logging for Reductionist and tower_http.
+    #[arg(long, env = "REDUCTIONIST_LOG_FILTER")]
+    pub log_filter: Option<String>,
+    /// Path to a file to which a line is written for each request, for auditing, separately from
+    /// other logging. Each line is a JSON object with the time, method, URI, status, latency in
+    /// milliseconds and request ID of a request. Lines are written by a background thread, so
+    /// requests only wait for file I/O if many lines are pending. Default is no access log.
+    #[arg(long, env = "REDUCTIONIST_ACCESS_LOG_FILE")]
+    pub access_log_file: Option<PathBuf>,
+    /// How often to start a new access log file. Unless this is `never`, the date and time of the
+    /// period covered by each file are appended to its name. Rotation is by time only, as
+    /// supported by `tracing-appender`, so files are not limited in size.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = AccessLogRotation::Never,
+        env = "REDUCTIONIST_ACCESS_LOG_ROTATION"
+    )]
+    pub access_log_rotation: AccessLogRotation,
+    /// Maximum number of rotated access log files to keep, deleting the oldest. Default is to
+    /// keep all files.
+    #[arg(long, env = "REDUCTIONIST_ACCESS_LOG_MAX_FILES")]
+    pub access_log_max_files: Option<NonZeroUsize>,
     /// Whether to use Rayon for execution of CPU-bound tasks.
     #[arg(long, default_value_t = false, env = "REDUCTIONIST_USE_RAYON")]
     pub use_rayon: bool,
+    /// Whether to decompress and filter data on Tokio's blocking thread pool, limited by
+    /// `--max-blocking-threads`, rather than on the runtime worker threads while holding a
+    /// `--thread-limit` task permit, so that the decompression of some requests may overlap with
+    /// the operations of others. Used only when use_rayon is false.
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "REDUCTIONIST_COMPRESSION_THREAD_POOL"
+    )]
+    pub compression_thread_pool: bool,
+    /// Number of Tokio runtime worker threads, which handle HTTP requests and object downloads.
+    /// Unless `--use-rayon` is set, CPU-bound work also runs on the worker threads, limited by
+    /// `--thread-limit`, so the thread limit should be less than the number of worker threads to
+    /// leave some free for asynchronous work. Default is the number of CPUs.
+    #[arg(long, env = "REDUCTIONIST_WORKER_THREADS")]
+    pub worker_threads: Option<NonZeroUsize>,
+    /// Maximum number of threads in the Tokio runtime's blocking thread pool, which is used for
+    /// blocking work such as DNS resolution and file I/O, separately from the worker threads and
+    /// the Rayon thread pool. Default is Tokio's default of 512.
+    #[arg(long, env = "REDUCTIONIST_MAX_BLOCKING_THREADS")]
+    pub max_blocking_threads: Option<NonZeroUsize>,
+    /// Strategy used to sum floating point values in the sum operation.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FloatSumStrategy::Pairwise,
+        env = "REDUCTIONIST_FLOAT_SUM_STRATEGY"
+    )]
+    pub float_sum_strategy: FloatSumStrategy,
+    /// Byte order of data in requests that do not specify `byte_order`. Default is the native
+    /// byte order of the server.
+    #[arg(long, value_enum, env = "REDUCTIONIST_DEFAULT_DTYPE_ENDIANNESS")]
+    pub default_dtype_endianness: Option<ByteOrder>,
+    /// Whether to check the byte order of floating point data. If the data has many more NaN,
+    /// infinite or subnormal values than it would if byte-swapped, the byte order in the request
+    /// is probably wrong, and a warning is logged and returned in the
+    /// `x-activestorage-byte-order-warning` response header. The request still succeeds.
+    #[arg(long, default_value_t = false, env = "REDUCTIONIST_BYTE_ORDER_CHECK")]
+    pub byte_order_check: bool,
     /// Memory limit in bytes. Default is no limit.
     #[arg(long, env = "REDUCTIONIST_MEMORY_LIMIT")]
     pub memory_limit: Option<usize>,
+    /// Maximum size in bytes of decompressed data. Decompression is aborted if this is exceeded.
+    /// Default is no limit.
+    #[arg(long, env = "REDUCTIONIST_MAX_DECOMPRESSED_SIZE")]
+    pub max_decompressed_size: Option<usize>,
+    /// Maximum number of dimensions in the `shape` of a request. Requests with more dimensions
+    /// fail with `400 Bad Request` before any data is downloaded.
+    #[arg(long, default_value_t = 32, env = "REDUCTIONIST_MAX_DIMENSIONS")]
+    pub max_dimensions: usize,
+    /// Maximum number of elements in the `shape` of a request. Requests with more elements fail
+    /// with `400 Bad Request` before any data is downloaded. Default is no limit.
+    #[arg(long, env = "REDUCTIONIST_MAX_ELEMENTS")]
+    pub max_elements: Option<usize>,
+    /// Maximum size in bytes of the JSON body of a request. Requests with larger bodies fail with
+    /// `413 Payload Too Large` without the body being buffered.
+    #[arg(long, default_value_t = 2 * 1024 * 1024, env = "REDUCTIONIST_MAX_JSON_BODY")]
+    pub max_json_body: usize,
+    /// Region used to sign requests to S3 sources that do not specify `region`.
+    #[arg(long, default_value = "us-east-1", env = "REDUCTIONIST_S3_REGION")]
+    pub s3_region: String,
+    /// Maximum number of S3 clients to keep for reuse. A client is created for each distinct
+    /// combination of source, region and credentials, and the least recently used client is
+    /// evicted if the limit is exceeded.
+    #[arg(
+        long,
+        default_value = "1024",
+        env = "REDUCTIONIST_S3_CLIENT_CACHE_SIZE"
+    )]
+    pub s3_client_cache_size: NonZeroUsize,
+    /// Path to a PEM file of CA certificates to trust for HTTPS connections to S3 sources, in
+    /// addition to the system's CA certificates.
+    #[arg(long, env = "REDUCTIONIST_S3_CA_BUNDLE")]
+    pub s3_ca_bundle: Option<PathBuf>,
+    /// Whether to skip verification of TLS certificates for HTTPS connections to S3 sources.
+    /// This is insecure, and intended only for testing against object stores with self-signed
+    /// certificates.
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "REDUCTIONIST_S3_INSECURE_SKIP_VERIFY"
+    )]
+    pub s3_insecure_skip_verify: bool,
+    /// URL of an endpoint to connect to for requests to S3 sources, instead of the host of the
+    /// `source`, such as a transparent proxy or cache in front of the object store. Only the
+    /// scheme, host and port are used. Requests are still signed for the `source`, whose host is
+    /// sent in the `Host` header. Requests using the http protocol or a presigned source are not
+    /// affected.
+    #[arg(long, env = "REDUCTIONIST_S3_ENDPOINT_OVERRIDE")]
+    pub s3_endpoint_override: Option<Url>,
+    /// Whether to verify the data of S3 objects against their checksums. The checksum of the
+    /// object is requested from the object store, and if present, the downloaded data must match
+    /// it, or the request fails with `502 Bad Gateway`. Only downloads of whole objects are
+    /// verified, since object stores do not return checksums for byte ranges.
+    #[arg(long, default_value_t = false, env = "REDUCTIONIST_VERIFY_CHECKSUMS")]
+    pub verify_checksums: bool,
     /// S3 connection limit. Default is no limit.
     #[arg(long, env = "REDUCTIONIST_S3_CONNECTION_LIMIT")]
     pub s3_connection_limit: Option<usize>,
+    /// Maximum number of idle connections to each S3 host kept open for reuse by later requests.
+    /// Default is no limit. Requests made while all pooled connections are busy open new
+    /// connections, which are closed after use if the pool is full. Use s3_connection_limit to
+    /// limit the total number of connections.
+    #[arg(long, env = "REDUCTIONIST_S3_MAX_IDLE_CONNECTIONS_PER_HOST")]
+    pub s3_max_idle_connections_per_host: Option<usize>,
+    /// Time in seconds after which idle connections to S3 hosts are closed. Default is 90
+    /// seconds.
+    #[arg(long, env = "REDUCTIONIST_S3_CONNECTION_IDLE_TIMEOUT")]
+    pub s3_connection_idle_timeout: Option<u64>,
     /// Thread limit for CPU-bound tasks. Default is one less than the number of CPUs. Used only
-    /// when use_rayon is false.
+    /// when use_rayon is false, and ignored with a warning otherwise.
     #[arg(long, env = "REDUCTIONIST_THREAD_LIMIT")]
     pub thread_limit: Option<usize>,
+    /// Maximum time in milliseconds to wait to acquire S3 connection, memory or task resources.
+    /// If exceeded, the request fails with `503 Service Unavailable` and a `Retry-After` header.
+    /// Default is to wait indefinitely.
+    #[arg(long, env = "REDUCTIONIST_RESOURCE_ACQUIRE_TIMEOUT")]
+    pub resource_acquire_timeout: Option<u64>,
+    /// Maximum number of `/v1` API requests to process concurrently. Default is no limit.
+    #[arg(long, env = "REDUCTIONIST_MAX_CONCURRENT_REQUESTS")]
+    pub max_concurrent_requests: Option<NonZeroUsize>,
+    /// Maximum number of requests to queue while waiting for one of the
+    /// `--max-concurrent-requests` slots. Further requests fail immediately with
+    /// `503 Service Unavailable` and a `Retry-After` header.
+    #[arg(long, default_value_t = 0, env = "REDUCTIONIST_MAX_QUEUED_REQUESTS")]
+    pub max_queued_requests: usize,
+    /// Maximum number of requests per second to each S3 source (scheme, host and port). If
+    /// exceeded, the request fails with `429 Too Many Requests` and a `Retry-After` header.
+    /// Default is no limit.
+    #[arg(long, env = "REDUCTIONIST_PER_SOURCE_RATE_LIMIT")]
+    pub per_source_rate_limit: Option<NonZeroU32>,
+    /// Bucket that requests are allowed to access. May be specified multiple times, or as a
+    /// comma-separated list in the environment variable. Requests for other buckets, including
+    /// requests using the http protocol or a presigned source, fail with `403 Forbidden` before
+    /// the object store is contacted. Default is to allow all buckets.
+    #[arg(
+        long = "allowed-bucket",
+        env = "REDUCTIONIST_ALLOWED_BUCKETS",
+        value_delimiter = ','
+    )]
+    pub allowed_buckets: Vec<String>,
+    /// Key prefix of the objects that requests are allowed to access. Requests for objects
+    /// without the prefix, including requests using the http protocol or a presigned source,
+    /// fail with `403 Forbidden` before the object store is contacted. The prefix is compared
+    /// with the object key as given, so should normally end with `/`. Default is to allow all
+    /// objects.
+    #[arg(long, env = "REDUCTIONIST_OBJECT_PREFIX")]
+    pub object_prefix: Option<String>,
+    /// URL of an object store source that requests are allowed to access. May be specified
+    /// multiple times, or as a comma-separated list in the environment variable. Sources match if
+    /// they have the same scheme, host and port. Requests for other sources fail with
+    /// `403 Forbidden`. Default is to allow all sources.
+    #[arg(
+        long = "allowed-source",
+        env = "REDUCTIONIST_ALLOWED_SOURCES",
+        value_delimiter = ','
+    )]
+    pub allowed_sources: Vec<Url>,
+    /// Whether to reject sources with a host that is or resolves to a loopback, private, shared
+    /// (CGNAT), link-local or unspecified IP address, to protect internal services. Hosts are
+    /// resolved again when connecting, and only public addresses are used. Sources with an IP
+    /// address specified using `--allowed-source` are always allowed.
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "REDUCTIONIST_DENY_PRIVATE_SOURCES"
+    )]
+    pub deny_private_sources: bool,
+    /// Maximum total size in bytes of object data to cache. Cached data is revalidated using the
+    /// object's ETag with a conditional request, and reused if the object has not been modified.
+    /// Memory used by the cache is not counted towards the memory limit. Default is no caching.
+    #[arg(long, env = "REDUCTIONIST_DOWNLOAD_CACHE_SIZE")]
+    pub download_cache_size: Option<usize>,
+    /// Path to a file listing objects to download into the download cache at startup, before
+    /// the server starts listening, so that the first requests for them are served from the
+    /// cache. Each line is a JSON object with the `source`, `bucket` and `object`, and optionally
+    /// the `region`, of an object. Blank lines and lines starting with `#` are ignored. Whole
+    /// objects are downloaded without credentials, and objects that cannot be downloaded are
+    /// logged and skipped. Requires `--download-cache-size`.
+    #[arg(long, env = "REDUCTIONIST_PRELOAD_FILE")]
+    pub preload_file: Option<PathBuf>,
+    /// Maximum total size in bytes of object data pinned by sessions created using the
+    /// `/v1/session` endpoint. Requests to create a session fail with `400 Bad Request` if it
+    /// would be exceeded. Memory used by sessions is also counted towards the memory limit until
+    /// the session is deleted or expires. Default is to disable sessions.
+    #[arg(long, env = "REDUCTIONIST_SESSION_STORE_SIZE")]
+    pub session_store_size: Option<usize>,
+    /// Time in seconds for which a session is valid after it is created.
+    #[arg(long, default_value_t = 300, env = "REDUCTIONIST_SESSION_TTL")]
+    pub session_ttl: u64,
+    /// Bearer token required to access the `/metrics` endpoint, using an
+    /// `Authorization: Bearer <token>` header. Requests without the token fail with
+    /// `401 Unauthorized`. Default is no authentication.
+    #[arg(long, env = "REDUCTIONIST_METRICS_AUTH_TOKEN")]
+    pub metrics_auth_token: Option<String>,
+    /// Whether to attach OpenMetrics exemplars to the buckets of the `response_time` histogram,
+    /// containing the trace ID of the most recent request observed in each bucket. The trace ID
+    /// is that of the request's span if Jaeger is enabled, or otherwise of its `traceparent`
+    /// header. Exemplars are only returned to clients that accept the OpenMetrics text format,
+    /// which is then used instead of the Prometheus text format.
+    #[arg(long, default_value_t = false, env = "REDUCTIONIST_METRICS_EXEMPLARS")]
+    pub metrics_exemplars: bool,
+    /// Whether to compress response bodies (gzip or zstd), negotiated using the client's
+    /// `Accept-Encoding` header.
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "REDUCTIONIST_RESPONSE_COMPRESSION"
+    )]
+    pub response_compression: bool,
+}
+
+impl CommandLineArgs {
+    /// Checks that the options are coherent, returning a description of each problem found.
+    ///
+    /// This catches misconfiguration at startup that would otherwise only cause requests to
+    /// fail.
+    pub fn check(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+        let largest_element = std::mem::size_of::<f64>();
+        require(
+            self.memory_limit
+                .map_or(true, |limit| limit >= largest_element),
+            "--memory-limit must be at least 8 bytes, the size of the largest data type",
+        );
+        require(
+            self.thread_limit != Some(0),
+            "--thread-limit must be greater than 0",
+        );
+        require(
+            !self.compression_thread_pool || !self.use_rayon,
+            "--compression-thread-pool cannot be used with --use-rayon",
+        );
+        require(
+            self.s3_connection_limit != Some(0),
+            "--s3-connection-limit must be greater than 0",
+        );
+        require(
+            self.max_dimensions > 0,
+            "--max-dimensions must be greater than 0",
+        );
+        require(
+            self.max_elements != Some(0),
+            "--max-elements must be greater than 0",
+        );
+        require(
+            self.max_decompressed_size != Some(0),
+            "--max-decompressed-size must be greater than 0",
+        );
+        require(
+            self.max_queued_requests == 0 || self.max_concurrent_requests.is_some(),
+            "--max-queued-requests requires --max-concurrent-requests",
+        );
+        require(
+            self.download_cache_size != Some(0),
+            "--download-cache-size must be greater than 0, or omitted to disable caching",
+        );
+        require(
+            self.preload_file.is_none() || self.download_cache_size.is_some(),
+            "--preload-file requires --download-cache-size",
+        );
+        require(
+            self.access_log_file.is_some()
+                || (self.access_log_rotation == AccessLogRotation::Never
+                    && self.access_log_max_files.is_none()),
+            "--access-log-rotation and --access-log-max-files require --access-log-file",
+        );
+        require(
+            self.access_log_file
+                .as_ref()
+                .and_then(|path| path.parent())
+                .map_or(true, |directory| {
+                    directory.as_os_str().is_empty() || directory.is_dir()
+                }),
+            "--access-log-file must be in an existing directory",
+        );
+        require(
+            self.log_filter
+                .as_ref()
+                .map_or(true, |directives| EnvFilter::try_new(directives).is_ok()),
+            "--log-filter must be a comma-separated list of valid filter directives",
+        );
+        require(
+            self.session_store_size != Some(0),
+            "--session-store-size must be greater than 0, or omitted to disable sessions",
+        );
+        require(
+            self.session_store_size.is_none() || self.session_ttl > 0,
+            "--session-ttl must be greater than 0 when sessions are enabled",
+        );
+        require(
+            self.s3_ca_bundle
+                .as_ref()
+                .map_or(true, |path| path.is_file()),
+            "--s3-ca-bundle must be an existing file",
+        );
+        require(
+            self.s3_endpoint_override
+                .as_ref()
+                .map_or(true, |url| matches!(url.scheme(), "http" | "https")),
+            "--s3-endpoint-override must be an http or https URL",
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
 }
 
 /// Returns parsed command line arguments.
+///
+/// Exits with an error message if the arguments are invalid or not coherent.
 pub fn parse() -> CommandLineArgs {
-    CommandLineArgs::parse()
+    let args = CommandLineArgs::parse();
+    if let Err(problems) = args.check() {
+        CommandLineArgs::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                problems.join("\n       "),
+            )
+            .exit();
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Returns the problems found by checking arguments.
+    fn problems(args: &[&str]) -> Vec<String> {
+        test_utils::get_test_args(args).check().unwrap_err()
+    }
+
+    #[test]
+    fn check_default() {
+        assert!(test_utils::get_test_args(&[]).check().is_ok());
+    }
+
+    #[test]
+    fn check_valid_limits() {
+        let args = test_utils::get_test_args(&[
+            "--memory-limit=8",
+            "--s3-connection-limit=1",
+            "--max-concurrent-requests=4",
+            "--max-queued-requests=8",
+            "--session-store-size=1024",
+            "--session-ttl=1",
+        ]);
+        assert!(args.check().is_ok());
+    }
+
+    #[test]
+    fn check_memory_limit() {
+        assert_eq!(
+            vec!["--memory-limit must be at least 8 bytes, the size of the largest data type"],
+            problems(&["--memory-limit=4"])
+        );
+        assert_eq!(1, problems(&["--memory-limit=0"]).len());
+    }
+
+    #[test]
+    fn check_thread_limit() {
+        // The test arguments already set a thread limit.
+        let args = CommandLineArgs::parse_from(["reductionist", "--thread-limit=0"]);
+        assert_eq!(
+            vec!["--thread-limit must be greater than 0"],
+            args.check().unwrap_err()
+        );
+        // A thread limit is ignored with Rayon, which only logs a warning.
+        assert!(test_utils::get_test_args(&["--use-rayon"]).check().is_ok());
+    }
+
+    #[test]
+    fn check_compression_thread_pool() {
+        let args = CommandLineArgs::parse_from([
+            "reductionist",
+            "--use-rayon",
+            "--compression-thread-pool",
+        ]);
+        assert_eq!(
+            vec!["--compression-thread-pool cannot be used with --use-rayon"],
+            args.check().unwrap_err()
+        );
+        assert!(test_utils::get_test_args(&["--compression-thread-pool"])
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn check_log_filter() {
+        assert!(
+            test_utils::get_test_args(&["--log-filter=reductionist::s3_client=debug,info"])
+                .check()
+                .is_ok()
+        );
+        assert_eq!(
+            vec!["--log-filter must be a comma-separated list of valid filter directives"],
+            problems(&["--log-filter=reductionist=loud"])
+        );
+    }
+
+    #[test]
+    fn check_queued_requests() {
+        assert_eq!(
+            vec!["--max-queued-requests requires --max-concurrent-requests"],
+            problems(&["--max-queued-requests=8"])
+        );
+    }
+
+    #[test]
+    fn check_preload_file() {
+        assert_eq!(
+            vec!["--preload-file requires --download-cache-size"],
+            problems(&["--preload-file=preload.jsonl"])
+        );
+    }
+
+    #[test]
+    fn check_access_log() {
+        assert!(test_utils::get_test_args(&[
+            "--access-log-file=access.log",
+            "--access-log-rotation=daily",
+            "--access-log-max-files=7",
+        ])
+        .check()
+        .is_ok());
+        assert_eq!(
+            vec!["--access-log-rotation and --access-log-max-files require --access-log-file"],
+            problems(&["--access-log-rotation=hourly"])
+        );
+        assert_eq!(
+            vec!["--access-log-file must be in an existing directory"],
+            problems(&["--access-log-file=/nonexistent/access.log"])
+        );
+    }
+
+    #[test]
+    fn check_sessions() {
+        assert_eq!(
+            vec!["--session-store-size must be greater than 0, or omitted to disable sessions"],
+            problems(&["--session-store-size=0"])
+        );
+        assert_eq!(
+            vec!["--session-ttl must be greater than 0 when sessions are enabled"],
+            problems(&["--session-store-size=1024", "--session-ttl=0"])
+        );
+        // The TTL is not used without a session store.
+        assert!(test_utils::get_test_args(&["--session-ttl=0"])
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn check_s3_options() {
+        assert_eq!(
+            vec!["--s3-ca-bundle must be an existing file"],
+            problems(&["--s3-ca-bundle=/nonexistent/ca.pem"])
+        );
+        assert_eq!(
+            vec!["--s3-endpoint-override must be an http or https URL"],
+            problems(&["--s3-endpoint-override=ftp://proxy.example.com"])
+        );
+        assert_eq!(
+            vec!["--s3-connection-limit must be greater than 0"],
+            problems(&["--s3-connection-limit=0"])
+        );
+    }
+
+    #[test]
+    fn check_reports_all_problems() {
+        let problems = problems(&[
+            "--max-dimensions=0",
+            "--max-elements=0",
+            "--max-decompressed-size=0",
+            "--download-cache-size=0",
+        ]);
+        assert_eq!(4, problems.len());
+    }
 }
@@ -4,56 +4,192 @@ use crate::error::ActiveStorageError;
 use crate::models;
 
 use axum::body::Bytes;
-use flate2::read::GzDecoder;
+use flate2::read::MultiGzDecoder;
 use std::io::Read;
+use zune_inflate::errors::{DecodeErrorStatus, InflateDecodeErrors};
 use zune_inflate::{DeflateDecoder, DeflateOptions};
 
+/// Size of the internal buffer used by the Brotli decompressor.
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
 /// Decompresses some Bytes and returns the uncompressed data.
 ///
 /// # Arguments
 ///
 /// * `compression`: Compression algorithm
 /// * `data`: Compressed data [Bytes]
+/// * `raw_size`: Expected size of the uncompressed data in bytes, if known
+/// * `max_size`: Optional maximum size of the uncompressed data in bytes. Decompression is
+///   aborted once this is exceeded.
 pub fn decompress(
     compression: models::Compression,
     data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
 ) -> Result<Bytes, ActiveStorageError> {
     match compression {
-        models::Compression::Gzip => decompress_flate2_gzip(data),
-        models::Compression::Zlib => decompress_zune_zlib(data),
+        models::Compression::Gzip => decompress_flate2_gzip(data, raw_size, max_size),
+        models::Compression::Zlib => decompress_zune_zlib(data, raw_size, max_size),
+        models::Compression::Deflate => decompress_zune_deflate(data, raw_size, max_size),
+        models::Compression::Brotli => decompress_brotli(data, raw_size, max_size),
+        models::Compression::Snappy { framed: false } => decompress_snappy_raw(data, max_size),
+        models::Compression::Snappy { framed: true } => {
+            decompress_snappy_framed(data, raw_size, max_size)
+        }
     }
 }
 
-fn decompress_flate2_gzip(data: &Bytes) -> Result<Bytes, ActiveStorageError> {
-    let mut decoder = GzDecoder::<&[u8]>::new(data);
+/// Maximum initial capacity of the buffer for the uncompressed data.
+const MAX_BUFFER_SIZE_HINT: usize = 64 * 1024 * 1024;
+
+/// Returns the initial capacity of the buffer for the uncompressed data.
+///
+/// The expected size of the uncompressed data is derived from the shape in the request, so is
+/// only a hint. It is clamped to the maximum size and [MAX_BUFFER_SIZE_HINT], so that a request
+/// with a huge shape cannot cause a huge allocation, and the buffer grows as needed beyond it.
+fn buffer_size(data: &Bytes, raw_size: Option<usize>, max_size: Option<usize>) -> usize {
+    let size = raw_size.unwrap_or(data.len()).min(MAX_BUFFER_SIZE_HINT);
+    max_size.map_or(size, |max_size| size.min(max_size))
+}
+
+/// Returns an 8-byte aligned buffer with sufficient capacity for the uncompressed data.
+fn aligned_buffer(data: &Bytes, raw_size: Option<usize>, max_size: Option<usize>) -> Vec<u8> {
+    maligned::align_first::<u8, maligned::A8>(buffer_size(data, raw_size, max_size))
+}
+
+/// Limits a streaming decompressor to reading one byte more than the maximum size, which is
+/// enough to detect that the maximum size has been exceeded.
+fn limit<R: Read>(decoder: R, max_size: Option<usize>) -> std::io::Take<R> {
+    decoder.take(max_size.map_or(u64::MAX, |max_size| max_size as u64 + 1))
+}
+
+/// Checks the size of decompressed data against the optional maximum size.
+fn check_max_size(size: usize, max_size: Option<usize>) -> Result<(), ActiveStorageError> {
+    match max_size {
+        Some(limit) if size > limit => Err(ActiveStorageError::DecompressedSizeExceeded { limit }),
+        _ => Ok(()),
+    }
+}
+
+fn decompress_flate2_gzip(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    // Read all members of a multi-member gzip stream, rather than stopping after the first.
+    let decoder = MultiGzDecoder::<&[u8]>::new(data);
     // The data returned by the S3 client does not have any alignment guarantees. In order to
     // reinterpret the data as an array of numbers with a higher alignment than 1, we need to
     // return the data in Bytes object in which the underlying data has a higher alignment.
     // For now we're hard-coding an alignment of 8 bytes, although this should depend on the
     // data type, and potentially whether there are any SIMD requirements.
     // Create an 8-byte aligned Vec<u8>.
-    // FIXME: The compressed length will not be enough to store the uncompressed data, and may
-    // result in a change in the underlying buffer to one that is not correctly aligned.
-    let mut buf = maligned::align_first::<u8, maligned::A8>(data.len());
-    decoder.read_to_end(&mut buf)?;
+    // FIXME: If the uncompressed size is not known, the compressed length will not be enough to
+    // store the uncompressed data, and may result in a change in the underlying buffer to one
+    // that is not correctly aligned.
+    let mut buf = aligned_buffer(data, raw_size, max_size);
+    limit(decoder, max_size).read_to_end(&mut buf)?;
+    check_max_size(buf.len(), max_size)?;
     // Release any unnecessary capacity.
     buf.shrink_to(0);
     Ok(buf.into())
 }
 
-fn decompress_zune_zlib(data: &Bytes) -> Result<Bytes, ActiveStorageError> {
-    let options = DeflateOptions::default().set_size_hint(data.len());
+fn decompress_zune_zlib(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    decompress_zune(data, raw_size, max_size, |decoder| decoder.decode_zlib())
+}
+
+fn decompress_zune_deflate(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    decompress_zune(data, raw_size, max_size, |decoder| decoder.decode_deflate())
+}
+
+/// Decompresses data using a zune-inflate decoder, with a decode function for the format of the
+/// data.
+fn decompress_zune(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+    decode: fn(&mut DeflateDecoder) -> Result<Vec<u8>, InflateDecodeErrors>,
+) -> Result<Bytes, ActiveStorageError> {
+    let mut options =
+        DeflateOptions::default().set_size_hint(buffer_size(data, raw_size, max_size));
+    if let Some(max_size) = max_size {
+        options = options.set_limit(max_size);
+    }
     let mut decoder = DeflateDecoder::new_with_options(data, options);
-    let data = decoder.decode_zlib()?;
+    let data = decode(&mut decoder).map_err(|err| match err.error {
+        DecodeErrorStatus::OutputLimitExceeded(limit, _) => {
+            ActiveStorageError::DecompressedSizeExceeded { limit }
+        }
+        _ => err.into(),
+    })?;
     Ok(data.into())
 }
 
+fn decompress_brotli(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    let decoder = brotli::Decompressor::new(data.as_ref(), BROTLI_BUFFER_SIZE);
+    // See decompress_flate2_gzip for details of alignment.
+    let mut buf = aligned_buffer(data, raw_size, max_size);
+    limit(decoder, max_size)
+        .read_to_end(&mut buf)
+        .map_err(ActiveStorageError::DecompressionBrotli)?;
+    check_max_size(buf.len(), max_size)?;
+    // Release any unnecessary capacity.
+    buf.shrink_to(0);
+    Ok(buf.into())
+}
+
+fn decompress_snappy_raw(
+    data: &Bytes,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    let snappy_error = |err: snap::Error| ActiveStorageError::DecompressionSnappy(err.into());
+    // The raw format starts with the uncompressed length, so we can check it up front.
+    let size = snap::raw::decompress_len(data).map_err(snappy_error)?;
+    check_max_size(size, max_size)?;
+    // See decompress_flate2_gzip for details of alignment.
+    let mut buf = maligned::align_first::<u8, maligned::A8>(size);
+    buf.resize(size, 0);
+    snap::raw::Decoder::new()
+        .decompress(data, &mut buf)
+        .map_err(snappy_error)?;
+    Ok(buf.into())
+}
+
+fn decompress_snappy_framed(
+    data: &Bytes,
+    raw_size: Option<usize>,
+    max_size: Option<usize>,
+) -> Result<Bytes, ActiveStorageError> {
+    let decoder = snap::read::FrameDecoder::new(data.as_ref());
+    // See decompress_flate2_gzip for details of alignment.
+    let mut buf = aligned_buffer(data, raw_size, max_size);
+    limit(decoder, max_size)
+        .read_to_end(&mut buf)
+        .map_err(ActiveStorageError::DecompressionSnappy)?;
+    check_max_size(buf.len(), max_size)?;
+    // Release any unnecessary capacity.
+    buf.shrink_to(0);
+    Ok(buf.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flate2::read::{GzEncoder, ZlibEncoder};
+    use flate2::read::{DeflateEncoder, GzEncoder, ZlibEncoder};
     use flate2::Compression;
-    use zune_inflate::errors::DecodeErrorStatus;
 
     fn compress_gzip() -> Vec<u8> {
         // Adapated from flate2 documentation.
@@ -64,6 +200,29 @@ mod tests {
         result
     }
 
+    fn compress_brotli() -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        let input = b"hello world";
+        let mut compressor = brotli::CompressorReader::new(&input[..], 4096, 5, 22);
+        compressor.read_to_end(&mut result).unwrap();
+        result
+    }
+
+    fn compress_snappy_raw() -> Vec<u8> {
+        snap::raw::Encoder::new()
+            .compress_vec(b"hello world")
+            .unwrap()
+    }
+
+    fn compress_snappy_framed() -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        let input = b"hello world";
+        snap::read::FrameEncoder::new(&input[..])
+            .read_to_end(&mut result)
+            .unwrap();
+        result
+    }
+
     fn compress_zlib() -> Vec<u8> {
         // Adapated from flate2 documentation.
         let mut result = Vec::<u8>::new();
@@ -73,18 +232,134 @@ mod tests {
         result
     }
 
+    fn compress_deflate() -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        let input = b"hello world";
+        let mut deflater = DeflateEncoder::new(&input[..], Compression::fast());
+        deflater.read_to_end(&mut result).unwrap();
+        result
+    }
+
     #[test]
     fn test_decompress_gzip() {
         let compressed = compress_gzip();
-        let result = decompress(models::Compression::Gzip, &compressed.into()).unwrap();
+        let result = decompress(models::Compression::Gzip, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_gzip_huge_raw_size() {
+        // The expected size is only a hint, so does not cause a huge allocation.
+        let compressed: Bytes = compress_gzip().into();
+        assert_eq!(
+            MAX_BUFFER_SIZE_HINT,
+            buffer_size(&compressed, Some(usize::MAX / 8), None)
+        );
+        let result = decompress(
+            models::Compression::Gzip,
+            &compressed,
+            Some(usize::MAX / 8),
+            None,
+        )
+        .unwrap();
         assert_eq!(result, b"hello world".as_ref());
+        let result = decompress(
+            models::Compression::Zlib,
+            &compress_zlib().into(),
+            Some(1 << 60),
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+    }
+
+    #[test]
+    fn test_decompress_gzip_multi_member() {
+        let mut compressed = compress_gzip();
+        compressed.extend(compress_gzip());
+        let result = decompress(models::Compression::Gzip, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello worldhello world".as_ref());
         assert_eq!(result.as_ptr().align_offset(8), 0);
     }
 
     #[test]
     fn test_decompress_zlib() {
         let compressed = compress_zlib();
-        let result = decompress(models::Compression::Zlib, &compressed.into()).unwrap();
+        let result = decompress(models::Compression::Zlib, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_deflate() {
+        let compressed = compress_deflate();
+        let result =
+            decompress(models::Compression::Deflate, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_gzip_raw_size() {
+        let compressed = compress_gzip();
+        let result = decompress(
+            models::Compression::Gzip,
+            &compressed.into(),
+            Some(11),
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_brotli() {
+        let compressed = compress_brotli();
+        let result =
+            decompress(models::Compression::Brotli, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_brotli_raw_size() {
+        let compressed = compress_brotli();
+        let result = decompress(
+            models::Compression::Brotli,
+            &compressed.into(),
+            Some(11),
+            None,
+        )
+        .unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_snappy_raw() {
+        let compressed = compress_snappy_raw();
+        let compression = models::Compression::Snappy { framed: false };
+        let result = decompress(compression, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_snappy_framed() {
+        let compressed = compress_snappy_framed();
+        let compression = models::Compression::Snappy { framed: true };
+        let result = decompress(compression, &compressed.into(), None, None).unwrap();
+        assert_eq!(result, b"hello world".as_ref());
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decompress_snappy_framed_raw_size() {
+        let compressed = compress_snappy_framed();
+        let compression = models::Compression::Snappy { framed: true };
+        let result = decompress(compression, &compressed.into(), Some(11), None).unwrap();
         assert_eq!(result, b"hello world".as_ref());
         assert_eq!(result.as_ptr().align_offset(8), 0);
     }
@@ -92,7 +367,13 @@ mod tests {
     #[test]
     fn test_decompress_invalid_gzip() {
         let invalid = b"invalid format";
-        let err = decompress(models::Compression::Gzip, &invalid.as_ref().into()).unwrap_err();
+        let err = decompress(
+            models::Compression::Gzip,
+            &invalid.as_ref().into(),
+            None,
+            None,
+        )
+        .unwrap_err();
         match err {
             ActiveStorageError::DecompressionFlate2(io_err) => {
                 assert_eq!(io_err.kind(), std::io::ErrorKind::InvalidInput);
@@ -105,7 +386,13 @@ mod tests {
     #[test]
     fn test_decompress_invalid_zlib() {
         let invalid = b"invalid format";
-        let err = decompress(models::Compression::Zlib, &invalid.as_ref().into()).unwrap_err();
+        let err = decompress(
+            models::Compression::Zlib,
+            &invalid.as_ref().into(),
+            None,
+            None,
+        )
+        .unwrap_err();
         match err {
             ActiveStorageError::DecompressionZune(zune_err) => match zune_err.error {
                 DecodeErrorStatus::GenericStr(message) => {
@@ -116,4 +403,118 @@ mod tests {
             err => panic!("unexpected error {}", err),
         }
     }
+
+    #[test]
+    fn test_decompress_invalid_brotli() {
+        let invalid = b"invalid format";
+        let err = decompress(
+            models::Compression::Brotli,
+            &invalid.as_ref().into(),
+            None,
+            None,
+        )
+        .unwrap_err();
+        match err {
+            ActiveStorageError::DecompressionBrotli(io_err) => {
+                assert_eq!(io_err.kind(), std::io::ErrorKind::InvalidData);
+            }
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn test_decompress_invalid_snappy() {
+        let invalid = b"invalid format";
+        for framed in [false, true] {
+            let compression = models::Compression::Snappy { framed };
+            let err = decompress(compression, &invalid.as_ref().into(), None, None).unwrap_err();
+            match err {
+                ActiveStorageError::DecompressionSnappy(_) => (),
+                err => panic!("unexpected error {}", err),
+            }
+        }
+    }
+
+    // A small compressed payload that expands to a large amount of data.
+    fn bomb() -> Vec<u8> {
+        vec![0_u8; 1 << 20]
+    }
+
+    fn assert_max_size_exceeded(compression: models::Compression, compressed: Vec<u8>) {
+        // The compressed data is within the limit, but the decompressed data is not.
+        assert!(compressed.len() < 1 << 16);
+        let err = decompress(compression, &compressed.into(), None, Some(1 << 16)).unwrap_err();
+        match err {
+            ActiveStorageError::DecompressedSizeExceeded { limit } => assert_eq!(limit, 1 << 16),
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn test_decompress_gzip_max_size_exceeded() {
+        let mut compressed = Vec::<u8>::new();
+        GzEncoder::new(bomb().as_slice(), Compression::best())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_max_size_exceeded(models::Compression::Gzip, compressed);
+    }
+
+    #[test]
+    fn test_decompress_deflate_max_size_exceeded() {
+        let mut compressed = Vec::<u8>::new();
+        DeflateEncoder::new(bomb().as_slice(), Compression::best())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_max_size_exceeded(models::Compression::Deflate, compressed);
+    }
+
+    #[test]
+    fn test_decompress_zlib_max_size_exceeded() {
+        let mut compressed = Vec::<u8>::new();
+        ZlibEncoder::new(bomb().as_slice(), Compression::best())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_max_size_exceeded(models::Compression::Zlib, compressed);
+    }
+
+    #[test]
+    fn test_decompress_brotli_max_size_exceeded() {
+        let mut compressed = Vec::<u8>::new();
+        brotli::CompressorReader::new(bomb().as_slice(), 4096, 5, 22)
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_max_size_exceeded(models::Compression::Brotli, compressed);
+    }
+
+    #[test]
+    fn test_decompress_snappy_max_size_exceeded() {
+        let compressed = snap::raw::Encoder::new().compress_vec(&bomb()).unwrap();
+        assert_max_size_exceeded(models::Compression::Snappy { framed: false }, compressed);
+        let mut compressed = Vec::<u8>::new();
+        snap::read::FrameEncoder::new(bomb().as_slice())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_max_size_exceeded(models::Compression::Snappy { framed: true }, compressed);
+    }
+
+    #[test]
+    fn test_decompress_max_size_equal() {
+        for (compression, compressed) in [
+            (models::Compression::Gzip, compress_gzip()),
+            (models::Compression::Zlib, compress_zlib()),
+            (models::Compression::Deflate, compress_deflate()),
+            (models::Compression::Brotli, compress_brotli()),
+            (
+                models::Compression::Snappy { framed: false },
+                compress_snappy_raw(),
+            ),
+            (
+                models::Compression::Snappy { framed: true },
+                compress_snappy_framed(),
+            ),
+        ] {
+            let result = decompress(compression, &compressed.into(), None, Some(11)).unwrap();
+            assert_eq!(result, b"hello world".as_ref());
+        }
+    }
 }
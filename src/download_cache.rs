@@ -0,0 +1,227 @@
+//! Cache of downloaded object data, revalidated using ETags
+
+use crate::models::RequestData;
+
+use axum::body::Bytes;
+use hashbrown::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use url::Url;
+
+/// Key identifying cached object data.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CacheKey {
+    /// URL of the object store or object.
+    source: Url,
+
+    /// Bucket containing the object.
+    bucket: String,
+
+    /// Object name.
+    object: String,
+
+    /// Byte range of the object, if any.
+    range: Option<String>,
+
+    /// Part number of a multipart object, if any.
+    part_number: Option<i32>,
+}
+
+impl CacheKey {
+    /// Returns a new CacheKey for a byte range of the object in the request data.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_data`: RequestData object for the request
+    /// * `range`: Optional byte range
+    pub fn new(request_data: &RequestData, range: &Option<String>) -> Self {
+        Self {
+            source: request_data.source.clone(),
+            bucket: request_data.bucket.clone(),
+            object: request_data.object.clone(),
+            range: range.clone(),
+            part_number: request_data.part_number,
+        }
+    }
+}
+
+/// Object data held in the cache.
+struct CachedObject {
+    /// Entity tag of the object when the data was downloaded.
+    etag: String,
+
+    /// Object data.
+    data: Bytes,
+}
+
+/// Contents of a [crate::download_cache::DownloadCache].
+#[derive(Default)]
+struct CacheState {
+    /// Cached objects.
+    objects: HashMap<CacheKey, CachedObject>,
+
+    /// Keys of cached objects, oldest first.
+    order: VecDeque<CacheKey>,
+
+    /// Total size of cached object data in bytes.
+    size: usize,
+}
+
+impl CacheState {
+    /// Remove an object from the cache, if present.
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(object) = self.objects.remove(key) {
+            self.size -= object.data.len();
+            self.order.retain(|k| k != key);
+        }
+    }
+}
+
+/// [crate::download_cache::DownloadCache] holds downloaded object data along with the object's
+/// ETag.
+///
+/// Cached data is not trusted on its own: it should be revalidated with a conditional download
+/// using the ETag, and only reused if the object has not been modified. Since the object store
+/// checks credentials before evaluating the condition, cached data is only reused for requests
+/// that are authorised to download the object.
+///
+/// The total size of cached data is limited, and the oldest objects are evicted first.
+pub struct DownloadCache {
+    /// Maximum total size of cached object data in bytes.
+    max_size: usize,
+
+    /// Cache contents.
+    state: Mutex<CacheState>,
+}
+
+impl DownloadCache {
+    /// Returns a new DownloadCache object.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size`: Maximum total size of cached object data in bytes
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the ETag and a copy of the data of a cached object, if present.
+    ///
+    /// The data is copied into a new 8-byte aligned buffer, since operations require exclusive
+    /// ownership of the data in order to convert it in place.
+    pub fn get(&self, key: &CacheKey) -> Option<(String, Bytes)> {
+        let state = self.state.lock().unwrap();
+        state.objects.get(key).map(|object| {
+            let mut buf = maligned::align_first::<u8, maligned::A8>(object.data.len());
+            buf.extend_from_slice(&object.data);
+            (object.etag.clone(), buf.into())
+        })
+    }
+
+    /// Store a copy of an object's data in the cache, replacing any existing data and evicting
+    /// the oldest objects if necessary.
+    ///
+    /// Objects larger than the maximum cache size are not cached.
+    pub fn insert(&self, key: CacheKey, etag: String, data: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state.remove(&key);
+        if data.len() > self.max_size {
+            return;
+        }
+        while state.size + data.len() > self.max_size {
+            let Some(oldest) = state.order.pop_front() else {
+                break;
+            };
+            if let Some(object) = state.objects.remove(&oldest) {
+                state.size -= object.data.len();
+            }
+        }
+        state.size += data.len();
+        state.order.push_back(key.clone());
+        let data = Bytes::copy_from_slice(data);
+        state.objects.insert(key, CachedObject { etag, data });
+    }
+
+    /// Remove an object from the cache, if present.
+    pub fn remove(&self, key: &CacheKey) {
+        self.state.lock().unwrap().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn make_key(object: &str) -> CacheKey {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.object = object.to_string();
+        CacheKey::new(&request_data, &None)
+    }
+
+    #[test]
+    fn get_missing() {
+        let cache = DownloadCache::new(10);
+        assert!(cache.get(&make_key("a")).is_none());
+    }
+
+    #[test]
+    fn insert_get() {
+        let cache = DownloadCache::new(10);
+        cache.insert(make_key("a"), "etag".to_string(), &[1, 2, 3]);
+        let (etag, data) = cache.get(&make_key("a")).unwrap();
+        assert_eq!("etag", etag);
+        assert_eq!(&[1, 2, 3][..], data);
+        // Ranges of the same object are cached separately.
+        let range_key = CacheKey::new(
+            &test_utils::get_test_request_data(),
+            &Some("bytes=0-1".to_string()),
+        );
+        assert!(cache.get(&range_key).is_none());
+    }
+
+    #[test]
+    fn insert_replace() {
+        let cache = DownloadCache::new(10);
+        cache.insert(make_key("a"), "etag1".to_string(), &[1, 2, 3]);
+        cache.insert(make_key("a"), "etag2".to_string(), &[4, 5]);
+        let (etag, data) = cache.get(&make_key("a")).unwrap();
+        assert_eq!("etag2", etag);
+        assert_eq!(&[4, 5][..], data);
+        let state = cache.state.lock().unwrap();
+        assert_eq!(2, state.size);
+        assert_eq!(1, state.order.len());
+    }
+
+    #[test]
+    fn insert_evicts_oldest() {
+        let cache = DownloadCache::new(10);
+        cache.insert(make_key("a"), "etag".to_string(), &[0; 4]);
+        cache.insert(make_key("b"), "etag".to_string(), &[0; 4]);
+        cache.insert(make_key("c"), "etag".to_string(), &[0; 4]);
+        assert!(cache.get(&make_key("a")).is_none());
+        assert!(cache.get(&make_key("b")).is_some());
+        assert!(cache.get(&make_key("c")).is_some());
+        assert_eq!(8, cache.state.lock().unwrap().size);
+    }
+
+    #[test]
+    fn insert_too_large() {
+        let cache = DownloadCache::new(10);
+        cache.insert(make_key("a"), "etag".to_string(), &[0; 4]);
+        cache.insert(make_key("b"), "etag".to_string(), &[0; 11]);
+        assert!(cache.get(&make_key("a")).is_some());
+        assert!(cache.get(&make_key("b")).is_none());
+    }
+
+    #[test]
+    fn remove() {
+        let cache = DownloadCache::new(10);
+        cache.insert(make_key("a"), "etag".to_string(), &[0; 4]);
+        cache.remove(&make_key("a"));
+        assert!(cache.get(&make_key("a")).is_none());
+        assert_eq!(0, cache.state.lock().unwrap().size);
+    }
+}
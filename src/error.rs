@@ -3,6 +3,7 @@
 use aws_sdk_s3::error::ProvideErrorMetadata;
 use aws_sdk_s3::error::SdkError;
 use aws_sdk_s3::operation::get_object::GetObjectError;
+use aws_sdk_s3::operation::head_object::HeadObjectError;
 use aws_smithy_types::byte_stream::error::Error as ByteStreamError;
 use axum::{
     extract::rejection::JsonRejection,
@@ -12,6 +13,7 @@ use axum::{
 };
 use ndarray::ShapeError;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::error::Error;
 use thiserror::Error;
 use tokio::sync::AcquireError;
@@ -26,10 +28,38 @@ use crate::types::DValue;
 /// Each variant may result in a different API error response.
 #[derive(Debug, Error)]
 pub enum ActiveStorageError {
+    /// Attempt to perform an operation along an axis that does not support it
+    #[error("cannot perform {operation} along an axis")]
+    AxisUnsupported { operation: &'static str },
+
+    /// Bucket is not in the list of allowed buckets
+    #[error("access to bucket {bucket:?} is not allowed")]
+    BucketNotAllowed { bucket: String },
+
+    /// Element is out of range of the output data type
+    #[error("selected element is out of range of output dtype {dtype}")]
+    CastOverflow { dtype: String },
+
+    /// Object data does not match its checksum
+    #[error("object data does not match its checksum")]
+    ChecksumMismatch,
+
+    /// Decompressed data exceeds the maximum size
+    #[error("decompressed data exceeds maximum size of {limit} bytes")]
+    DecompressedSizeExceeded { limit: usize },
+
+    /// Error decompressing Brotli data
+    #[error("failed to decompress data")]
+    DecompressionBrotli(#[source] std::io::Error),
+
     /// Error decompressing data
     #[error("failed to decompress data")]
     DecompressionFlate2(#[from] std::io::Error),
 
+    /// Error decompressing Snappy data
+    #[error("failed to decompress data")]
+    DecompressionSnappy(#[source] std::io::Error),
+
     /// Error decompressing data
     #[error("failed to decompress data")]
     DecompressionZune(#[from] InflateDecodeErrors),
@@ -42,6 +72,18 @@ pub enum ActiveStorageError {
     #[error("failed to convert from bytes to {type_name}")]
     FromBytes { type_name: &'static str },
 
+    /// Missing Content-Length header in HTTP response.
+    #[error("HTTP response missing Content-Length header")]
+    HttpContentLengthMissing,
+
+    /// HTTP server returned the whole object in response to a byte range request
+    #[error("HTTP server does not support byte range requests")]
+    HttpRangeNotSupported,
+
+    /// Error while retrieving an object from an HTTP server
+    #[error("error retrieving object from HTTP server")]
+    HttpRequest(#[from] reqwest::Error),
+
     /// Incompatible missing data descriptor
     #[error("Incompatible value {0} for missing")]
     IncompatibleMissing(DValue),
@@ -50,6 +92,49 @@ pub enum ActiveStorageError {
     #[error("Insufficient memory to process request ({requested} > {total})")]
     InsufficientMemory { requested: usize, total: usize },
 
+    /// Attempt to perform an operation on an element that is NaN or infinite
+    #[error("cannot perform {operation} on non-finite value at index {index:?}")]
+    NonFiniteValue {
+        operation: &'static str,
+        index: Vec<usize>,
+    },
+
+    /// Attempt to perform an operation on an element that is not positive
+    #[error("cannot perform {operation} on non-positive values")]
+    NonPositiveValue { operation: &'static str },
+
+    /// Object is not under the allowed object prefix
+    #[error("access to object {object:?} is not allowed")]
+    ObjectNotAllowed { object: String },
+
+    /// Requested byte range extends beyond the end of the object
+    #[error("requested byte range {range} exceeds object size of {object_size} bytes")]
+    ObjectRangeOutOfBounds { range: String, object_size: usize },
+
+    /// Byte range of a part of a multipart object does not match the requested byte range
+    #[error("part {part_number} has byte range {part_range}, not the requested range {range}")]
+    PartRangeMismatch {
+        part_number: i32,
+        part_range: String,
+        range: String,
+    },
+
+    /// Too many concurrent requests
+    #[error("server is overloaded, retry after {retry_after} seconds")]
+    Overloaded { retry_after: u64 },
+
+    /// Timed out waiting to acquire resources
+    #[error("timed out waiting for resources, retry after {retry_after} seconds")]
+    ResourceAcquireTimeout { retry_after: u64 },
+
+    /// Request rate limit exceeded for a source
+    #[error("request rate limit exceeded for source, retry after {retry_after} seconds")]
+    RateLimited { retry_after: u64 },
+
+    /// Request body exceeds the maximum size
+    #[error("request body exceeds the maximum size")]
+    RequestBodyTooLarge(#[source] JsonRejection),
+
     /// Error deserialising request data into RequestData
     #[error("request data is not valid")]
     RequestDataJsonRejection(#[from] JsonRejection),
@@ -71,26 +156,285 @@ pub enum ActiveStorageError {
     S3ContentLengthMissing,
 
     /// Error while retrieving an object from S3
+    ///
+    /// AWS SDK errors are large, so are boxed to keep the size of the error type small.
     #[error("error retrieving object from S3 storage")]
-    S3GetObject(#[from] SdkError<GetObjectError>),
+    S3GetObject(#[source] Box<SdkError<GetObjectError>>),
+
+    /// Error while retrieving an object's metadata from S3
+    #[error("error retrieving object metadata from S3 storage")]
+    S3HeadObject(#[source] Box<SdkError<HeadObjectError>>),
 
     /// Error acquiring a semaphore
     #[error("error acquiring resources")]
     SemaphoreAcquireError(#[from] AcquireError),
 
+    /// Session does not exist, has expired, or is for a different object
+    #[error("session not found")]
+    SessionNotFound,
+
     /// Error creating ndarray ArrayView from Shape
     #[error("failed to create array from shape")]
     ShapeInvalid(#[from] ShapeError),
 
+    /// Invalid Zarr v3 shard
+    #[error("invalid Zarr shard: {0}")]
+    ShardInvalid(String),
+
+    /// Object data ended before the expected number of bytes was received
+    #[error("received {actual} of {expected} bytes of object data")]
+    ShortRead { expected: usize, actual: usize },
+
+    /// Source is not allowed by the source filter
+    #[error("access to source {url} is not allowed")]
+    SourceNotAllowed { url: String },
+
     /// Error converting between integer types
     #[error(transparent)]
     TryFromInt(#[from] std::num::TryFromIntError),
 
+    /// Operation requested on an unsupported data type
+    #[error("cannot perform {operation} on {dtype} data")]
+    UnsupportedDType {
+        operation: &'static str,
+        dtype: String,
+    },
+
     /// Unsupported operation requested
     #[error("unsupported operation {operation}")]
     UnsupportedOperation { operation: String },
 }
 
+/// Stable machine-readable error code
+///
+/// Each [crate::error::ActiveStorageError] maps to one of these codes, which is returned in the
+/// `code` field of error responses. Clients may branch on the code rather than the message.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Access to the object store was denied
+    AccessDenied,
+    /// Operation cannot be performed along an axis
+    AxisUnsupported,
+    /// Bucket is not in the list of allowed buckets
+    BucketNotAllowed,
+    /// Bucket does not exist
+    BucketNotFound,
+    /// Element is out of range of the output data type
+    CastOverflow,
+    /// Object data does not match its checksum
+    ChecksumMismatch,
+    /// Response from the object store is missing a Content-Length header
+    ContentLengthMissing,
+    /// Decompressed data exceeds the maximum size
+    DecompressedSizeExceeded,
+    /// Data could not be decompressed
+    DecompressionFailed,
+    /// Operation cannot be performed on an empty array or selection
+    EmptyArray,
+    /// Missing data descriptor is incompatible with the data type
+    IncompatibleMissing,
+    /// Insufficient memory to process the request
+    InsufficientMemory,
+    /// Internal server error
+    InternalError,
+    /// Request data is not valid
+    InvalidRequest,
+    /// Shape of the data is not valid
+    InvalidShape,
+    /// Zarr v3 shard is not valid
+    InvalidShard,
+    /// Operation cannot be performed on values that are NaN or infinite
+    NonFiniteValue,
+    /// Operation cannot be performed on values that are not positive
+    NonPositiveValue,
+    /// Object is not under the allowed object prefix
+    ObjectNotAllowed,
+    /// Object does not exist
+    ObjectNotFound,
+    /// Requested byte range extends beyond the end of the object
+    ObjectRangeOutOfBounds,
+    /// Other error accessing the object store
+    ObjectStoreError,
+    /// Server has too many concurrent requests
+    Overloaded,
+    /// Byte range of a part does not match the requested byte range
+    PartRangeMismatch,
+    /// HTTP server does not support byte range requests
+    RangeNotSupported,
+    /// Request rate limit exceeded for the source
+    RateLimited,
+    /// Request body exceeds the maximum size
+    RequestBodyTooLarge,
+    /// Timed out waiting to acquire resources
+    ResourceAcquireTimeout,
+    /// Session does not exist, has expired, or is for a different object
+    SessionNotFound,
+    /// Object data ended before the expected number of bytes was received
+    ShortRead,
+    /// Source is not allowed by the source filter
+    SourceNotAllowed,
+    /// Operation is not supported for the data type
+    UnsupportedDtype,
+    /// Operation is not supported
+    UnsupportedOperation,
+}
+
+impl ActiveStorageError {
+    /// Returns the error code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ActiveStorageError::AxisUnsupported { operation: _ } => ErrorCode::AxisUnsupported,
+            ActiveStorageError::BucketNotAllowed { bucket: _ } => ErrorCode::BucketNotAllowed,
+            ActiveStorageError::CastOverflow { dtype: _ } => ErrorCode::CastOverflow,
+            ActiveStorageError::ChecksumMismatch => ErrorCode::ChecksumMismatch,
+            ActiveStorageError::DecompressedSizeExceeded { limit: _ } => {
+                ErrorCode::DecompressedSizeExceeded
+            }
+            ActiveStorageError::DecompressionBrotli(_)
+            | ActiveStorageError::DecompressionFlate2(_)
+            | ActiveStorageError::DecompressionSnappy(_)
+            | ActiveStorageError::DecompressionZune(_) => ErrorCode::DecompressionFailed,
+            ActiveStorageError::EmptyArray { operation: _ } => ErrorCode::EmptyArray,
+            ActiveStorageError::FromBytes { type_name: _ }
+            | ActiveStorageError::SemaphoreAcquireError(_)
+            | ActiveStorageError::TryFromInt(_) => ErrorCode::InternalError,
+            ActiveStorageError::HttpContentLengthMissing
+            | ActiveStorageError::S3ContentLengthMissing => ErrorCode::ContentLengthMissing,
+            ActiveStorageError::HttpRangeNotSupported => ErrorCode::RangeNotSupported,
+            ActiveStorageError::HttpRequest(http_error) => {
+                match http_error.status().map(|status| status.as_u16()) {
+                    Some(404) => ErrorCode::ObjectNotFound,
+                    Some(401 | 403) => ErrorCode::AccessDenied,
+                    _ => ErrorCode::ObjectStoreError,
+                }
+            }
+            ActiveStorageError::IncompatibleMissing(_) => ErrorCode::IncompatibleMissing,
+            ActiveStorageError::InsufficientMemory {
+                requested: _,
+                total: _,
+            } => ErrorCode::InsufficientMemory,
+            ActiveStorageError::NonFiniteValue {
+                operation: _,
+                index: _,
+            } => ErrorCode::NonFiniteValue,
+            ActiveStorageError::NonPositiveValue { operation: _ } => ErrorCode::NonPositiveValue,
+            ActiveStorageError::ObjectNotAllowed { object: _ } => ErrorCode::ObjectNotAllowed,
+            ActiveStorageError::ObjectRangeOutOfBounds {
+                range: _,
+                object_size: _,
+            } => ErrorCode::ObjectRangeOutOfBounds,
+            ActiveStorageError::Overloaded { retry_after: _ } => ErrorCode::Overloaded,
+            ActiveStorageError::PartRangeMismatch {
+                part_number: _,
+                part_range: _,
+                range: _,
+            } => ErrorCode::PartRangeMismatch,
+            ActiveStorageError::RateLimited { retry_after: _ } => ErrorCode::RateLimited,
+            ActiveStorageError::RequestBodyTooLarge(_) => ErrorCode::RequestBodyTooLarge,
+            ActiveStorageError::RequestDataJsonRejection(_)
+            | ActiveStorageError::RequestDataValidationSingle(_)
+            | ActiveStorageError::RequestDataValidation(_) => ErrorCode::InvalidRequest,
+            ActiveStorageError::ResourceAcquireTimeout { retry_after: _ } => {
+                ErrorCode::ResourceAcquireTimeout
+            }
+            ActiveStorageError::S3ByteStream(_) => ErrorCode::ObjectStoreError,
+            ActiveStorageError::S3GetObject(sdk_error) => match sdk_error.as_service_error() {
+                Some(GetObjectError::NoSuchKey(_)) => ErrorCode::ObjectNotFound,
+                Some(get_obj_error) => match get_obj_error.code() {
+                    Some("NoSuchBucket") => ErrorCode::BucketNotFound,
+                    Some("InvalidAccessKeyId")
+                    | Some("SignatureDoesNotMatch")
+                    | Some("AccessDenied") => ErrorCode::AccessDenied,
+                    _ => ErrorCode::ObjectStoreError,
+                },
+                None => ErrorCode::ObjectStoreError,
+            },
+            ActiveStorageError::S3HeadObject(sdk_error) => match sdk_error.as_ref() {
+                // HEAD responses have no body, so use the HTTP status code.
+                SdkError::ServiceError(head_obj_error) => {
+                    match (head_obj_error.err(), head_obj_error.raw().status().as_u16()) {
+                        (HeadObjectError::NotFound(_), _) => ErrorCode::ObjectNotFound,
+                        (_, 401 | 403) => ErrorCode::AccessDenied,
+                        _ => ErrorCode::ObjectStoreError,
+                    }
+                }
+                _ => ErrorCode::ObjectStoreError,
+            },
+            ActiveStorageError::SessionNotFound => ErrorCode::SessionNotFound,
+            ActiveStorageError::ShapeInvalid(_) => ErrorCode::InvalidShape,
+            ActiveStorageError::ShardInvalid(_) => ErrorCode::InvalidShard,
+            ActiveStorageError::ShortRead {
+                expected: _,
+                actual: _,
+            } => ErrorCode::ShortRead,
+            ActiveStorageError::SourceNotAllowed { url: _ } => ErrorCode::SourceNotAllowed,
+            ActiveStorageError::UnsupportedDType {
+                operation: _,
+                dtype: _,
+            } => ErrorCode::UnsupportedDtype,
+            ActiveStorageError::UnsupportedOperation { operation: _ } => {
+                ErrorCode::UnsupportedOperation
+            }
+        }
+    }
+
+    /// Returns structured details of this error, if any.
+    fn detail(&self) -> Option<serde_json::Value> {
+        let detail = match self {
+            ActiveStorageError::AxisUnsupported { operation } => json!({ "operation": operation }),
+            ActiveStorageError::BucketNotAllowed { bucket } => json!({ "bucket": bucket }),
+            ActiveStorageError::CastOverflow { dtype } => json!({ "dtype": dtype }),
+            ActiveStorageError::DecompressedSizeExceeded { limit } => json!({ "limit": limit }),
+            ActiveStorageError::EmptyArray { operation } => json!({ "operation": operation }),
+            ActiveStorageError::IncompatibleMissing(value) => json!({ "value": value }),
+            ActiveStorageError::InsufficientMemory { requested, total } => {
+                json!({ "requested": requested, "total": total })
+            }
+            ActiveStorageError::NonFiniteValue { operation, index } => {
+                json!({ "operation": operation, "index": index })
+            }
+            ActiveStorageError::NonPositiveValue { operation } => json!({ "operation": operation }),
+            ActiveStorageError::ObjectNotAllowed { object } => json!({ "object": object }),
+            ActiveStorageError::ObjectRangeOutOfBounds { range, object_size } => {
+                json!({ "range": range, "object_size": object_size })
+            }
+            ActiveStorageError::PartRangeMismatch {
+                part_number,
+                part_range,
+                range,
+            } => {
+                json!({ "part_number": part_number, "part_range": part_range, "range": range })
+            }
+            ActiveStorageError::Overloaded { retry_after }
+            | ActiveStorageError::RateLimited { retry_after }
+            | ActiveStorageError::ResourceAcquireTimeout { retry_after } => {
+                json!({ "retry_after": retry_after })
+            }
+            ActiveStorageError::RequestDataValidationSingle(error) => json!(error),
+            ActiveStorageError::RequestDataValidation(errors) => json!(errors),
+            ActiveStorageError::ShortRead { expected, actual } => {
+                json!({ "expected": expected, "actual": actual })
+            }
+            ActiveStorageError::SourceNotAllowed { url } => json!({ "url": url }),
+            ActiveStorageError::UnsupportedDType { operation, dtype } => {
+                json!({ "operation": operation, "dtype": dtype })
+            }
+            ActiveStorageError::UnsupportedOperation { operation } => {
+                json!({ "operation": operation })
+            }
+            _ => return None,
+        };
+        Some(detail)
+    }
+
+    /// Returns the body of the error response for this error as JSON, including its error code
+    /// and message, such as for reporting the errors of individual objects in a batch.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(ErrorBody::new(self)).expect("error body is serialisable")
+    }
+}
+
 impl IntoResponse for ActiveStorageError {
     /// Convert from an `ActiveStorageError` into an [axum::response::Response].
     fn into_response(self) -> Response {
@@ -103,9 +447,16 @@ impl IntoResponse for ActiveStorageError {
 /// Implements serde (de)serialise.
 #[derive(Deserialize, Serialize)]
 struct ErrorBody {
+    /// Error code
+    code: ErrorCode,
+
     /// Main error message
     message: String,
 
+    /// Optional structured details, depending on the error code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<serde_json::Value>,
+
     /// Optional list of causes
     #[serde(skip_serializing_if = "Option::is_none")]
     caused_by: Option<Vec<String>>,
@@ -117,10 +468,7 @@ impl ErrorBody {
     /// # Arguments
     ///
     /// * `error`: The error that occurred
-    fn new<E>(error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn new(error: &ActiveStorageError) -> Self {
         let message = error.to_string();
         let mut caused_by = None;
         let mut current = error.source();
@@ -134,7 +482,12 @@ impl ErrorBody {
         if let Some(caused_by) = caused_by.as_mut() {
             caused_by.dedup()
         }
-        ErrorBody { message, caused_by }
+        ErrorBody {
+            code: error.code(),
+            message,
+            detail: error.detail(),
+            caused_by,
+        }
     }
 }
 
@@ -147,6 +500,10 @@ struct ErrorResponse {
     #[serde(skip)]
     status: StatusCode,
 
+    /// Optional number of seconds after which the client may retry
+    #[serde(skip)]
+    retry_after: Option<u64>,
+
     /// Response body
     error: ErrorBody,
 }
@@ -158,47 +515,76 @@ impl ErrorResponse {
     ///
     /// * `status`: HTTP status of the response
     /// * `error`: The error that occurred. This will be formatted into a suitable `ErrorBody`
-    fn new<E>(status: StatusCode, error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn new(status: StatusCode, error: &ActiveStorageError) -> Self {
         ErrorResponse {
             status,
+            retry_after: None,
             error: ErrorBody::new(error),
         }
     }
 
     /// Return a 400 bad request ErrorResponse
-    fn bad_request<E>(error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn bad_request(error: &ActiveStorageError) -> Self {
         Self::new(StatusCode::BAD_REQUEST, error)
     }
 
     /// Return a 401 unauthorised ErrorResponse
-    fn unauthorised<E>(error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn unauthorised(error: &ActiveStorageError) -> Self {
         Self::new(StatusCode::UNAUTHORIZED, error)
     }
 
+    /// Return a 403 forbidden ErrorResponse
+    fn forbidden(error: &ActiveStorageError) -> Self {
+        Self::new(StatusCode::FORBIDDEN, error)
+    }
+
     /// Return a 404 not found ErrorResponse
-    fn not_found<E>(error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn not_found(error: &ActiveStorageError) -> Self {
         Self::new(StatusCode::NOT_FOUND, error)
     }
 
+    /// Return a 413 payload too large ErrorResponse
+    fn payload_too_large(error: &ActiveStorageError) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, error)
+    }
+
     /// Return a 500 internal server error ErrorResponse
-    fn internal_server_error<E>(error: &E) -> Self
-    where
-        E: std::error::Error + Send + Sync,
-    {
+    fn internal_server_error(error: &ActiveStorageError) -> Self {
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, error)
     }
+
+    /// Return a 502 bad gateway ErrorResponse
+    fn bad_gateway(error: &ActiveStorageError) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, error)
+    }
+
+    /// Return a 429 too many requests ErrorResponse with a Retry-After header
+    fn too_many_requests(error: &ActiveStorageError, retry_after: u64) -> Self {
+        ErrorResponse {
+            retry_after: Some(retry_after),
+            ..Self::new(StatusCode::TOO_MANY_REQUESTS, error)
+        }
+    }
+
+    /// Return a 503 service unavailable ErrorResponse with a Retry-After header
+    fn service_unavailable(error: &ActiveStorageError, retry_after: u64) -> Self {
+        ErrorResponse {
+            retry_after: Some(retry_after),
+            ..Self::new(StatusCode::SERVICE_UNAVAILABLE, error)
+        }
+    }
+}
+
+impl From<SdkError<GetObjectError>> for ActiveStorageError {
+    fn from(error: SdkError<GetObjectError>) -> Self {
+        ActiveStorageError::S3GetObject(Box::new(error))
+    }
+}
+
+impl From<SdkError<HeadObjectError>> for ActiveStorageError {
+    fn from(error: SdkError<HeadObjectError>) -> Self {
+        ActiveStorageError::S3HeadObject(Box::new(error))
+    }
 }
 
 impl From<ActiveStorageError> for ErrorResponse {
@@ -206,58 +592,145 @@ impl From<ActiveStorageError> for ErrorResponse {
     fn from(error: ActiveStorageError) -> Self {
         let response = match &error {
             // Bad request
-            ActiveStorageError::DecompressionFlate2(_)
+            ActiveStorageError::AxisUnsupported { operation: _ }
+            | ActiveStorageError::CastOverflow { dtype: _ }
+            | ActiveStorageError::DecompressedSizeExceeded { limit: _ }
+            | ActiveStorageError::DecompressionBrotli(_)
+            | ActiveStorageError::DecompressionFlate2(_)
+            | ActiveStorageError::DecompressionSnappy(_)
             | ActiveStorageError::DecompressionZune(_)
             | ActiveStorageError::EmptyArray { operation: _ }
+            | ActiveStorageError::HttpContentLengthMissing
+            | ActiveStorageError::HttpRangeNotSupported
             | ActiveStorageError::IncompatibleMissing(_)
             | ActiveStorageError::InsufficientMemory {
                 requested: _,
                 total: _,
             }
+            | ActiveStorageError::NonFiniteValue {
+                operation: _,
+                index: _,
+            }
+            | ActiveStorageError::NonPositiveValue { operation: _ }
+            | ActiveStorageError::ObjectRangeOutOfBounds {
+                range: _,
+                object_size: _,
+            }
+            | ActiveStorageError::PartRangeMismatch {
+                part_number: _,
+                part_range: _,
+                range: _,
+            }
             | ActiveStorageError::RequestDataJsonRejection(_)
             | ActiveStorageError::RequestDataValidationSingle(_)
             | ActiveStorageError::RequestDataValidation(_)
             | ActiveStorageError::S3ContentLengthMissing
-            | ActiveStorageError::ShapeInvalid(_) => Self::bad_request(&error),
+            | ActiveStorageError::ShapeInvalid(_)
+            | ActiveStorageError::ShardInvalid(_)
+            | ActiveStorageError::UnsupportedDType {
+                operation: _,
+                dtype: _,
+            } => Self::bad_request(&error),
+
+            // Forbidden
+            ActiveStorageError::BucketNotAllowed { bucket: _ }
+            | ActiveStorageError::ObjectNotAllowed { object: _ }
+            | ActiveStorageError::SourceNotAllowed { url: _ } => Self::forbidden(&error),
 
             // Not found
-            ActiveStorageError::UnsupportedOperation { operation: _ } => Self::not_found(&error),
+            ActiveStorageError::SessionNotFound
+            | ActiveStorageError::UnsupportedOperation { operation: _ } => Self::not_found(&error),
+
+            // Payload too large
+            ActiveStorageError::RequestBodyTooLarge(_) => Self::payload_too_large(&error),
 
             // Internal server error
             ActiveStorageError::FromBytes { type_name: _ }
             | ActiveStorageError::TryFromInt(_)
-            | ActiveStorageError::S3ByteStream(_)
             | ActiveStorageError::SemaphoreAcquireError(_) => Self::internal_server_error(&error),
 
+            // Bad gateway
+            ActiveStorageError::ChecksumMismatch
+            | ActiveStorageError::S3ByteStream(_)
+            | ActiveStorageError::ShortRead {
+                expected: _,
+                actual: _,
+            } => Self::bad_gateway(&error),
+
+            // Too many requests
+            ActiveStorageError::RateLimited { retry_after } => {
+                Self::too_many_requests(&error, *retry_after)
+            }
+
+            // Service unavailable
+            ActiveStorageError::Overloaded { retry_after }
+            | ActiveStorageError::ResourceAcquireTimeout { retry_after } => {
+                Self::service_unavailable(&error, *retry_after)
+            }
+
+            ActiveStorageError::HttpRequest(http_error) => {
+                // Mirror the S3 error mapping using the HTTP status code, if any.
+                match http_error.status().map(|status| status.as_u16()) {
+                    // Not found
+                    Some(404) => Self::not_found(&error),
+
+                    // Unauthorised
+                    Some(401) => Self::unauthorised(&error),
+
+                    // Forbidden
+                    Some(403) => Self::forbidden(&error),
+
+                    // Bad gateway
+                    Some(500..=599) => Self::bad_gateway(&error),
+                    None if !http_error.is_builder() => Self::bad_gateway(&error),
+
+                    // Internal server error
+                    _ => Self::internal_server_error(&error),
+                }
+            }
+
             ActiveStorageError::S3GetObject(sdk_error) => {
                 // Tailor the response based on the specific SdkError variant.
-                match &sdk_error {
+                match sdk_error.as_ref() {
                     // These are generic SdkError variants.
                     // Internal server error
-                    SdkError::ConstructionFailure(_)
-                    | SdkError::DispatchFailure(_)
+                    SdkError::ConstructionFailure(_) => Self::internal_server_error(&error),
+
+                    // Bad gateway
+                    SdkError::DispatchFailure(_)
                     | SdkError::ResponseError(_)
-                    | SdkError::TimeoutError(_) => Self::internal_server_error(&error),
+                    | SdkError::TimeoutError(_) => Self::bad_gateway(&error),
 
                     // This is a more specific ServiceError variant, with GetObjectError as the
                     // inner error.
-                    SdkError::ServiceError(get_obj_error) => {
-                        let get_obj_error = get_obj_error.err();
+                    SdkError::ServiceError(service_error) => {
+                        let get_obj_error = service_error.err();
                         match get_obj_error {
-                            GetObjectError::InvalidObjectState(_)
-                            | GetObjectError::NoSuchKey(_) => Self::bad_request(&error),
+                            // Bad request
+                            GetObjectError::InvalidObjectState(_) => Self::bad_request(&error),
+
+                            // Not found
+                            GetObjectError::NoSuchKey(_) => Self::not_found(&error),
 
                             // Quite a lot of error cases end up as unhandled. Attempt to determine
                             // the error from the code.
                             _ => {
                                 match get_obj_error.code() {
-                                    // Bad request
-                                    Some("NoSuchBucket") => Self::bad_request(&error),
+                                    // Not found
+                                    Some("NoSuchBucket") => Self::not_found(&error),
 
                                     // Unauthorised
-                                    Some("InvalidAccessKeyId")
-                                    | Some("SignatureDoesNotMatch")
-                                    | Some("AccessDenied") => Self::unauthorised(&error),
+                                    Some("InvalidAccessKeyId") | Some("SignatureDoesNotMatch") => {
+                                        Self::unauthorised(&error)
+                                    }
+
+                                    // Forbidden
+                                    Some("AccessDenied") => Self::forbidden(&error),
+
+                                    // Bad gateway
+                                    _ if service_error.raw().status().is_server_error() => {
+                                        Self::bad_gateway(&error)
+                                    }
 
                                     // Internal server error
                                     _ => Self::internal_server_error(&error),
@@ -270,6 +743,38 @@ impl From<ActiveStorageError> for ErrorResponse {
                     _ => Self::internal_server_error(&error),
                 }
             }
+            ActiveStorageError::S3HeadObject(sdk_error) => {
+                match sdk_error.as_ref() {
+                    // HEAD responses have no body, so the error code is typically unavailable.
+                    // Use the HTTP status code instead.
+                    SdkError::ServiceError(head_obj_error) => {
+                        match (head_obj_error.err(), head_obj_error.raw().status().as_u16()) {
+                            // Not found
+                            (HeadObjectError::NotFound(_), _) => Self::not_found(&error),
+
+                            // Unauthorised
+                            (_, 401) => Self::unauthorised(&error),
+
+                            // Forbidden
+                            (_, 403) => Self::forbidden(&error),
+
+                            // Bad gateway
+                            (_, 500..=599) => Self::bad_gateway(&error),
+
+                            // Internal server error
+                            _ => Self::internal_server_error(&error),
+                        }
+                    }
+
+                    // Bad gateway
+                    SdkError::DispatchFailure(_)
+                    | SdkError::ResponseError(_)
+                    | SdkError::TimeoutError(_) => Self::bad_gateway(&error),
+
+                    // Internal server error
+                    _ => Self::internal_server_error(&error),
+                }
+            }
         };
 
         // Log server errors.
@@ -298,12 +803,20 @@ impl IntoResponse for ErrorResponse {
                 format!("Failed to serialise error response: {}", err),
             )
                 .into_response(),
-            Ok(json_body) => (
-                self.status,
-                [(&header::CONTENT_TYPE, mime::APPLICATION_JSON.to_string())],
-                json_body,
-            )
-                .into_response(),
+            Ok(json_body) => {
+                let mut response = (
+                    self.status,
+                    [(&header::CONTENT_TYPE, mime::APPLICATION_JSON.to_string())],
+                    json_body,
+                )
+                    .into_response();
+                if let Some(retry_after) = self.retry_after {
+                    response
+                        .headers_mut()
+                        .insert(header::RETRY_AFTER, retry_after.into());
+                }
+                response
+            }
         }
     }
 }
@@ -312,7 +825,8 @@ impl IntoResponse for ErrorResponse {
 mod tests {
     use super::*;
 
-    use aws_sdk_s3::types::error::NoSuchKey;
+    use aws_sdk_s3::types::error::{NoSuchKey, NotFound};
+    use aws_smithy_runtime_api::client::result::ConnectorError;
     use aws_smithy_runtime_api::http::Response as SmithyResponse;
     use aws_smithy_runtime_api::http::StatusCode as SmithyStatusCode;
     use aws_smithy_types::error::ErrorMetadata as SmithyError;
@@ -335,6 +849,7 @@ mod tests {
         message: &str,
         caused_by: Option<Vec<&'static str>>,
     ) {
+        let code = error.code();
         let response = error.into_response();
         assert_eq!(status, response.status());
         let mut headers = HeaderMap::new();
@@ -342,12 +857,138 @@ mod tests {
         assert_eq!(headers, *response.headers());
         let error_response: ErrorResponse =
             serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(code, error_response.error.code);
         assert_eq!(message.to_string(), error_response.error.message);
         // Map Vec items from str to String
         let caused_by = caused_by.map(|cb| cb.iter().map(|s| s.to_string()).collect());
         assert_eq!(caused_by, error_response.error.caused_by);
     }
 
+    #[tokio::test]
+    async fn object_range_out_of_bounds_error() {
+        let error = ActiveStorageError::ObjectRangeOutOfBounds {
+            range: "8-23".to_string(),
+            object_size: 16,
+        };
+        let message = "requested byte range 8-23 exceeds object size of 16 bytes";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn part_range_mismatch_error() {
+        let error = ActiveStorageError::PartRangeMismatch {
+            part_number: 2,
+            part_range: "16-31".to_string(),
+            range: "8-23".to_string(),
+        };
+        let message = "part 2 has byte range 16-31, not the requested range 8-23";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn overloaded_error() {
+        let error = ActiveStorageError::Overloaded { retry_after: 1 };
+        let response = error.into_response();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!("1", response.headers()[header::RETRY_AFTER]);
+        let error_response: ErrorResponse =
+            serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(ErrorCode::Overloaded, error_response.error.code);
+        assert_eq!(
+            "server is overloaded, retry after 1 seconds",
+            error_response.error.message
+        );
+        assert_eq!(None, error_response.error.caused_by);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_error() {
+        let error = ActiveStorageError::RateLimited { retry_after: 1 };
+        let response = error.into_response();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!("1", response.headers()[header::RETRY_AFTER]);
+        let error_response: ErrorResponse =
+            serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(
+            "request rate limit exceeded for source, retry after 1 seconds",
+            error_response.error.message
+        );
+        assert_eq!(None, error_response.error.caused_by);
+    }
+
+    #[tokio::test]
+    async fn resource_acquire_timeout_error() {
+        let error = ActiveStorageError::ResourceAcquireTimeout { retry_after: 2 };
+        let response = error.into_response();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!("2", response.headers()[header::RETRY_AFTER]);
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        let error_response: ErrorResponse =
+            serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(
+            "timed out waiting for resources, retry after 2 seconds",
+            error_response.error.message
+        );
+        assert_eq!(None, error_response.error.caused_by);
+    }
+
+    #[tokio::test]
+    async fn axis_unsupported_error() {
+        let error = ActiveStorageError::AxisUnsupported { operation: "foo" };
+        let message = "cannot perform foo along an axis";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn bucket_not_allowed_error() {
+        let error = ActiveStorageError::BucketNotAllowed {
+            bucket: "foo".to_string(),
+        };
+        let message = "access to bucket \"foo\" is not allowed";
+        test_active_storage_error(error, StatusCode::FORBIDDEN, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn object_not_allowed_error() {
+        let error = ActiveStorageError::ObjectNotAllowed {
+            object: "foo".to_string(),
+        };
+        let message = "access to object \"foo\" is not allowed";
+        test_active_storage_error(error, StatusCode::FORBIDDEN, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn cast_overflow_error() {
+        let error = ActiveStorageError::CastOverflow {
+            dtype: "int32".to_string(),
+        };
+        let message = "selected element is out of range of output dtype int32";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_error() {
+        let error = ActiveStorageError::ChecksumMismatch;
+        let message = "object data does not match its checksum";
+        test_active_storage_error(error, StatusCode::BAD_GATEWAY, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn decompressed_size_exceeded_error() {
+        let error = ActiveStorageError::DecompressedSizeExceeded { limit: 1024 };
+        let message = "decompressed data exceeds maximum size of 1024 bytes";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn decompression_brotli_error() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::InvalidData, "brotli error");
+        let error = ActiveStorageError::DecompressionBrotli(io_error);
+        let message = "failed to decompress data";
+        let caused_by = Some(vec!["brotli error"]);
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
+    }
+
     #[tokio::test]
     async fn decompression_flate2_error() {
         let io_error = std::io::Error::new(std::io::ErrorKind::InvalidInput, "decompression error");
@@ -357,6 +998,14 @@ mod tests {
         test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
     }
 
+    #[tokio::test]
+    async fn decompression_snappy_error() {
+        let error = ActiveStorageError::DecompressionSnappy(snap::Error::Empty.into());
+        let message = "failed to decompress data";
+        let caused_by = Some(vec!["snappy: corrupt input (empty)"]);
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
+    }
+
     #[tokio::test]
     async fn decompression_zune_error() {
         let zune_error = InflateDecodeErrors::new_with_error(
@@ -385,6 +1034,62 @@ mod tests {
             .await;
     }
 
+    #[tokio::test]
+    async fn http_content_length_missing() {
+        let error = ActiveStorageError::HttpContentLengthMissing;
+        let message = "HTTP response missing Content-Length header";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn http_range_not_supported() {
+        let error = ActiveStorageError::HttpRangeNotSupported;
+        let message = "HTTP server does not support byte range requests";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    // Helper function for HTTP request errors
+    async fn test_http_request_error(
+        http_status: StatusCode,
+        status: StatusCode,
+        caused_by: Vec<&'static str>,
+    ) {
+        let response = axum::http::Response::builder()
+            .status(http_status)
+            .body(String::new())
+            .unwrap();
+        let http_error = reqwest::Response::from(response)
+            .error_for_status()
+            .unwrap_err();
+        let error = ActiveStorageError::HttpRequest(http_error);
+        let message = "error retrieving object from HTTP server";
+        test_active_storage_error(error, status, message, Some(caused_by)).await;
+    }
+
+    #[tokio::test]
+    async fn http_request_not_found_error() {
+        let caused_by = vec![
+            "HTTP status client error (404 Not Found) for url (http://no.url.provided.local/)",
+        ];
+        test_http_request_error(StatusCode::NOT_FOUND, StatusCode::NOT_FOUND, caused_by).await;
+    }
+
+    #[tokio::test]
+    async fn http_request_forbidden_error() {
+        let caused_by = vec![
+            "HTTP status client error (403 Forbidden) for url (http://no.url.provided.local/)",
+        ];
+        test_http_request_error(StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, caused_by).await;
+    }
+
+    #[tokio::test]
+    async fn http_request_server_error() {
+        let caused_by = vec![
+            "HTTP status server error (502 Bad Gateway) for url (http://no.url.provided.local/)",
+        ];
+        test_http_request_error(StatusCode::BAD_GATEWAY, StatusCode::BAD_GATEWAY, caused_by).await;
+    }
+
     #[tokio::test]
     async fn incompatible_missing() {
         let value = 32.into();
@@ -405,6 +1110,25 @@ mod tests {
         test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
     }
 
+    #[tokio::test]
+    async fn non_finite_value() {
+        let error = ActiveStorageError::NonFiniteValue {
+            operation: "foo",
+            index: vec![1, 2],
+        };
+        let message = "cannot perform foo on non-finite value at index [1, 2]";
+        let caused_by = None;
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
+    }
+
+    #[tokio::test]
+    async fn non_positive_value() {
+        let error = ActiveStorageError::NonPositiveValue { operation: "foo" };
+        let message = "cannot perform foo on non-positive values";
+        let caused_by = None;
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
+    }
+
     #[tokio::test]
     async fn request_data_validation_single() {
         let validation_error = validator::ValidationError::new("foo");
@@ -436,9 +1160,11 @@ mod tests {
     async fn test_s3_get_object_error(
         sdk_error: SdkError<GetObjectError>,
         status: StatusCode,
+        code: ErrorCode,
         caused_by: Option<Vec<&'static str>>,
     ) {
-        let error = ActiveStorageError::S3GetObject(sdk_error);
+        let error = ActiveStorageError::from(sdk_error);
+        assert_eq!(code, error.code());
         let message = "error retrieving object from S3 storage";
         test_active_storage_error(error, status, message, caused_by).await;
     }
@@ -456,7 +1182,13 @@ mod tests {
         let get_object_error = GetObjectError::NoSuchKey(no_such_key);
         let sdk_error = SdkError::service_error(get_object_error, get_smithy_response());
         let caused_by = Some(vec!["service error", "NoSuchKey"]);
-        test_s3_get_object_error(sdk_error, StatusCode::BAD_REQUEST, caused_by).await;
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::NOT_FOUND,
+            ErrorCode::ObjectNotFound,
+            caused_by,
+        )
+        .await;
     }
 
     #[tokio::test]
@@ -473,7 +1205,13 @@ mod tests {
             "unhandled error (InvalidAccessKeyId)",
             "Error { code: \"InvalidAccessKeyId\", message: \"fake smithy error\" }",
         ]);
-        test_s3_get_object_error(sdk_error, StatusCode::UNAUTHORIZED, caused_by).await;
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::AccessDenied,
+            caused_by,
+        )
+        .await;
     }
 
     #[tokio::test]
@@ -490,7 +1228,13 @@ mod tests {
             "unhandled error (NoSuchBucket)",
             "Error { code: \"NoSuchBucket\", message: \"fake smithy error\" }",
         ]);
-        test_s3_get_object_error(sdk_error, StatusCode::BAD_REQUEST, caused_by).await;
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::NOT_FOUND,
+            ErrorCode::BucketNotFound,
+            caused_by,
+        )
+        .await;
     }
 
     #[tokio::test]
@@ -507,7 +1251,13 @@ mod tests {
             "unhandled error (SignatureDoesNotMatch)",
             "Error { code: \"SignatureDoesNotMatch\", message: \"fake smithy error\" }",
         ]);
-        test_s3_get_object_error(sdk_error, StatusCode::UNAUTHORIZED, caused_by).await;
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::AccessDenied,
+            caused_by,
+        )
+        .await;
     }
 
     #[tokio::test]
@@ -524,7 +1274,99 @@ mod tests {
             "unhandled error (AccessDenied)",
             "Error { code: \"AccessDenied\", message: \"fake smithy error\" }",
         ]);
-        test_s3_get_object_error(sdk_error, StatusCode::UNAUTHORIZED, caused_by).await;
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::FORBIDDEN,
+            ErrorCode::AccessDenied,
+            caused_by,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn s3_get_object_dispatch_failure() {
+        let connector_error = ConnectorError::io("connection refused".into());
+        let sdk_error = SdkError::dispatch_failure(connector_error);
+        let caused_by = Some(vec!["dispatch failure", "io error", "connection refused"]);
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::BAD_GATEWAY,
+            ErrorCode::ObjectStoreError,
+            caused_by,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn s3_get_object_server_error() {
+        let smithy_error = SmithyError::builder()
+            .message("fake smithy error")
+            .code("NotImplemented")
+            .build();
+        let get_object_error = GetObjectError::generic(smithy_error);
+        let status: SmithyStatusCode = 501.try_into().unwrap();
+        let smithy_response = SmithyResponse::new(status, "".into());
+        let sdk_error = SdkError::service_error(get_object_error, smithy_response);
+        let caused_by = Some(vec![
+            "service error",
+            "unhandled error (NotImplemented)",
+            "Error { code: \"NotImplemented\", message: \"fake smithy error\" }",
+        ]);
+        test_s3_get_object_error(
+            sdk_error,
+            StatusCode::BAD_GATEWAY,
+            ErrorCode::ObjectStoreError,
+            caused_by,
+        )
+        .await;
+    }
+
+    // Helper function for S3 HeadObjectError errors
+    async fn test_s3_head_object_error(
+        sdk_error: SdkError<HeadObjectError>,
+        status: StatusCode,
+        code: ErrorCode,
+        caused_by: Option<Vec<&'static str>>,
+    ) {
+        let error = ActiveStorageError::from(sdk_error);
+        assert_eq!(code, error.code());
+        let message = "error retrieving object metadata from S3 storage";
+        test_active_storage_error(error, status, message, caused_by).await;
+    }
+
+    #[tokio::test]
+    async fn s3_head_object_not_found_error() {
+        let head_object_error = HeadObjectError::NotFound(NotFound::builder().build());
+        let sdk_error = SdkError::service_error(head_object_error, get_smithy_response());
+        let caused_by = Some(vec!["service error", "NotFound"]);
+        test_s3_head_object_error(
+            sdk_error,
+            StatusCode::NOT_FOUND,
+            ErrorCode::ObjectNotFound,
+            caused_by,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn s3_head_object_forbidden_error() {
+        let smithy_error = SmithyError::builder().message("fake smithy error").build();
+        let head_object_error = HeadObjectError::generic(smithy_error);
+        let status: SmithyStatusCode = 403.try_into().unwrap();
+        let smithy_response = SmithyResponse::new(status, "".into());
+        let sdk_error = SdkError::service_error(head_object_error, smithy_response);
+        let caused_by = Some(vec![
+            "service error",
+            "unhandled error",
+            "Error { message: \"fake smithy error\" }",
+        ]);
+        test_s3_head_object_error(
+            sdk_error,
+            StatusCode::FORBIDDEN,
+            ErrorCode::AccessDenied,
+            caused_by,
+        )
+        .await;
     }
 
     #[tokio::test]
@@ -535,8 +1377,7 @@ mod tests {
         );
         let message = "error receiving object from S3 storage";
         let caused_by = Some(vec!["IO error", "unexpected end of file"]);
-        test_active_storage_error(error, StatusCode::INTERNAL_SERVER_ERROR, message, caused_by)
-            .await;
+        test_active_storage_error(error, StatusCode::BAD_GATEWAY, message, caused_by).await;
     }
 
     #[tokio::test]
@@ -560,6 +1401,32 @@ mod tests {
         test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
     }
 
+    #[tokio::test]
+    async fn shard_invalid_error() {
+        let error = ActiveStorageError::ShardInvalid("inner chunk 1 is empty".to_string());
+        let message = "invalid Zarr shard: inner chunk 1 is empty";
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn short_read_error() {
+        let error = ActiveStorageError::ShortRead {
+            expected: 1024,
+            actual: 512,
+        };
+        let message = "received 512 of 1024 bytes of object data";
+        test_active_storage_error(error, StatusCode::BAD_GATEWAY, message, None).await;
+    }
+
+    #[tokio::test]
+    async fn source_not_allowed_error() {
+        let error = ActiveStorageError::SourceNotAllowed {
+            url: "http://127.0.0.1/".to_string(),
+        };
+        let message = "access to source http://127.0.0.1/ is not allowed";
+        test_active_storage_error(error, StatusCode::FORBIDDEN, message, None).await;
+    }
+
     #[tokio::test]
     async fn try_from_int_error() {
         let error = ActiveStorageError::TryFromInt(u8::try_from(-1_i8).unwrap_err());
@@ -569,6 +1436,25 @@ mod tests {
             .await;
     }
 
+    #[tokio::test]
+    async fn unsupported_dtype() {
+        let error = ActiveStorageError::UnsupportedDType {
+            operation: "foo",
+            dtype: "float32".to_string(),
+        };
+        let message = "cannot perform foo on float32 data";
+        let caused_by = None;
+        test_active_storage_error(error, StatusCode::BAD_REQUEST, message, caused_by).await;
+    }
+
+    #[tokio::test]
+    async fn session_not_found() {
+        let error = ActiveStorageError::SessionNotFound;
+        let message = "session not found";
+        let caused_by = None;
+        test_active_storage_error(error, StatusCode::NOT_FOUND, message, caused_by).await;
+    }
+
     #[tokio::test]
     async fn unsupported_operation() {
         let error = ActiveStorageError::UnsupportedOperation {
@@ -578,4 +1464,75 @@ mod tests {
         let caused_by = None;
         test_active_storage_error(error, StatusCode::NOT_FOUND, message, caused_by).await;
     }
+
+    // Helper function that returns the JSON body of the response for an error.
+    async fn error_json(error: ActiveStorageError) -> serde_json::Value {
+        serde_json::from_str(&body_string(error.into_response()).await).unwrap()
+    }
+
+    #[tokio::test]
+    async fn error_json_s3_not_found() {
+        let no_such_key = NoSuchKey::builder().build();
+        let get_object_error = GetObjectError::NoSuchKey(no_such_key);
+        let sdk_error = SdkError::service_error(get_object_error, get_smithy_response());
+        let error = ActiveStorageError::from(sdk_error);
+        let expected = json!({
+            "error": {
+                "code": "object_not_found",
+                "message": "error retrieving object from S3 storage",
+                "caused_by": ["service error", "NoSuchKey"]
+            }
+        });
+        assert_eq!(expected, error_json(error).await);
+    }
+
+    #[tokio::test]
+    async fn error_to_json() {
+        // The JSON of an error is the body of its error response.
+        let error = ActiveStorageError::EmptyArray { operation: "foo" };
+        let expected = error_json(ActiveStorageError::EmptyArray { operation: "foo" }).await;
+        assert_eq!(expected["error"], error.to_json());
+    }
+
+    #[tokio::test]
+    async fn error_json_bad_request() {
+        let error = ActiveStorageError::ObjectRangeOutOfBounds {
+            range: "8-23".to_string(),
+            object_size: 16,
+        };
+        let expected = json!({
+            "error": {
+                "code": "object_range_out_of_bounds",
+                "message": "requested byte range 8-23 exceeds object size of 16 bytes",
+                "detail": {"range": "8-23", "object_size": 16}
+            }
+        });
+        assert_eq!(expected, error_json(error).await);
+        let error =
+            ActiveStorageError::RequestDataValidationSingle(validator::ValidationError::new("foo"));
+        let expected = json!({
+            "error": {
+                "code": "invalid_request",
+                "message": "request data is not valid",
+                "detail": {"code": "foo", "message": null, "params": {}},
+                "caused_by": ["Validation error: foo [{}]"]
+            }
+        });
+        assert_eq!(expected, error_json(error).await);
+    }
+
+    #[tokio::test]
+    async fn error_json_unsupported_operation() {
+        let error = ActiveStorageError::UnsupportedOperation {
+            operation: "foo".to_string(),
+        };
+        let expected = json!({
+            "error": {
+                "code": "unsupported_operation",
+                "message": "unsupported operation foo",
+                "detail": {"operation": "foo"}
+            }
+        });
+        assert_eq!(expected, error_json(error).await);
+    }
 }
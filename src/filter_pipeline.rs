@@ -15,31 +15,52 @@ use axum::body::Bytes;
 ///
 /// * `request_data`: RequestData object for the request
 /// * `data`: Data [Bytes](axum::body::Bytes) to apply the pipeline to.
+/// * `max_decompressed_size`: Optional maximum size in bytes of decompressed data
 #[tracing::instrument(skip(request_data, data))]
 pub fn filter_pipeline(
     request_data: &models::RequestData,
     mut data: Bytes,
+    max_decompressed_size: Option<usize>,
 ) -> Result<Bytes, ActiveStorageError> {
     // First decompress.
     if let Some(compression) = request_data.compression {
-        data = compression::decompress(compression, &data)?
+        data = compression::decompress(
+            compression,
+            &data,
+            raw_size(request_data),
+            max_decompressed_size,
+        )?
     };
     // Then decode the filters in reverse order.
     if let Some(filters) = &request_data.filters {
         for filter in filters.iter().rev() {
-            data = filters::decode(filter, &data)?
+            data = filters::decode(filter, &data, request_data)?
         }
     };
     Ok(data)
 }
 
+/// Returns the expected size in bytes of the decompressed data, if it can be derived from the
+/// layout shape and stored data type of the request.
+///
+/// Only the FixedScaleOffset, Bitpacked and PackBits filters change the size of the data.
+fn raw_size(request_data: &models::RequestData) -> Option<usize> {
+    request_data
+        .layout_shape()
+        .as_ref()
+        .map(|shape| request_data.stored_size(shape.iter().product()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::operation::Operation;
+    use crate::operations;
     use crate::test_utils;
-    use flate2::read::{GzEncoder, ZlibEncoder};
+    use flate2::read::{DeflateEncoder, GzEncoder, ZlibEncoder};
     use flate2::Compression;
     use std::io::Read;
+    use zerocopy::AsBytes;
 
     fn compress_gzip(data: &[u8]) -> Bytes {
         // Adapated from flate2 documentation.
@@ -49,6 +70,13 @@ mod tests {
         result.into()
     }
 
+    fn compress_brotli(data: &[u8]) -> Bytes {
+        let mut result = Vec::<u8>::new();
+        let mut compressor = brotli::CompressorReader::new(data, 4096, 5, 22);
+        compressor.read_to_end(&mut result).unwrap();
+        result.into()
+    }
+
     fn compress_zlib(data: &[u8]) -> Bytes {
         // Adapated from flate2 documentation.
         let mut result = Vec::<u8>::new();
@@ -62,7 +90,7 @@ mod tests {
         let data = [1, 2, 3, 4];
         let bytes = Bytes::copy_from_slice(&data);
         let request_data = test_utils::get_test_request_data();
-        let result = filter_pipeline(&request_data, bytes).unwrap();
+        let result = filter_pipeline(&request_data, bytes, None).unwrap();
         assert_eq!(data.as_ref(), result);
     }
 
@@ -72,18 +100,125 @@ mod tests {
         let bytes = compress_gzip(data.as_ref());
         let mut request_data = test_utils::get_test_request_data();
         request_data.compression = Some(models::Compression::Gzip);
-        let result = filter_pipeline(&request_data, bytes).unwrap();
+        let result = filter_pipeline(&request_data, bytes, None).unwrap();
         assert_eq!(data.as_ref(), result);
     }
 
+    #[test]
+    fn test_filter_pipeline_gzip_multi_member() {
+        let first = [1_u32, 2, 3];
+        let second = [4_u32, 5];
+        let mut bytes = compress_gzip(first.as_bytes()).to_vec();
+        bytes.extend_from_slice(&compress_gzip(second.as_bytes()));
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.compression = Some(models::Compression::Gzip);
+        let data = filter_pipeline(&request_data, bytes.into(), None).unwrap();
+        assert_eq!(20, data.len());
+        let response = operations::Sum::execute(&request_data, data.into()).unwrap();
+        assert_eq!(15_u32.as_bytes(), response.body);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn test_filter_pipeline_deflate_sum() {
+        let data: Vec<i64> = (0..100).collect();
+        let mut bytes = Vec::<u8>::new();
+        DeflateEncoder::new(data.as_bytes(), Compression::fast())
+            .read_to_end(&mut bytes)
+            .unwrap();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.compression = Some(models::Compression::Deflate);
+        let data = filter_pipeline(&request_data, bytes.into(), None).unwrap();
+        assert_eq!(800, data.len());
+        let response = operations::Sum::execute(&request_data, data.into()).unwrap();
+        assert_eq!(4950_i64.as_bytes(), response.body);
+        assert_eq!(100, response.count);
+    }
+
+    #[test]
+    fn test_filter_pipeline_brotli() {
+        let data = [1, 2, 3, 4];
+        let bytes = compress_brotli(data.as_ref());
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.compression = Some(models::Compression::Brotli);
+        let result = filter_pipeline(&request_data, bytes, None).unwrap();
+        assert_eq!(data.as_ref(), result);
+    }
+
+    #[test]
+    fn test_filter_pipeline_snappy() {
+        let data = [1, 2, 3, 4];
+        let raw = snap::raw::Encoder::new().compress_vec(&data).unwrap();
+        let mut framed = Vec::new();
+        snap::read::FrameEncoder::new(data.as_ref())
+            .read_to_end(&mut framed)
+            .unwrap();
+        let mut request_data = test_utils::get_test_request_data();
+        for (framed, bytes) in [(false, raw), (true, framed)] {
+            request_data.compression = Some(models::Compression::Snappy { framed });
+            let result = filter_pipeline(&request_data, bytes.into(), None).unwrap();
+            assert_eq!(data.as_ref(), result);
+        }
+    }
+
+    #[test]
+    fn test_filter_pipeline_brotli_f64_min() {
+        let floats: Vec<f64> = (0..64).map(|i| 100.0 - f64::from(i) * 0.5).collect();
+        let bytes = compress_brotli(floats.as_bytes());
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![8, 8]);
+        request_data.compression = Some(models::Compression::Brotli);
+        let data = filter_pipeline(&request_data, bytes, None).unwrap();
+        assert_eq!(floats.as_bytes(), data.as_ref());
+        assert_eq!(data.as_ptr().align_offset(8), 0);
+        let response = operations::Min::execute(&request_data, data.into()).unwrap();
+        let expected = 68.5_f64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(64, response.count);
+    }
+
+    #[test]
+    fn test_filter_pipeline_raw_size() {
+        let mut request_data = test_utils::get_test_request_data();
+        assert_eq!(None, raw_size(&request_data));
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![2, 3]);
+        assert_eq!(Some(48), raw_size(&request_data));
+        request_data.filters = Some(vec![models::Filter::Bitpacked]);
+        assert_eq!(Some(1), raw_size(&request_data));
+        request_data.filters = Some(vec![models::Filter::PackBits]);
+        assert_eq!(Some(2), raw_size(&request_data));
+    }
+
+    #[test]
+    fn test_filter_pipeline_packbits_zlib() {
+        // numcodecs.PackBits().encode(np.arange(12) % 3 == 0)
+        let bytes = compress_zlib(&[0x04, 0b1001_0010, 0b0100_0000]);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![12]);
+        request_data.compression = Some(models::Compression::Zlib);
+        request_data.filters = Some(vec![models::Filter::PackBits]);
+        let data = filter_pipeline(&request_data, bytes, None).unwrap();
+        assert_eq!(48, data.len());
+        let response = operations::Sum::execute(&request_data, data.into()).unwrap();
+        assert_eq!(4_i32.as_bytes(), response.body);
+        assert_eq!(12, response.count);
+    }
+
     #[test]
     fn test_filter_pipeline_shuffle() {
         let data = [1, 2, 3, 4, 5, 6, 7, 8];
         let bytes = Bytes::copy_from_slice(&data);
         let shuffled = filters::shuffle::test_utils::shuffle(&bytes, 4);
         let mut request_data = test_utils::get_test_request_data();
-        request_data.filters = Some(vec![models::Filter::Shuffle { element_size: 4 }]);
-        let result = filter_pipeline(&request_data, shuffled).unwrap();
+        request_data.filters = Some(vec![models::Filter::Shuffle {
+            element_size: Some(4),
+        }]);
+        let result = filter_pipeline(&request_data, shuffled, None).unwrap();
         assert_eq!(data.as_ref(), result);
     }
 
@@ -95,8 +230,10 @@ mod tests {
         let bytes = compress_zlib(shuffled.as_ref());
         let mut request_data = test_utils::get_test_request_data();
         request_data.compression = Some(models::Compression::Zlib);
-        request_data.filters = Some(vec![models::Filter::Shuffle { element_size: 4 }]);
-        let result = filter_pipeline(&request_data, bytes).unwrap();
+        request_data.filters = Some(vec![models::Filter::Shuffle {
+            element_size: Some(4),
+        }]);
+        let result = filter_pipeline(&request_data, bytes, None).unwrap();
         assert_eq!(data.as_ref(), result.as_ref());
     }
 
@@ -112,10 +249,50 @@ mod tests {
         let mut request_data = test_utils::get_test_request_data();
         request_data.compression = Some(models::Compression::Zlib);
         request_data.filters = Some(vec![
-            models::Filter::Shuffle { element_size: 4 },
-            models::Filter::Shuffle { element_size: 2 },
+            models::Filter::Shuffle {
+                element_size: Some(4),
+            },
+            models::Filter::Shuffle {
+                element_size: Some(2),
+            },
         ]);
-        let result = filter_pipeline(&request_data, bytes).unwrap();
+        let result = filter_pipeline(&request_data, bytes, None).unwrap();
         assert_eq!(data.as_ref(), result.as_ref());
     }
+
+    #[test]
+    fn test_filter_pipeline_fixed_scale_offset_shuffle_zlib_big_endian() {
+        // Big endian integers packed with a scale and offset, shuffled then compressed, as
+        // written by netCDF4.
+        let floats = [271.5_f32, 272.25, 273.0, 270.75, 269.5, 274.0];
+        let packed: Vec<u8> = floats
+            .iter()
+            .flat_map(|f| (((f - 270.0) * 4.0).round() as i32).to_be_bytes())
+            .collect();
+        let shuffled = filters::shuffle::test_utils::shuffle(&packed.into(), 4);
+        let bytes = compress_zlib(shuffled.as_ref());
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.byte_order = Some(crate::types::ByteOrder::Big);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.compression = Some(models::Compression::Zlib);
+        request_data.filters = Some(vec![
+            models::Filter::FixedScaleOffset {
+                offset: 270.0,
+                scale: 4.0,
+                astype: models::DType::Int32,
+            },
+            models::Filter::Shuffle {
+                element_size: Some(4),
+            },
+        ]);
+        assert_eq!(Some(24), raw_size(&request_data));
+        let data = filter_pipeline(&request_data, bytes, None).unwrap();
+        // The decoded data remains big endian until the operation converts it.
+        let expected: Vec<u8> = floats.iter().flat_map(|f| f.to_be_bytes()).collect();
+        assert_eq!(expected, data.as_ref());
+        let response = operations::Sum::execute(&request_data, data.into()).unwrap();
+        assert_eq!(1631.0_f32.as_bytes(), response.body);
+        assert_eq!(6, response.count);
+    }
 }
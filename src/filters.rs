@@ -1,5 +1,8 @@
 //! Filter implementations.
 
+pub mod bitpacked;
+pub mod fixed_scale_offset;
+pub mod packbits;
 pub mod shuffle;
 
 use crate::error::ActiveStorageError;
@@ -13,9 +16,27 @@ use axum::body::Bytes;
 ///
 /// * `filter`: Filter algorithm
 /// * `data`: Filtered data [Bytes]
-pub fn decode(filter: &models::Filter, data: &Bytes) -> Result<Bytes, ActiveStorageError> {
+/// * `request_data`: RequestData object for the request
+pub fn decode(
+    filter: &models::Filter,
+    data: &Bytes,
+    request_data: &models::RequestData,
+) -> Result<Bytes, ActiveStorageError> {
     match filter {
-        models::Filter::Shuffle { element_size } => Ok(shuffle::deshuffle(data, *element_size)),
+        models::Filter::Shuffle { element_size } => {
+            let element_size =
+                element_size.unwrap_or_else(|| request_data.stored_dtype().size_of());
+            Ok(shuffle::deshuffle(data, element_size))
+        }
+        // Quantization is lossy and decode-transparent.
+        models::Filter::Quantize { digits: _ } => Ok(data.clone()),
+        models::Filter::FixedScaleOffset {
+            offset,
+            scale,
+            astype,
+        } => fixed_scale_offset::decode(data, *offset, *scale, *astype, request_data),
+        models::Filter::Bitpacked => bitpacked::decode(data, request_data),
+        models::Filter::PackBits => packbits::decode(data, request_data),
     }
 }
 
@@ -23,14 +44,79 @@ pub fn decode(filter: &models::Filter, data: &Bytes) -> Result<Bytes, ActiveStor
 mod tests {
     use super::*;
     use crate::filters;
+    use crate::test_utils;
 
     #[test]
     fn test_decode_shuffle() {
         let data = [1, 2, 3, 4, 5, 6, 7, 8];
         let bytes = Bytes::copy_from_slice(&data);
         let shuffled = filters::shuffle::test_utils::shuffle(&bytes, 4);
-        let filter = models::Filter::Shuffle { element_size: 4 };
-        let result = decode(&filter, &shuffled).unwrap();
+        let filter = models::Filter::Shuffle {
+            element_size: Some(4),
+        };
+        let result = decode(&filter, &shuffled, &test_utils::get_test_request_data()).unwrap();
         assert_eq!(data.as_ref(), result);
     }
+
+    #[test]
+    fn test_decode_shuffle_default_element_size() {
+        let data: Vec<u8> = [1_i64, -2, 3]
+            .iter()
+            .flat_map(|i| i.to_ne_bytes())
+            .collect();
+        let shuffled = filters::shuffle::test_utils::shuffle(&data.clone().into(), 8);
+        let filter = models::Filter::Shuffle { element_size: None };
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        let result = decode(&filter, &shuffled, &request_data).unwrap();
+        assert_eq!(data, result);
+    }
+
+    #[test]
+    fn test_decode_quantize() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+        let bytes = Bytes::copy_from_slice(&data);
+        let filter = models::Filter::Quantize { digits: 2 };
+        let result = decode(&filter, &bytes, &test_utils::get_test_request_data()).unwrap();
+        assert_eq!(data.as_ref(), result);
+        // Decoding should not copy the data.
+        assert_eq!(bytes.as_ptr(), result.as_ptr());
+    }
+
+    #[test]
+    fn test_decode_fixed_scale_offset() {
+        let bytes: Bytes = [4_i32, -2].iter().flat_map(|i| i.to_ne_bytes()).collect();
+        let filter = models::Filter::FixedScaleOffset {
+            offset: 1.0,
+            scale: 2.0,
+            astype: models::DType::Int32,
+        };
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let result = decode(&filter, &bytes, &request_data).unwrap();
+        let expected: Vec<u8> = [3.0_f32, 0.0]
+            .iter()
+            .flat_map(|f| f.to_ne_bytes())
+            .collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_decode_bitpacked() {
+        let bytes = Bytes::from_static(&[0b1010_0000]);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![3]);
+        let result = decode(&models::Filter::Bitpacked, &bytes, &request_data).unwrap();
+        let expected: Vec<u8> = [1_i32, 0, 1].iter().flat_map(|i| i.to_ne_bytes()).collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_decode_packbits() {
+        let bytes = Bytes::from_static(&[0x05, 0b1010_0000]);
+        let request_data = test_utils::get_test_request_data();
+        let result = decode(&models::Filter::PackBits, &bytes, &request_data).unwrap();
+        let expected: Vec<u8> = [1_i32, 0, 1].iter().flat_map(|i| i.to_ne_bytes()).collect();
+        assert_eq!(expected, result);
+    }
 }
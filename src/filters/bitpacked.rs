@@ -0,0 +1,152 @@
+//! Bit-packed boolean filter.
+
+use crate::array;
+use crate::error::ActiveStorageError;
+use crate::models::{self, DType};
+
+use axum::body::Bytes;
+use num_traits::FromPrimitive;
+use validator::ValidationError;
+use zerocopy::{AsBytes, FromBytes};
+
+/// Decodes bit-packed boolean data and returns the result.
+///
+/// Each bit, most significant first, is decoded to an element of value 0 or 1 of the data type
+/// of the request, in the byte order of the request, so that the decoded data is converted to
+/// native byte order in the same way as unfiltered data. The number of elements is the product of
+/// the stored shape or shape, if specified, and any remaining padding bits are ignored. Otherwise every bit is
+/// decoded.
+///
+/// # Arguments
+///
+/// * `data`: Bit-packed data [Bytes]
+/// * `request_data`: RequestData object for the request
+pub fn decode(
+    data: &Bytes,
+    request_data: &models::RequestData,
+) -> Result<Bytes, ActiveStorageError> {
+    let elements = match request_data.layout_shape() {
+        Some(shape) => shape.iter().product(),
+        None => data.len() * 8,
+    };
+    if elements > data.len() * 8 {
+        let mut error = ValidationError::new("Bit-packed data is too small for the shape");
+        error.add_param("raw size".into(), &data.len());
+        error.add_param("elements".into(), &elements);
+        return Err(error.into());
+    }
+    Ok(unpack(data, elements, request_data))
+}
+
+/// Unpacks the first `elements` bits of bit-packed data, most significant first, to elements of
+/// value 0 or 1 of the data type of the request, in the byte order of the request.
+///
+/// # Arguments
+///
+/// * `data`: Bit-packed data, with at least `elements` bits
+/// * `elements`: Number of elements to unpack
+/// * `request_data`: RequestData object for the request
+pub fn unpack(data: &[u8], elements: usize, request_data: &models::RequestData) -> Bytes {
+    let bits = (0..elements).map(|i| (data[i / 8] >> (7 - i % 8)) & 1);
+    let native = array::is_native_byte_order(request_data);
+    match request_data.dtype {
+        DType::Int32 => write::<i32>(bits, native),
+        DType::Int64 => write::<i64>(bits, native),
+        DType::Uint32 => write::<u32>(bits, native),
+        DType::Uint64 => write::<u64>(bits, native),
+        DType::Float16 => write::<half::f16>(bits, native),
+        DType::Bfloat16 => write::<half::bf16>(bits, native),
+        DType::Float32 => write::<f32>(bits, native),
+        DType::Float64 => write::<f64>(bits, native),
+    }
+}
+
+/// Returns some bits converted to type `T`, in an 8-byte aligned buffer.
+fn write<T>(bits: impl ExactSizeIterator<Item = u8>, native: bool) -> Bytes
+where
+    T: AsBytes + FromBytes + FromPrimitive,
+{
+    // See S3Client::download_object for why the data is copied into an aligned buffer.
+    let mut buf = maligned::align_first::<u8, maligned::A8>(bits.len() * std::mem::size_of::<T>());
+    for bit in bits {
+        let value = T::from_u8(bit).expect("all data types convert from 0 and 1");
+        let value = if native {
+            value
+        } else {
+            array::reversed_byte_order(value)
+        };
+        buf.extend_from_slice(value.as_bytes());
+    }
+    buf.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::Operation;
+    use crate::operations;
+    use crate::test_utils;
+
+    // 13 elements, with 3 padding bits set to check that they are ignored.
+    const PACKED: [u8; 2] = [0b1101_0011, 0b0110_1111];
+    const BITS: [i32; 13] = [1, 1, 0, 1, 0, 0, 1, 1, 0, 1, 1, 0, 1];
+
+    #[test]
+    fn test_decode_i32() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![13]);
+        let result = decode(&Bytes::from_static(&PACKED), &request_data).unwrap();
+        assert_eq!(BITS.as_bytes(), result);
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decode_no_shape() {
+        let request_data = test_utils::get_test_request_data();
+        let result = decode(&Bytes::from_static(&PACKED[..1]), &request_data).unwrap();
+        assert_eq!(BITS[..8].as_bytes(), result);
+    }
+
+    #[test]
+    fn test_decode_f64_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.shape = Some(vec![3]);
+        let result = decode(&Bytes::from_static(&[0b0100_0000]), &request_data).unwrap();
+        // The decoded data remains in the byte order of the request.
+        let expected: Vec<u8> = [0.0_f64, 1.0, 0.0]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_decode_too_small() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![17]);
+        match decode(&Bytes::from_static(&PACKED), &request_data) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => {
+                assert_eq!("Bit-packed data is too small for the shape", error.code)
+            }
+            _ => panic!("expected decode to fail"),
+        }
+    }
+
+    #[test]
+    fn test_count_set_bits() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![13]);
+        request_data.filters = Some(vec![models::Filter::Bitpacked]);
+        let data = decode(&Bytes::from_static(&PACKED), &request_data).unwrap();
+        let response = operations::Sum::execute(&request_data, data.clone().into()).unwrap();
+        assert_eq!(8_i32.as_bytes(), response.body);
+        assert_eq!(13, response.count);
+        // Counting elements that are not missing, with unset bits as missing, also counts the set
+        // bits.
+        request_data.missing = Some(crate::types::Missing::MissingValue(0.into()));
+        let response = operations::Count::execute(&request_data, data.into()).unwrap();
+        assert_eq!(8_i64.as_bytes(), response.body);
+    }
+}
@@ -0,0 +1,164 @@
+//! Numcodecs FixedScaleOffset filter.
+
+use crate::array;
+use crate::error::ActiveStorageError;
+use crate::models::{self, DType};
+
+use axum::body::Bytes;
+use num_traits::{FromPrimitive, ToPrimitive};
+use zerocopy::{AsBytes, FromBytes};
+
+/// Decodes data encoded using the Numcodecs FixedScaleOffset filter and returns the result.
+///
+/// Each stored value `y` of type `astype` is decoded to `y / scale + offset` of the data type of
+/// the request. Stored and decoded values are both in the byte order of the request, so that the
+/// decoded data is converted to native byte order in the same way as unfiltered data.
+///
+/// # Arguments
+///
+/// * `data`: Encoded data [Bytes]
+/// * `offset`: Value subtracted from each element when encoding
+/// * `scale`: Value by which each element is multiplied when encoding
+/// * `astype`: Data type of the encoded data
+/// * `request_data`: RequestData object for the request
+pub fn decode(
+    data: &Bytes,
+    offset: f64,
+    scale: f64,
+    astype: DType,
+    request_data: &models::RequestData,
+) -> Result<Bytes, ActiveStorageError> {
+    models::validate_raw_size(data.len(), astype, &None)?;
+    let native = array::is_native_byte_order(request_data);
+    let values = match astype {
+        DType::Int32 => read::<i32>(data, native),
+        DType::Int64 => read::<i64>(data, native),
+        DType::Uint32 => read::<u32>(data, native),
+        DType::Uint64 => read::<u64>(data, native),
+        DType::Float16 => read::<half::f16>(data, native),
+        DType::Bfloat16 => read::<half::bf16>(data, native),
+        DType::Float32 => read::<f32>(data, native),
+        DType::Float64 => read::<f64>(data, native),
+    };
+    let values = values.into_iter().map(|value| value / scale + offset);
+    match request_data.dtype {
+        DType::Float16 => Ok(write::<half::f16>(values, native)),
+        DType::Bfloat16 => Ok(write::<half::bf16>(values, native)),
+        DType::Float32 => Ok(write::<f32>(values, native)),
+        DType::Float64 => Ok(write::<f64>(values, native)),
+        dtype => Err(ActiveStorageError::UnsupportedDType {
+            operation: "fixedscaleoffset",
+            dtype: dtype.to_string().to_lowercase(),
+        }),
+    }
+}
+
+/// Returns the values of type `T` in some data as [f64].
+fn read<T>(data: &[u8], native: bool) -> Vec<f64>
+where
+    T: AsBytes + FromBytes + ToPrimitive,
+{
+    data.chunks_exact(std::mem::size_of::<T>())
+        .map(|bytes| {
+            let value = T::read_from(bytes).expect("chunk is the size of T");
+            let value = if native {
+                value
+            } else {
+                array::reversed_byte_order(value)
+            };
+            value.to_f64().unwrap_or(f64::NAN)
+        })
+        .collect()
+}
+
+/// Returns some [f64] values converted to type `T`, in an 8-byte aligned buffer.
+fn write<T>(values: impl ExactSizeIterator<Item = f64>, native: bool) -> Bytes
+where
+    T: AsBytes + FromBytes + FromPrimitive,
+{
+    // See S3Client::download_object for why the data is copied into an aligned buffer.
+    let mut buf =
+        maligned::align_first::<u8, maligned::A8>(values.len() * std::mem::size_of::<T>());
+    for value in values {
+        let value = T::from_f64(value).expect("floating point types convert from f64");
+        let value = if native {
+            value
+        } else {
+            array::reversed_byte_order(value)
+        };
+        buf.extend_from_slice(value.as_bytes());
+    }
+    buf.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn encode_i32(values: &[f64], offset: f64, scale: f64) -> Bytes {
+        values
+            .iter()
+            .flat_map(|value| (((value - offset) * scale).round() as i32).to_ne_bytes())
+            .collect::<Vec<u8>>()
+            .into()
+    }
+
+    #[test]
+    fn test_decode_i32_to_f32() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        let data = encode_i32(&[1000.0, 1000.5, 999.25], 1000.0, 4.0);
+        let result = decode(&data, 1000.0, 4.0, DType::Int32, &request_data).unwrap();
+        let expected: Vec<u8> = [1000.0_f32, 1000.5, 999.25]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_decode_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let data: Vec<u8> = [-3_i32, 5]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        let result = decode(&data.into(), 10.0, 2.0, DType::Int32, &request_data).unwrap();
+        // The decoded data remains in the byte order of the request.
+        let expected: Vec<u8> = [8.5_f64, 12.5]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_decode_invalid_size() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        let data = Bytes::from_static(&[1, 2, 3]);
+        match decode(&data, 0.0, 1.0, DType::Int32, &request_data) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => assert_eq!(
+                "Raw data size must be a multiple of dtype size in bytes",
+                error.code
+            ),
+            _ => panic!("expected decode to fail"),
+        }
+    }
+
+    #[test]
+    fn test_decode_integer_dtype() {
+        let request_data = test_utils::get_test_request_data();
+        let data = encode_i32(&[1.0], 0.0, 1.0);
+        match decode(&data, 0.0, 1.0, DType::Int32, &request_data) {
+            Err(ActiveStorageError::UnsupportedDType { operation, dtype }) => {
+                assert_eq!("fixedscaleoffset", operation);
+                assert_eq!("int32", dtype);
+            }
+            _ => panic!("expected decode to fail"),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Numcodecs PackBits filter.
+
+use crate::error::ActiveStorageError;
+use crate::filters::bitpacked;
+use crate::models;
+
+use axum::body::Bytes;
+use validator::ValidationError;
+
+/// Decodes data encoded by Numcodecs PackBits and returns the result.
+///
+/// The first byte of the data is the number of padding bits at the end of the data, and the
+/// remaining bytes are bit-packed as for the Bitpacked filter, so are decoded in the same way by
+/// [bitpacked::unpack]. The number of elements is the number of bits less the padding, which must
+/// equal the product of the stored shape or shape, if specified.
+///
+/// # Arguments
+///
+/// * `data`: PackBits encoded data [Bytes]
+/// * `request_data`: RequestData object for the request
+pub fn decode(
+    data: &Bytes,
+    request_data: &models::RequestData,
+) -> Result<Bytes, ActiveStorageError> {
+    let Some((&padding, packed)) = data.split_first() else {
+        return Err(ValidationError::new("PackBits data must start with a header byte").into());
+    };
+    let padding = usize::from(padding);
+    if padding > 7 || (packed.is_empty() && padding > 0) {
+        let mut error = ValidationError::new("PackBits header has invalid padding");
+        error.add_param("padding".into(), &padding);
+        return Err(error.into());
+    }
+    let bits = packed.len() * 8 - padding;
+    if let Some(shape) = request_data.layout_shape() {
+        let elements: usize = shape.iter().product();
+        if elements != bits {
+            let mut error = ValidationError::new("PackBits data does not match the shape");
+            error.add_param("bits".into(), &bits);
+            error.add_param("elements".into(), &elements);
+            return Err(error.into());
+        }
+    }
+    Ok(bitpacked::unpack(packed, bits, request_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::Operation;
+    use crate::operations;
+    use crate::test_utils;
+    use zerocopy::AsBytes;
+
+    // numcodecs.PackBits().encode(np.array(BITS, dtype=bool))
+    const ENCODED: [u8; 3] = [0x06, 0xb3, 0x40];
+    const BITS: [i32; 10] = [1, 0, 1, 1, 0, 0, 1, 1, 0, 1];
+
+    #[test]
+    fn test_decode_i32() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 5]);
+        let result = decode(&Bytes::from_static(&ENCODED), &request_data).unwrap();
+        assert_eq!(BITS.as_bytes(), result);
+        assert_eq!(result.as_ptr().align_offset(8), 0);
+    }
+
+    #[test]
+    fn test_decode_no_shape() {
+        // The number of elements is given by the padding.
+        let request_data = test_utils::get_test_request_data();
+        let result = decode(&Bytes::from_static(&ENCODED), &request_data).unwrap();
+        assert_eq!(BITS.as_bytes(), result);
+        // numcodecs.PackBits().encode(np.ones(8, dtype=bool))
+        let result = decode(&Bytes::from_static(&[0x00, 0xff]), &request_data).unwrap();
+        assert_eq!([1_i32; 8].as_bytes(), result);
+        // numcodecs.PackBits().encode(np.array([], dtype=bool))
+        let result = decode(&Bytes::from_static(&[0x00]), &request_data).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let mut request_data = test_utils::get_test_request_data();
+        let invalid: [(&[u8], &str); 3] = [
+            (&[], "PackBits data must start with a header byte"),
+            (&[0x08, 0xff], "PackBits header has invalid padding"),
+            (&[0x01], "PackBits header has invalid padding"),
+        ];
+        for (data, code) in invalid {
+            match decode(&Bytes::copy_from_slice(data), &request_data) {
+                Err(ActiveStorageError::RequestDataValidationSingle(error)) => {
+                    assert_eq!(code, error.code)
+                }
+                _ => panic!("expected decode to fail"),
+            }
+        }
+        request_data.shape = Some(vec![11]);
+        match decode(&Bytes::from_static(&ENCODED), &request_data) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => {
+                assert_eq!("PackBits data does not match the shape", error.code)
+            }
+            _ => panic!("expected decode to fail"),
+        }
+    }
+
+    #[test]
+    fn test_count_set_bits() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.filters = Some(vec![models::Filter::PackBits]);
+        let data = decode(&Bytes::from_static(&ENCODED), &request_data).unwrap();
+        let response = operations::Sum::execute(&request_data, data.into()).unwrap();
+        assert_eq!(6_i64.as_bytes(), response.body);
+        assert_eq!(10, response.count);
+    }
+}
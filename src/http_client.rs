@@ -0,0 +1,411 @@
+//! A simple HTTP client that supports downloading objects from plain HTTP(S) servers, as an
+//! alternative to S3. Objects are addressed directly by URL and byte ranges are requested using
+//! the HTTP Range header.
+
+use crate::error::ActiveStorageError;
+use crate::models::ObjectInfo;
+use crate::resource_manager::{ResourceManager, ResourcePermit};
+use crate::s3_client::{self, ObjectData, S3Credentials};
+use crate::source_filter::PublicResolver;
+
+use aws_smithy_types::date_time::Format;
+use aws_smithy_types::DateTime;
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use std::sync::Arc;
+use tracing::Instrument;
+use url::Url;
+
+/// HTTP client object.
+///
+/// The underlying [reqwest::Client] holds a connection pool, so a single client should be shared
+/// between requests.
+///
+/// Redirects are not followed, since their targets have not been checked by the
+/// [crate::source_filter::SourceFilter].
+#[derive(Clone)]
+pub struct HttpClient {
+    /// Underlying reqwest client object.
+    client: reqwest::Client,
+}
+
+impl HttpClient {
+    /// Creates an HttpClient object
+    ///
+    /// # Arguments
+    ///
+    /// * `deny_private_sources`: Whether to refuse to connect to hosts that resolve to private IP
+    ///   addresses
+    pub fn new(deny_private_sources: bool) -> Self {
+        let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if deny_private_sources {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        Self {
+            client: builder.build().expect("Failed to create HTTP client"),
+        }
+    }
+
+    /// Downloads an object from an HTTP server and returns the data as Bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `url`: URL of the object
+    /// * `credentials`: Optional credentials, sent using basic authentication
+    /// * `range`: Optional byte range
+    /// * `resource_manager`: ResourceManager object
+    /// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
+    pub async fn download_object<'a>(
+        self: &HttpClient,
+        url: &Url,
+        credentials: &S3Credentials,
+        range: Option<String>,
+        resource_manager: &'a ResourceManager,
+        mem_permits: &mut Option<ResourcePermit<'a>>,
+    ) -> Result<Bytes, ActiveStorageError> {
+        let response = self.get(url, credentials, &range, None).await?;
+        read_object(response, &range, resource_manager, mem_permits).await
+    }
+
+    /// Downloads an object from an HTTP server unless its ETag matches `etag`
+    ///
+    /// Returns `None` if the object has not been modified, otherwise the data and the object's
+    /// ETag.
+    ///
+    /// # Arguments
+    ///
+    /// * `url`: URL of the object
+    /// * `credentials`: Optional credentials, sent using basic authentication
+    /// * `range`: Optional byte range
+    /// * `etag`: ETag of a previously downloaded copy of the object
+    /// * `resource_manager`: ResourceManager object
+    /// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
+    pub async fn download_object_if_none_match<'a>(
+        self: &HttpClient,
+        url: &Url,
+        credentials: &S3Credentials,
+        range: Option<String>,
+        etag: Option<&str>,
+        resource_manager: &'a ResourceManager,
+        mem_permits: &mut Option<ResourcePermit<'a>>,
+    ) -> Result<Option<ObjectData>, ActiveStorageError> {
+        let response = self.get(url, credentials, &range, etag).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let etag = header_str(response.headers(), header::ETAG).map(str::to_string);
+        let data = read_object(response, &range, resource_manager, mem_permits).await?;
+        Ok(Some(ObjectData { data, etag }))
+    }
+
+    /// Retrieves an object's metadata from an HTTP server without downloading its data
+    ///
+    /// # Arguments
+    ///
+    /// * `url`: URL of the object
+    /// * `credentials`: Optional credentials, sent using basic authentication
+    pub async fn head_object(
+        self: &HttpClient,
+        url: &Url,
+        credentials: &S3Credentials,
+    ) -> Result<ObjectInfo, ActiveStorageError> {
+        let response = self
+            .request(Method::HEAD, url, credentials)
+            .send()
+            .instrument(tracing::Span::current())
+            .await?;
+        let response = check_status(response)?;
+        let headers = response.headers();
+        let last_modified = header_str(headers, header::LAST_MODIFIED)
+            .and_then(|time| DateTime::from_str(time, Format::HttpDate).ok())
+            .and_then(|time| time.fmt(Format::DateTime).ok());
+        Ok(ObjectInfo {
+            content_length: content_length(headers)?,
+            last_modified,
+            etag: header_str(headers, header::ETAG).map(str::to_string),
+        })
+    }
+
+    /// Sends a GET request for an object, optionally restricted to a byte range and conditional
+    /// on the object's ETag not matching `etag`.
+    async fn get(
+        &self,
+        url: &Url,
+        credentials: &S3Credentials,
+        range: &Option<String>,
+        etag: Option<&str>,
+    ) -> Result<reqwest::Response, ActiveStorageError> {
+        let mut request = self.request(Method::GET, url, credentials);
+        if let Some(range) = range {
+            request = request.header(header::RANGE, range);
+        }
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().instrument(tracing::Span::current()).await?;
+        check_status(response)
+    }
+
+    /// Returns a request builder for an object, with any credentials applied.
+    fn request(
+        &self,
+        method: Method,
+        url: &Url,
+        credentials: &S3Credentials,
+    ) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url.clone());
+        match credentials {
+            S3Credentials::AccessKey {
+                access_key,
+                secret_key,
+            } => request.basic_auth(access_key, Some(secret_key)),
+            S3Credentials::None => request,
+        }
+    }
+}
+
+/// Returns the response if its status is successful or `304 Not Modified`, or otherwise an error.
+///
+/// Redirects are rejected with a [crate::error::ActiveStorageError::SourceNotAllowed] error for
+/// their target, since it has not been checked by the source filter.
+///
+/// # Arguments
+///
+/// * `response`: Response from the HTTP server
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ActiveStorageError> {
+    if response.status().is_redirection() && response.status() != StatusCode::NOT_MODIFIED {
+        let url = header_str(response.headers(), header::LOCATION)
+            .and_then(|location| response.url().join(location).ok())
+            .unwrap_or_else(|| response.url().clone());
+        return Err(ActiveStorageError::SourceNotAllowed {
+            url: url.to_string(),
+        });
+    }
+    Ok(response.error_for_status()?)
+}
+
+/// Reads the data of a GET response into memory and returns it as Bytes
+///
+/// # Arguments
+///
+/// * `response`: GET response
+/// * `range`: Byte range requested, if any
+/// * `resource_manager`: ResourceManager object
+/// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
+async fn read_object<'a>(
+    mut response: reqwest::Response,
+    range: &Option<String>,
+    resource_manager: &'a ResourceManager,
+    mem_permits: &mut Option<ResourcePermit<'a>>,
+) -> Result<Bytes, ActiveStorageError> {
+    // A server that does not support ranges returns the whole object.
+    if range.is_some() && response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(ActiveStorageError::HttpRangeNotSupported);
+    }
+    let content_length = content_length(response.headers())?;
+
+    if mem_permits.is_none() {
+        *mem_permits = resource_manager.memory(content_length).await?;
+    };
+    // See S3Client::download_object for why the data is copied into an aligned buffer.
+    let mut buf = maligned::align_first::<u8, maligned::A8>(content_length);
+    while let Some(bytes) = response
+        .chunk()
+        .instrument(tracing::Span::current())
+        .await
+        .map_err(|error| {
+            if buf.len() < content_length {
+                // The connection was most likely closed before the body was complete.
+                tracing::warn!(%error, "error receiving object from HTTP server");
+                ActiveStorageError::ShortRead {
+                    expected: content_length,
+                    actual: buf.len(),
+                }
+            } else {
+                error.into()
+            }
+        })?
+    {
+        buf.extend_from_slice(&bytes)
+    }
+    s3_client::check_length(content_length, buf.len())?;
+    Ok(buf.into())
+}
+
+/// Returns the value of a header as a string, if present and valid.
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Returns the value of the Content-Length header.
+///
+/// The header is read directly rather than using [reqwest::Response::content_length], which
+/// reports the size of the body and is therefore zero for HEAD requests.
+fn content_length(headers: &HeaderMap) -> Result<usize, ActiveStorageError> {
+    header_str(headers, header::CONTENT_LENGTH)
+        .and_then(|length| length.parse().ok())
+        .ok_or(ActiveStorageError::HttpContentLengthMissing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn object_url(s3: &test_utils::MockS3) -> Url {
+        s3.url.join("bar/baz").unwrap()
+    }
+
+    #[tokio::test]
+    async fn download_object() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let data = client
+            .download_object(&object_url(&s3), &S3Credentials::None, None, &rm, &mut None)
+            .await
+            .unwrap();
+        assert_eq!(&[1, 2, 3, 4, 5][..], data);
+    }
+
+    #[tokio::test]
+    async fn download_object_range() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let range = Some("bytes=1-3".to_string());
+        let data = client
+            .download_object(
+                &object_url(&s3),
+                &S3Credentials::None,
+                range,
+                &rm,
+                &mut None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(&[2, 3, 4][..], data);
+    }
+
+    #[tokio::test]
+    async fn download_object_short_read() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![0; 1024]);
+        s3.set_truncated("bar", "baz", 100);
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let result = client
+            .download_object(&object_url(&s3), &S3Credentials::None, None, &rm, &mut None)
+            .await;
+        match result {
+            Err(ActiveStorageError::ShortRead { expected, actual }) => {
+                assert_eq!((1024, 100), (expected, actual))
+            }
+            _ => panic!("expected short read error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_object_not_found() {
+        let s3 = test_utils::MockS3::start().await;
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let result = client
+            .download_object(&object_url(&s3), &S3Credentials::None, None, &rm, &mut None)
+            .await;
+        match result {
+            Err(ActiveStorageError::HttpRequest(error)) => {
+                assert_eq!(Some(StatusCode::NOT_FOUND), error.status())
+            }
+            _ => panic!("expected HTTP request error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_object_redirect() {
+        // Redirects are not followed, since their target has not been checked.
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        s3.set_error("bar", "baz", StatusCode::FOUND, "Found");
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let result = client
+            .download_object(&object_url(&s3), &S3Credentials::None, None, &rm, &mut None)
+            .await;
+        match result {
+            Err(ActiveStorageError::SourceNotAllowed { url }) => {
+                assert_eq!(object_url(&s3).to_string(), url)
+            }
+            _ => panic!("expected source not allowed error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_object_deny_private() {
+        // Hosts that resolve to private addresses are refused when connecting.
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        let mut url = object_url(&s3);
+        url.set_host(Some("localhost")).unwrap();
+        let rm = ResourceManager::new(None, None, None, None);
+        let client = HttpClient::new(false);
+        client
+            .download_object(&url, &S3Credentials::None, None, &rm, &mut None)
+            .await
+            .unwrap();
+        let client = HttpClient::new(true);
+        let result = client
+            .download_object(&url, &S3Credentials::None, None, &rm, &mut None)
+            .await;
+        assert!(matches!(result, Err(ActiveStorageError::HttpRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn download_object_if_none_match() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        let client = HttpClient::new(false);
+        let rm = ResourceManager::new(None, None, None, None);
+        let credentials = S3Credentials::None;
+        let url = object_url(&s3);
+        let object = client
+            .download_object_if_none_match(&url, &credentials, None, None, &rm, &mut None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&[1, 2, 3, 4, 5][..], object.data);
+        let etag = object.etag.unwrap();
+        assert_eq!(test_utils::mock_s3_etag(&[1, 2, 3, 4, 5]), etag);
+        let object = client
+            .download_object_if_none_match(&url, &credentials, None, Some(&etag), &rm, &mut None)
+            .await
+            .unwrap();
+        assert!(object.is_none());
+        s3.put("bar", "baz", vec![6, 7]);
+        let object = client
+            .download_object_if_none_match(&url, &credentials, None, Some(&etag), &rm, &mut None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&[6, 7][..], object.data);
+    }
+
+    #[tokio::test]
+    async fn head_object() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4, 5]);
+        let client = HttpClient::new(false);
+        let info = client
+            .head_object(&object_url(&s3), &S3Credentials::None)
+            .await
+            .unwrap();
+        let expected = ObjectInfo {
+            content_length: 5,
+            last_modified: Some("2015-10-21T07:28:00Z".to_string()),
+            etag: Some(test_utils::mock_s3_etag(&[1, 2, 3, 4, 5])),
+        };
+        assert_eq!(expected, info);
+    }
+}
@@ -20,14 +20,17 @@
 //! Reductionist provides the following features:
 //!
 //! * HTTP(S) API with JSON request data
-//! * Access to data stored in S3-compatible storage
-//! * Basic numerical operations on multi-dimensional arrays (count, min, max, select, sum)
+//! * Access to data stored in S3-compatible storage or on plain HTTP(S) servers
+//! * Basic numerical operations on multi-dimensional arrays (approx_distinct, approx_percentile, count, count_missing, cumsum, diff, first, geometric_mean, last, min, max, minmax_loc, mode, nanmin, nanmax, ptp, select, stats, sum, sum_of_squares, trimmed_mean, valid_fraction, weighted_sum)
 //! * Perform calculations on a selection/slice of an array
+//! * Combine results over a batch of objects in a single request
 //! * Perform calculations allowing for missing data
-//! * Compressed data (GZip, Zlib)
-//! * Filtered data (byte shuffle)
+//! * Compressed data (GZip, Zlib, raw DEFLATE, Brotli, Snappy)
+//! * Filtered data (byte shuffle, fixed scale and offset)
 //! * Data with non-native byte order (endianness)
+//! * Inner chunks of Zarr v3 shards
 //! * Server resource (CPU, memory, files) management
+//! * Per-source request rate limiting
 //! * [Prometheus](https://prometheus.io/) metrics
 //! * Tracing with an option to send data to [Jaeger](https://www.jaegertracing.io/)
 //! * Ansible-based containerised deployment
@@ -48,18 +51,26 @@
 
 pub mod app;
 pub mod array;
+pub mod byte_order_check;
 pub mod cli;
 pub mod compression;
+pub mod download_cache;
 pub mod error;
 pub mod filter_pipeline;
 pub mod filters;
+pub mod http_client;
 pub mod metrics;
 pub mod models;
 pub mod operation;
 pub mod operations;
+pub mod preload;
+pub mod rate_limiter;
 pub mod resource_manager;
 pub mod s3_client;
 pub mod server;
+pub mod session_store;
+pub mod shard;
+pub mod source_filter;
 #[cfg(test)]
 pub mod test_utils;
 pub mod tracing;
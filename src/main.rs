@@ -7,13 +7,21 @@ use reductionist::server;
 use reductionist::tracing;
 
 /// Application entry point
-#[tokio::main]
-async fn main() {
+fn main() {
     let args = cli::parse();
-    tracing::init_tracing(&args);
-    metrics::register_metrics();
-    app::init(&args);
-    let service = app::service(&args);
-    server::serve(&args, service).await;
-    tracing::shutdown_tracing();
+    let runtime = server::runtime(&args).expect("Failed to build Tokio runtime");
+    runtime.block_on(async {
+        tracing::init_tracing(&args);
+        metrics::register_metrics();
+        app::init(&args);
+        let service = match app::service(&args).await {
+            Ok(service) => service,
+            Err(error) => {
+                eprintln!("Failed to start service: {}", error);
+                std::process::exit(1)
+            }
+        };
+        server::serve(&args, service).await;
+        tracing::shutdown_tracing();
+    });
 }
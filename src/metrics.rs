@@ -1,10 +1,25 @@
 //! Prometheus metrics
 
-use std::time::Instant;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
 
-use axum::{http::Request, middleware::Next, response::IntoResponse};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
 use lazy_static::lazy_static;
-use prometheus::{self, Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts};
+use prometheus::proto::{LabelPair, MetricFamily, MetricType};
+use prometheus::{self, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts};
+
+/// Buckets of the [RESPONSE_TIME_COLLECTOR] histogram.
+const RESPONSE_TIME_BUCKETS: &[f64] = prometheus::DEFAULT_BUCKETS;
+
+/// Content type of metrics in the OpenMetrics text format.
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
 
 lazy_static! {
     // Simple request counter
@@ -21,10 +36,50 @@ lazy_static! {
     pub static ref RESPONSE_TIME_COLLECTOR: HistogramVec = HistogramVec::new(
         HistogramOpts{
             common_opts: Opts::new("response_time", "The time taken to respond to each request"),
-            buckets: prometheus::DEFAULT_BUCKETS.to_vec(), // Change buckets here if desired
+            buckets: RESPONSE_TIME_BUCKETS.to_vec(), // Change buckets here if desired
         },
         &["status_code", "http_method", "path"],
     ).expect("Prometheus metric options should be valid");
+    // Most recent exemplar for each bucket of the response time histogram, by status code, HTTP
+    // method, path and bucket index
+    static ref RESPONSE_TIME_EXEMPLARS: Mutex<HashMap<ExemplarKey, Exemplar>> =
+        Mutex::new(HashMap::new());
+    // Histogram of the number of non-missing elements operated on, by operation
+    pub static ref ELEMENT_COUNT_COLLECTOR: HistogramVec = HistogramVec::new(
+        HistogramOpts{
+            common_opts: Opts::new(
+                "operation_element_count",
+                "The number of non-missing elements operated on by each successful operation"
+            ),
+            buckets: prometheus::exponential_buckets(1.0, 10.0, 10)
+                .expect("Prometheus histogram buckets should be valid"),
+        },
+        &["operation"],
+    ).expect("Prometheus metric options should be valid");
+    // Histogram of the time taken to download object data, by the host of allowed sources, or
+    // "other" for sources that are not explicitly allowed
+    pub static ref S3_DOWNLOAD_TIME_COLLECTOR: HistogramVec = HistogramVec::new(
+        HistogramOpts{
+            common_opts: Opts::new(
+                "reductionist_s3_download_seconds",
+                "The time taken to download the object data for each request"
+            ),
+            buckets: prometheus::DEFAULT_BUCKETS.to_vec(),
+        },
+        &["source_host"],
+    ).expect("Prometheus metric options should be valid");
+    // Gauge of S3 connection permits currently held
+    pub static ref S3_CONNECTIONS_GAUGE: IntGauge = IntGauge::new(
+        "resource_s3_connections", "The number of S3 connection permits currently held"
+    ).expect("Prometheus metric options should be valid");
+    // Gauge of memory permits currently held
+    pub static ref MEMORY_GAUGE: IntGauge = IntGauge::new(
+        "resource_memory_bytes", "The number of bytes of memory currently reserved"
+    ).expect("Prometheus metric options should be valid");
+    // Gauge of task permits currently held
+    pub static ref TASKS_GAUGE: IntGauge = IntGauge::new(
+        "resource_tasks", "The number of task permits currently held"
+    ).expect("Prometheus metric options should be valid");
 }
 
 /// Registers various prometheus metrics with the global registry
@@ -39,10 +94,64 @@ pub fn register_metrics() {
     registry
         .register(Box::new(RESPONSE_TIME_COLLECTOR.clone()))
         .expect("Prometheus metrics registration should not fail during initialization");
+    registry
+        .register(Box::new(ELEMENT_COUNT_COLLECTOR.clone()))
+        .expect("Prometheus metrics registration should not fail during initialization");
+    registry
+        .register(Box::new(S3_DOWNLOAD_TIME_COLLECTOR.clone()))
+        .expect("Prometheus metrics registration should not fail during initialization");
+    registry
+        .register(Box::new(S3_CONNECTIONS_GAUGE.clone()))
+        .expect("Prometheus metrics registration should not fail during initialization");
+    registry
+        .register(Box::new(MEMORY_GAUGE.clone()))
+        .expect("Prometheus metrics registration should not fail during initialization");
+    registry
+        .register(Box::new(TASKS_GAUGE.clone()))
+        .expect("Prometheus metrics registration should not fail during initialization");
 }
 
+/// Key of an exemplar of the response time histogram: the status code, HTTP method and path
+/// labels, and the index of the bucket.
+type ExemplarKey = (String, String, String, usize);
+
+/// An OpenMetrics exemplar, linking an observation of a histogram to a trace.
+struct Exemplar {
+    /// ID of the trace of the request
+    trace_id: String,
+    /// Observed value
+    value: f64,
+    /// Time of the observation in seconds since the Unix epoch
+    timestamp: f64,
+}
+
+/// Trace ID of a request, added to the extensions of its response by [attach_trace_id] so that
+/// [track_metrics] can record it in an exemplar.
+#[derive(Clone)]
+pub struct TraceId(pub String);
+
 /// Returns currently gathered prometheus metrics
-pub async fn metrics_handler() -> String {
+///
+/// If exemplars are enabled and the `Accept` header of the request allows the OpenMetrics text
+/// format, the metrics are returned in that format with exemplars for the response time
+/// histogram. Otherwise they are returned in the Prometheus text format, which does not support
+/// exemplars.
+///
+/// # Arguments
+///
+/// * `exemplars`: Whether exemplars are enabled
+/// * `headers`: Headers of the request
+pub async fn metrics_handler(State(exemplars): State<bool>, headers: HeaderMap) -> Response {
+    let openmetrics = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("application/openmetrics-text"));
+    if exemplars && openmetrics {
+        let body = encode_openmetrics(&prometheus::gather());
+        return ([(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)], body).into_response();
+    }
+
     let encoder = prometheus::TextEncoder::new();
     let mut buffer = Vec::new();
 
@@ -50,7 +159,202 @@ pub async fn metrics_handler() -> String {
         .encode(&prometheus::gather(), &mut buffer)
         .expect("could not encode gathered metrics into temporary buffer");
 
-    String::from_utf8(buffer).expect("could not convert metrics buffer into string")
+    String::from_utf8(buffer)
+        .expect("could not convert metrics buffer into string")
+        .into_response()
+}
+
+/// Encodes metric families in the OpenMetrics text format, with the exemplars of the
+/// [RESPONSE_TIME_COLLECTOR] histogram.
+///
+/// Counter samples have a `_total` suffix, as OpenMetrics requires, and histograms include a
+/// `+Inf` bucket.
+fn encode_openmetrics(families: &[MetricFamily]) -> String {
+    let exemplars = RESPONSE_TIME_EXEMPLARS.lock().unwrap();
+    let mut out = String::new();
+    for family in families {
+        let (name, metric_type) = match family.get_field_type() {
+            MetricType::COUNTER => (
+                family
+                    .get_name()
+                    .strip_suffix("_total")
+                    .unwrap_or(family.get_name()),
+                "counter",
+            ),
+            MetricType::GAUGE => (family.get_name(), "gauge"),
+            MetricType::HISTOGRAM => (family.get_name(), "histogram"),
+            MetricType::SUMMARY => (family.get_name(), "summary"),
+            MetricType::UNTYPED => (family.get_name(), "unknown"),
+        };
+        writeln!(out, "# HELP {} {}", name, escape(family.get_help())).unwrap();
+        writeln!(out, "# TYPE {} {}", name, metric_type).unwrap();
+        for metric in family.get_metric() {
+            let labels = metric.get_label();
+            match family.get_field_type() {
+                MetricType::COUNTER => {
+                    let value = metric.get_counter().get_value();
+                    write_sample(&mut out, name, "_total", labels, None, value);
+                }
+                MetricType::GAUGE => {
+                    let value = metric.get_gauge().get_value();
+                    write_sample(&mut out, name, "", labels, None, value);
+                }
+                MetricType::UNTYPED => {
+                    let value = metric.get_untyped().get_value();
+                    write_sample(&mut out, name, "", labels, None, value);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    for quantile in summary.get_quantile() {
+                        let label = ("quantile", format_value(quantile.get_quantile()));
+                        let value = quantile.get_value();
+                        write_sample(&mut out, name, "", labels, Some(label), value);
+                    }
+                    let count = summary.get_sample_count() as f64;
+                    write_sample(&mut out, name, "_count", labels, None, count);
+                    let sum = summary.get_sample_sum();
+                    write_sample(&mut out, name, "_sum", labels, None, sum);
+                }
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    let label = |name: &str| {
+                        labels
+                            .iter()
+                            .find(|label| label.get_name() == name)
+                            .map_or("", |label| label.get_value())
+                            .to_string()
+                    };
+                    let key = |index| {
+                        (family.get_name() == "response_time").then(|| {
+                            (
+                                label("status_code"),
+                                label("http_method"),
+                                label("path"),
+                                index,
+                            )
+                        })
+                    };
+                    let buckets = histogram.get_bucket();
+                    let bounds = buckets
+                        .iter()
+                        .map(|bucket| (bucket.get_upper_bound(), bucket.get_cumulative_count()))
+                        .chain(std::iter::once((
+                            f64::INFINITY,
+                            histogram.get_sample_count(),
+                        )));
+                    for (index, (upper_bound, count)) in bounds.enumerate() {
+                        let label = ("le", format_value(upper_bound));
+                        write_sample(&mut out, name, "_bucket", labels, Some(label), count as f64);
+                        if let Some(exemplar) = key(index).and_then(|key| exemplars.get(&key)) {
+                            // Replace the newline with the exemplar.
+                            out.pop();
+                            writeln!(
+                                out,
+                                " # {{trace_id=\"{}\"}} {} {:.3}",
+                                exemplar.trace_id,
+                                format_value(exemplar.value),
+                                exemplar.timestamp
+                            )
+                            .unwrap();
+                        }
+                    }
+                    let count = histogram.get_sample_count() as f64;
+                    write_sample(&mut out, name, "_count", labels, None, count);
+                    let sum = histogram.get_sample_sum();
+                    write_sample(&mut out, name, "_sum", labels, None, sum);
+                }
+            }
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Writes a line of a sample in the OpenMetrics text format.
+///
+/// # Arguments
+///
+/// * `out`: String to write to
+/// * `name`: Name of the metric family
+/// * `suffix`: Suffix of the sample name, e.g. `_total`
+/// * `labels`: Labels of the metric
+/// * `extra_label`: Optional additional label, e.g. `le` for histogram buckets
+/// * `value`: Value of the sample
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    suffix: &str,
+    labels: &[LabelPair],
+    extra_label: Option<(&str, String)>,
+    value: f64,
+) {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|label| (label.get_name(), label.get_value().to_string()))
+        .chain(extra_label)
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(&value)))
+        .collect();
+    if labels.is_empty() {
+        writeln!(out, "{}{} {}", name, suffix, format_value(value)).unwrap();
+    } else {
+        let labels = labels.join(",");
+        writeln!(
+            out,
+            "{}{}{{{}}} {}",
+            name,
+            suffix,
+            labels,
+            format_value(value)
+        )
+        .unwrap();
+    }
+}
+
+/// Escapes a help text or label value in the OpenMetrics text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('"', "\\\"")
+}
+
+/// Formats a value in the OpenMetrics text format, which spells infinities and NaN differently
+/// from Rust.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Adds the trace ID of a request to the extensions of its response, if exemplars are enabled.
+///
+/// This must run within the request span created by [crate::tracing::request_span], so that the
+/// trace ID of the span is available.
+///
+/// # Arguments
+///
+/// * `exemplars`: Whether exemplars are enabled
+/// * `request`: The HTTP request
+/// * `next`: The next layer
+pub async fn attach_trace_id<B>(
+    State(exemplars): State<bool>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let trace_id = exemplars
+        .then(|| crate::tracing::trace_id(&request))
+        .flatten();
+    let mut response = next.run(request).await;
+    if let Some(trace_id) = trace_id {
+        response.extensions_mut().insert(TraceId(trace_id));
+    }
+    response
 }
 
 pub async fn track_metrics<B>(request: Request<B>, next: Next<B>) -> impl IntoResponse {
@@ -80,6 +384,76 @@ pub async fn track_metrics<B>(request: Request<B>, next: Next<B>) -> impl IntoRe
     RESPONSE_TIME_COLLECTOR
         .with_label_values(&[status_code.as_str(), http_method, &request_path])
         .observe(latency);
+    if let Some(TraceId(trace_id)) = response.extensions().get::<TraceId>() {
+        let index = RESPONSE_TIME_BUCKETS
+            .iter()
+            .position(|upper_bound| latency <= *upper_bound)
+            .unwrap_or(RESPONSE_TIME_BUCKETS.len());
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0.0, |duration| duration.as_secs_f64());
+        let key = (
+            status_code.as_str().to_string(),
+            http_method.clone(),
+            request_path,
+            index,
+        );
+        let exemplar = Exemplar {
+            trace_id: trace_id.clone(),
+            value: latency,
+            timestamp,
+        };
+        RESPONSE_TIME_EXEMPLARS
+            .lock()
+            .unwrap()
+            .insert(key, exemplar);
+    }
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_openmetrics_counter_and_gauge() {
+        let registry = prometheus::Registry::new();
+        let counter = IntCounterVec::new(Opts::new("foo", "Foo \"help\""), &["path"]).unwrap();
+        counter.with_label_values(&["/a\nb"]).inc_by(3);
+        let gauge = IntGauge::new("bar_total", "Bar").unwrap();
+        gauge.set(-2);
+        registry.register(Box::new(counter)).unwrap();
+        registry.register(Box::new(gauge)).unwrap();
+        let expected = "# HELP bar_total Bar\n\
+            # TYPE bar_total gauge\n\
+            bar_total -2\n\
+            # HELP foo Foo \\\"help\\\"\n\
+            # TYPE foo counter\n\
+            foo_total{path=\"/a\\nb\"} 3\n\
+            # EOF\n";
+        assert_eq!(expected, encode_openmetrics(&registry.gather()));
+    }
+
+    #[test]
+    fn encode_openmetrics_histogram() {
+        let registry = prometheus::Registry::new();
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("baz_seconds", "Baz").buckets(vec![0.5, 1.0]),
+            &["path"],
+        )
+        .unwrap();
+        histogram.with_label_values(&["/"]).observe(0.75);
+        histogram.with_label_values(&["/"]).observe(2.0);
+        registry.register(Box::new(histogram)).unwrap();
+        let expected = "# HELP baz_seconds Baz\n\
+            # TYPE baz_seconds histogram\n\
+            baz_seconds_bucket{path=\"/\",le=\"0.5\"} 0\n\
+            baz_seconds_bucket{path=\"/\",le=\"1\"} 1\n\
+            baz_seconds_bucket{path=\"/\",le=\"+Inf\"} 2\n\
+            baz_seconds_count{path=\"/\"} 2\n\
+            baz_seconds_sum{path=\"/\"} 2.75\n\
+            # EOF\n";
+        assert_eq!(expected, encode_openmetrics(&registry.gather()));
+    }
+}
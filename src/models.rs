@@ -6,10 +6,14 @@ use strum_macros::Display;
 use url::Url;
 use validator::{Validate, ValidationError};
 
-use crate::types::{ByteOrder, DValue, Missing};
+use crate::array;
+use crate::s3_client;
+use crate::shard;
+use crate::types::dvalue::TryFromDValue;
+use crate::types::{ByteOrder, DValue, Missing, NATIVE_BYTE_ORDER};
 
 /// Supported numerical data types
-#[derive(Clone, Copy, Debug, Deserialize, Display, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Display, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DType {
     /// [i32]
@@ -20,6 +24,10 @@ pub enum DType {
     Uint32,
     /// [u64]
     Uint64,
+    /// [f16](half::f16)
+    Float16,
+    /// [bf16](half::bf16)
+    Bfloat16,
     /// [f32]
     Float32,
     /// [f64]
@@ -34,16 +42,61 @@ impl DType {
             Self::Int64 => std::mem::size_of::<i64>(),
             Self::Uint32 => std::mem::size_of::<u32>(),
             Self::Uint64 => std::mem::size_of::<u64>(),
+            Self::Float16 => std::mem::size_of::<half::f16>(),
+            Self::Bfloat16 => std::mem::size_of::<half::bf16>(),
             Self::Float32 => std::mem::size_of::<f32>(),
             Self::Float64 => std::mem::size_of::<f64>(),
         }
     }
+
+    /// Returns the data type of the sum of values of this type.
+    ///
+    /// Half precision floats are promoted to [f32] to avoid loss of precision.
+    pub fn sum_dtype(self) -> DType {
+        match self {
+            Self::Float16 | Self::Bfloat16 => Self::Float32,
+            dtype => dtype,
+        }
+    }
+
+    /// Returns the data type of the cumulative sum of values of this type.
+    ///
+    /// 32-bit integers are promoted to 64-bit integers of the same signedness to avoid overflow,
+    /// and half precision floats are promoted to [f32] to avoid loss of precision.
+    pub fn cumsum_dtype(self) -> DType {
+        match self {
+            Self::Int32 => Self::Int64,
+            Self::Uint32 => Self::Uint64,
+            dtype => dtype.sum_dtype(),
+        }
+    }
+
+    /// Returns the data type of the peak-to-peak range (maximum minus minimum) of values of this
+    /// type.
+    ///
+    /// Signed integers are promoted to unsigned integers of the same size, which can represent
+    /// the range of any two values without overflow.
+    pub fn ptp_dtype(self) -> DType {
+        match self {
+            Self::Int32 => Self::Uint32,
+            Self::Int64 => Self::Uint64,
+            dtype => dtype,
+        }
+    }
+
+    /// Returns whether this is a floating point data type.
+    pub fn is_float(self) -> bool {
+        matches!(
+            self,
+            Self::Float16 | Self::Bfloat16 | Self::Float32 | Self::Float64
+        )
+    }
 }
 
 /// Array ordering
 ///
 /// Defines an ordering for multi-dimensional arrays.
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Order {
     /// Row-major (C) ordering
     C,
@@ -51,6 +104,19 @@ pub enum Order {
     F,
 }
 
+/// Ordering of the elements of a selection in a response
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum OutputOrder {
+    /// Row-major (C) ordering
+    #[default]
+    C,
+    /// Column-major (Fortran) ordering
+    F,
+    /// The ordering of the data in the request, as for NumPy's `order="K"`, which avoids
+    /// reordering the elements
+    K,
+}
+
 /// A slice of a single dimension of an array
 ///
 /// The API uses NumPy slice semantics:
@@ -87,8 +153,118 @@ impl Slice {
     }
 }
 
+/// A range of bytes within an object
+// NOTE: As for Slice, this may be deserialised from an [<offset>, <size>] sequence or a map.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ByteRange {
+    /// Offset of the range in bytes
+    pub offset: usize,
+    /// Size of the range in bytes
+    #[validate(range(min = 1, message = "range size must be greater than 0"))]
+    pub size: usize,
+}
+
+impl ByteRange {
+    /// Return a new ByteRange object.
+    pub fn new(offset: usize, size: usize) -> Self {
+        ByteRange { offset, size }
+    }
+}
+
+/// Location of the index within a Zarr v3 shard
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardIndexLocation {
+    /// Index precedes the inner chunks
+    Start,
+    /// Index follows the inner chunks
+    #[default]
+    End,
+}
+
+/// A Zarr v3 shard containing the data
+///
+/// The shard occupies the byte range given by `offset` and `size`, or the whole object. Its index
+/// contains an (offset, size) pair of little endian uint64 values for each inner chunk, with
+/// offsets relative to the start of the shard, optionally followed by a CRC32C checksum.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct Shard {
+    /// Number of inner chunks in the shard
+    #[validate(range(min = 1, message = "shard chunks must be greater than 0"))]
+    pub chunks: usize,
+    /// Index of the inner chunk to operate on, in C order
+    pub chunk: usize,
+    /// Location of the shard index
+    #[serde(default)]
+    pub index_location: ShardIndexLocation,
+    /// Whether the shard index is followed by a CRC32C checksum
+    #[serde(default = "default_index_checksum")]
+    pub index_checksum: bool,
+}
+
+/// Default for [Shard::index_checksum], matching the Zarr v3 default index codecs
+fn default_index_checksum() -> bool {
+    true
+}
+
+#[cfg(test)]
+impl Shard {
+    /// Return a new Shard object with the default index location and checksum.
+    pub fn new(chunks: usize, chunk: usize) -> Self {
+        Shard {
+            chunks,
+            chunk,
+            index_location: ShardIndexLocation::default(),
+            index_checksum: default_index_checksum(),
+        }
+    }
+}
+
+/// A hyperslab of a multi-dimensional array, containing `count` elements from index `start` in
+/// each dimension
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hyperslab {
+    /// Index of the first element in each dimension
+    pub start: Vec<usize>,
+    /// Number of elements in each dimension
+    pub count: Vec<usize>,
+}
+
+#[cfg(test)]
+impl Hyperslab {
+    /// Return a new Hyperslab object.
+    pub fn new(start: Vec<usize>, count: Vec<usize>) -> Self {
+        Hyperslab { start, count }
+    }
+}
+
+/// Protocol used to access an object
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// S3 API, addressing an object by `bucket` and `object` within an object store
+    #[default]
+    S3,
+    /// Plain HTTP(S), using `source` as the URL of the object
+    Http,
+}
+
+/// Units of the offset and size of the numerical data
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Bytes
+    #[default]
+    Bytes,
+    /// Array elements of the data type
+    Elements,
+}
+
 /// Compression algorithm
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "id")]
 pub enum Compression {
@@ -96,58 +272,586 @@ pub enum Compression {
     Gzip,
     /// Zlib
     Zlib,
+    /// Raw DEFLATE, without a zlib or gzip header
+    Deflate,
+    /// Brotli
+    Brotli,
+    /// Snappy, in either the raw format or the framed format if `framed` is true
+    Snappy {
+        #[serde(default)]
+        framed: bool,
+    },
 }
 
 /// Filter algorithm
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "id")]
 pub enum Filter {
     /// Byte shuffle
-    Shuffle { element_size: usize },
+    ///
+    /// The element size defaults to the size of the stored data type.
+    Shuffle {
+        #[serde(default)]
+        element_size: Option<usize>,
+    },
+    /// Numcodecs Quantize
+    ///
+    /// Quantization is a lossy encoding: the quantized values are stored as floating point
+    /// numbers, so there is nothing to undo when decoding. Other Numcodecs parameters such as
+    /// `dtype` and `astype` are accepted but ignored.
+    Quantize { digits: usize },
+    /// Numcodecs FixedScaleOffset
+    ///
+    /// Values are stored as `round((x - offset) * scale)` in the `astype` data type, and decoded
+    /// to the data type of the request. Other Numcodecs parameters such as `dtype` are accepted
+    /// but ignored, since the data type of the request describes the decoded data.
+    FixedScaleOffset {
+        offset: f64,
+        scale: f64,
+        astype: DType,
+    },
+    /// Bit-packed boolean values
+    ///
+    /// Each element is stored as a single bit, most significant bit first, as written by NumPy's
+    /// `packbits`, and decoded to a value of 0 or 1 in the data type of the request. The number of
+    /// elements is given by the shape, and any padding bits at the end of the data are ignored.
+    Bitpacked,
+    /// Numcodecs PackBits
+    ///
+    /// As for `Bitpacked`, preceded by a byte containing the number of padding bits at the end of
+    /// the data, so that the number of elements is known without a shape.
+    PackBits,
+}
+
+impl Filter {
+    /// Validate a Filter object against the data type of the request and the data type of the
+    /// data as stored.
+    pub fn validate(&self, dtype: DType, stored_dtype: DType) -> Result<(), ValidationError> {
+        match self {
+            Filter::Shuffle { element_size } => {
+                if let Some(element_size) = element_size {
+                    if *element_size != stored_dtype.size_of() {
+                        let mut error = ValidationError::new(
+                            "Shuffle element_size must match the size of the stored data type",
+                        );
+                        error.add_param("element_size".into(), element_size);
+                        error.add_param("dtype size".into(), &stored_dtype.size_of());
+                        return Err(error);
+                    }
+                }
+            }
+            Filter::FixedScaleOffset {
+                offset,
+                scale,
+                astype: _,
+            } => {
+                if !offset.is_finite() || !scale.is_finite() || *scale == 0.0 {
+                    return Err(ValidationError::new(
+                        "FixedScaleOffset offset and scale must be finite and scale non-zero",
+                    ));
+                }
+                if !dtype.is_float() {
+                    return Err(ValidationError::new(
+                        "FixedScaleOffset filter requires a floating point data type",
+                    ));
+                }
+            }
+            Filter::Quantize { digits } => {
+                if *digits == 0 {
+                    return Err(ValidationError::new(
+                        "Quantize digits must be greater than 0",
+                    ));
+                }
+                if !dtype.is_float() {
+                    return Err(ValidationError::new(
+                        "Quantize filter requires a floating point data type",
+                    ));
+                }
+            }
+            Filter::Bitpacked | Filter::PackBits => (),
+        };
+        Ok(())
+    }
 }
 
 /// Request data for operations
-#[derive(Debug, Deserialize, PartialEq, Validate)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_request_data"))]
 pub struct RequestData {
-    /// URL of the S3-compatible object store
+    /// URL of the S3-compatible object store, or of the object if `protocol` is `http`
     // TODO: Investigate using lifetimes to enable zero-copy: https://serde.rs/lifetimes.html
     pub source: Url,
-    /// S3 bucket containing the object
-    #[validate(length(min = 1, message = "bucket must not be empty"))]
+    /// Protocol used to access the object. Default is `s3`.
+    pub protocol: Option<Protocol>,
+    /// Whether `source` is a presigned URL of the object, which is fetched directly without
+    /// signing the request or sending credentials. Default is to detect presigned URLs by their
+    /// query string signature. Must not be specified if `protocol` is `http`.
+    pub presigned: Option<bool>,
+    /// Region used to sign requests to the S3-compatible object store. Default is the server's
+    /// `--s3-region`. Must not be specified if `protocol` is `http`. May contain only ASCII
+    /// letters, digits, `-`, `_` and `.`, up to [MAX_REGION_LENGTH] characters.
+    #[validate(
+        length(min = 1, message = "region must not be empty"),
+        custom = "validate_region"
+    )]
+    pub region: Option<String>,
+    /// S3 bucket containing the object. Must not be specified if `protocol` is `http`. May be
+    /// omitted if the bucket is embedded in a virtual-hosted-style `source`.
+    #[serde(default)]
     pub bucket: String,
-    /// S3 object containing the data
-    #[validate(length(min = 1, message = "object must not be empty"))]
+    /// S3 object containing the data. Must not be specified if `protocol` is `http`.
+    #[serde(default)]
     pub object: String,
+    /// Token of a session created using `/v1/session`, whose pinned data for the object is used
+    /// instead of downloading it. `offset`, `size`, `ranges` and `shard` are applied to the
+    /// pinned data.
+    #[validate(length(min = 1, message = "session must not be empty"))]
+    pub session: Option<String>,
     /// Data type
     pub dtype: DType,
     /// Byte order of data
     pub byte_order: Option<ByteOrder>,
-    /// Offset in bytes of the numerical data within the object
+    /// Offset of the numerical data within the object, in `units`
     pub offset: Option<usize>,
-    /// Size in bytes of the numerical data from the offset
+    /// Size in bytes of a header preceding the numerical data, which is skipped in addition to
+    /// `offset`, and is not included in `size`
+    pub skip_header_bytes: Option<usize>,
+    /// Size of the numerical data from the offset, in `units`
     #[validate(range(min = 1, message = "size must be greater than 0"))]
     pub size: Option<usize>,
+    /// Units of `offset` and `size`. Default is bytes.
+    pub units: Option<Units>,
+    /// Part number of a multipart S3 object to download, instead of the whole object. If `offset`
+    /// and `size` are also specified, they must match the byte range of the part.
+    #[validate(range(
+        min = 1,
+        max = 10000,
+        message = "part_number must be between 1 and 10000"
+    ))]
+    pub part_number: Option<i32>,
+    /// List of byte ranges of the numerical data within the object, as an alternative to offset
+    /// and size. The ranges are downloaded and concatenated before processing.
+    #[validate]
+    #[validate(length(min = 1, message = "ranges length must be greater than 0"))]
+    pub ranges: Option<Vec<ByteRange>>,
+    /// Zarr v3 shard containing the data. If specified, the inner chunk is located using the
+    /// shard index, and `shape`, `compression` and `filters` describe the inner chunk.
+    #[validate]
+    pub shard: Option<Shard>,
     /// Shape of the multi-dimensional array
     #[validate(
         length(min = 1, message = "shape length must be greater than 0"),
         custom = "validate_shape"
     )]
     pub shape: Option<Vec<usize>>,
+    /// Shape with which the multi-dimensional array is stored, if larger than `shape`, such as a
+    /// chunk at the edge of an array that is padded to the full chunk shape. The data is laid out
+    /// with this shape, and only the region of `shape` at the start of each axis is used.
+    #[validate(
+        length(min = 1, message = "stored_shape length must be greater than 0"),
+        custom = "validate_shape"
+    )]
+    pub stored_shape: Option<Vec<usize>>,
     /// Order of the multi-dimensional array
     pub order: Option<Order>,
     /// Subset of the data to operate on
     #[validate]
     #[validate(length(min = 1, message = "selection length must be greater than 0"))]
     pub selection: Option<Vec<Slice>>,
+    /// Flat indices of the elements of the array to operate on, in C order (row major) of the
+    /// shape, as an alternative to `selection`. The elements are gathered into a 1D array in the
+    /// order of the indices before the operation is performed.
+    #[validate(length(min = 1, message = "indices length must be greater than 0"))]
+    pub indices: Option<Vec<usize>>,
+    /// Hyperslab of the array to operate on, as an alternative to `size` and `selection`. Only the
+    /// byte range of the object containing the hyperslab is downloaded.
+    pub hyperslab: Option<Hyperslab>,
+    /// Axis of the selection along which to reduce, returning a result for each lane along the
+    /// axis rather than a scalar. Not supported by all operations
+    pub axis: Option<usize>,
+    /// Whether to download only the byte ranges of the object needed for the selection, rather
+    /// than the whole array. Only used by the select operation, and rejected by others
+    pub sparse_fetch: Option<bool>,
     /// Compression filter name
     pub compression: Option<Compression>,
     /// List of filter algorithms
     pub filters: Option<Vec<Filter>>,
     /// Missing data
     pub missing: Option<Missing<DValue>>,
+    /// Fill value of uninitialised elements, such as a Zarr `fill_value`. Elements equal to the
+    /// fill value are excluded in the same way as missing data, but are not counted as missing.
+    pub fill_value: Option<DValue>,
+    /// Data type used to accumulate and return the sum. Must be `int64` or `float64`. Only used by
+    /// the sum operation, and rejected by others
+    pub accumulate_dtype: Option<DType>,
+    /// Data type to which selected elements are cast before being returned. Only used by the
+    /// select operation, and rejected by others
+    pub output_dtype: Option<DType>,
+    /// Ordering of the selected elements in the response. Default is C. Only used by the select
+    /// operation, and rejected by others
+    pub output_order: Option<OutputOrder>,
+    /// Lower bound to which elements are clamped before a sum. Only used by the sum operation,
+    /// and rejected by others
+    pub clip_min: Option<DValue>,
+    /// Upper bound to which elements are clamped before a sum. Only used by the sum operation,
+    /// and rejected by others
+    pub clip_max: Option<DValue>,
+    /// Whether a sum of floating point data fails if any non-missing selected element is NaN or
+    /// infinite, rather than returning a non-finite result. Only used by the sum operation, and
+    /// rejected by others
+    pub reject_nonfinite: Option<bool>,
+    /// Percentile to compute, between 0 and 100. Only used by the approx_percentile operation,
+    /// and rejected by others
+    #[validate(range(
+        min = 0.0,
+        max = 100.0,
+        message = "percentile must be between 0 and 100"
+    ))]
+    pub percentile: Option<f64>,
+    /// Fraction of elements to discard from each end before averaging, between 0 and 0.5. Only
+    /// used by the trimmed_mean operation, and rejected by others
+    #[validate(range(
+        min = 0.0,
+        max = 0.5,
+        message = "trim fraction must be between 0 and 0.5"
+    ))]
+    pub trim_fraction: Option<f64>,
+    /// S3 bucket containing the weights for a weighted sum. Default is `bucket`.
+    #[validate(length(min = 1, message = "weights bucket must not be empty"))]
+    pub weights_bucket: Option<String>,
+    /// S3 object containing the weights for a weighted sum, with the same shape as the data
+    #[validate(length(min = 1, message = "weights object must not be empty"))]
+    pub weights_object: Option<String>,
+    /// Data type of the weights for a weighted sum. Default is `dtype`.
+    pub weights_dtype: Option<DType>,
+    /// S3 bucket containing the mask of elements to operate on. Default is `bucket`.
+    #[validate(length(min = 1, message = "mask bucket must not be empty"))]
+    pub mask_bucket: Option<String>,
+    /// S3 object containing the mask of elements to operate on, with the same shape and order as
+    /// the data and one byte per element, such as a NumPy boolean array. Elements whose mask byte
+    /// is non-zero are operated on, excluding any missing data.
+    #[validate(length(min = 1, message = "mask object must not be empty"))]
+    pub mask_object: Option<String>,
+    /// Expected peak memory use of the request in bytes, reserved instead of the server's estimate
+    /// of the memory required if it is larger, for example to account for a large `select`
+    /// result. Must not exceed the memory limit of the server.
+    #[validate(range(min = 1, message = "expected memory must be greater than 0"))]
+    pub expected_memory: Option<usize>,
+}
+
+impl RequestData {
+    /// Returns the offset of the numerical data within the object in bytes, including any
+    /// skipped header, if specified.
+    pub fn offset_bytes(&self) -> Option<usize> {
+        match (self.offset, self.skip_header_bytes) {
+            (None, None) => None,
+            (offset, skip_header_bytes) => Some(
+                offset.map_or(0, |offset| self.to_bytes(offset)) + skip_header_bytes.unwrap_or(0),
+            ),
+        }
+    }
+
+    /// Returns the size of the numerical data in bytes, if specified.
+    pub fn size_bytes(&self) -> Option<usize> {
+        self.size.map(|size| self.to_bytes(size))
+    }
+
+    /// Returns the data type of the data as stored, before decoding any FixedScaleOffset filter.
+    pub fn stored_dtype(&self) -> DType {
+        self.filters
+            .iter()
+            .flatten()
+            .find_map(|filter| match filter {
+                Filter::FixedScaleOffset { astype, .. } => Some(*astype),
+                _ => None,
+            })
+            .unwrap_or(self.dtype)
+    }
+
+    /// Returns the Bitpacked or PackBits filter of bit-packed data, if specified.
+    fn bitpacked_filter(&self) -> Option<&Filter> {
+        self.filters
+            .iter()
+            .flatten()
+            .find(|filter| matches!(filter, Filter::Bitpacked | Filter::PackBits))
+    }
+
+    /// Returns whether the data is bit-packed, using a Bitpacked or PackBits filter.
+    pub fn is_bitpacked(&self) -> bool {
+        self.bitpacked_filter().is_some()
+    }
+
+    /// Returns the size in bytes of a number of elements of the data as stored, before decoding
+    /// any FixedScaleOffset, Bitpacked or PackBits filter.
+    pub fn stored_size(&self, elements: usize) -> usize {
+        match self.bitpacked_filter() {
+            // PackBits data starts with a byte containing the number of padding bits.
+            Some(Filter::PackBits) => 1 + elements.div_ceil(8),
+            Some(_) => elements.div_ceil(8),
+            None => elements * self.stored_dtype().size_of(),
+        }
+    }
+
+    /// Converts a quantity in `units` to bytes.
+    fn to_bytes(&self, value: usize) -> usize {
+        match self.units.unwrap_or_default() {
+            Units::Bytes => value,
+            Units::Elements => value * self.stored_dtype().size_of(),
+        }
+    }
+
+    /// Returns request data describing the weights object for a weighted sum, if specified.
+    ///
+    /// The weights are read from the whole object without compression or filters, and share the
+    /// shape, order, selection and byte order of the data.
+    pub fn weights_request_data(&self) -> Option<RequestData> {
+        let weights_object = self.weights_object.as_ref()?;
+        let mut request_data = self.clone();
+        if let Some(weights_bucket) = &self.weights_bucket {
+            request_data.bucket.clone_from(weights_bucket);
+        }
+        request_data.object.clone_from(weights_object);
+        request_data.session = None;
+        request_data.dtype = self.weights_dtype.unwrap_or(self.dtype);
+        request_data.offset = None;
+        request_data.skip_header_bytes = None;
+        request_data.size = None;
+        request_data.units = None;
+        request_data.part_number = None;
+        request_data.ranges = None;
+        request_data.shard = None;
+        request_data.compression = None;
+        request_data.filters = None;
+        request_data.missing = None;
+        request_data.fill_value = None;
+        request_data.sparse_fetch = None;
+        request_data.output_dtype = None;
+        request_data.output_order = None;
+        Some(request_data)
+    }
+
+    /// Returns request data describing the mask object, if specified.
+    ///
+    /// The mask is read from the whole object without compression or filters, and shares the
+    /// shape and order of the data.
+    pub fn mask_request_data(&self) -> Option<RequestData> {
+        let mask_object = self.mask_object.as_ref()?;
+        let mut request_data = self.clone();
+        if let Some(mask_bucket) = &self.mask_bucket {
+            request_data.bucket.clone_from(mask_bucket);
+        }
+        request_data.object.clone_from(mask_object);
+        request_data.session = None;
+        request_data.offset = None;
+        request_data.skip_header_bytes = None;
+        request_data.size = None;
+        request_data.units = None;
+        request_data.part_number = None;
+        request_data.ranges = None;
+        request_data.shard = None;
+        request_data.stored_shape = None;
+        request_data.compression = None;
+        request_data.filters = None;
+        request_data.missing = None;
+        request_data.fill_value = None;
+        request_data.mask_bucket = None;
+        request_data.mask_object = None;
+        Some(request_data)
+    }
+
+    /// Converts a virtual-hosted-style source with an embedded bucket to a path-style source and
+    /// an explicit bucket. Other request data is unchanged.
+    pub fn path_style(&mut self) {
+        if self.protocol.unwrap_or_default() == Protocol::S3 && !self.is_presigned() {
+            path_style(&mut self.source, &mut self.bucket);
+        }
+    }
+
+    /// Returns whether the source is a presigned URL of the object.
+    pub fn is_presigned(&self) -> bool {
+        self.protocol.unwrap_or_default() == Protocol::S3
+            && self
+                .presigned
+                .unwrap_or_else(|| s3_client::is_presigned_url(&self.source))
+    }
+
+    /// Returns the shape with which the data is laid out: the stored shape if specified, or
+    /// otherwise the shape.
+    pub fn layout_shape(&self) -> &Option<Vec<usize>> {
+        if self.stored_shape.is_some() {
+            &self.stored_shape
+        } else {
+            &self.shape
+        }
+    }
+
+    /// Check the shape of the request data against server-configured limits on the number of
+    /// dimensions and elements, before any data is downloaded or allocated.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_dimensions`: Maximum number of dimensions
+    /// * `max_elements`: Optional maximum number of elements
+    pub fn check_shape_limits(
+        &self,
+        max_dimensions: usize,
+        max_elements: Option<usize>,
+    ) -> Result<(), ValidationError> {
+        let Some(shape) = self.layout_shape() else {
+            return Ok(());
+        };
+        if shape.len() > max_dimensions {
+            let mut error = ValidationError::new("shape has too many dimensions");
+            error.add_param("dimensions".into(), &shape.len());
+            error.add_param("max dimensions".into(), &max_dimensions);
+            return Err(error);
+        }
+        if let Some(max_elements) = max_elements {
+            let elements = shape_elements(shape);
+            if elements.map_or(true, |elements| elements > max_elements) {
+                let mut error = ValidationError::new("shape has too many elements");
+                error.add_param("elements".into(), &elements);
+                error.add_param("max elements".into(), &max_elements);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts a hyperslab to the byte range of the object containing it, and the shape and
+    /// selection of the hyperslab within that range. Other request data is unchanged.
+    pub fn resolve_hyperslab(&mut self) {
+        let (Some(hyperslab), Some(shape)) = (self.hyperslab.take(), &self.shape) else {
+            return;
+        };
+        let (range, shape, selection) =
+            array::hyperslab_byte_range(shape, self.order, &hyperslab, self.dtype.size_of());
+        self.offset = Some(self.offset_bytes().unwrap_or(0) + range.offset);
+        self.skip_header_bytes = None;
+        self.size = Some(range.size);
+        self.units = None;
+        self.shape = Some(shape);
+        self.selection = Some(selection);
+    }
+
+    /// Sets the shape of a 1D array with the number of elements given by `size`, if the shape is
+    /// not specified. Other request data is unchanged.
+    ///
+    /// The shape is not set if the number of elements is not known before the data is downloaded,
+    /// i.e. if `size` is not specified, or refers to compressed data, a shard or bit-packed data,
+    /// in which case the shape is a 1D array of all the data. Validation ensures that the size is
+    /// a multiple of the size of the stored data type.
+    pub fn resolve_shape(&mut self) {
+        if self.shape.is_some()
+            || self.compression.is_some()
+            || self.shard.is_some()
+            || self.is_bitpacked()
+        {
+            return;
+        }
+        if let Some(size) = self.size_bytes() {
+            self.shape = Some(vec![size / self.stored_dtype().size_of()]);
+        }
+    }
+
+    /// Validate that the request data does not specify fields that are only used by other
+    /// operations, which would otherwise be silently ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation`: Name of the operation
+    pub fn validate_operation(&self, operation: &str) -> Result<(), ValidationError> {
+        let fields: [(&str, bool, &[&str]); 9] = [
+            ("sparse_fetch", self.sparse_fetch.is_some(), &["select"]),
+            (
+                "accumulate_dtype",
+                self.accumulate_dtype.is_some(),
+                &["sum"],
+            ),
+            ("output_dtype", self.output_dtype.is_some(), &["select"]),
+            ("output_order", self.output_order.is_some(), &["select"]),
+            ("clip_min", self.clip_min.is_some(), &["sum"]),
+            ("clip_max", self.clip_max.is_some(), &["sum"]),
+            (
+                "reject_nonfinite",
+                self.reject_nonfinite.is_some(),
+                &["sum"],
+            ),
+            (
+                "percentile",
+                self.percentile.is_some(),
+                &["approx_percentile"],
+            ),
+            (
+                "trim_fraction",
+                self.trim_fraction.is_some(),
+                &["trimmed_mean"],
+            ),
+        ];
+        for (field, specified, operations) in fields {
+            if specified && !operations.contains(&operation) {
+                let mut error = ValidationError::new("Field is not used by the operation");
+                error.add_param("field".into(), &field);
+                error.add_param("operation".into(), &operation);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the request data with defaults applied to optional fields that have them.
+    ///
+    /// Virtual-hosted-style sources are converted to path-style.
+    pub fn normalized(&self) -> RequestData {
+        let mut request_data = self.clone();
+        request_data.path_style();
+        request_data.protocol = Some(self.protocol.unwrap_or_default());
+        request_data.byte_order = Some(self.byte_order.unwrap_or(NATIVE_BYTE_ORDER));
+        request_data.units = Some(self.units.unwrap_or_default());
+        request_data.order = Some(self.order.unwrap_or(Order::C));
+        request_data
+    }
+}
+
+/// Converts a virtual-hosted-style source with an embedded bucket to a path-style source and
+/// bucket, if no bucket is specified.
+fn path_style(source: &mut Url, bucket: &mut String) {
+    if bucket.is_empty() {
+        if let Some((endpoint, embedded_bucket)) = s3_client::virtual_hosted_bucket(source) {
+            *source = endpoint;
+            *bucket = embedded_bucket;
+        }
+    }
+}
+
+/// Validate that either an explicit bucket or a virtual-hosted-style source specifies the bucket.
+///
+/// The bucket is only inferred from the source if no bucket is specified, so an explicit bucket is
+/// used with any source.
+fn validate_bucket(source: &Url, bucket: &str) -> Result<(), ValidationError> {
+    if bucket.is_empty() && s3_client::virtual_hosted_bucket(source).is_none() {
+        return Err(ValidationError::new("bucket must not be empty"));
+    }
+    Ok(())
+}
+
+/// Maximum length of a region.
+pub const MAX_REGION_LENGTH: usize = 64;
+
+/// Validate a region, which is used in the key of the S3 client cache and in signed requests.
+fn validate_region(region: &str) -> Result<(), ValidationError> {
+    if region.len() > MAX_REGION_LENGTH
+        || !region
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(ValidationError::new(
+            "region must contain only letters, digits, '-', '_' and '.', up to 64 characters",
+        ));
+    }
+    Ok(())
 }
 
 /// Validate an array shape
@@ -155,9 +859,22 @@ fn validate_shape(shape: &[usize]) -> Result<(), ValidationError> {
     if shape.iter().any(|index| *index == 0) {
         return Err(ValidationError::new("shape indices must be greater than 0"));
     }
+    // The size of the array in bytes must fit in an isize for the largest data type, so that
+    // later size calculations cannot overflow.
+    let max_elements = isize::MAX as usize / std::mem::size_of::<f64>();
+    if shape_elements(shape).map_or(true, |elements| elements > max_elements) {
+        return Err(ValidationError::new("shape has too many elements"));
+    }
     Ok(())
 }
 
+/// Returns the number of elements in an array shape, or `None` on overflow.
+fn shape_elements(shape: &[usize]) -> Option<usize> {
+    shape
+        .iter()
+        .try_fold(1_usize, |elements, index| elements.checked_mul(*index))
+}
+
 /// Validate an array slice
 fn validate_slice(slice: &Slice) -> Result<(), ValidationError> {
     if slice.stride == 0 {
@@ -179,6 +896,121 @@ fn validate_shape_selection(shape: &[usize], selection: &[Slice]) -> Result<(),
     Ok(())
 }
 
+/// Validate the stored shape against the shape and other fields of the request data
+fn validate_stored_shape(
+    request_data: &RequestData,
+    stored_shape: &[usize],
+) -> Result<(), ValidationError> {
+    let Some(shape) = &request_data.shape else {
+        return Err(ValidationError::new(
+            "Stored shape requires shape to be specified",
+        ));
+    };
+    if request_data.hyperslab.is_some() {
+        return Err(ValidationError::new(
+            "Stored shape cannot be specified with hyperslab",
+        ));
+    }
+    if shape.len() != stored_shape.len() {
+        let mut error = ValidationError::new("Shape and stored shape must have the same length");
+        error.add_param("shape".into(), &shape.len());
+        error.add_param("stored_shape".into(), &stored_shape.len());
+        return Err(error);
+    }
+    if std::iter::zip(shape, stored_shape).any(|(length, stored_length)| length > stored_length) {
+        let mut error = ValidationError::new("Shape must not exceed stored shape");
+        error.add_param("shape".into(), shape);
+        error.add_param("stored_shape".into(), &stored_shape);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Validate flat indices against the shape and other fields of the request data
+fn validate_indices(request_data: &RequestData, indices: &[usize]) -> Result<(), ValidationError> {
+    if request_data.selection.is_some()
+        || request_data.hyperslab.is_some()
+        || request_data.axis.is_some()
+        || request_data.sparse_fetch.is_some()
+        || request_data.weights_object.is_some()
+    {
+        return Err(ValidationError::new(
+            "Indices cannot be specified with selection, hyperslab, axis, sparse_fetch or weights",
+        ));
+    }
+    // Indices are checked against the number of elements when the data is decoded if the shape is
+    // not specified.
+    if let Some(elements) = request_data.shape.as_deref().and_then(shape_elements) {
+        validate_indices_in_range(indices, elements)?;
+    }
+    Ok(())
+}
+
+/// Validate that flat indices are within an array with a number of elements.
+///
+/// # Arguments
+///
+/// * `indices`: Flat indices
+/// * `elements`: Number of elements in the array
+pub fn validate_indices_in_range(
+    indices: &[usize],
+    elements: usize,
+) -> Result<(), ValidationError> {
+    if let Some(index) = indices.iter().find(|index| **index >= elements) {
+        let mut error = ValidationError::new("Indices must be less than the number of elements");
+        error.add_param("index".into(), index);
+        error.add_param("elements".into(), &elements);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Validate a hyperslab against the shape and other fields of the request data
+fn validate_hyperslab(
+    request_data: &RequestData,
+    hyperslab: &Hyperslab,
+) -> Result<(), ValidationError> {
+    let Some(shape) = &request_data.shape else {
+        return Err(ValidationError::new(
+            "Hyperslab requires shape to be specified",
+        ));
+    };
+    if request_data.size.is_some()
+        || request_data.ranges.is_some()
+        || request_data.shard.is_some()
+        || request_data.selection.is_some()
+        || request_data.compression.is_some()
+        || request_data.filters.is_some()
+        || request_data.weights_object.is_some()
+    {
+        return Err(ValidationError::new(
+            "Hyperslab cannot be specified with size, ranges, shard, selection, compression, filters or weights",
+        ));
+    }
+    if hyperslab.start.len() != shape.len() || hyperslab.count.len() != shape.len() {
+        let mut error =
+            ValidationError::new("Hyperslab start and count must have the same length as shape");
+        error.add_param("shape".into(), &shape.len());
+        error.add_param("start".into(), &hyperslab.start.len());
+        error.add_param("count".into(), &hyperslab.count.len());
+        return Err(error);
+    }
+    if hyperslab.count.contains(&0) {
+        return Err(ValidationError::new(
+            "Hyperslab count must be greater than 0",
+        ));
+    }
+    let within_shape = std::iter::zip(&hyperslab.start, &hyperslab.count)
+        .zip(shape)
+        .all(|((start, count), length)| {
+            start.checked_add(*count).is_some_and(|end| end <= *length)
+        });
+    if !within_shape {
+        return Err(ValidationError::new("Hyperslab must be within shape"));
+    }
+    Ok(())
+}
+
 /// Validate raw data size against data type and shape.
 ///
 /// # Arguments
@@ -212,14 +1044,182 @@ pub fn validate_raw_size(
     Ok(())
 }
 
+/// Validate the size in bytes of the data as stored against the shape, if specified.
+///
+/// Bit-packed data has one bit per element, padded to a whole number of bytes, with an additional
+/// header byte for PackBits.
+fn validate_stored_size(request_data: &RequestData, size: usize) -> Result<(), ValidationError> {
+    if !request_data.is_bitpacked() {
+        return validate_raw_size(
+            size,
+            request_data.stored_dtype(),
+            request_data.layout_shape(),
+        );
+    }
+    if let Some(shape) = request_data.layout_shape() {
+        let expected_size = request_data.stored_size(shape.iter().product());
+        if size != expected_size {
+            let mut error = ValidationError::new(
+                "Bit-packed data size must be the product of shape indices divided by 8, rounded up, plus any header",
+            );
+            error.add_param("raw size".into(), &size);
+            error.add_param("expected size".into(), &expected_size);
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
 /// Validate request data
 fn validate_request_data(request_data: &RequestData) -> Result<(), ValidationError> {
     // Validation of multiple fields in RequestData.
-    if let Some(size) = &request_data.size {
+    match request_data.protocol.unwrap_or_default() {
+        Protocol::S3 if request_data.is_presigned() => {
+            if !request_data.bucket.is_empty() || !request_data.object.is_empty() {
+                return Err(ValidationError::new(
+                    "Bucket and object cannot be specified with a presigned source",
+                ));
+            }
+            if request_data.region.is_some() {
+                return Err(ValidationError::new(
+                    "Region cannot be specified with a presigned source",
+                ));
+            }
+        }
+        Protocol::S3 => {
+            validate_bucket(&request_data.source, &request_data.bucket)?;
+            if request_data.object.is_empty() {
+                return Err(ValidationError::new("object must not be empty"));
+            }
+        }
+        Protocol::Http => {
+            if request_data.presigned.is_some() {
+                return Err(ValidationError::new(
+                    "Presigned cannot be specified with the http protocol",
+                ));
+            }
+            if !request_data.bucket.is_empty() || !request_data.object.is_empty() {
+                return Err(ValidationError::new(
+                    "Bucket and object cannot be specified with the http protocol",
+                ));
+            }
+            if request_data.region.is_some() {
+                return Err(ValidationError::new(
+                    "Region cannot be specified with the http protocol",
+                ));
+            }
+        }
+    };
+    if request_data.units == Some(Units::Elements) {
+        if request_data.compression.is_some()
+            || request_data.ranges.is_some()
+            || request_data.shard.is_some()
+        {
+            return Err(ValidationError::new(
+                "Units of elements cannot be specified with compression, ranges or shard",
+            ));
+        }
+        let dtype_size = request_data.stored_dtype().size_of();
+        for value in [request_data.offset, request_data.size]
+            .into_iter()
+            .flatten()
+        {
+            if value.checked_mul(dtype_size).is_none() {
+                let mut error = ValidationError::new("Offset and size in elements are too large");
+                error.add_param("value".into(), &value);
+                return Err(error);
+            }
+        }
+    };
+    if let Some(skip_header_bytes) = request_data.skip_header_bytes {
+        if request_data.ranges.is_some()
+            || request_data.shard.is_some()
+            || request_data.part_number.is_some()
+        {
+            return Err(ValidationError::new(
+                "Skip header bytes cannot be specified with ranges, shard or part_number",
+            ));
+        }
+        let offset = request_data
+            .offset
+            .map_or(0, |offset| request_data.to_bytes(offset));
+        if offset.checked_add(skip_header_bytes).is_none() {
+            let mut error = ValidationError::new("Offset and skip header bytes are too large");
+            error.add_param("skip_header_bytes".into(), &skip_header_bytes);
+            return Err(error);
+        }
+    };
+    if let Some(size) = request_data.size_bytes() {
         // If the data is compressed then the size refers to the size of the compressed data, so we
-        // can't validate it at this point.
+        // can't validate it at this point. Similarly the size of a shard includes its index and
+        // other inner chunks.
+        if request_data.compression.is_none() && request_data.shard.is_none() {
+            validate_stored_size(request_data, size)?;
+        }
+    };
+    if let Some(ranges) = &request_data.ranges {
+        if request_data.offset.is_some() || request_data.size.is_some() {
+            return Err(ValidationError::new(
+                "Ranges cannot be specified with offset or size",
+            ));
+        }
         if request_data.compression.is_none() {
-            validate_raw_size(*size, request_data.dtype, &request_data.shape)?;
+            let size = ranges.iter().map(|range| range.size).sum();
+            validate_stored_size(request_data, size)?;
+        }
+    };
+    if request_data.part_number.is_some() {
+        if request_data.protocol == Some(Protocol::Http) || request_data.is_presigned() {
+            return Err(ValidationError::new(
+                "Part number cannot be specified with the http protocol or a presigned source",
+            ));
+        }
+        if request_data.ranges.is_some()
+            || request_data.shard.is_some()
+            || request_data.hyperslab.is_some()
+        {
+            return Err(ValidationError::new(
+                "Part number cannot be specified with ranges, shard or hyperslab",
+            ));
+        }
+        if request_data.offset.is_some() != request_data.size.is_some() {
+            return Err(ValidationError::new(
+                "Part number requires both or neither of offset and size",
+            ));
+        }
+    };
+    if request_data.session.is_some()
+        && (request_data.protocol == Some(Protocol::Http)
+            || request_data.is_presigned()
+            || request_data.part_number.is_some())
+    {
+        return Err(ValidationError::new(
+            "Session cannot be specified with the http protocol, a presigned source or part_number",
+        ));
+    }
+    if let Some(shard) = &request_data.shard {
+        if request_data.ranges.is_some() {
+            return Err(ValidationError::new(
+                "Shard cannot be specified with ranges",
+            ));
+        }
+        if shard.chunk >= shard.chunks {
+            let mut error = ValidationError::new("Shard chunk must be less than shard chunks");
+            error.add_param("chunk".into(), &shard.chunk);
+            error.add_param("chunks".into(), &shard.chunks);
+            return Err(error);
+        }
+        if shard::index_size(shard).is_err() {
+            let mut error = ValidationError::new("Shard index is too large");
+            error.add_param("chunks".into(), &shard.chunks);
+            return Err(error);
+        }
+        let offset = request_data.offset.unwrap_or(0);
+        if request_data
+            .size
+            .is_some_and(|size| offset.checked_add(size).is_none())
+        {
+            return Err(ValidationError::new("Shard offset and size are too large"));
         }
     };
     match (&request_data.shape, &request_data.selection) {
@@ -233,23 +1233,445 @@ fn validate_request_data(request_data: &RequestData) -> Result<(), ValidationErr
         }
         _ => (),
     };
+    if let Some(stored_shape) = &request_data.stored_shape {
+        validate_stored_shape(request_data, stored_shape)?;
+    };
+    if let Some(indices) = &request_data.indices {
+        validate_indices(request_data, indices)?;
+    };
+    if let Some(hyperslab) = &request_data.hyperslab {
+        validate_hyperslab(request_data, hyperslab)?;
+    };
+    if let Some(axis) = request_data.axis {
+        let ndim = request_data.shape.as_ref().map_or(1, Vec::len);
+        if axis >= ndim {
+            let mut error = ValidationError::new("Axis must be less than the number of dimensions");
+            error.add_param("axis".into(), &axis);
+            error.add_param("ndim".into(), &ndim);
+            return Err(error);
+        }
+    };
+    if let Some(filters) = &request_data.filters {
+        for filter in filters {
+            filter.validate(request_data.dtype, request_data.stored_dtype())?;
+        }
+        let fixed_scale_offsets = filters
+            .iter()
+            .filter(|filter| matches!(filter, Filter::FixedScaleOffset { .. }))
+            .count();
+        if fixed_scale_offsets > 1 {
+            return Err(ValidationError::new(
+                "Only one FixedScaleOffset filter may be specified",
+            ));
+        }
+        if request_data.is_bitpacked() {
+            if filters.len() > 1 {
+                return Err(ValidationError::new(
+                    "Bit-packed filters cannot be combined with other filters",
+                ));
+            }
+            if request_data.units == Some(Units::Elements) {
+                return Err(ValidationError::new(
+                    "Units of elements cannot be specified with bit-packed filters",
+                ));
+            }
+        }
+    };
     if let Some(missing) = &request_data.missing {
         missing.validate(request_data.dtype)?;
     };
-    Ok(())
-}
-
-/// Response containing the result of a computation and associated metadata.
-pub struct Response {
-    /// Response data. May be a scalar or multi-dimensional array.
-    pub body: Bytes,
-    /// Data type of the response
-    pub dtype: DType,
-    /// Shape of the response
-    pub shape: Vec<usize>,
-    /// Number of non-missing elements operated on to generate response
-    pub count: i64,
-}
+    if request_data.weights_object.is_some() {
+        if request_data.protocol == Some(Protocol::Http) {
+            return Err(ValidationError::new(
+                "Weights cannot be specified with the http protocol",
+            ));
+        }
+        if request_data.is_presigned() {
+            return Err(ValidationError::new(
+                "Weights cannot be specified with a presigned source",
+            ));
+        }
+    } else if request_data.weights_bucket.is_some() || request_data.weights_dtype.is_some() {
+        return Err(ValidationError::new(
+            "Weights bucket and dtype require weights object to be specified",
+        ));
+    };
+    if request_data.mask_object.is_some() {
+        if request_data.protocol == Some(Protocol::Http) || request_data.is_presigned() {
+            return Err(ValidationError::new(
+                "Mask cannot be specified with the http protocol or a presigned source",
+            ));
+        }
+        if request_data.indices.is_some()
+            || request_data.selection.is_some()
+            || request_data.hyperslab.is_some()
+            || request_data.axis.is_some()
+            || request_data.sparse_fetch.is_some()
+            || request_data.weights_object.is_some()
+        {
+            return Err(ValidationError::new(
+                "Mask cannot be specified with indices, selection, hyperslab, axis, sparse_fetch or weights",
+            ));
+        }
+    } else if request_data.mask_bucket.is_some() {
+        return Err(ValidationError::new(
+            "Mask bucket requires mask object to be specified",
+        ));
+    };
+    match (request_data.accumulate_dtype, request_data.dtype) {
+        (None, _) | (Some(DType::Float64), _) => (),
+        (Some(DType::Int64), DType::Int32 | DType::Int64 | DType::Uint32) => (),
+        (Some(DType::Int64), _) => {
+            return Err(ValidationError::new(
+                "Accumulate dtype int64 requires a dtype of int32, int64 or uint32",
+            ))
+        }
+        (Some(_), _) => {
+            return Err(ValidationError::new(
+                "Accumulate dtype must be int64 or float64",
+            ))
+        }
+    };
+    if let Some(output_dtype) = request_data.output_dtype {
+        if request_data.dtype.is_float() && !output_dtype.is_float() {
+            return Err(ValidationError::new(
+                "Output dtype must be a floating point type for floating point data",
+            ));
+        }
+    };
+    match request_data.dtype {
+        DType::Int32 => validate_values::<i32>(request_data)?,
+        DType::Int64 => validate_values::<i64>(request_data)?,
+        DType::Uint32 => validate_values::<u32>(request_data)?,
+        DType::Uint64 => validate_values::<u64>(request_data)?,
+        DType::Float16 => validate_values::<half::f16>(request_data)?,
+        DType::Bfloat16 => validate_values::<half::bf16>(request_data)?,
+        DType::Float32 => validate_values::<f32>(request_data)?,
+        DType::Float64 => validate_values::<f64>(request_data)?,
+    };
+    Ok(())
+}
+
+/// Validate the clip bounds and fill value in the request data for a numeric type `T`.
+///
+/// Each bound and the fill value must be representable in the data type, and the lower bound
+/// must not be greater than the upper bound.
+fn validate_values<T: PartialOrd + Serialize + TryFromDValue>(
+    request_data: &RequestData,
+) -> Result<(), ValidationError> {
+    if let Some(fill_value) = &request_data.fill_value {
+        T::try_from_dvalue(fill_value.clone()).map_err(|err| {
+            let mut error = ValidationError::new("Fill value is invalid for the data type");
+            error.add_param("error".into(), &err.to_string());
+            error
+        })?;
+    };
+    let to_bound = |value: &Option<DValue>| {
+        value
+            .clone()
+            .map(T::try_from_dvalue)
+            .transpose()
+            .map_err(|err| {
+                let mut error = ValidationError::new("Clip bound is invalid for the data type");
+                error.add_param("error".into(), &err.to_string());
+                error
+            })
+    };
+    let min = to_bound(&request_data.clip_min)?;
+    let max = to_bound(&request_data.clip_max)?;
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            let mut error = ValidationError::new("Clip min must not be greater than clip max");
+            error.add_param("min".into(), &min);
+            error.add_param("max".into(), &max);
+            return Err(error);
+        }
+    };
+    Ok(())
+}
+
+/// Handling of failures of the operation on individual objects of a batch
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// The whole batch fails
+    #[default]
+    Fail,
+    /// The results of the other objects are combined, and the errors are reported in the
+    /// response
+    Skip,
+}
+
+/// Maximum number of requests in a batch.
+pub const MAX_BATCH_REQUESTS: usize = 1000;
+
+/// Request data for operations on a batch of objects.
+///
+/// The results of the operation on each object are combined into a single result, so all
+/// requests must have the same data type.
+#[derive(Debug, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_batch_request_data"))]
+pub struct BatchRequestData {
+    /// Request data for each object in the batch, up to [MAX_BATCH_REQUESTS]
+    #[validate]
+    pub requests: Vec<RequestData>,
+    /// Handling of failures for individual objects. Default is to fail the whole batch.
+    pub on_error: Option<OnError>,
+}
+
+/// Validate batch request data
+fn validate_batch_request_data(
+    batch_request_data: &BatchRequestData,
+) -> Result<(), ValidationError> {
+    let mut requests = batch_request_data.requests.iter();
+    let Some(first) = requests.next() else {
+        return Err(ValidationError::new(
+            "requests length must be greater than 0",
+        ));
+    };
+    if batch_request_data.requests.len() > MAX_BATCH_REQUESTS {
+        let mut error = ValidationError::new("requests length must not exceed the maximum");
+        error.add_param("max".into(), &MAX_BATCH_REQUESTS);
+        return Err(error);
+    }
+    if requests.any(|request_data| {
+        request_data.dtype != first.dtype || request_data.accumulate_dtype != first.accumulate_dtype
+    }) {
+        return Err(ValidationError::new(
+            "Batch requests must have the same dtype and accumulate_dtype",
+        ));
+    }
+    if batch_request_data
+        .requests
+        .iter()
+        .any(|request_data| request_data.axis.is_some())
+    {
+        return Err(ValidationError::new(
+            "Batch requests cannot be specified with axis",
+        ));
+    }
+    Ok(())
+}
+
+/// Request data for the object information endpoint.
+#[derive(Debug, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_info_request_data"))]
+pub struct InfoRequestData {
+    /// URL of the S3-compatible object store
+    pub source: Url,
+    /// Region used to sign requests to the object store. Default is the server's `--s3-region`.
+    /// Validated as for [RequestData::region].
+    #[validate(
+        length(min = 1, message = "region must not be empty"),
+        custom = "validate_region"
+    )]
+    pub region: Option<String>,
+    /// S3 bucket containing the object. May be omitted if the bucket is embedded in a
+    /// virtual-hosted-style `source`.
+    #[serde(default)]
+    pub bucket: String,
+    /// S3 object to describe
+    #[validate(length(min = 1, message = "object must not be empty"))]
+    pub object: String,
+}
+
+impl InfoRequestData {
+    /// Converts a virtual-hosted-style source with an embedded bucket to a path-style source and
+    /// an explicit bucket.
+    pub fn path_style(&mut self) {
+        path_style(&mut self.source, &mut self.bucket);
+    }
+}
+
+/// Validate info request data
+fn validate_info_request_data(request_data: &InfoRequestData) -> Result<(), ValidationError> {
+    validate_bucket(&request_data.source, &request_data.bucket)
+}
+
+/// Metadata describing an object in object storage.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ObjectInfo {
+    /// Size of the object in bytes
+    pub content_length: usize,
+    /// Time at which the object was last modified, in RFC 3339 format
+    pub last_modified: Option<String>,
+    /// Entity tag of the object
+    pub etag: Option<String>,
+}
+
+/// Request data for the estimate endpoint.
+#[derive(Debug, Deserialize, PartialEq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct EstimateRequestData {
+    /// Name of the operation to estimate, e.g. `sum`
+    pub operation: String,
+    /// Request data for the operation
+    #[validate]
+    pub request: RequestData,
+}
+
+/// Estimate of the data reduction of an operation, returned by the estimate endpoint.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Estimate {
+    /// Number of bytes of the object that would be downloaded
+    pub download_bytes: usize,
+    /// Number of bytes that would be returned in the response body
+    pub response_bytes: usize,
+    /// Bytes downloaded per byte returned, or `None` if no bytes would be returned
+    pub reduction_ratio: Option<f64>,
+}
+
+/// Information about a session created using the session endpoint.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SessionInfo {
+    /// Token identifying the session
+    pub session: String,
+    /// Size in bytes of the object data pinned by the session
+    pub size: usize,
+    /// Time in seconds after which the session expires
+    pub expires_in: u64,
+}
+
+/// Returns a value as a JSON number of a data type, or `null` if it is NaN.
+pub fn json_value<T: num_traits::ToPrimitive>(x: T, dtype: DType) -> serde_json::Value {
+    match dtype {
+        DType::Int32 | DType::Int64 => serde_json::json!(x.to_i64()),
+        DType::Uint32 | DType::Uint64 => serde_json::json!(x.to_u64()),
+        DType::Float64 => serde_json::json!(x.to_f64()),
+        // JSON numbers are f64, so use the shortest decimal representation of single and half
+        // precision values to avoid spurious digits, e.g. 0.1 rather than 0.10000000149011612.
+        DType::Float16 | DType::Bfloat16 | DType::Float32 => serde_json::json!(x
+            .to_f32()
+            .map(|x| x.to_string().parse::<f64>().expect("f32 is a valid f64"))),
+    }
+}
+
+/// Returns an element of a data type in native byte order as a JSON number.
+fn json_element(bytes: &[u8], dtype: DType) -> serde_json::Value {
+    fn json_element_t<T: num_traits::ToPrimitive + zerocopy::FromBytes>(
+        bytes: &[u8],
+        dtype: DType,
+    ) -> serde_json::Value {
+        json_value(
+            T::read_from(bytes).expect("element is the size of T"),
+            dtype,
+        )
+    }
+    match dtype {
+        DType::Int32 => json_element_t::<i32>(bytes, dtype),
+        DType::Int64 => json_element_t::<i64>(bytes, dtype),
+        DType::Uint32 => json_element_t::<u32>(bytes, dtype),
+        DType::Uint64 => json_element_t::<u64>(bytes, dtype),
+        DType::Float16 => json_element_t::<half::f16>(bytes, dtype),
+        DType::Bfloat16 => json_element_t::<half::bf16>(bytes, dtype),
+        DType::Float32 => json_element_t::<f32>(bytes, dtype),
+        DType::Float64 => json_element_t::<f64>(bytes, dtype),
+    }
+}
+
+/// The elements of a data type in some data as JSON numbers.
+///
+/// Elements are converted one at a time as they are serialised, so no copy of the data or list
+/// of JSON values is held in memory.
+struct JsonValues<'a> {
+    data: &'a [u8],
+    dtype: DType,
+    byte_order: ByteOrder,
+}
+
+impl JsonValues<'_> {
+    /// Returns an iterator over the elements as JSON numbers.
+    fn iter(&self) -> impl Iterator<Item = serde_json::Value> + '_ {
+        self.data.chunks_exact(self.dtype.size_of()).map(|bytes| {
+            if self.byte_order == NATIVE_BYTE_ORDER {
+                json_element(bytes, self.dtype)
+            } else {
+                let mut element = [0; 8];
+                let element = &mut element[..bytes.len()];
+                element.copy_from_slice(bytes);
+                element.reverse();
+                json_element(element, self.dtype)
+            }
+        })
+    }
+}
+
+impl Serialize for JsonValues<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Summary statistics of the non-missing selected elements of an array, computed in a single
+/// pass by the stats operation.
+///
+/// Values are JSON numbers, with NaN represented as `null`.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Stats {
+    /// Minimum, in the data type of the request
+    pub min: serde_json::Value,
+    /// Maximum, in the data type of the request
+    pub max: serde_json::Value,
+    /// Sum, in the [DType::sum_dtype] of the data type of the request
+    pub sum: serde_json::Value,
+    /// Number of non-missing elements
+    pub count: i64,
+}
+
+/// Value of an element of an array and its index.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Location {
+    /// Value of the element, in the data type of the request
+    pub value: serde_json::Value,
+    /// Index of the element in the array
+    pub index: Vec<usize>,
+}
+
+/// Minimum and maximum of the non-missing, non-NaN selected elements of an array, and their
+/// indices, computed in a single pass by the minmax_loc operation.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct MinMaxLoc {
+    /// Minimum and the index of its first occurrence
+    pub min: Location,
+    /// Maximum and the index of its first occurrence
+    pub max: Location,
+}
+
+/// Summary of the objects of a batch whose failures were skipped.
+#[derive(Debug)]
+pub struct BatchSummary {
+    /// Number of objects whose results were combined
+    pub objects: usize,
+    /// Index within the batch and JSON error body of each object for which the operation failed
+    pub errors: Vec<(usize, serde_json::Value)>,
+}
+
+/// Response containing the result of a computation and associated metadata.
+#[derive(Debug)]
+pub struct Response {
+    /// Response data. May be a scalar or multi-dimensional array.
+    pub body: Bytes,
+    /// Data type of the response
+    pub dtype: DType,
+    /// Shape of the response
+    pub shape: Vec<usize>,
+    /// Number of non-missing elements operated on to generate response
+    pub count: i64,
+    /// Sum of the weights of the non-missing elements, for a weighted sum
+    pub weights_sum: Option<f64>,
+    /// Whether the byte order of the data in the request looks wrong
+    pub byte_order_suspicious: bool,
+    /// Whether to return the response as JSON rather than raw bytes
+    pub json: bool,
+    /// Ordering of the elements of an array response, if reported by the operation
+    pub order: Option<Order>,
+    /// Byte order of the elements in the body
+    pub byte_order: ByteOrder,
+    /// Objects that contributed to the result of a batch whose failures were skipped
+    pub batch: Option<BatchSummary>,
+}
 
 impl Response {
     /// Return a Response object
@@ -259,7 +1681,65 @@ impl Response {
             dtype,
             shape,
             count,
+            weights_sum: None,
+            byte_order_suspicious: false,
+            json: false,
+            order: None,
+            byte_order: NATIVE_BYTE_ORDER,
+            batch: None,
+        }
+    }
+
+    /// Returns the response as a JSON object, as serialised by its [Serialize] implementation.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("response is valid JSON")
+    }
+}
+
+impl Serialize for Response {
+    /// Serialises the response as a JSON object containing its data type, shape, count and
+    /// result.
+    ///
+    /// The result is a JSON number for a scalar, or otherwise a list of numbers in C order (row
+    /// major), or in F order (column major) if that is the order of the response, which is then
+    /// included. The weights sum is included for a weighted sum. For a batch whose failures were
+    /// skipped, the number of objects that contributed to the result and the errors of the other
+    /// objects are included.
+    ///
+    /// Elements of the result are serialised directly from the body, so large results do not
+    /// require an intermediate JSON document.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("dtype", &self.dtype)?;
+        map.serialize_entry("shape", &self.shape)?;
+        map.serialize_entry("count", &self.count)?;
+        let result = JsonValues {
+            data: &self.body,
+            dtype: self.dtype,
+            byte_order: self.byte_order,
+        };
+        if self.shape.is_empty() && self.body.len() == self.dtype.size_of() {
+            map.serialize_entry("result", &result.iter().next())?;
+        } else {
+            map.serialize_entry("result", &result)?;
+        }
+        if let Some(weights_sum) = self.weights_sum {
+            map.serialize_entry("weights_sum", &weights_sum)?;
         }
+        if self.order == Some(Order::F) {
+            map.serialize_entry("order", &Order::F)?;
+        }
+        if let Some(batch) = &self.batch {
+            map.serialize_entry("objects", &batch.objects)?;
+            let errors: Vec<_> = batch
+                .errors
+                .iter()
+                .map(|(index, error)| serde_json::json!({"index": index, "error": error}))
+                .collect();
+            map.serialize_entry("errors", &errors)?;
+        }
+        map.end()
     }
 }
 
@@ -310,6 +1790,11 @@ mod tests {
                 },
                 Token::Str("source"),
                 Token::Str("http://example.com"),
+                Token::Str("protocol"),
+                Token::Some,
+                Token::Enum { name: "Protocol" },
+                Token::Str("s3"),
+                Token::Unit,
                 Token::Str("bucket"),
                 Token::Str("bar"),
                 Token::Str("object"),
@@ -354,6 +1839,9 @@ mod tests {
                 Token::U32(6),
                 Token::SeqEnd,
                 Token::SeqEnd,
+                Token::Str("axis"),
+                Token::Some,
+                Token::U32(0),
                 Token::Str("compression"),
                 Token::Some,
                 Token::Map { len: None },
@@ -412,19 +1900,104 @@ mod tests {
     }
 
     #[test]
+    #[should_panic(expected = "bucket must not be empty")]
     fn test_missing_bucket() {
-        assert_de_tokens_error::<RequestData>(
-            &[
-                Token::Struct {
-                    name: "RequestData",
-                    len: 2,
-                },
-                Token::Str("source"),
-                Token::Str("http://example.com"),
-                Token::StructEnd,
-            ],
-            "missing field `bucket`",
-        )
+        let json = r#"{"source": "http://example.com", "object": "baz", "dtype": "int32"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_path_style_source() {
+        let json = r#"{"source": "https://s3.eu-west-2.amazonaws.com", "bucket": "bar", "object": "baz", "dtype": "int32"}"#;
+        let mut request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        request_data.path_style();
+        assert_eq!(
+            "https://s3.eu-west-2.amazonaws.com/",
+            request_data.source.as_str()
+        );
+        assert_eq!("bar", request_data.bucket);
+    }
+
+    #[test]
+    fn test_virtual_hosted_style_source() {
+        let json = r#"{"source": "https://bar.s3.eu-west-2.amazonaws.com", "object": "baz", "dtype": "int32"}"#;
+        let mut request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        request_data.path_style();
+        assert_eq!(
+            "https://s3.eu-west-2.amazonaws.com/",
+            request_data.source.as_str()
+        );
+        assert_eq!("bar", request_data.bucket);
+        request_data.validate().unwrap();
+    }
+
+    #[test]
+    fn test_virtual_hosted_style_source_with_bucket() {
+        // An explicit bucket is used as is, without inferring a bucket from the source.
+        let json = r#"{"source": "https://foo.s3-server.example.com", "bucket": "bar", "object": "baz", "dtype": "int32"}"#;
+        let mut request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        request_data.path_style();
+        assert_eq!(
+            "https://foo.s3-server.example.com/",
+            request_data.source.as_str()
+        );
+        assert_eq!("bar", request_data.bucket);
+    }
+
+    #[test]
+    fn test_presigned_source() {
+        let json = r#"{"source": "https://bar.s3.amazonaws.com/baz?X-Amz-Signature=abc", "dtype": "int32"}"#;
+        let mut request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert!(request_data.is_presigned());
+        // Presigned sources are not converted to path-style.
+        request_data.path_style();
+        assert_eq!(
+            "https://bar.s3.amazonaws.com/baz?X-Amz-Signature=abc",
+            request_data.source.as_str()
+        );
+        assert_eq!("", request_data.bucket);
+        request_data.presigned = Some(false);
+        assert!(!request_data.is_presigned());
+        let json = r#"{"source": "https://example.com/baz", "presigned": true, "dtype": "int32"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert!(request_data.is_presigned());
+    }
+
+    #[test]
+    #[should_panic(expected = "Bucket and object cannot be specified with a presigned source")]
+    fn test_presigned_source_with_object() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.presigned = Some(true);
+        request_data.bucket = "".to_string();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Region cannot be specified with a presigned source")]
+    fn test_presigned_source_with_region() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.presigned = Some(true);
+        request_data.bucket = "".to_string();
+        request_data.object = "".to_string();
+        request_data.region = Some("eu-west-2".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Presigned cannot be specified with the http protocol")]
+    fn test_http_protocol_with_presigned() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.protocol = Some(Protocol::Http);
+        request_data.presigned = Some(true);
+        request_data.bucket = "".to_string();
+        request_data.object = "".to_string();
+        request_data.validate().unwrap()
     }
 
     #[test]
@@ -436,31 +2009,97 @@ mod tests {
     }
 
     #[test]
+    #[should_panic(expected = "object must not be empty")]
     fn test_missing_object() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "dtype": "int32"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "object must not be empty")]
+    fn test_invalid_object() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.object = "".to_string();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_http_protocol() {
+        let json =
+            r#"{"source": "http://example.com/foo.nc", "protocol": "http", "dtype": "int32"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some(Protocol::Http), request_data.protocol);
+        assert_eq!("", request_data.bucket);
+        assert_eq!("", request_data.object);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bucket and object cannot be specified with the http protocol")]
+    fn test_http_protocol_with_bucket() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.protocol = Some(Protocol::Http);
+        request_data.object = "".to_string();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Region cannot be specified with the http protocol")]
+    fn test_http_protocol_with_region() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.protocol = Some(Protocol::Http);
+        request_data.bucket = "".to_string();
+        request_data.object = "".to_string();
+        request_data.region = Some("eu-west-2".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "region must not be empty")]
+    fn test_invalid_region() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.region = Some("".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_region_characters() {
+        let mut request_data = test_utils::get_test_request_data();
+        for region in ["eu-west-2", "RegionOne", "my_region.1"] {
+            request_data.region = Some(region.to_string());
+            request_data.validate().unwrap();
+        }
+        for region in [
+            "eu west 2",
+            "eu-west-2/",
+            "région",
+            &"a".repeat(MAX_REGION_LENGTH + 1),
+        ] {
+            request_data.region = Some(region.to_string());
+            let error = request_data.validate().unwrap_err().to_string();
+            assert!(error.contains("region must contain only"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_invalid_protocol() {
         assert_de_tokens_error::<RequestData>(
             &[
                 Token::Struct {
                     name: "RequestData",
                     len: 2,
                 },
-                Token::Str("source"),
-                Token::Str("http://example.com"),
-                Token::Str("bucket"),
-                Token::Str("bar"),
+                Token::Str("protocol"),
+                Token::Some,
+                Token::Enum { name: "Protocol" },
+                Token::Str("ftp"),
                 Token::StructEnd,
             ],
-            "missing field `object`",
+            "unknown variant `ftp`, expected `s3` or `http`",
         )
     }
 
-    #[test]
-    #[should_panic(expected = "object must not be empty")]
-    fn test_invalid_object() {
-        let mut request_data = test_utils::get_test_request_data();
-        request_data.object = "".to_string();
-        request_data.validate().unwrap()
-    }
-
     #[test]
     fn test_missing_dtype() {
         assert_de_tokens_error::<RequestData>(
@@ -490,7 +2129,7 @@ mod tests {
             Token::Str("foo"),
             Token::StructEnd
             ],
-            "unknown variant `foo`, expected one of `int32`, `int64`, `uint32`, `uint64`, `float32`, `float64`"
+            "unknown variant `foo`, expected one of `int32`, `int64`, `uint32`, `uint64`, `float16`, `bfloat16`, `float32`, `float64`"
         )
     }
 
@@ -521,181 +2160,1172 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "shape length must be greater than 0")]
-    fn test_invalid_shape() {
+    #[should_panic(expected = "expected memory must be greater than 0")]
+    fn test_invalid_expected_memory() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![]);
+        request_data.expected_memory = Some(0);
         request_data.validate().unwrap()
     }
 
     #[test]
-    #[should_panic(expected = "shape indices must be greater than 0")]
-    fn test_invalid_shape_indices() {
+    #[should_panic(expected = "ranges length must be greater than 0")]
+    fn test_invalid_ranges() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![0]);
+        request_data.ranges = Some(vec![]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_invalid_order() {
-        assert_de_tokens_error::<RequestData>(
-            &[
+    #[should_panic(expected = "range size must be greater than 0")]
+    fn test_invalid_range_size() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.ranges = Some(vec![ByteRange::new(0, 0)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Ranges cannot be specified with offset or size")]
+    fn test_ranges_with_size() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(8);
+        request_data.ranges = Some(vec![ByteRange::new(0, 8)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_units_elements() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.offset = Some(2);
+        request_data.size = Some(3);
+        request_data.shape = Some(vec![3]);
+        request_data.units = Some(Units::Elements);
+        request_data.validate().unwrap();
+        assert_eq!(Some(16), request_data.offset_bytes());
+        assert_eq!(Some(24), request_data.size_bytes());
+    }
+
+    #[test]
+    fn test_units_bytes() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(2);
+        request_data.size = Some(8);
+        assert_eq!(Some(2), request_data.offset_bytes());
+        assert_eq!(Some(8), request_data.size_bytes());
+        request_data.units = Some(Units::Bytes);
+        assert_eq!(Some(2), request_data.offset_bytes());
+        assert_eq!(Some(8), request_data.size_bytes());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Raw data size must be equal to the product of shape indices and dtype size in bytes"
+    )]
+    fn test_invalid_units_elements_size_for_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(8);
+        request_data.shape = Some(vec![2]);
+        request_data.units = Some(Units::Elements);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_skip_header_bytes() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.skip_header_bytes = Some(10);
+        request_data.size = Some(8);
+        request_data.validate().unwrap();
+        assert_eq!(Some(10), request_data.offset_bytes());
+        // The header is not included in the size.
+        assert_eq!(Some(8), request_data.size_bytes());
+        request_data.dtype = DType::Float64;
+        request_data.offset = Some(2);
+        request_data.size = Some(3);
+        request_data.units = Some(Units::Elements);
+        request_data.validate().unwrap();
+        assert_eq!(Some(26), request_data.offset_bytes());
+        assert_eq!(Some(24), request_data.size_bytes());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Skip header bytes cannot be specified with ranges, shard or part_number"
+    )]
+    fn test_invalid_skip_header_bytes_with_ranges() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.skip_header_bytes = Some(8);
+        request_data.ranges = Some(vec![ByteRange::new(0, 8)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Offset and skip header bytes are too large")]
+    fn test_skip_header_bytes_overflow() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(usize::MAX);
+        request_data.skip_header_bytes = Some(1);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Offset and size in elements are too large")]
+    fn test_units_elements_overflow() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(usize::MAX / 2);
+        request_data.units = Some(Units::Elements);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Units of elements cannot be specified with compression, ranges or shard"
+    )]
+    fn test_units_elements_with_compression() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(2);
+        request_data.compression = Some(Compression::Gzip);
+        request_data.units = Some(Units::Elements);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Raw data size must be equal to the product of shape indices and dtype size in bytes"
+    )]
+    fn test_invalid_ranges_size_for_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![3]);
+        request_data.ranges = Some(vec![ByteRange::new(0, 4), ByteRange::new(16, 4)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_accumulate_dtype() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "uint32", "accumulate_dtype": "int64"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        assert_eq!(Some(DType::Int64), request_data.accumulate_dtype);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_accumulate_dtype_float64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float16;
+        request_data.accumulate_dtype = Some(DType::Float64);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Accumulate dtype must be int64 or float64")]
+    fn test_invalid_accumulate_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.accumulate_dtype = Some(DType::Float32);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Accumulate dtype int64 requires a dtype of int32, int64 or uint32")]
+    fn test_invalid_accumulate_dtype_int64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        request_data.accumulate_dtype = Some(DType::Int64);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_output_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.output_dtype = Some(DType::Float32);
+        request_data.validate().unwrap();
+        request_data.dtype = DType::Float64;
+        request_data.validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate_operation() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.validate_operation("sum").unwrap();
+        request_data.output_dtype = Some(DType::Float32);
+        request_data.validate_operation("select").unwrap();
+        let error = request_data.validate_operation("sum").unwrap_err();
+        assert_eq!("Field is not used by the operation", error.code);
+        assert_eq!(serde_json::json!("output_dtype"), error.params["field"]);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.clip_min = Some(0.into());
+        request_data.percentile = Some(50.0);
+        request_data.trim_fraction = Some(0.1);
+        for (operation, field) in [
+            ("sum", "percentile"),
+            ("approx_percentile", "clip_min"),
+            ("trimmed_mean", "clip_min"),
+        ] {
+            let error = request_data.validate_operation(operation).unwrap_err();
+            assert_eq!(serde_json::json!(field), error.params["field"]);
+        }
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.reject_nonfinite = Some(true);
+        request_data.validate_operation("sum").unwrap();
+        let error = request_data.validate_operation("max").unwrap_err();
+        assert_eq!(serde_json::json!("reject_nonfinite"), error.params["field"]);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.accumulate_dtype = Some(DType::Float64);
+        request_data.validate_operation("sum").unwrap();
+        let error = request_data.validate_operation("cumsum").unwrap_err();
+        assert_eq!(serde_json::json!("accumulate_dtype"), error.params["field"]);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.output_order = Some(OutputOrder::F);
+        request_data.sparse_fetch = Some(true);
+        request_data.validate_operation("select").unwrap();
+        let error = request_data.validate_operation("sum").unwrap_err();
+        assert_eq!(serde_json::json!("sparse_fetch"), error.params["field"]);
+        request_data.sparse_fetch = None;
+        let error = request_data.validate_operation("sum").unwrap_err();
+        assert_eq!(serde_json::json!("output_order"), error.params["field"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Output dtype must be a floating point type for floating point data")]
+    fn test_invalid_output_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.output_dtype = Some(DType::Int64);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing data valid range min must be less than max")]
+    fn test_json_invalid_missing_valid_range_inverted() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "missing": {"valid_range": [10, -10]}}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "expected value")]
+    fn test_json_invalid_missing_valid_min_and_max() {
+        // A valid range must be used to specify both bounds.
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "missing": {"valid_min": 10, "valid_max": -10}}"#;
+        serde_json::from_str::<RequestData>(json).unwrap();
+    }
+
+    #[test]
+    fn test_json_clip() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "float32", "clip_min": -1.5, "clip_max": 2}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        assert_eq!(serde_json::Number::from_f64(-1.5), request_data.clip_min);
+        assert_eq!(Some(2.into()), request_data.clip_max);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_clip_min_only() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.clip_min = Some(0.into());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_fill_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.fill_value = Some((-1).into());
+        request_data.missing = Some(Missing::MissingValue((-2).into()));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Fill value is invalid for the data type")]
+    fn test_invalid_fill_value_out_of_range() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Uint32;
+        request_data.fill_value = Some((-1).into());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Clip bound is invalid for the data type")]
+    fn test_invalid_clip_min_out_of_range() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Uint32;
+        request_data.clip_min = Some((-1).into());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Clip bound is invalid for the data type")]
+    fn test_invalid_clip_max_float_for_int() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "clip_max": 1.5}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Clip min must not be greater than clip max")]
+    fn test_invalid_clip_min_gt_max() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.clip_min = Some(2.into());
+        request_data.clip_max = Some(1.into());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_part_number() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "part_number": 3, "offset": 16, "size": 8}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some(3), request_data.part_number);
+    }
+
+    #[test]
+    #[should_panic(expected = "part_number must be between 1 and 10000")]
+    fn test_invalid_part_number() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.part_number = Some(0);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Part number requires both or neither of offset and size")]
+    fn test_invalid_part_number_offset_only() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.part_number = Some(1);
+        request_data.offset = Some(8);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Part number cannot be specified with ranges, shard or hyperslab")]
+    fn test_invalid_part_number_with_ranges() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.part_number = Some(1);
+        request_data.ranges = Some(vec![ByteRange::new(0, 8)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_session() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "session": "abc", "dtype": "int32"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some("abc".to_string()), request_data.session);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Session cannot be specified with the http protocol, a presigned source or part_number"
+    )]
+    fn test_invalid_session_with_part_number() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.session = Some("abc".to_string());
+        request_data.part_number = Some(1);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Part number cannot be specified with the http protocol or a presigned source"
+    )]
+    fn test_invalid_part_number_presigned() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.part_number = Some(1);
+        request_data.presigned = Some(true);
+        request_data.bucket = "".to_string();
+        request_data.object = "".to_string();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_percentile() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "percentile": 99.5}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some(99.5), request_data.percentile);
+    }
+
+    #[test]
+    fn test_json_trim_fraction() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "trim_fraction": 0.1}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some(0.1), request_data.trim_fraction);
+    }
+
+    #[test]
+    fn test_json_output_order() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "output_order": "K"}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        assert_eq!(Some(OutputOrder::K), request_data.output_order);
+    }
+
+    #[test]
+    #[should_panic(expected = "percentile must be between 0 and 100")]
+    fn test_invalid_percentile() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.percentile = Some(100.5);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "trim fraction must be between 0 and 0.5")]
+    fn test_invalid_trim_fraction() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.trim_fraction = Some(0.6);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_shard() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "int32",
+                        "size": 1023,
+                        "shard": {"chunks": 4, "chunk": 3, "index_location": "start", "index_checksum": false}
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.size = Some(1023);
+        expected.shard = Some(Shard {
+            chunks: 4,
+            chunk: 3,
+            index_location: ShardIndexLocation::Start,
+            index_checksum: false,
+        });
+        assert_eq!(request_data, expected);
+        // The size of the shard need not be a multiple of the dtype size.
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_shard_defaults() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "int32",
+                        "shard": {"chunks": 4, "chunk": 3}
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        let shard = request_data.shard.unwrap();
+        assert_eq!(Shard::new(4, 3), shard);
+        assert_eq!(ShardIndexLocation::End, shard.index_location);
+        assert!(shard.index_checksum);
+    }
+
+    #[test]
+    #[should_panic(expected = "Shard chunk must be less than shard chunks")]
+    fn test_invalid_shard_chunk() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shard = Some(Shard::new(4, 4));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "shard chunks must be greater than 0")]
+    fn test_invalid_shard_chunks() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shard = Some(Shard::new(0, 0));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shard index is too large")]
+    fn test_shard_index_overflow() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shard = Some(Shard::new(usize::MAX / 8, 0));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shard offset and size are too large")]
+    fn test_shard_offset_size_overflow() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(usize::MAX);
+        request_data.size = Some(1);
+        request_data.shard = Some(Shard::new(4, 1));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shard cannot be specified with ranges")]
+    fn test_shard_with_ranges() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.ranges = Some(vec![ByteRange::new(0, 4)]);
+        request_data.shard = Some(Shard::new(4, 1));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "shape length must be greater than 0")]
+    fn test_invalid_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "shape indices must be greater than 0")]
+    fn test_invalid_shape_indices() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![0]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_invalid_order() {
+        assert_de_tokens_error::<RequestData>(
+            &[
+                Token::Struct {
+                    name: "RequestData",
+                    len: 2,
+                },
+                Token::Str("order"),
+                Token::Some,
+                Token::Enum { name: "Order" },
+                Token::Str("foo"),
+                Token::StructEnd,
+            ],
+            "unknown variant `foo`, expected `C` or `F`",
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "selection length must be greater than 0")]
+    fn test_invalid_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.selection = Some(vec![]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Selection stride must not be equal to zero")]
+    fn test_invalid_selection2() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.selection = Some(vec![Slice::new(1, 2, 0)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_end_lt_start() {
+        // Numpy sementics: start >= end yields an empty array
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![1]);
+        request_data.selection = Some(vec![Slice::new(1, 0, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_negative_stride() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![1]);
+        request_data.selection = Some(vec![Slice::new(1, 0, -1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(16);
+        request_data.validate().unwrap();
+        request_data.resolve_shape();
+        assert_eq!(Some(vec![4]), request_data.shape);
+        // The size may be in elements.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.offset = Some(1);
+        request_data.size = Some(5);
+        request_data.units = Some(Units::Elements);
+        request_data.resolve_shape();
+        assert_eq!(Some(vec![5]), request_data.shape);
+        // An explicit shape is unchanged.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(24);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.resolve_shape();
+        assert_eq!(Some(vec![2, 3]), request_data.shape);
+    }
+
+    #[test]
+    fn test_resolve_shape_stored_dtype() {
+        // The number of elements is based on the data type as stored.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.size = Some(12);
+        request_data.filters = Some(vec![Filter::FixedScaleOffset {
+            offset: 0.0,
+            scale: 1.0,
+            astype: DType::Int32,
+        }]);
+        request_data.validate().unwrap();
+        request_data.resolve_shape();
+        assert_eq!(Some(vec![3]), request_data.shape);
+    }
+
+    #[test]
+    fn test_resolve_shape_unknown() {
+        // Without a size, the number of elements is not known until the data is downloaded.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.resolve_shape();
+        assert_eq!(None, request_data.shape);
+        // The size of compressed data, a shard or bit-packed data does not give the number of
+        // elements.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(16);
+        request_data.compression = Some(Compression::Gzip);
+        request_data.resolve_shape();
+        assert_eq!(None, request_data.shape);
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(16);
+        request_data.filters = Some(vec![Filter::Bitpacked]);
+        request_data.resolve_shape();
+        assert_eq!(None, request_data.shape);
+    }
+
+    #[test]
+    #[should_panic(expected = "Raw data size must be a multiple of dtype size in bytes")]
+    fn test_invalid_size_for_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(1);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Raw data size must be equal to the product of shape indices and dtype size in bytes"
+    )]
+    fn test_invalid_size_for_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = Some(4);
+        request_data.shape = Some(vec![1, 2]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shape and selection must have the same length")]
+    fn test_shape_selection_mismatch() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![1, 2]);
+        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_start_gt_shape() {
+        // Numpy sementics: start > length yields an empty array
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(5, 5, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_start_lt_negative_shape() {
+        // Numpy sementics: start < -length gets clamped to zero
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(-5, 5, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_end_gt_shape() {
+        // Numpy semantics: end > length gets clamped to length
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(1, 5, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_selection_end_lt_negative_shape() {
+        // Numpy semantics: end < -length gets clamped to zero
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(1, -5, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.axis = Some(0);
+        request_data.validate().unwrap();
+        request_data.shape = Some(vec![2, 2]);
+        request_data.axis = Some(1);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Axis must be less than the number of dimensions")]
+    fn test_axis_out_of_range() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 2]);
+        request_data.axis = Some(2);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Selection requires shape to be specified")]
+    fn test_selection_without_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_hyperslab() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(8);
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 2], vec![2, 2]));
+        request_data.validate().unwrap();
+        request_data.resolve_hyperslab();
+        assert_eq!(None, request_data.hyperslab);
+        assert_eq!(Some(28), request_data.offset);
+        assert_eq!(Some(40), request_data.size);
+        assert_eq!(Some(vec![2, 5]), request_data.shape);
+        assert_eq!(
+            Some(vec![Slice::new(0, 2, 1), Slice::new(2, 4, 1)]),
+            request_data.selection
+        );
+        request_data.validate().unwrap();
+    }
+
+    #[test]
+    fn test_stored_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![2, 4]);
+        request_data.size = Some(32);
+        request_data.validate().unwrap();
+        assert_eq!(&Some(vec![2, 4]), request_data.layout_shape());
+    }
+
+    #[test]
+    #[should_panic(expected = "Raw data size must be equal to the product of shape indices")]
+    fn test_stored_shape_size_mismatch() {
+        // The size is that of the stored shape, not the shape.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![2, 4]);
+        request_data.size = Some(24);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Stored shape requires shape to be specified")]
+    fn test_stored_shape_without_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.stored_shape = Some(vec![4]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shape and stored shape must have the same length")]
+    fn test_stored_shape_length_mismatch() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![6]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Shape must not exceed stored shape")]
+    fn test_shape_exceeds_stored_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![3, 2]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Stored shape cannot be specified with hyperslab")]
+    fn test_stored_shape_with_hyperslab() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 5]);
+        request_data.stored_shape = Some(vec![4, 6]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 2], vec![2, 2]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_hyperslab_skip_header_bytes() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.skip_header_bytes = Some(8);
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 2], vec![2, 2]));
+        request_data.validate().unwrap();
+        request_data.resolve_hyperslab();
+        // The header is included in the offset of the hyperslab, so is not skipped twice.
+        assert_eq!(Some(28), request_data.offset);
+        assert_eq!(None, request_data.skip_header_bytes);
+        assert_eq!(Some(28), request_data.offset_bytes());
+    }
+
+    #[test]
+    fn test_hyperslab_elements() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.offset = Some(2);
+        request_data.units = Some(Units::Elements);
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![3, 0], vec![1, 5]));
+        request_data.resolve_hyperslab();
+        assert_eq!(Some(68), request_data.offset);
+        assert_eq!(Some(20), request_data.size);
+        assert_eq!(None, request_data.units);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hyperslab requires shape to be specified")]
+    fn test_hyperslab_without_shape() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.hyperslab = Some(Hyperslab::new(vec![1], vec![2]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Hyperslab cannot be specified with size, ranges, shard, selection, compression, filters or weights"
+    )]
+    fn test_hyperslab_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1], vec![2]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Hyperslab start and count must have the same length as shape")]
+    fn test_hyperslab_invalid_length() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 2], vec![2]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Hyperslab count must be greater than 0")]
+    fn test_hyperslab_zero_count() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 2], vec![2, 0]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Hyperslab must be within shape")]
+    fn test_hyperslab_out_of_bounds() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 5]);
+        request_data.hyperslab = Some(Hyperslab::new(vec![1, 4], vec![2, 2]));
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "indices length must be greater than 0")]
+    fn test_empty_indices() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.indices = Some(vec![]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Indices cannot be specified with selection, hyperslab, axis, sparse_fetch or weights"
+    )]
+    fn test_indices_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+        request_data.indices = Some(vec![1, 2]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Indices must be less than the number of elements")]
+    fn test_indices_out_of_bounds() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.indices = Some(vec![0, 5, 6]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_indices_without_shape() {
+        // Indices are checked against the data when the shape is not specified.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.indices = Some(vec![0, 100]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_invalid_compression() {
+        assert_de_tokens_error::<RequestData>(
+            &[
                 Token::Struct {
                     name: "RequestData",
                     len: 2,
                 },
-                Token::Str("order"),
+                Token::Str("compression"),
                 Token::Some,
-                Token::Enum { name: "Order" },
+                Token::Map { len: None },
+                Token::Str("id"),
                 Token::Str("foo"),
-                Token::StructEnd,
+                Token::MapEnd,
             ],
-            "unknown variant `foo`, expected `C` or `F`",
+            "unknown variant `foo`, expected one of `gzip`, `zlib`, `deflate`, `brotli`, `snappy`",
         )
     }
 
     #[test]
-    #[should_panic(expected = "selection length must be greater than 0")]
-    fn test_invalid_selection() {
+    fn test_invalid_filter() {
+        assert_de_tokens_error::<RequestData>(
+            &[
+                Token::Struct {
+                    name: "RequestData",
+                    len: 2,
+                },
+                Token::Str("filters"),
+                Token::Some,
+                Token::Seq { len: Some(1) },
+                Token::Map { len: None },
+                Token::Str("id"),
+                Token::Str("foo"),
+                Token::MapEnd,
+            ],
+            "unknown variant `foo`, expected one of `shuffle`, `quantize`, `fixedscaleoffset`, `bitpacked`, `packbits`",
+        )
+    }
+
+    #[test]
+    #[should_panic(expected = "Shuffle element_size must match the size of the stored data type")]
+    fn test_invalid_shuffle_element_size() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.selection = Some(vec![]);
+        request_data.filters = Some(vec![Filter::Shuffle {
+            element_size: Some(8),
+        }]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    #[should_panic(expected = "Selection stride must not be equal to zero")]
-    fn test_invalid_selection2() {
+    fn test_shuffle_element_size_stored_dtype() {
+        // The element size is checked against the data type before FixedScaleOffset decoding.
         let mut request_data = test_utils::get_test_request_data();
-        request_data.selection = Some(vec![Slice::new(1, 2, 0)]);
+        request_data.dtype = DType::Float64;
+        request_data.filters = Some(vec![
+            Filter::FixedScaleOffset {
+                offset: 0.0,
+                scale: 1.0,
+                astype: DType::Int32,
+            },
+            Filter::Shuffle {
+                element_size: Some(4),
+            },
+        ]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_end_lt_start() {
-        // Numpy sementics: start >= end yields an empty array
+    #[should_panic(expected = "shape has too many elements")]
+    fn test_invalid_shape_overflow() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![1]);
-        request_data.selection = Some(vec![Slice::new(1, 0, 1)]);
+        request_data.size = None;
+        request_data.shape = Some(vec![usize::MAX / 2, 4]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_negative_stride() {
+    fn test_check_shape_limits_dimensions() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![1]);
-        request_data.selection = Some(vec![Slice::new(1, 0, -1)]);
+        request_data.size = None;
+        request_data.check_shape_limits(1, None).unwrap();
+        request_data.shape = Some(vec![1, 2, 1]);
+        request_data.check_shape_limits(3, None).unwrap();
+        let error = request_data.check_shape_limits(2, None).unwrap_err();
+        assert_eq!("shape has too many dimensions", error.code);
+        assert_eq!(serde_json::json!(3), error.params["dimensions"]);
+        assert_eq!(serde_json::json!(2), error.params["max dimensions"]);
+    }
+
+    #[test]
+    fn test_check_shape_limits_elements() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.size = None;
+        request_data.shape = Some(vec![10, 20]);
+        request_data.check_shape_limits(32, Some(200)).unwrap();
+        let error = request_data.check_shape_limits(32, Some(199)).unwrap_err();
+        assert_eq!("shape has too many elements", error.code);
+        assert_eq!(serde_json::json!(200), error.params["elements"]);
+        assert_eq!(serde_json::json!(199), error.params["max elements"]);
+        // Overflow of the number of elements exceeds any limit.
+        request_data.shape = Some(vec![usize::MAX, 2]);
+        let error = request_data.check_shape_limits(32, Some(1)).unwrap_err();
+        assert_eq!("shape has too many elements", error.code);
+    }
+
+    #[test]
+    #[should_panic(expected = "Quantize digits must be greater than 0")]
+    fn test_invalid_quantize_digits() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.filters = Some(vec![Filter::Quantize { digits: 0 }]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    #[should_panic(expected = "Raw data size must be a multiple of dtype size in bytes")]
-    fn test_invalid_size_for_dtype() {
+    #[should_panic(expected = "Quantize filter requires a floating point data type")]
+    fn test_invalid_quantize_dtype() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.size = Some(1);
+        request_data.filters = Some(vec![Filter::Quantize { digits: 3 }]);
         request_data.validate().unwrap()
     }
 
     #[test]
     #[should_panic(
-        expected = "Raw data size must be equal to the product of shape indices and dtype size in bytes"
+        expected = "FixedScaleOffset offset and scale must be finite and scale non-zero"
     )]
-    fn test_invalid_size_for_shape() {
+    fn test_invalid_fixed_scale_offset_scale() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.size = Some(4);
-        request_data.shape = Some(vec![1, 2]);
+        request_data.dtype = DType::Float32;
+        request_data.filters = Some(vec![Filter::FixedScaleOffset {
+            offset: 0.0,
+            scale: 0.0,
+            astype: DType::Int32,
+        }]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "FixedScaleOffset filter requires a floating point data type")]
+    fn test_invalid_fixed_scale_offset_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.filters = Some(vec![Filter::FixedScaleOffset {
+            offset: 0.0,
+            scale: 1.0,
+            astype: DType::Int32,
+        }]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Only one FixedScaleOffset filter may be specified")]
+    fn test_invalid_fixed_scale_offset_multiple() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float32;
+        let filter = Filter::FixedScaleOffset {
+            offset: 0.0,
+            scale: 1.0,
+            astype: DType::Int32,
+        };
+        request_data.filters = Some(vec![filter, filter]);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_fixed_scale_offset_stored_size() {
+        // The size refers to the stored data type.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float64;
+        request_data.size = Some(8);
+        request_data.shape = Some(vec![2]);
+        request_data.filters = Some(vec![Filter::FixedScaleOffset {
+            offset: 0.0,
+            scale: 1.0,
+            astype: DType::Int32,
+        }]);
+        assert_eq!(DType::Int32, request_data.stored_dtype());
+        request_data.validate().unwrap();
+        request_data.size = Some(16);
+        assert!(request_data.validate().is_err());
+    }
+
+    #[test]
+    fn test_json_bitpacked() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "shape": [13], "size": 2, "filters": [{"id": "bitpacked"}]}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        assert_eq!(Some(vec![Filter::Bitpacked]), request_data.filters);
+        assert!(request_data.is_bitpacked());
+        assert_eq!(2, request_data.stored_size(13));
         request_data.validate().unwrap()
     }
 
     #[test]
-    #[should_panic(expected = "Shape and selection must have the same length")]
-    fn test_shape_selection_mismatch() {
-        let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![1, 2]);
-        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+    fn test_json_packbits() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "shape": [13], "size": 3, "filters": [{"id": "packbits"}]}"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        assert_eq!(Some(vec![Filter::PackBits]), request_data.filters);
+        assert!(request_data.is_bitpacked());
+        assert_eq!(3, request_data.stored_size(13));
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_start_gt_shape() {
-        // Numpy sementics: start > length yields an empty array
+    #[should_panic(
+        expected = "Bit-packed data size must be the product of shape indices divided by 8, rounded up, plus any header"
+    )]
+    fn test_invalid_bitpacked_size() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![4]);
-        request_data.selection = Some(vec![Slice::new(5, 5, 1)]);
+        request_data.shape = Some(vec![16]);
+        request_data.size = Some(3);
+        request_data.filters = Some(vec![Filter::Bitpacked]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_start_lt_negative_shape() {
-        // Numpy sementics: start < -length gets clamped to zero
+    #[should_panic(expected = "Bit-packed filters cannot be combined with other filters")]
+    fn test_invalid_bitpacked_with_shuffle() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![4]);
-        request_data.selection = Some(vec![Slice::new(-5, 5, 1)]);
+        request_data.filters = Some(vec![
+            Filter::Bitpacked,
+            Filter::Shuffle { element_size: None },
+        ]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_end_gt_shape() {
-        // Numpy semantics: end > length gets clamped to length
+    #[should_panic(expected = "Units of elements cannot be specified with bit-packed filters")]
+    fn test_invalid_bitpacked_units_elements() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![4]);
-        request_data.selection = Some(vec![Slice::new(1, 5, 1)]);
+        request_data.units = Some(Units::Elements);
+        request_data.size = Some(8);
+        request_data.filters = Some(vec![Filter::Bitpacked]);
         request_data.validate().unwrap()
     }
 
     #[test]
-    fn test_selection_end_lt_negative_shape() {
-        // Numpy semantics: end < -length gets clamped to zero
-        let mut request_data = test_utils::get_test_request_data();
-        request_data.shape = Some(vec![4]);
-        request_data.selection = Some(vec![Slice::new(1, -5, 1)]);
-        request_data.validate().unwrap()
+    fn test_dtype_size_of_float16() {
+        assert_eq!(2, DType::Float16.size_of());
+        assert_eq!(2, DType::Bfloat16.size_of());
     }
 
     #[test]
-    #[should_panic(expected = "Selection requires shape to be specified")]
-    fn test_selection_without_shape() {
-        let mut request_data = test_utils::get_test_request_data();
-        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
-        request_data.validate().unwrap()
+    fn test_dtype_is_float() {
+        assert!(DType::Float16.is_float());
+        assert!(DType::Bfloat16.is_float());
+        assert!(DType::Float64.is_float());
+        assert!(!DType::Uint32.is_float());
     }
 
     #[test]
-    fn test_invalid_compression() {
-        assert_de_tokens_error::<RequestData>(
-            &[
-                Token::Struct {
-                    name: "RequestData",
-                    len: 2,
-                },
-                Token::Str("compression"),
-                Token::Some,
-                Token::Map { len: None },
-                Token::Str("id"),
-                Token::Str("foo"),
-                Token::MapEnd,
-            ],
-            "unknown variant `foo`, expected `gzip` or `zlib`",
-        )
+    fn test_dtype_sum_dtype() {
+        assert_eq!(DType::Float32, DType::Float16.sum_dtype());
+        assert_eq!(DType::Float32, DType::Bfloat16.sum_dtype());
+        assert_eq!(DType::Float32, DType::Float32.sum_dtype());
+        assert_eq!(DType::Int32, DType::Int32.sum_dtype());
     }
 
     #[test]
-    fn test_invalid_filter() {
-        assert_de_tokens_error::<RequestData>(
-            &[
-                Token::Struct {
-                    name: "RequestData",
-                    len: 2,
-                },
-                Token::Str("filters"),
-                Token::Some,
-                Token::Seq { len: Some(1) },
-                Token::Map { len: None },
-                Token::Str("id"),
-                Token::Str("foo"),
-                Token::MapEnd,
-            ],
-            "unknown variant `foo`, expected `shuffle`",
-        )
+    #[should_panic(
+        expected = "Raw data size must be equal to the product of shape indices and dtype size in bytes"
+    )]
+    fn test_invalid_size_for_shape_float16() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = DType::Float16;
+        request_data.shape = Some(vec![3]);
+        request_data.size = Some(8);
+        request_data.validate().unwrap()
     }
 
     #[test]
@@ -724,6 +3354,109 @@ mod tests {
         request_data.validate().unwrap()
     }
 
+    #[test]
+    fn test_mask_request_data() {
+        let mut request_data = test_utils::get_test_request_data_optional();
+        request_data.selection = None;
+        request_data.axis = None;
+        request_data.mask_object = Some("mask".to_string());
+        let mask = request_data.mask_request_data().unwrap();
+        assert_eq!("bar", mask.bucket);
+        assert_eq!("mask", mask.object);
+        assert_eq!(None, mask.offset);
+        assert_eq!(None, mask.size);
+        assert_eq!(None, mask.compression);
+        assert_eq!(None, mask.filters);
+        assert_eq!(None, mask.missing);
+        assert_eq!(None, mask.mask_object);
+        assert_eq!(request_data.shape, mask.shape);
+        assert_eq!(request_data.order, mask.order);
+        request_data.mask_bucket = Some("mask-bucket".to_string());
+        let mask = request_data.mask_request_data().unwrap();
+        assert_eq!("mask-bucket", mask.bucket);
+        assert_eq!(
+            None,
+            test_utils::get_test_request_data().mask_request_data()
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Mask cannot be specified with indices, selection, hyperslab, axis, sparse_fetch or weights"
+    )]
+    fn test_mask_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![Slice::new(1, 2, 1)]);
+        request_data.mask_object = Some("mask".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Mask bucket requires mask object to be specified")]
+    fn test_mask_bucket_without_object() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.mask_bucket = Some("mask-bucket".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_weights_request_data() {
+        let mut request_data = test_utils::get_test_request_data_optional();
+        request_data.weights_object = Some("weights".to_string());
+        request_data.weights_dtype = Some(DType::Float32);
+        let weights = request_data.weights_request_data().unwrap();
+        assert_eq!("bar", weights.bucket);
+        assert_eq!("weights", weights.object);
+        assert_eq!(DType::Float32, weights.dtype);
+        assert_eq!(None, weights.offset);
+        assert_eq!(None, weights.size);
+        assert_eq!(None, weights.compression);
+        assert_eq!(None, weights.filters);
+        assert_eq!(None, weights.missing);
+        assert_eq!(request_data.shape, weights.shape);
+        assert_eq!(request_data.selection, weights.selection);
+        assert_eq!(request_data.byte_order, weights.byte_order);
+        request_data.weights_bucket = Some("weights-bucket".to_string());
+        request_data.weights_dtype = None;
+        let weights = request_data.weights_request_data().unwrap();
+        assert_eq!("weights-bucket", weights.bucket);
+        assert_eq!(DType::Int32, weights.dtype);
+    }
+
+    #[test]
+    fn test_weights_request_data_none() {
+        let request_data = test_utils::get_test_request_data();
+        assert_eq!(None, request_data.weights_request_data());
+    }
+
+    #[test]
+    #[should_panic(expected = "weights object must not be empty")]
+    fn test_invalid_weights_object() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.weights_object = Some("".to_string());
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Weights bucket and dtype require weights object to be specified")]
+    fn test_weights_dtype_without_object() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.weights_dtype = Some(DType::Float64);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Weights cannot be specified with the http protocol")]
+    fn test_weights_http_protocol() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.protocol = Some(Protocol::Http);
+        request_data.bucket = "".to_string();
+        request_data.object = "".to_string();
+        request_data.weights_object = Some("weights".to_string());
+        request_data.validate().unwrap()
+    }
+
     #[test]
     fn test_unknown_field() {
         assert_de_tokens_error::<RequestData>(&[
@@ -731,7 +3464,7 @@ mod tests {
             Token::Str("foo"),
             Token::StructEnd
             ],
-            "unknown field `foo`, expected one of `source`, `bucket`, `object`, `dtype`, `byte_order`, `offset`, `size`, `shape`, `order`, `selection`, `compression`, `filters`, `missing`"
+            "unknown field `foo`, expected one of `source`, `protocol`, `presigned`, `region`, `bucket`, `object`, `session`, `dtype`, `byte_order`, `offset`, `skip_header_bytes`, `size`, `units`, `part_number`, `ranges`, `shard`, `shape`, `stored_shape`, `order`, `selection`, `indices`, `hyperslab`, `axis`, `sparse_fetch`, `compression`, `filters`, `missing`, `fill_value`, `accumulate_dtype`, `output_dtype`, `output_order`, `clip_min`, `clip_max`, `reject_nonfinite`, `percentile`, `trim_fraction`, `weights_bucket`, `weights_object`, `weights_dtype`, `mask_bucket`, `mask_object`, `expected_memory`"
         )
     }
 
@@ -748,6 +3481,7 @@ mod tests {
     fn test_json_optional_fields() {
         let json = r#"{
                         "source": "http://example.com",
+                        "protocol": "s3",
                         "bucket": "bar",
                         "object": "baz",
                         "dtype": "int32",
@@ -757,6 +3491,7 @@ mod tests {
                         "shape": [2, 5],
                         "order": "C",
                         "selection": [[1, 2, 3], [4, 5, 6]],
+                        "axis": 0,
                         "compression": {"id": "gzip"},
                         "filters": [{"id": "shuffle", "element_size": 4}],
                         "missing": {"missing_value": 42}
@@ -769,6 +3504,7 @@ mod tests {
     fn test_json_optional_fields2() {
         let json = r#"{
                         "source": "http://example.com",
+                        "protocol": "s3",
                         "bucket": "bar",
                         "object": "baz",
                         "dtype": "float64",
@@ -778,6 +3514,7 @@ mod tests {
                         "shape": [2, 5, 10],
                         "order": "F",
                         "selection": [[1, 2, 3], [4, 5, 6], [7, 8, 9]],
+                        "axis": 0,
                         "compression": {"id": "zlib"},
                         "filters": [{"id": "shuffle", "element_size": 8}],
                         "missing": {"valid_range": [-1.0, 999.0]}
@@ -794,7 +3531,9 @@ mod tests {
             Slice::new(7, 8, 9),
         ]);
         expected.compression = Some(Compression::Zlib);
-        expected.filters = Some(vec![Filter::Shuffle { element_size: 8 }]);
+        expected.filters = Some(vec![Filter::Shuffle {
+            element_size: Some(8),
+        }]);
         expected.missing = Some(Missing::ValidRange(
             DValue::from_f64(-1.0).unwrap(),
             DValue::from_f64(999.0).unwrap(),
@@ -802,6 +3541,253 @@ mod tests {
         assert_eq!(request_data, expected);
     }
 
+    #[test]
+    fn test_json_shuffle_default_element_size() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "int32",
+                        "filters": [{"id": "shuffle"}]
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.filters = Some(vec![Filter::Shuffle { element_size: None }]);
+        assert_eq!(request_data, expected);
+    }
+
+    #[test]
+    fn test_json_quantize() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "float32",
+                        "filters": [{"id": "quantize", "digits": 3, "dtype": "<f4"}]
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.dtype = DType::Float32;
+        expected.filters = Some(vec![Filter::Quantize { digits: 3 }]);
+        assert_eq!(request_data, expected);
+    }
+
+    #[test]
+    fn test_json_fixed_scale_offset() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "float32",
+                        "filters": [
+                            {"id": "fixedscaleoffset", "offset": 1000, "scale": 10, "astype": "int32"}
+                        ]
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.dtype = DType::Float32;
+        expected.filters = Some(vec![Filter::FixedScaleOffset {
+            offset: 1000.0,
+            scale: 10.0,
+            astype: DType::Int32,
+        }]);
+        assert_eq!(request_data, expected);
+    }
+
+    #[test]
+    fn test_json_ranges() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "int32",
+                        "ranges": [[0, 8], {"offset": 32, "size": 4}]
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.ranges = Some(vec![ByteRange::new(0, 8), ByteRange::new(32, 4)]);
+        assert_eq!(request_data, expected);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_normalized() {
+        let request_data = test_utils::get_test_request_data();
+        let normalized = request_data.normalized();
+        let mut expected = request_data.clone();
+        expected.protocol = Some(Protocol::S3);
+        expected.byte_order = Some(NATIVE_BYTE_ORDER);
+        expected.units = Some(Units::Bytes);
+        expected.order = Some(Order::C);
+        assert_eq!(expected, normalized);
+        // Specified values are unchanged.
+        let mut request_data = test_utils::get_test_request_data_optional();
+        request_data.units = Some(Units::Elements);
+        assert_eq!(request_data.clone(), request_data.normalized());
+    }
+
+    #[test]
+    fn test_json_batch() {
+        let json = r#"{
+                        "requests": [
+                          {"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32"},
+                          {"source": "http://example.com", "bucket": "bar", "object": "qux", "dtype": "int32", "size": 8}
+                        ]
+                      }"#;
+        let batch_request_data = serde_json::from_str::<BatchRequestData>(json).unwrap();
+        let first = test_utils::get_test_request_data();
+        let mut second = test_utils::get_test_request_data();
+        second.object = "qux".to_string();
+        second.size = Some(8);
+        assert_eq!(
+            batch_request_data,
+            BatchRequestData {
+                requests: vec![first, second],
+                on_error: None,
+            }
+        );
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_batch_on_error() {
+        let json = r#"{
+                        "requests": [
+                          {"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32"}
+                        ],
+                        "on_error": "skip"
+                      }"#;
+        let batch_request_data = serde_json::from_str::<BatchRequestData>(json).unwrap();
+        assert_eq!(Some(OnError::Skip), batch_request_data.on_error);
+        let json = r#"{"requests": [], "on_error": "ignore"}"#;
+        let error = serde_json::from_str::<BatchRequestData>(json).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("unknown variant `ignore`, expected `fail` or `skip`"));
+    }
+
+    #[test]
+    fn test_json_estimate() {
+        let json = r#"{
+                        "operation": "sum",
+                        "request": {"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32"}
+                      }"#;
+        let estimate_request_data = serde_json::from_str::<EstimateRequestData>(json).unwrap();
+        assert_eq!(
+            estimate_request_data,
+            EstimateRequestData {
+                operation: "sum".to_string(),
+                request: test_utils::get_test_request_data(),
+            }
+        );
+        estimate_request_data.validate().unwrap();
+        let json = r#"{
+                        "operation": "sum",
+                        "request": {"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "size": 0}
+                      }"#;
+        let estimate_request_data = serde_json::from_str::<EstimateRequestData>(json).unwrap();
+        assert!(estimate_request_data.validate().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "requests length must be greater than 0")]
+    fn test_batch_empty() {
+        let batch_request_data = BatchRequestData {
+            requests: vec![],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "requests length must not exceed the maximum")]
+    fn test_batch_too_large() {
+        let batch_request_data = BatchRequestData {
+            requests: vec![test_utils::get_test_request_data(); MAX_BATCH_REQUESTS + 1],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_batch_max_size() {
+        let batch_request_data = BatchRequestData {
+            requests: vec![test_utils::get_test_request_data(); MAX_BATCH_REQUESTS],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch requests cannot be specified with axis")]
+    fn test_batch_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.axis = Some(0);
+        let batch_request_data = BatchRequestData {
+            requests: vec![request_data],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch requests must have the same dtype and accumulate_dtype")]
+    fn test_batch_dtype_mismatch() {
+        let mut second = test_utils::get_test_request_data();
+        second.dtype = DType::Int64;
+        let batch_request_data = BatchRequestData {
+            requests: vec![test_utils::get_test_request_data(), second],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "size must be greater than 0")]
+    fn test_batch_invalid_request() {
+        let mut second = test_utils::get_test_request_data();
+        second.size = Some(0);
+        let batch_request_data = BatchRequestData {
+            requests: vec![test_utils::get_test_request_data(), second],
+            on_error: None,
+        };
+        batch_request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_json_units() {
+        let json = r#"{
+                        "source": "http://example.com",
+                        "bucket": "bar",
+                        "object": "baz",
+                        "dtype": "int32",
+                        "offset": 1,
+                        "size": 2,
+                        "units": "elements"
+                      }"#;
+        let request_data = serde_json::from_str::<RequestData>(json).unwrap();
+        let mut expected = test_utils::get_test_request_data();
+        expected.offset = Some(1);
+        expected.size = Some(2);
+        expected.units = Some(Units::Elements);
+        assert_eq!(request_data, expected);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_invalid_json_units() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32", "units": "bits"}"#;
+        let error = serde_json::from_str::<RequestData>(json).unwrap_err();
+        assert!(
+            error.to_string().starts_with("unknown variant `bits`"),
+            "{}",
+            error
+        );
+    }
+
     #[test]
     fn test_json_optional_fields3() {
         let json = format!(
@@ -827,4 +3813,98 @@ mod tests {
         ]));
         assert_eq!(request_data, expected);
     }
+
+    #[test]
+    fn test_info_request_data() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz"}"#;
+        let request_data = serde_json::from_str::<InfoRequestData>(json).unwrap();
+        let expected = InfoRequestData {
+            source: Url::parse("http://example.com").unwrap(),
+            region: None,
+            bucket: "bar".to_string(),
+            object: "baz".to_string(),
+        };
+        assert_eq!(request_data, expected);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_info_request_data_region() {
+        let json = r#"{"source": "http://example.com", "region": "eu-west-2", "bucket": "bar", "object": "baz"}"#;
+        let request_data = serde_json::from_str::<InfoRequestData>(json).unwrap();
+        assert_eq!(Some("eu-west-2".to_string()), request_data.region);
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_info_request_data_virtual_hosted_style() {
+        let json = r#"{"source": "https://bar.s3.amazonaws.com", "object": "baz"}"#;
+        let mut request_data = serde_json::from_str::<InfoRequestData>(json).unwrap();
+        request_data.validate().unwrap();
+        request_data.path_style();
+        assert_eq!("https://s3.amazonaws.com/", request_data.source.as_str());
+        assert_eq!("bar", request_data.bucket);
+    }
+
+    #[test]
+    #[should_panic(expected = "bucket must not be empty")]
+    fn test_info_request_data_missing_bucket() {
+        let json = r#"{"source": "http://example.com", "object": "baz"}"#;
+        let request_data = serde_json::from_str::<InfoRequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown field `dtype`")]
+    fn test_info_request_data_unknown_field() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": "baz", "dtype": "int32"}"#;
+        serde_json::from_str::<InfoRequestData>(json).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "object must not be empty")]
+    fn test_info_request_data_invalid_object() {
+        let json = r#"{"source": "http://example.com", "bucket": "bar", "object": ""}"#;
+        let request_data = serde_json::from_str::<InfoRequestData>(json).unwrap();
+        request_data.validate().unwrap()
+    }
+
+    #[test]
+    fn test_response_to_json_scalar() {
+        let response = Response::new(6_i64.to_ne_bytes().to_vec().into(), DType::Int64, vec![], 3);
+        assert_eq!(
+            serde_json::json!({"dtype": "int64", "shape": [], "count": 3, "result": 6}),
+            response.to_json()
+        );
+    }
+
+    #[test]
+    fn test_response_to_json_array() {
+        let body: Vec<u8> = [1.5_f32, f32::NAN, -2.0, 0.1]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect();
+        let mut response = Response::new(body.into(), DType::Float32, vec![2, 2], 4);
+        response.weights_sum = Some(2.5);
+        assert_eq!(
+            serde_json::json!({
+                "dtype": "float32", "shape": [2, 2], "count": 4,
+                "result": [1.5, null, -2.0, 0.1], "weights_sum": 2.5
+            }),
+            response.to_json()
+        );
+    }
+
+    #[test]
+    fn test_response_to_json_byte_order() {
+        let body: Vec<u8> = [1_u32, 2, 3].iter().flat_map(|x| x.to_be_bytes()).collect();
+        let mut response = Response::new(body.into(), DType::Uint32, vec![3], 6);
+        response.byte_order = ByteOrder::Big;
+        let expected =
+            serde_json::json!({"dtype": "uint32", "shape": [3], "count": 6, "result": [1, 2, 3]});
+        assert_eq!(expected, response.to_json());
+        let json: serde_json::Value =
+            serde_json::from_slice(&serde_json::to_vec(&response).unwrap()).unwrap();
+        assert_eq!(expected, json);
+    }
 }
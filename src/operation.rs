@@ -6,18 +6,18 @@ use crate::types::dvalue::TryFromDValue;
 
 /// Trait for array elements.
 pub trait Element:
-    Clone
+    Accumulate
+    + Clone
     + Copy
     + PartialOrd
-    + num_traits::FromBytes<Bytes = <Self as num_traits::ToBytes>::Bytes>
     + num_traits::FromPrimitive
-    + num_traits::ToBytes
+    + num_traits::ToPrimitive
     + num_traits::Zero
-    + std::convert::From<u16>
     + std::fmt::Debug
     + std::iter::Sum
     + std::ops::Add<Output = Self>
     + std::ops::Div<Output = Self>
+    + std::ops::Sub<Output = Self>
     + TryFromDValue
     + zerocopy::AsBytes
     + zerocopy::FromBytes
@@ -26,25 +26,92 @@ pub trait Element:
 
 /// Blanket implementation of Element.
 impl<T> Element for T where
-    T: Clone
+    T: Accumulate
+        + Clone
         + Copy
         + PartialOrd
-        + num_traits::FromBytes<Bytes = <T as num_traits::ToBytes>::Bytes>
         + num_traits::FromPrimitive
         + num_traits::One
-        + num_traits::ToBytes
+        + num_traits::ToPrimitive
         + num_traits::Zero
-        + std::convert::From<u16>
         + std::fmt::Debug
         + std::iter::Sum
         + std::ops::Add<Output = Self>
         + std::ops::Div<Output = Self>
+        + std::ops::Sub<Output = Self>
         + TryFromDValue
         + zerocopy::AsBytes
         + zerocopy::FromBytes
 {
 }
 
+/// Trait for accumulating array elements in reductions such as sum.
+///
+/// Most types are accumulated in the same type. Half precision floats are accumulated in [f32] to
+/// avoid loss of precision, which is reflected in [models::DType::sum_dtype].
+pub trait Accumulate: Copy {
+    /// Type used to accumulate elements.
+    type Accumulator: Copy
+        + num_traits::Zero
+        + std::convert::From<Self>
+        + std::ops::Add<Output = Self::Accumulator>
+        + std::ops::Sub<Output = Self::Accumulator>
+        + num_traits::ToPrimitive
+        + zerocopy::AsBytes;
+
+    /// Returns the sum of all elements in an array.
+    fn sum<D: ndarray::Dimension>(array: &ndarray::ArrayView<Self, D>) -> Self::Accumulator;
+}
+
+/// Implement Accumulate for types that are accumulated in the same type.
+macro_rules! impl_accumulate {
+    ($($t:ty),*) => {
+        $(
+            impl Accumulate for $t {
+                type Accumulator = $t;
+
+                fn sum<D: ndarray::Dimension>(array: &ndarray::ArrayView<Self, D>) -> Self {
+                    array.sum()
+                }
+            }
+        )*
+    };
+}
+
+impl_accumulate!(i32, i64, u32, u64, f32, f64);
+
+/// Implement Accumulate for half precision types that are accumulated in [f32].
+macro_rules! impl_accumulate_f32 {
+    ($($t:ty),*) => {
+        $(
+            impl Accumulate for $t {
+                type Accumulator = f32;
+
+                fn sum<D: ndarray::Dimension>(array: &ndarray::ArrayView<Self, D>) -> f32 {
+                    array.fold(0.0, |sum, x| sum + f32::from(*x))
+                }
+            }
+        )*
+    };
+}
+
+impl_accumulate_f32!(half::f16, half::bf16);
+
+/// Strategy used to sum floating point values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum FloatSumStrategy {
+    /// Sum values from left to right. Fastest, but the error grows linearly with the number of
+    /// values.
+    Naive,
+    /// Kahan compensated summation. Slowest, with an error that is independent of the number of
+    /// values.
+    Kahan,
+    /// Sum blocks of values from left to right, then sum the block sums in pairs. The error grows
+    /// logarithmically with the number of values.
+    #[default]
+    Pairwise,
+}
+
 /// Trait for active storage operations.
 ///
 /// This forms the contract between the API layer and operations.
@@ -61,6 +128,67 @@ pub trait Operation {
         request_data: &models::RequestData,
         data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError>;
+
+    /// Returns the data type and shape of the result of the operation, without executing it, or
+    /// `None` if the shape is not known until the data is decoded.
+    ///
+    /// The default is a reduction of the selected elements to a scalar, or along the `axis` of
+    /// the request, in the data type of the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_data`: RequestData object for the request
+    /// * `shape`: Shape of the selected elements, or `None` if it is not known until the data is
+    ///   decoded
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((request_data.dtype, reduced_shape(request_data, shape)?))
+    }
+
+    /// Returns the size in bytes of the response body of the operation, without executing it, or
+    /// `None` if it is not known until the data is decoded.
+    ///
+    /// The default is the size of the result given by [Operation::output]. Operations with a JSON
+    /// response body return an upper bound on its size.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_data`: RequestData object for the request
+    /// * `shape`: Shape of the selected elements, or `None` if it is not known until the data is
+    ///   decoded
+    fn response_bytes(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        let (dtype, shape) = Self::output(request_data, shape)?;
+        Some(shape.iter().product::<usize>() * dtype.size_of())
+    }
+}
+
+/// Returns the shape of the result of reducing the selected elements to a scalar, or along the
+/// `axis` of the request, or `None` if the shape of the selected elements is needed but unknown.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `shape`: Shape of the selected elements, if known
+pub fn reduced_shape(
+    request_data: &models::RequestData,
+    shape: Option<&[usize]>,
+) -> Option<Vec<usize>> {
+    match request_data.axis {
+        None => Some(vec![]),
+        Some(axis) => shape.map(|shape| {
+            shape
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != axis)
+                .map(|(_, length)| *length)
+                .collect()
+        }),
+    }
 }
 
 /// Trait for active storage operations on numerical data.
@@ -71,6 +199,37 @@ pub trait NumOperation: Operation {
         request_data: &models::RequestData,
         data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError>;
+
+    /// Returns the data type and shape of the result of the operation, as for
+    /// [Operation::output].
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((request_data.dtype, reduced_shape(request_data, shape)?))
+    }
+
+    /// Returns the size in bytes of the response body of the operation, as for
+    /// [Operation::response_bytes].
+    fn response_bytes(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        let (dtype, shape) = <Self as NumOperation>::output(request_data, shape)?;
+        Some(shape.iter().product::<usize>() * dtype.size_of())
+    }
+}
+
+/// Trait for active storage operations whose results for several objects may be combined.
+pub trait Combine: Operation {
+    /// Combine the results of the operation on several objects into a single result.
+    ///
+    /// Returns a [models::Response] object with the combined response data.
+    ///
+    /// # Arguments
+    ///
+    /// * `responses`: Non-empty list of responses for each object, with the same data type.
+    fn combine(responses: Vec<models::Response>) -> Result<models::Response, ActiveStorageError>;
 }
 
 impl<T: NumOperation> Operation for T {
@@ -87,10 +246,26 @@ impl<T: NumOperation> Operation for T {
             models::DType::Int64 => Self::execute_t::<i64>(request_data, data),
             models::DType::Uint32 => Self::execute_t::<u32>(request_data, data),
             models::DType::Uint64 => Self::execute_t::<u64>(request_data, data),
+            models::DType::Float16 => Self::execute_t::<half::f16>(request_data, data),
+            models::DType::Bfloat16 => Self::execute_t::<half::bf16>(request_data, data),
             models::DType::Float32 => Self::execute_t::<f32>(request_data, data),
             models::DType::Float64 => Self::execute_t::<f64>(request_data, data),
         }
     }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        <Self as NumOperation>::output(request_data, shape)
+    }
+
+    fn response_bytes(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        <Self as NumOperation>::response_bytes(request_data, shape)
+    }
 }
 
 #[cfg(test)]
@@ -146,6 +321,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn num_operation_output() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        assert_eq!(
+            Some((models::DType::Int64, vec![])),
+            <TestNumOp as Operation>::output(&request_data, None)
+        );
+        assert_eq!(
+            Some(8),
+            <TestNumOp as Operation>::response_bytes(&request_data, None)
+        );
+        request_data.axis = Some(1);
+        assert_eq!(None, <TestNumOp as Operation>::output(&request_data, None));
+        assert_eq!(
+            Some((models::DType::Int64, vec![2, 4])),
+            <TestNumOp as Operation>::output(&request_data, Some(&[2, 3, 4]))
+        );
+        assert_eq!(
+            Some(64),
+            <TestNumOp as Operation>::response_bytes(&request_data, Some(&[2, 3, 4]))
+        );
+    }
+
     #[test]
     fn num_operation_i64() {
         let mut request_data = test_utils::get_test_request_data();
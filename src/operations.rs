@@ -6,12 +6,16 @@
 use crate::array;
 use crate::error::ActiveStorageError;
 use crate::models;
-use crate::operation::{Element, NumOperation};
-use crate::types::Missing;
+use crate::operation::{self, Accumulate, Combine, Element, FloatSumStrategy, NumOperation};
+use crate::types::{DValue, Missing, NATIVE_BYTE_ORDER};
 
 use axum::body::Bytes;
-use ndarray::ArrayView;
+use ndarray::{ArrayView, Axis, Dimension};
 use ndarray_stats::{errors::MinMaxError, QuantileExt};
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use validator::ValidationError;
 // Bring trait into scope to use as_bytes method.
 use zerocopy::AsBytes;
 
@@ -31,6 +35,263 @@ fn missing_filter<'a, T: Element>(missing: &'a Missing<T>) -> Box<dyn Fn(&T) ->
     }
 }
 
+/// Description of the elements excluded from operations: missing data, and elements equal to the
+/// fill value of uninitialised regions of the data.
+struct Mask<T> {
+    /// Missing data description
+    missing: Option<Missing<T>>,
+    /// Fill value
+    fill_value: Option<T>,
+}
+
+impl<T: Element> Mask<T> {
+    /// Returns the mask described by the request data for a numeric type, or `None` if no elements
+    /// are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_data`: RequestData object for the request
+    fn from_request_data(
+        request_data: &models::RequestData,
+    ) -> Result<Option<Self>, ActiveStorageError> {
+        let missing = request_data
+            .missing
+            .as_ref()
+            .map(Missing::<T>::try_from)
+            .transpose()?;
+        let fill_value = request_data
+            .fill_value
+            .clone()
+            .map(T::try_from_dvalue)
+            .transpose()?;
+        if missing.is_none() && fill_value.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Mask {
+            missing,
+            fill_value,
+        }))
+    }
+}
+
+/// Returns a filter function that excludes missing data and the fill value, in the same way as
+/// [missing_filter].
+///
+/// # Arguments
+///
+/// * `mask`: Mask of excluded elements.
+fn mask_filter<'a, T: Element>(mask: &'a Mask<T>) -> Box<dyn Fn(&T) -> bool + 'a> {
+    match (&mask.missing, mask.fill_value) {
+        (Some(missing), None) => missing_filter(missing),
+        (None, Some(fill_value)) => Box::new(move |x: &T| *x != fill_value),
+        (Some(missing), Some(fill_value)) => {
+            let filter = missing_filter(missing);
+            Box::new(move |x: &T| *x != fill_value && filter(x))
+        }
+        (None, None) => Box::new(|_: &T| true),
+    }
+}
+
+/// Strategy used to sum floating point values, set at startup.
+static FLOAT_SUM_STRATEGY: OnceLock<FloatSumStrategy> = OnceLock::new();
+
+/// Set the strategy used to sum floating point values.
+///
+/// This should be called once at startup, and has no effect if the strategy is already set.
+pub fn set_float_sum_strategy(strategy: FloatSumStrategy) {
+    let _ = FLOAT_SUM_STRATEGY.set(strategy);
+}
+
+/// Returns the strategy used to sum floating point values.
+fn float_sum_strategy() -> FloatSumStrategy {
+    FLOAT_SUM_STRATEGY.get().copied().unwrap_or_default()
+}
+
+/// Number of values summed from left to right in each block of a pairwise summation.
+const PAIRWISE_BLOCK_SIZE: usize = 128;
+
+/// A running sum using a [FloatSumStrategy].
+///
+/// Integer sums are exact, so the strategy only affects floating point sums.
+enum Summation<A> {
+    /// Sum of the values so far.
+    Naive(A),
+    /// Sum of the values so far, and compensation for the low-order bits lost from it.
+    Kahan { sum: A, compensation: A },
+    /// Sum and length of the current block, and sums of previous blocks along with the base 2
+    /// logarithm of the number of blocks in each. Partial sums of the same number of blocks are
+    /// summed as they are completed.
+    Pairwise {
+        block: A,
+        length: usize,
+        partials: Vec<(A, u32)>,
+    },
+}
+
+impl<A> Summation<A>
+where
+    A: Copy + Zero + std::ops::Add<Output = A> + std::ops::Sub<Output = A>,
+{
+    /// Returns an empty sum using a strategy.
+    fn new(strategy: FloatSumStrategy) -> Self {
+        match strategy {
+            FloatSumStrategy::Naive => Self::Naive(A::zero()),
+            FloatSumStrategy::Kahan => Self::Kahan {
+                sum: A::zero(),
+                compensation: A::zero(),
+            },
+            FloatSumStrategy::Pairwise => Self::Pairwise {
+                block: A::zero(),
+                length: 0,
+                partials: vec![],
+            },
+        }
+    }
+
+    /// Returns the sum with a value added.
+    fn add(self, x: A) -> Self {
+        match self {
+            Self::Naive(sum) => Self::Naive(sum + x),
+            Self::Kahan { sum, compensation } => {
+                let y = x - compensation;
+                let t = sum + y;
+                Self::Kahan {
+                    sum: t,
+                    compensation: (t - sum) - y,
+                }
+            }
+            Self::Pairwise {
+                block,
+                length,
+                mut partials,
+            } => {
+                let block = block + x;
+                if length + 1 < PAIRWISE_BLOCK_SIZE {
+                    return Self::Pairwise {
+                        block,
+                        length: length + 1,
+                        partials,
+                    };
+                }
+                let mut partial = (block, 0);
+                while let Some(&(sum, level)) = partials.last() {
+                    if level != partial.1 {
+                        break;
+                    }
+                    partials.pop();
+                    partial = (sum + partial.0, level + 1);
+                }
+                partials.push(partial);
+                Self::Pairwise {
+                    block: A::zero(),
+                    length: 0,
+                    partials,
+                }
+            }
+        }
+    }
+
+    /// Returns the total sum.
+    fn finish(self) -> A {
+        match self {
+            Self::Naive(sum) => sum,
+            Self::Kahan {
+                sum,
+                compensation: _,
+            } => sum,
+            // Sum the smallest partial sums first.
+            Self::Pairwise {
+                block,
+                length: _,
+                partials,
+            } => partials
+                .into_iter()
+                .rev()
+                .fold(block, |sum, (partial, _)| sum + partial),
+        }
+    }
+}
+
+/// Returns whether a value is NaN.
+///
+/// NaN is the only value that has no ordering with respect to itself, so this is always false for
+/// integer types.
+fn is_nan<T: PartialOrd>(x: &T) -> bool {
+    x.partial_cmp(x).is_none()
+}
+
+/// Fold over the non-missing elements of an array.
+///
+/// Elements in non-native byte order are converted as they are read, so the array may be built
+/// using [array::build_array_unconverted]. This avoids a separate pass over the data to reverse
+/// its byte order. Elements are visited in an arbitrary order.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `array`: The array to fold over
+/// * `missing`: Optional missing data description
+/// * `init`: Initial value of the accumulator
+/// * `f`: Function combining the accumulator with an element
+fn fold_native<T: Element, B>(
+    request_data: &models::RequestData,
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    missing: &Option<Mask<T>>,
+    init: B,
+    f: impl Fn(B, T) -> B,
+) -> B {
+    // Dispatch on byte order here so that the conversion can be inlined into the fold.
+    if array::is_native_byte_order(request_data) {
+        fold_converted(array, |x| x, missing, init, f)
+    } else {
+        fold_converted(array, array::reversed_byte_order, missing, init, f)
+    }
+}
+
+/// Fold over the non-missing elements of an array, after applying a conversion function to each.
+fn fold_converted<T: Element, B>(
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    convert: impl Fn(T) -> T,
+    missing: &Option<Mask<T>>,
+    init: B,
+    f: impl Fn(B, T) -> B,
+) -> B {
+    match missing {
+        Some(missing) => {
+            let filter = mask_filter(missing);
+            array.fold(init, |acc, x| {
+                let x = convert(*x);
+                if filter(&x) {
+                    f(acc, x)
+                } else {
+                    acc
+                }
+            })
+        }
+        None => array.fold(init, |acc, x| f(acc, convert(*x))),
+    }
+}
+
+/// Count the non-missing elements of an array, comparing elements with missing data in native
+/// byte order.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `array`: The array to count
+/// * `missing`: Optional missing data description
+fn count_native<T: Element>(
+    request_data: &models::RequestData,
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    missing: &Option<Mask<T>>,
+) -> usize {
+    if missing.is_some() {
+        fold_native(request_data, array, missing, 0, |count, _| count + 1)
+    } else {
+        array.len()
+    }
+}
+
 /// Count the non-missing elements in an array with missing data.
 ///
 /// # Arguments
@@ -39,578 +300,5471 @@ fn missing_filter<'a, T: Element>(missing: &'a Missing<T>) -> Box<dyn Fn(&T) ->
 /// * `request_data`: RequestData object for the request
 fn count_non_missing<T: Element>(
     array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
-    missing: &Missing<T>,
+    mask: &Mask<T>,
 ) -> Result<usize, ActiveStorageError> {
-    let filter = missing_filter(missing);
+    let filter = mask_filter(mask);
     Ok(array.iter().copied().filter(filter).count())
 }
 
-/// Return the number of selected elements in the array.
-pub struct Count {}
+/// Apply a scalar reduction to a selection, or to each lane of the selection along the axis in
+/// the request data.
+///
+/// Returns the result, its shape, and the total count of the elements reduced. When reducing
+/// along an axis, the results for each lane are concatenated in C order (row major), and the
+/// shape is the shape of the selection with the axis removed.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `sliced`: The selection to reduce
+/// * `reduce`: Function returning the result of the reduction of an array and a count of the
+///   elements reduced
+fn reduce_axis<T: Element>(
+    request_data: &models::RequestData,
+    sliced: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    reduce: impl Fn(
+        &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    ) -> Result<(Bytes, usize), ActiveStorageError>,
+) -> Result<(Bytes, Vec<usize>, i64), ActiveStorageError> {
+    let Some(axis) = request_data.axis else {
+        let (body, count) = reduce(sliced)?;
+        return Ok((body, vec![], i64::try_from(count)?));
+    };
+    let mut body = vec![];
+    let mut count = 0;
+    // Lanes are visited in logical (row major) order regardless of the memory layout.
+    for lane in sliced.lanes(Axis(axis)) {
+        let (lane_body, lane_count) = reduce(&lane.into_dyn())?;
+        body.extend_from_slice(&lane_body);
+        count += lane_count;
+    }
+    let mut shape = sliced.shape().to_vec();
+    shape.remove(axis);
+    Ok((body.into(), shape, i64::try_from(count)?))
+}
 
-impl NumOperation for Count {
+/// Returns an error if the request data specifies an axis, for operations that cannot be
+/// performed along an axis.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `operation`: Name of the operation, used in errors
+fn check_no_axis(
+    request_data: &models::RequestData,
+    operation: &'static str,
+) -> Result<(), ActiveStorageError> {
+    match request_data.axis {
+        Some(_) => Err(ActiveStorageError::AxisUnsupported { operation }),
+        None => Ok(()),
+    }
+}
+
+/// Gather the elements at the flat indices in the request data into a 1D array, if specified.
+///
+/// Returns the request data and data to use for the operation. The gathered elements keep the
+/// byte order of the data, and the request data describes a 1D array of them in the order of the
+/// indices. Request data and data are returned unchanged if no indices are specified.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `data`: Decoded data
+pub fn gather_indices(
+    mut request_data: models::RequestData,
+    data: Vec<u8>,
+) -> Result<(models::RequestData, Vec<u8>), ActiveStorageError> {
+    let Some(indices) = request_data.indices.take() else {
+        return Ok((request_data, data));
+    };
+    let shape = gather_shape(&request_data, &data);
+    models::validate_indices_in_range(&indices, shape.iter().product())?;
+    Ok(gather(request_data, data, indices.into_iter()))
+}
+
+/// Returns the shape of the array from which elements are gathered.
+fn gather_shape(request_data: &models::RequestData, data: &[u8]) -> Vec<usize> {
+    request_data
+        .shape
+        .clone()
+        .or_else(|| request_data.layout_shape().clone())
+        .unwrap_or_else(|| vec![data.len() / request_data.dtype.size_of()])
+}
+
+/// Gather the elements at some flat indices into a 1D array, as for [gather_indices]. The indices
+/// must be in range.
+fn gather(
+    mut request_data: models::RequestData,
+    data: Vec<u8>,
+    indices: impl Iterator<Item = usize>,
+) -> (models::RequestData, Vec<u8>) {
+    let element_size = request_data.dtype.size_of();
+    let shape = gather_shape(&request_data, &data);
+    let layout_shape = request_data.layout_shape().clone().unwrap_or(shape.clone());
+    let mut gathered = Vec::with_capacity(indices.size_hint().0 * element_size);
+    for index in indices {
+        let offset = array::flat_index_offset(index, &shape, &layout_shape, request_data.order)
+            * element_size;
+        gathered.extend_from_slice(&data[offset..offset + element_size]);
+    }
+    request_data.shape = Some(vec![gathered.len() / element_size]);
+    request_data.stored_shape = None;
+    request_data.order = None;
+    (request_data, gathered)
+}
+
+/// Check that the size of a mask is the number of elements in the data.
+///
+/// # Arguments
+///
+/// * `mask_size`: Size of the mask in bytes
+/// * `elements`: Number of elements in the data
+pub fn check_mask_size(mask_size: usize, elements: usize) -> Result<(), ActiveStorageError> {
+    if mask_size != elements {
+        let mut error =
+            ValidationError::new("Mask size must be equal to the number of elements in the data");
+        error.add_param("mask size".into(), &mask_size);
+        error.add_param("elements".into(), &elements);
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Gather the elements selected by a mask into a 1D array, as for [gather_indices].
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `data`: Decoded data
+/// * `mask`: Mask with one byte per element of the array, with the same shape and order as the
+///   data. Elements whose mask byte is non-zero are gathered, in C order (row major).
+pub fn gather_mask(
+    request_data: models::RequestData,
+    data: Vec<u8>,
+    mask: &[u8],
+) -> Result<(models::RequestData, Vec<u8>), ActiveStorageError> {
+    use ndarray::ShapeBuilder;
+
+    let shape = gather_shape(&request_data, &data);
+    check_mask_size(mask.len(), shape.iter().product())?;
+    let shape = ndarray::IxDyn(&shape).set_f(request_data.order == Some(models::Order::F));
+    let mask = ArrayView::from_shape(shape, mask)?;
+    let indices = mask
+        .iter()
+        .enumerate()
+        .filter(|(_, include)| **include != 0)
+        .map(|(index, _)| index);
+    Ok(gather(request_data, data, indices))
+}
+
+/// Trait for combining the scalar results of an operation on several objects.
+trait CombineScalar {
+    /// Combine two scalar results.
+    fn combine_scalar<T: Element>(a: T, b: T) -> T;
+}
+
+/// Combine the scalar results of an operation on several objects.
+///
+/// The combined count is the total count of the responses.
+///
+/// # Arguments
+///
+/// * `responses`: Non-empty list of scalar responses, with the same data type.
+fn combine_scalars<C: CombineScalar>(
+    responses: Vec<models::Response>,
+) -> Result<models::Response, ActiveStorageError> {
+    let Some(first) = responses.first() else {
+        return Err(ValidationError::new("Batch responses must not be empty").into());
+    };
+    // Convert runtime data type into concrete types.
+    match first.dtype {
+        models::DType::Int32 => combine_scalars_t::<C, i32>(responses),
+        models::DType::Int64 => combine_scalars_t::<C, i64>(responses),
+        models::DType::Uint32 => combine_scalars_t::<C, u32>(responses),
+        models::DType::Uint64 => combine_scalars_t::<C, u64>(responses),
+        models::DType::Float16 => combine_scalars_t::<C, half::f16>(responses),
+        models::DType::Bfloat16 => combine_scalars_t::<C, half::bf16>(responses),
+        models::DType::Float32 => combine_scalars_t::<C, f32>(responses),
+        models::DType::Float64 => combine_scalars_t::<C, f64>(responses),
+    }
+}
+
+/// Combine the scalar results of an operation on several objects, for a concrete data type.
+fn combine_scalars_t<C: CombineScalar, T: Element>(
+    responses: Vec<models::Response>,
+) -> Result<models::Response, ActiveStorageError> {
+    let dtype = responses[0].dtype;
+    let count = responses.iter().map(|response| response.count).sum();
+    let mut value: Option<T> = None;
+    for response in &responses {
+        if response.dtype != dtype {
+            return Err(ValidationError::new("Batch responses must have the same dtype").into());
+        }
+        let Some(scalar) = T::read_from(&response.body[..]) else {
+            return Err(ValidationError::new("Batch responses must be scalars").into());
+        };
+        value = Some(value.map_or(scalar, |value| C::combine_scalar(value, scalar)));
+    }
+    let Some(value) = value else {
+        return Err(ValidationError::new("Batch responses must not be empty").into());
+    };
+    // Need to copy to provide ownership to caller.
+    let body = Bytes::copy_from_slice(value.as_bytes());
+    Ok(models::Response::new(body, dtype, vec![], count))
+}
+
+/// Number of most significant bits of the hash of a value that select its [HyperLogLog] register.
+const HLL_PRECISION: u32 = 12;
+
+/// Number of registers of a [HyperLogLog].
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// A HyperLogLog sketch estimating the number of distinct values added to it, with memory use
+/// independent of the number of values.
+///
+/// Each value is hashed, and the register selected by the [HLL_PRECISION] most significant bits
+/// of the hash records the maximum position of the first set bit in the remaining bits. The
+/// estimate uses linear counting of the empty registers while it is small, as described by
+/// Heule et al. in "HyperLogLog in Practice". A 64 bit hash makes a large range correction
+/// unnecessary.
+struct HyperLogLog {
+    /// Maximum rank of the hashes in each register
+    registers: Vec<u8>,
+    /// Total number of values
+    count: usize,
+}
+
+impl HyperLogLog {
+    /// Returns an empty sketch.
+    fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; HLL_REGISTERS],
+            count: 0,
+        }
+    }
+
+    /// Returns the sketch with a value added.
+    fn add<T: Element>(mut self, x: T) -> Self {
+        let hash = Self::hash(x);
+        let register = (hash >> (64 - HLL_PRECISION)) as usize;
+        // Setting the bit after the remaining bits limits the rank when they are all zero.
+        let rank = ((hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1))).leading_zeros() + 1;
+        self.registers[register] = self.registers[register].max(rank as u8);
+        self.count += 1;
+        self
+    }
+
+    /// Returns a 64 bit hash of a value in native byte order.
+    ///
+    /// The bytes of the value are mixed using the finaliser of MurmurHash3, which is a bijection,
+    /// so distinct values of up to 64 bits have distinct hashes.
+    fn hash<T: Element>(x: T) -> u64 {
+        let mut bytes = [0_u8; 8];
+        bytes[..std::mem::size_of::<T>()].copy_from_slice(x.as_bytes());
+        let mut hash = u64::from_ne_bytes(bytes);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+        hash ^ (hash >> 33)
+    }
+
+    /// Returns the estimated number of distinct values.
+    fn estimate(&self) -> f64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|rank| 2_f64.powi(-i32::from(*rank)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let empty = self.registers.iter().filter(|rank| **rank == 0).count();
+        if estimate <= 2.5 * m && empty > 0 {
+            m * (m / empty as f64).ln()
+        } else {
+            estimate
+        }
+    }
+}
+
+/// Return the approximate number of distinct selected elements in the array as `int64`.
+///
+/// The distinct elements are counted using a HyperLogLog sketch, which is built in a single pass
+/// over the data with memory use independent of the number of elements. The standard error of the
+/// estimate is `1.04 / sqrt(HLL_REGISTERS)`, i.e. about 1.6%, and small numbers of distinct
+/// elements are usually counted exactly. NaN elements are ignored, and negative zero is counted as
+/// zero.
+pub struct ApproxDistinct {}
+
+impl NumOperation for ApproxDistinct {
     fn execute_t<T: Element>(
         request_data: &models::RequestData,
         mut data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError> {
-        let array = array::build_array::<T>(request_data, &mut data)?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
         let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
         let sliced = array.slice(slice_info);
-        let count = if let Some(missing) = &request_data.missing {
-            let missing = Missing::<T>::try_from(missing)?;
-            count_non_missing(&sliced, &missing)?
-        } else {
-            sliced.len()
-        };
-        let count = i64::try_from(count)?;
-        let body = count.to_ne_bytes();
-        // Need to copy to provide ownership to caller.
-        let body = Bytes::copy_from_slice(&body);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let sketch = fold_native(
+                request_data,
+                sliced,
+                &missing,
+                HyperLogLog::new(),
+                |sketch, x| {
+                    if is_nan(&x) {
+                        sketch
+                    } else if x == T::zero() {
+                        sketch.add(T::zero())
+                    } else {
+                        sketch.add(x)
+                    }
+                },
+            );
+            let body = (sketch.estimate().round() as i64).to_ne_bytes();
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(&body), sketch.count))
+        })?;
         Ok(models::Response::new(
             body,
             models::DType::Int64,
-            vec![],
+            shape,
             count,
         ))
     }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Int64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
 }
 
-/// Return the maximum of selected elements in the array.
-pub struct Max {}
+/// Number of most significant mantissa bits that distinguish the bins of a [Histogram].
+const HISTOGRAM_MANTISSA_BITS: u32 = 7;
 
-impl NumOperation for Max {
+/// Number of least significant bits of the [f64] representation of a value that are discarded
+/// to give its [Histogram] bin.
+const HISTOGRAM_SHIFT: u32 = f64::MANTISSA_DIGITS - 1 - HISTOGRAM_MANTISSA_BITS;
+
+/// Sign bit of the [f64] representation of a value.
+const SIGN_BIT: u64 = 1 << 63;
+
+/// A histogram of floating point values with logarithmically spaced bins, which may be built in a
+/// single pass over the data without knowing its range.
+///
+/// Each bin contains the values with the same sign, exponent and [HISTOGRAM_MANTISSA_BITS] most
+/// significant bits of the mantissa of their [f64] representation, so the width of a bin is
+/// at most `2^-HISTOGRAM_MANTISSA_BITS` times the magnitude of its values. Memory use depends on
+/// the range of magnitudes of the values, rather than the number of values.
+struct Histogram {
+    /// Number of values in each non-empty bin
+    bins: HashMap<u64, u64>,
+    /// Total number of values
+    count: u64,
+    /// Minimum value
+    min: f64,
+    /// Maximum value
+    max: f64,
+}
+
+impl Histogram {
+    /// Returns an empty histogram.
+    fn new() -> Self {
+        Histogram {
+            bins: HashMap::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Returns the histogram with a non-NaN value added.
+    fn add(mut self, x: f64) -> Self {
+        *self.bins.entry(Self::bin(x)).or_default() += 1;
+        self.count += 1;
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        self
+    }
+
+    /// Returns the bin containing a value.
+    ///
+    /// The sign bit of positive values is set and negative values are inverted, so that bins are
+    /// ordered in the same way as the values they contain.
+    fn bin(x: f64) -> u64 {
+        // Adding zero converts negative zero to positive zero.
+        let x = x + 0.0;
+        let bits = x.to_bits();
+        let ordered = if x.is_sign_negative() {
+            !bits
+        } else {
+            bits | SIGN_BIT
+        };
+        ordered >> HISTOGRAM_SHIFT
+    }
+
+    /// Returns the value at the midpoint of a bin.
+    fn midpoint(bin: u64) -> f64 {
+        let value = |ordered: u64| {
+            f64::from_bits(if ordered & SIGN_BIT != 0 {
+                ordered & !SIGN_BIT
+            } else {
+                !ordered
+            })
+        };
+        let low = value(bin << HISTOGRAM_SHIFT);
+        let high = value((bin << HISTOGRAM_SHIFT) | ((1 << HISTOGRAM_SHIFT) - 1));
+        // The bins of infinite values contain no finite values, but are bounded by NaN. Keep zero
+        // exact, rather than the midpoint of the smallest subnormal values.
+        if low.is_infinite() {
+            low
+        } else if high.is_infinite() {
+            high
+        } else if low == 0.0 {
+            0.0
+        } else {
+            low / 2.0 + high / 2.0
+        }
+    }
+
+    /// Returns the approximate percentile of the values, or `None` if there are none.
+    ///
+    /// The percentile is linearly interpolated between the values at the two closest ranks, like
+    /// NumPy's `percentile` with the default `linear` method, using the midpoint of the bin of
+    /// each value, limited to the minimum and maximum values. The lowest and highest ranks are
+    /// the exact minimum and maximum values.
+    fn percentile(&self, percentile: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let mut bins: Vec<(u64, u64)> = self.bins.iter().map(|(bin, n)| (*bin, *n)).collect();
+        bins.sort_unstable();
+        let value_at = |rank: u64| {
+            if rank == 0 {
+                return self.min;
+            }
+            if rank == self.count - 1 {
+                return self.max;
+            }
+            let mut cumulative = 0;
+            for (bin, n) in &bins {
+                cumulative += n;
+                if rank < cumulative {
+                    return Self::midpoint(*bin).clamp(self.min, self.max);
+                }
+            }
+            self.max
+        };
+        let rank = percentile / 100.0 * (self.count - 1) as f64;
+        let lower = rank.floor() as u64;
+        let (a, b) = (value_at(lower), value_at((lower + 1).min(self.count - 1)));
+        Some(a + (b - a) * (rank - lower as f64))
+    }
+}
+
+/// Return an approximate percentile of selected elements in the array as `float64`.
+///
+/// The elements are counted in a histogram, which is built in a single pass over the data rather
+/// than sorting a copy of them. Only the histogram is independent of the number of elements: as
+/// for other operations, the whole object is downloaded and decoded into memory first. NaN
+/// elements are ignored. Each of the two values interpolated between is within a relative error of
+/// `2^-(HISTOGRAM_MANTISSA_BITS + 1)`, i.e. 1/256, of the exact value at its rank.
+pub struct ApproxPercentile {}
+
+impl NumOperation for ApproxPercentile {
     fn execute_t<T: Element>(
         request_data: &models::RequestData,
         mut data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError> {
-        let array = array::build_array::<T>(request_data, &mut data)?;
+        let percentile = request_data.percentile.ok_or_else(|| {
+            ValidationError::new("Approx percentile requires percentile to be specified")
+        })?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
         let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
         let sliced = array.slice(slice_info);
-        let (max, count) = if let Some(missing) = &request_data.missing {
-            let missing = Missing::<T>::try_from(missing)?;
-            // Use a fold to simultaneously max and count the non-missing data.
-            // TODO: separate float impl?
-            // TODO: inifinite/NaN
-            let (max, count) = sliced
-                .iter()
-                .copied()
-                .filter(missing_filter(&missing))
-                .fold((None, 0), |(a, count), b| {
-                    let max = match (a, b) {
-                        (None, b) => Some(b), //FIXME: if b.is_finite() { Some(b) } else { None },
-                        (Some(a), b) => Some(std::cmp::max_by(a, b, |x, y| {
-                            x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Greater)
-                        })),
-                    };
-                    (max, count + 1)
-                });
-            let max = max.ok_or(ActiveStorageError::EmptyArray { operation: "max" })?;
-            (max, count)
-        } else {
-            let max = *sliced.max().map_err(|err| match err {
-                MinMaxError::EmptyInput => ActiveStorageError::EmptyArray { operation: "max" },
-                MinMaxError::UndefinedOrder => panic!("unexpected undefined order error for max"),
-            })?;
-            let count = sliced.len();
-            (max, count)
-        };
-        let count = i64::try_from(count)?;
-        let body = max.as_bytes();
-        // Need to copy to provide ownership to caller.
-        let body = Bytes::copy_from_slice(body);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let histogram = fold_native(
+                request_data,
+                sliced,
+                &missing,
+                Histogram::new(),
+                |histogram, x| match x.to_f64() {
+                    Some(x) if !x.is_nan() => histogram.add(x),
+                    _ => histogram,
+                },
+            );
+            let result =
+                histogram
+                    .percentile(percentile)
+                    .ok_or(ActiveStorageError::EmptyArray {
+                        operation: "approx_percentile",
+                    })?;
+            // Need to copy to provide ownership to caller.
+            Ok((
+                Bytes::copy_from_slice(result.as_bytes()),
+                usize::try_from(histogram.count)?,
+            ))
+        })?;
         Ok(models::Response::new(
             body,
-            request_data.dtype,
-            vec![],
+            models::DType::Float64,
+            shape,
             count,
         ))
     }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Float64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
 }
 
-/// Return the minimum of selected elements in the array.
-pub struct Min {}
+/// Return the number of selected elements in the array.
+pub struct Count {}
 
-impl NumOperation for Min {
+impl NumOperation for Count {
     fn execute_t<T: Element>(
         request_data: &models::RequestData,
         mut data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError> {
-        let array = array::build_array::<T>(request_data, &mut data)?;
+        // Byte order only matters when comparing with missing data.
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
         let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
         let sliced = array.slice(slice_info);
-        let (min, count) = if let Some(missing) = &request_data.missing {
-            let missing = Missing::<T>::try_from(missing)?;
-            // Use a fold to simultaneously min and count the non-missing data.
-            // TODO: separate float impl?
-            // TODO: inifinite/NaN
-            let (min, count) = sliced
-                .iter()
-                .copied()
-                .filter(missing_filter(&missing))
-                .fold((None, 0), |(a, count), b| {
-                    let min = match (a, b) {
-                        (None, b) => Some(b), //FIXME: if b.is_finite() { Some(b) } else { None },
-                        (Some(a), b) => Some(std::cmp::min_by(a, b, |x, y| {
-                            x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Less)
-                        })),
-                    };
-                    (min, count + 1)
-                });
-            let min = min.ok_or(ActiveStorageError::EmptyArray { operation: "min" })?;
-            (min, count)
-        } else {
-            let min = *sliced.min().map_err(|err| match err {
-                MinMaxError::EmptyInput => ActiveStorageError::EmptyArray { operation: "min" },
-                MinMaxError::UndefinedOrder => panic!("unexpected undefined order error for min"),
-            })?;
-            let count = sliced.len();
-            (min, count)
-        };
-        let count = i64::try_from(count)?;
-        let body = min.as_bytes();
-        // Need to copy to provide ownership to caller.
-        let body = Bytes::copy_from_slice(body);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let count = count_native(request_data, sliced, &missing);
+            let body = i64::try_from(count)?.to_ne_bytes();
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(&body), count))
+        })?;
         Ok(models::Response::new(
             body,
-            request_data.dtype,
-            vec![],
+            models::DType::Int64,
+            shape,
             count,
         ))
     }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Int64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
 }
 
-/// Return all selected elements in the array.
-pub struct Select {}
+impl CombineScalar for Count {
+    fn combine_scalar<T: Element>(a: T, b: T) -> T {
+        a + b
+    }
+}
 
-impl NumOperation for Select {
+impl Combine for Count {
+    fn combine(responses: Vec<models::Response>) -> Result<models::Response, ActiveStorageError> {
+        combine_scalars::<Self>(responses)
+    }
+}
+
+/// Return the number of selected elements in the array that are missing.
+///
+/// Elements equal to the fill value are not counted, even if they are also missing. The count in
+/// the response is the number of elements that are neither missing nor equal to the fill value,
+/// as for [Count].
+pub struct CountMissing {}
+
+impl NumOperation for CountMissing {
     fn execute_t<T: Element>(
         request_data: &models::RequestData,
         mut data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError> {
-        let array = array::build_array::<T>(request_data, &mut data)?;
+        // Byte order only matters when comparing with missing data.
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
         let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
         let sliced = array.slice(slice_info);
-        let count = if let Some(missing) = &request_data.missing {
-            let missing = Missing::<T>::try_from(missing)?;
-            count_non_missing(&sliced, &missing)?
-        } else {
-            sliced.len()
-        };
-        let count = i64::try_from(count)?;
-        let shape = sliced.shape().to_vec();
-        // Transpose Fortran ordered arrays before iterating.
-        let body = if !array.is_standard_layout() {
-            let sliced_ordered = sliced.t();
-            sliced_ordered.iter().copied().collect::<Vec<T>>()
-        } else {
-            sliced.iter().copied().collect::<Vec<T>>()
-        };
-        let body = body.as_bytes();
-        // Need to copy to provide ownership to caller.
-        let body = Bytes::copy_from_slice(body);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        // Elements equal to the fill value are uninitialised rather than missing, so are not
+        // counted.
+        let fill = missing
+            .as_ref()
+            .and_then(|mask| mask.fill_value)
+            .map(|fill_value| Mask {
+                missing: None,
+                fill_value: Some(fill_value),
+            });
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let count = count_native(request_data, sliced, &missing);
+            let fills = sliced.len() - count_native(request_data, sliced, &fill);
+            let body = i64::try_from(sliced.len() - count - fills)?.to_ne_bytes();
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(&body), count))
+        })?;
         Ok(models::Response::new(
             body,
-            request_data.dtype,
+            models::DType::Int64,
             shape,
             count,
         ))
     }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Int64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
 }
 
-/// Return the sum of selected elements in the array.
-pub struct Sum {}
+/// Return the fraction of selected elements in the array that are not missing, as a float64 in
+/// the range `[0, 1]`.
+///
+/// An empty selection has no valid fraction, and fails with an
+/// [ActiveStorageError::EmptyArray] error.
+pub struct ValidFraction {}
 
-impl NumOperation for Sum {
+impl NumOperation for ValidFraction {
     fn execute_t<T: Element>(
         request_data: &models::RequestData,
         mut data: Vec<u8>,
     ) -> Result<models::Response, ActiveStorageError> {
-        let array = array::build_array::<T>(request_data, &mut data)?;
+        // Byte order only matters when comparing with missing data.
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
         let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
         let sliced = array.slice(slice_info);
-        let (sum, count) = if let Some(missing) = &request_data.missing {
-            let missing = Missing::<T>::try_from(missing)?;
-            // Use a fold to simultaneously sum and count the non-missing data.
-            sliced
-                .iter()
-                .copied()
-                .filter(missing_filter(&missing))
-                .fold((T::zero(), 0), |(a, count), b| (a + b, count + 1))
-        } else {
-            (sliced.sum(), sliced.len())
-        };
-        let count = i64::try_from(count)?;
-        let body = sum.as_bytes();
-        // Need to copy to provide ownership to caller.
-        let body = Bytes::copy_from_slice(body);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            if sliced.is_empty() {
+                return Err(ActiveStorageError::EmptyArray {
+                    operation: "valid_fraction",
+                });
+            }
+            let count = count_native(request_data, sliced, &missing);
+            let body = (count as f64 / sliced.len() as f64).to_ne_bytes();
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(&body), count))
+        })?;
         Ok(models::Response::new(
             body,
-            request_data.dtype,
-            vec![],
+            models::DType::Float64,
+            shape,
             count,
         ))
     }
-}
 
-#[cfg(test)]
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Float64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+/// Returns the cumulative sum of the elements of an array in logical (row major) order.
+///
+/// Missing elements do not contribute to the sum.
+///
+/// # Arguments
+///
+/// * `array`: The array to sum
+/// * `missing`: Optional missing data description
+/// * `convert`: Function to convert elements to the accumulator type
+fn cumsum<T: Element, A>(
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    missing: &Option<Mask<T>>,
+    convert: fn(T) -> Option<A>,
+) -> Vec<A>
+where
+    A: Copy + Zero + std::ops::Add<Output = A>,
+{
+    let filter = missing.as_ref().map(mask_filter);
+    array
+        .iter()
+        .scan(A::zero(), |sum, x| {
+            if filter.as_ref().map_or(true, |filter| filter(x)) {
+                let x = convert(*x).expect("cumsum dtype can represent all values");
+                *sum = *sum + x;
+            }
+            Some(*sum)
+        })
+        .collect()
+}
+
+/// Return the cumulative sum of selected elements in the array.
+///
+/// The result has the same shape as the selection and is returned in C order. The data type of the
+/// result is given by [models::DType::cumsum_dtype].
+pub struct CumSum {}
+
+impl NumOperation for CumSum {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "cumsum")?;
+        let array = array::build_array::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let count = if let Some(missing) = &missing {
+            count_non_missing(&sliced, missing)?
+        } else {
+            sliced.len()
+        };
+        let count = i64::try_from(count)?;
+        let shape = sliced.shape().to_vec();
+        let dtype = request_data.dtype.cumsum_dtype();
+        let body = match dtype {
+            models::DType::Int64 => cumsum(&sliced, &missing, |x| x.to_i64())
+                .as_bytes()
+                .to_vec(),
+            models::DType::Uint64 => cumsum(&sliced, &missing, |x| x.to_u64())
+                .as_bytes()
+                .to_vec(),
+            models::DType::Float32 => cumsum(&sliced, &missing, |x| x.to_f32())
+                .as_bytes()
+                .to_vec(),
+            models::DType::Float64 => cumsum(&sliced, &missing, |x| x.to_f64())
+                .as_bytes()
+                .to_vec(),
+            _ => unreachable!("cumsum dtype is always 64-bit integer or float"),
+        };
+        Ok(models::Response::new(body.into(), dtype, shape, count))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((request_data.dtype.cumsum_dtype(), shape?.to_vec()))
+    }
+}
+
+/// Returns `b - a`, wrapping around on overflow of integer data types as NumPy does.
+///
+/// # Arguments
+///
+/// * `dtype`: Data type of the values
+/// * `a`: Value to subtract
+/// * `b`: Value to subtract from
+fn wrapping_sub<T: Element>(dtype: models::DType, a: T, b: T) -> T {
+    if dtype.is_float() {
+        return b - a;
+    }
+    let to_i128 = |x: T| {
+        x.to_i128()
+            .expect("integer types are representable as i128")
+    };
+    let modulus = 1_i128 << (8 * dtype.size_of());
+    let mut diff = (to_i128(b) - to_i128(a)).rem_euclid(modulus);
+    if matches!(dtype, models::DType::Int32 | models::DType::Int64) && diff >= modulus / 2 {
+        diff -= modulus;
+    }
+    T::from_i128(diff).expect("wrapped difference is representable in the data type")
+}
+
+/// Return the differences between successive selected elements in logical (row major) order.
+///
+/// The result is a one dimensional array with one element fewer than the selection, in the data
+/// type of the request, like NumPy's `diff` of the flattened selection. Integer differences wrap
+/// around on overflow, as in NumPy. Missing elements are not excluded, as for [Select], but the
+/// count is the number of selected elements that are not missing.
+pub struct Diff {}
+
+impl NumOperation for Diff {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "diff")?;
+        let array = array::build_array::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let count = if let Some(mask) = Mask::<T>::from_request_data(request_data)? {
+            count_non_missing(&sliced, &mask)?
+        } else {
+            sliced.len()
+        };
+        let count = i64::try_from(count)?;
+        let dtype = request_data.dtype;
+        let diffs: Vec<T> = sliced
+            .iter()
+            .zip(sliced.iter().skip(1))
+            .map(|(a, b)| wrapping_sub(dtype, *a, *b))
+            .collect();
+        let shape = vec![diffs.len()];
+        Ok(models::Response::new(
+            Bytes::copy_from_slice(diffs.as_bytes()),
+            dtype,
+            shape,
+            count,
+        ))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        let elements = shape?.iter().product::<usize>();
+        Some((request_data.dtype, vec![elements.saturating_sub(1)]))
+    }
+}
+
+/// Returns the first or last non-missing element of a selection in logical (row major) order, or
+/// of each lane along the axis in the request data.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `data`: Data to operate on
+/// * `operation`: Name of the operation, used in errors
+/// * `last`: Whether to return the last element rather than the first
+fn first_last<T: Element>(
+    request_data: &models::RequestData,
+    data: &mut [u8],
+    operation: &'static str,
+    last: bool,
+) -> Result<models::Response, ActiveStorageError> {
+    let array = array::build_array_unconverted::<T>(request_data, data)?;
+    let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+    let sliced = array.slice(slice_info);
+    let missing = Mask::<T>::from_request_data(request_data)?;
+    let filter = missing.as_ref().map(mask_filter);
+    let native = array::is_native_byte_order(request_data);
+    let native_value = |x: &T| {
+        if native {
+            *x
+        } else {
+            array::reversed_byte_order(*x)
+        }
+    };
+    let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+        // Iteration is in logical (row major) order regardless of the memory layout, so the
+        // last element is found by iterating with every axis reversed. Iteration stops at the
+        // first non-missing element.
+        let is_valid = |x: &T| filter.as_ref().map_or(true, |filter| filter(x));
+        let element = if last {
+            let reversed = sliced.slice_each_axis(|_| ndarray::Slice::new(0, None, -1));
+            reversed.iter().map(native_value).find(is_valid)
+        } else {
+            sliced.iter().map(native_value).find(is_valid)
+        };
+        let element = element.ok_or(ActiveStorageError::EmptyArray { operation })?;
+        let count = count_native(request_data, sliced, &missing);
+        // Need to copy to provide ownership to caller.
+        Ok((Bytes::copy_from_slice(element.as_bytes()), count))
+    })?;
+    Ok(models::Response::new(
+        body,
+        request_data.dtype,
+        shape,
+        count,
+    ))
+}
+
+/// Return the first non-missing element of the selection in logical (row major) order.
+pub struct First {}
+
+impl NumOperation for First {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        first_last::<T>(request_data, &mut data, "first", false)
+    }
+}
+
+/// Return the last non-missing element of the selection in logical (row major) order.
+pub struct Last {}
+
+impl NumOperation for Last {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        first_last::<T>(request_data, &mut data, "last", true)
+    }
+}
+
+/// Return the geometric mean of selected elements in the array as `float64`.
+///
+/// The geometric mean is computed as `exp(mean(ln(x)))` over the non-missing elements, which
+/// avoids the overflow of multiplying the elements together. The logarithm is undefined for
+/// elements less than or equal to zero, so rather than silently excluding them, the operation
+/// fails with an [ActiveStorageError::NonPositiveValue] error if any non-missing element is not
+/// positive. Such elements may instead be excluded by describing them as missing, for example
+/// using a valid minimum. NaN elements result in NaN. An empty selection, or one in which all
+/// elements are missing, fails with an [ActiveStorageError::EmptyArray] error.
+pub struct GeometricMean {}
+
+impl NumOperation for GeometricMean {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            // Use a fold to simultaneously sum the logarithms, count the non-missing data and
+            // check for non-positive elements.
+            let (sum, count, non_positive) = fold_native(
+                request_data,
+                sliced,
+                &missing,
+                (0.0_f64, 0_usize, false),
+                |(sum, count, non_positive), x| {
+                    let x = x
+                        .to_f64()
+                        .expect("all supported types are representable as f64");
+                    (sum + x.ln(), count + 1, non_positive || x <= 0.0)
+                },
+            );
+            if non_positive {
+                return Err(ActiveStorageError::NonPositiveValue {
+                    operation: "geometric_mean",
+                });
+            }
+            if count == 0 {
+                return Err(ActiveStorageError::EmptyArray {
+                    operation: "geometric_mean",
+                });
+            }
+            let result = (sum / count as f64).exp();
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(result.as_bytes()), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            models::DType::Float64,
+            shape,
+            count,
+        ))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Float64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+/// Return the maximum of selected elements in the array.
+pub struct Max {}
+
+impl NumOperation for Max {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let (max, count) = if missing.is_some() || !array::is_native_byte_order(request_data) {
+                // Use a fold to simultaneously max and count the non-missing data.
+                // TODO: separate float impl?
+                // TODO: inifinite/NaN
+                let (max, count) = fold_native(
+                    request_data,
+                    sliced,
+                    &missing,
+                    (None, 0),
+                    |(a, count), b| {
+                        let max = match (a, b) {
+                            (None, b) => Some(b), //FIXME: if b.is_finite() { Some(b) } else { None },
+                            (Some(a), b) => Some(std::cmp::max_by(a, b, |x, y| {
+                                x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Greater)
+                            })),
+                        };
+                        (max, count + 1)
+                    },
+                );
+                let max = max.ok_or(ActiveStorageError::EmptyArray { operation: "max" })?;
+                (max, count)
+            } else {
+                let max = *sliced.max().map_err(|err| match err {
+                    MinMaxError::EmptyInput => ActiveStorageError::EmptyArray { operation: "max" },
+                    MinMaxError::UndefinedOrder => {
+                        panic!("unexpected undefined order error for max")
+                    }
+                })?;
+                let count = sliced.len();
+                (max, count)
+            };
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(max.as_bytes()), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            request_data.dtype,
+            shape,
+            count,
+        ))
+    }
+}
+
+impl CombineScalar for Max {
+    fn combine_scalar<T: Element>(a: T, b: T) -> T {
+        std::cmp::max_by(a, b, |x, y| {
+            x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Greater)
+        })
+    }
+}
+
+impl Combine for Max {
+    fn combine(responses: Vec<models::Response>) -> Result<models::Response, ActiveStorageError> {
+        combine_scalars::<Self>(responses)
+    }
+}
+
+/// Return the minimum of selected elements in the array.
+pub struct Min {}
+
+impl NumOperation for Min {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let (min, count) = if missing.is_some() || !array::is_native_byte_order(request_data) {
+                // Use a fold to simultaneously min and count the non-missing data.
+                // TODO: separate float impl?
+                // TODO: inifinite/NaN
+                let (min, count) = fold_native(
+                    request_data,
+                    sliced,
+                    &missing,
+                    (None, 0),
+                    |(a, count), b| {
+                        let min = match (a, b) {
+                            (None, b) => Some(b), //FIXME: if b.is_finite() { Some(b) } else { None },
+                            (Some(a), b) => Some(std::cmp::min_by(a, b, |x, y| {
+                                x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Less)
+                            })),
+                        };
+                        (min, count + 1)
+                    },
+                );
+                let min = min.ok_or(ActiveStorageError::EmptyArray { operation: "min" })?;
+                (min, count)
+            } else {
+                let min = *sliced.min().map_err(|err| match err {
+                    MinMaxError::EmptyInput => ActiveStorageError::EmptyArray { operation: "min" },
+                    MinMaxError::UndefinedOrder => {
+                        panic!("unexpected undefined order error for min")
+                    }
+                })?;
+                let count = sliced.len();
+                (min, count)
+            };
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(min.as_bytes()), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            request_data.dtype,
+            shape,
+            count,
+        ))
+    }
+}
+
+impl CombineScalar for Min {
+    fn combine_scalar<T: Element>(a: T, b: T) -> T {
+        std::cmp::min_by(a, b, |x, y| {
+            x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Less)
+        })
+    }
+}
+
+impl Combine for Min {
+    fn combine(responses: Vec<models::Response>) -> Result<models::Response, ActiveStorageError> {
+        combine_scalars::<Self>(responses)
+    }
+}
+
+/// Return the most frequent of selected elements in the array.
+///
+/// Only integer data types are supported. Ties are broken toward the smallest value.
+pub struct Mode {}
+
+impl NumOperation for Mode {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        if request_data.dtype.is_float() {
+            return Err(ActiveStorageError::UnsupportedDType {
+                operation: "mode",
+                dtype: request_data.dtype.to_string().to_lowercase(),
+            });
+        }
+        let array = array::build_array::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let filter = missing.as_ref().map(mask_filter);
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let mut values = sliced
+                .iter()
+                .copied()
+                .filter(|x| filter.as_ref().map_or(true, |filter| filter(x)))
+                .collect::<Vec<T>>();
+            // Sort the values so that equal values are adjacent. Integers have a total order.
+            values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            let count = values.len();
+            // Find the longest run of equal values. Only replacing the mode when a strictly longer
+            // run is found breaks ties toward the smallest value.
+            let (mode, _) = values
+                .chunk_by(|a, b| a == b)
+                .fold((None, 0), |(mode, max), run| {
+                    if run.len() > max {
+                        (Some(run[0]), run.len())
+                    } else {
+                        (mode, max)
+                    }
+                });
+            let mode = mode.ok_or(ActiveStorageError::EmptyArray { operation: "mode" })?;
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(mode.as_bytes()), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            request_data.dtype,
+            shape,
+            count,
+        ))
+    }
+}
+
+/// Reduce the non-missing, non-NaN selected elements in an array using a binary function.
+///
+/// If there are selected elements but all of them are missing or NaN, returns NaN for floating
+/// point data types, with a count of zero.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `data`: [`Vec<u8>`] containing data to operate on.
+/// * `operation`: Name of the operation, used in errors
+/// * `reduce`: Function returning one of two elements
+fn nan_reduce<T: Element>(
+    request_data: &models::RequestData,
+    mut data: Vec<u8>,
+    operation: &'static str,
+    reduce: fn(T, T) -> T,
+) -> Result<models::Response, ActiveStorageError> {
+    let array = array::build_array::<T>(request_data, &mut data)?;
+    let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+    let sliced = array.slice(slice_info);
+    let missing = Mask::<T>::from_request_data(request_data)?;
+    let missing_filter = missing.as_ref().map(mask_filter);
+    let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+        let (result, count) = sliced
+            .iter()
+            .copied()
+            .filter(|x| !is_nan(x))
+            .filter(|x| missing_filter.as_ref().map_or(true, |filter| filter(x)))
+            .fold((None, 0_usize), |(a, count), b| {
+                let result = match a {
+                    None => b,
+                    Some(a) => reduce(a, b),
+                };
+                (Some(result), count + 1)
+            });
+        let result = match result {
+            Some(result) => result,
+            None if !sliced.is_empty() && request_data.dtype.is_float() => {
+                T::from_f64(f64::NAN).expect("NaN should be representable by float types")
+            }
+            None => return Err(ActiveStorageError::EmptyArray { operation }),
+        };
+        // Need to copy to provide ownership to caller.
+        Ok((Bytes::copy_from_slice(result.as_bytes()), count))
+    })?;
+    Ok(models::Response::new(
+        body,
+        request_data.dtype,
+        shape,
+        count,
+    ))
+}
+
+/// Return the maximum of selected elements in the array, ignoring NaNs.
+///
+/// This follows the semantics of NumPy's `nanmax`.
+pub struct NanMax {}
+
+impl NumOperation for NanMax {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        nan_reduce::<T>(
+            request_data,
+            data,
+            "nanmax",
+            |a, b| if b > a { b } else { a },
+        )
+    }
+}
+
+/// Return the minimum of selected elements in the array, ignoring NaNs.
+///
+/// This follows the semantics of NumPy's `nanmin`.
+pub struct NanMin {}
+
+impl NumOperation for NanMin {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        nan_reduce::<T>(
+            request_data,
+            data,
+            "nanmin",
+            |a, b| if b < a { b } else { a },
+        )
+    }
+}
+
+/// Returns the peak-to-peak range of two values in the data type given by
+/// [models::DType::ptp_dtype].
+///
+/// # Arguments
+///
+/// * `dtype`: Data type of the values
+/// * `min`: Minimum value
+/// * `max`: Maximum value
+fn ptp_bytes<T: Element>(dtype: models::DType, min: T, max: T) -> Bytes {
+    let range = || {
+        max.to_i128()
+            .expect("integer types are representable as i128")
+            - min
+                .to_i128()
+                .expect("integer types are representable as i128")
+    };
+    match dtype {
+        models::DType::Int32 => {
+            let range = u32::try_from(range()).expect("int32 range is representable as uint32");
+            Bytes::copy_from_slice(range.as_bytes())
+        }
+        models::DType::Int64 => {
+            let range = u64::try_from(range()).expect("int64 range is representable as uint64");
+            Bytes::copy_from_slice(range.as_bytes())
+        }
+        // Unsigned integers cannot underflow since the maximum is at least the minimum.
+        _ => Bytes::copy_from_slice((max - min).as_bytes()),
+    }
+}
+
+/// Return the peak-to-peak range (maximum minus minimum) of selected elements in the array.
+///
+/// This follows the semantics of NumPy's `ptp`, except that signed integers are promoted to
+/// unsigned integers of the same size to avoid overflow.
+pub struct Ptp {}
+
+impl NumOperation for Ptp {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            // Use a fold to simultaneously find the min, max and count of the non-missing data,
+            // comparing elements in the same way as the min and max operations.
+            let (min_max, count) = fold_native(
+                request_data,
+                sliced,
+                &missing,
+                (None, 0),
+                |(min_max, count), b| {
+                    let min_max = match min_max {
+                        None => Some((b, b)),
+                        Some((min, max)) => {
+                            Some((Min::combine_scalar(min, b), Max::combine_scalar(max, b)))
+                        }
+                    };
+                    (min_max, count + 1)
+                },
+            );
+            let (min, max) = min_max.ok_or(ActiveStorageError::EmptyArray { operation: "ptp" })?;
+            Ok((ptp_bytes(request_data.dtype, min, max), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            request_data.dtype.ptp_dtype(),
+            shape,
+            count,
+        ))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            request_data.dtype.ptp_dtype(),
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+/// Returns an element cast to another type, or `None` if it is out of range.
+///
+/// Integers are cast exactly to integer types if they are in range. Integers cast to floating
+/// point types are rounded to the nearest representable value, so 64-bit integers with a
+/// magnitude above 2^53 may lose precision when cast to `float64`. Floating point values, which
+/// may only be cast to floating point types, are also rounded to the nearest representable value,
+/// becoming infinite if they are out of range.
+fn cast<T: Element, U: Element>(x: T, is_float: bool) -> Option<U> {
+    if is_float {
+        U::from_f64(x.to_f64()?)
+    } else {
+        x.to_i64()
+            .and_then(U::from_i64)
+            .or_else(|| x.to_u64().and_then(U::from_u64))
+    }
+}
+
+/// Returns the elements of an array in logical (row major) order cast to an output data type, in
+/// native byte order.
+///
+/// Returns a [ActiveStorageError::CastOverflow] error if any element is out of range.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `array`: The array to cast, which may be in non-native byte order
+/// * `output_dtype`: Data type to cast to
+fn cast_body<T: Element>(
+    request_data: &models::RequestData,
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    output_dtype: models::DType,
+) -> Result<Bytes, ActiveStorageError> {
+    fn cast_body_t<T: Element, U: Element>(
+        request_data: &models::RequestData,
+        array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+        output_dtype: models::DType,
+    ) -> Result<Bytes, ActiveStorageError> {
+        let native = array::is_native_byte_order(request_data);
+        let mut body = Vec::with_capacity(array.len() * std::mem::size_of::<U>());
+        for element in array.iter() {
+            let element = if native {
+                *element
+            } else {
+                array::reversed_byte_order(*element)
+            };
+            let element =
+                cast::<T, U>(element, request_data.dtype.is_float()).ok_or_else(|| {
+                    ActiveStorageError::CastOverflow {
+                        dtype: output_dtype.to_string().to_lowercase(),
+                    }
+                })?;
+            body.extend_from_slice(element.as_bytes());
+        }
+        Ok(body.into())
+    }
+    match output_dtype {
+        models::DType::Int32 => cast_body_t::<T, i32>(request_data, array, output_dtype),
+        models::DType::Int64 => cast_body_t::<T, i64>(request_data, array, output_dtype),
+        models::DType::Uint32 => cast_body_t::<T, u32>(request_data, array, output_dtype),
+        models::DType::Uint64 => cast_body_t::<T, u64>(request_data, array, output_dtype),
+        models::DType::Float16 => cast_body_t::<T, half::f16>(request_data, array, output_dtype),
+        models::DType::Bfloat16 => cast_body_t::<T, half::bf16>(request_data, array, output_dtype),
+        models::DType::Float32 => cast_body_t::<T, f32>(request_data, array, output_dtype),
+        models::DType::Float64 => cast_body_t::<T, f64>(request_data, array, output_dtype),
+    }
+}
+
+/// Return all selected elements in the array.
+///
+/// If an output dtype is specified, elements are cast to it and returned in native byte order.
+/// Integers are cast exactly to integer types if they are in range. Integers cast to floating
+/// point types are rounded to the nearest representable value, so 64-bit integers with a
+/// magnitude above 2^53 may lose precision when cast to `float64`. Floating point values, which
+/// may only be cast to floating point types, are also rounded to the nearest representable value,
+/// becoming infinite if they are out of range. Otherwise, the bytes of the selected elements are
+/// returned verbatim in the byte order of the data, which is reported in the response, avoiding a
+/// pass over the data to convert it. Elements are returned in the output order of the request, which is reported in
+/// the response. An output order of K keeps the order of the data, so that a contiguous selection
+/// is returned without copying whatever its order.
+pub struct Select {}
+
+impl NumOperation for Select {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "select")?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let mask = Mask::<T>::from_request_data(request_data)?;
+        let count = if mask.is_some() {
+            fold_native(request_data, &sliced, &mask, 0, |count, _| count + 1)
+        } else {
+            sliced.len()
+        };
+        let count = i64::try_from(count)?;
+        let shape = sliced.shape().to_vec();
+        let order = match request_data.output_order.unwrap_or_default() {
+            models::OutputOrder::C => models::Order::C,
+            models::OutputOrder::F => models::Order::F,
+            models::OutputOrder::K => request_data.order.unwrap_or(models::Order::C),
+        };
+        // Iteration is in logical (row major) order regardless of the memory layout, so reverse
+        // the axes to iterate in column major order.
+        let ordered = match order {
+            models::Order::C => sliced,
+            models::Order::F => sliced.reversed_axes(),
+        };
+        // A selection that is contiguous in the output order can be returned as a slice of the
+        // data, without copying.
+        let contiguous = ordered
+            .as_slice()
+            .map(|slice| (slice.as_ptr() as usize, std::mem::size_of_val(slice)));
+        let output_dtype = request_data.output_dtype.unwrap_or(request_data.dtype);
+        let verbatim = output_dtype == request_data.dtype;
+        let body = match contiguous {
+            _ if !verbatim => cast_body(request_data, &ordered, output_dtype)?,
+            Some((ptr, size)) if size > 0 => {
+                let offset = ptr - data.as_ptr() as usize;
+                Bytes::from(data).slice(offset..offset + size)
+            }
+            _ => {
+                let mut body = Vec::with_capacity(ordered.len() * std::mem::size_of::<T>());
+                for element in ordered.iter() {
+                    body.extend_from_slice(element.as_bytes());
+                }
+                Bytes::from(body)
+            }
+        };
+        let mut response = models::Response::new(body, output_dtype, shape, count);
+        response.order = Some(order);
+        if verbatim {
+            response.byte_order = request_data.byte_order.unwrap_or(NATIVE_BYTE_ORDER);
+        }
+        Ok(response)
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        let dtype = request_data.output_dtype.unwrap_or(request_data.dtype);
+        Some((dtype, shape?.to_vec()))
+    }
+}
+
+/// Returns the JSON number of a data type with the longest representation, to bound the size of
+/// JSON response bodies.
+fn max_json_number(dtype: models::DType) -> serde_json::Value {
+    if dtype.is_float() {
+        serde_json::json!(-f64::MIN_POSITIVE)
+    } else {
+        serde_json::json!(i64::MIN)
+    }
+}
+
+/// Return the minimum, maximum, sum and count of selected elements in the array.
+///
+/// The statistics are computed in a single pass over the data, comparing elements in the same way
+/// as the min and max operations, and summing them in the same way as the sum operation without
+/// an accumulate dtype or clip bounds. The response body is a JSON-encoded [models::Stats]
+/// object.
+pub struct Stats {}
+
+impl NumOperation for Stats {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "stats")?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (min_max, sum, count) = fold_native(
+            request_data,
+            &sliced,
+            &missing,
+            (
+                None,
+                Summation::<T::Accumulator>::new(float_sum_strategy()),
+                0_usize,
+            ),
+            |(min_max, sum, count), x| {
+                let min_max = match min_max {
+                    None => Some((x, x)),
+                    Some((min, max)) => {
+                        Some((Min::combine_scalar(min, x), Max::combine_scalar(max, x)))
+                    }
+                };
+                (min_max, sum.add(x.into()), count + 1)
+            },
+        );
+        let (min, max) = min_max.ok_or(ActiveStorageError::EmptyArray { operation: "stats" })?;
+        let count = i64::try_from(count)?;
+        let stats = models::Stats {
+            min: models::json_value(min, request_data.dtype),
+            max: models::json_value(max, request_data.dtype),
+            sum: models::json_value(sum.finish(), request_data.dtype.sum_dtype()),
+            count,
+        };
+        let body = serde_json::to_vec(&stats).expect("stats are serialisable");
+        Ok(models::Response::new(
+            body.into(),
+            request_data.dtype,
+            vec![],
+            count,
+        ))
+    }
+
+    fn response_bytes(
+        request_data: &models::RequestData,
+        _shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        let stats = models::Stats {
+            min: max_json_number(request_data.dtype),
+            max: max_json_number(request_data.dtype),
+            sum: max_json_number(request_data.dtype.sum_dtype()),
+            count: i64::MAX,
+        };
+        Some(
+            serde_json::to_vec(&stats)
+                .expect("stats are serialisable")
+                .len(),
+        )
+    }
+}
+
+/// Return the minimum and maximum of selected elements in the array, and their indices.
+///
+/// NaN and missing elements are ignored, like NumPy's `nanargmin` and `nanargmax`. If the minimum
+/// or maximum occurs more than once, the index of its first occurrence in the selection, in C
+/// order, is returned. Indices are in the array given by the shape, rather than the selection,
+/// unless the elements are gathered using indices or a mask, in which case they are positions in
+/// the gathered elements. The response body is a JSON-encoded [models::MinMaxLoc] object.
+pub struct MinMaxLoc {}
+
+impl NumOperation for MinMaxLoc {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "minmax_loc")?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let filter = missing.as_ref().map(mask_filter);
+        let native = array::is_native_byte_order(request_data);
+        // Elements are visited in C order, so only strictly smaller or larger elements replace
+        // the first occurrence.
+        let mut min_max: Option<((T, ndarray::IxDyn), (T, ndarray::IxDyn))> = None;
+        let mut count = 0_usize;
+        for (index, x) in sliced.indexed_iter() {
+            let x = if native {
+                *x
+            } else {
+                array::reversed_byte_order(*x)
+            };
+            if is_nan(&x) || !filter.as_ref().map_or(true, |filter| filter(&x)) {
+                continue;
+            }
+            count += 1;
+            match &mut min_max {
+                None => min_max = Some(((x, index.clone()), (x, index))),
+                Some((min, max)) => {
+                    if x < min.0 {
+                        *min = (x, index);
+                    } else if x > max.0 {
+                        *max = (x, index);
+                    }
+                }
+            }
+        }
+        let (min, max) = min_max.ok_or(ActiveStorageError::EmptyArray {
+            operation: "minmax_loc",
+        })?;
+        let location = |(value, index): (T, ndarray::IxDyn)| models::Location {
+            value: models::json_value(value, request_data.dtype),
+            index: array::unselected_index(array.shape(), &request_data.selection, index.slice()),
+        };
+        let min_max_loc = models::MinMaxLoc {
+            min: location(min),
+            max: location(max),
+        };
+        let body = serde_json::to_vec(&min_max_loc).expect("locations are serialisable");
+        Ok(models::Response::new(
+            body.into(),
+            request_data.dtype,
+            vec![],
+            i64::try_from(count)?,
+        ))
+    }
+
+    fn response_bytes(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        // Indices have one element per axis of the selection, or of the gathered elements.
+        let axes = shape.map_or(1, |shape| shape.len());
+        let location = || models::Location {
+            value: max_json_number(request_data.dtype),
+            index: vec![usize::MAX; axes],
+        };
+        let min_max_loc = models::MinMaxLoc {
+            min: location(),
+            max: location(),
+        };
+        Some(
+            serde_json::to_vec(&min_max_loc)
+                .expect("locations are serialisable")
+                .len(),
+        )
+    }
+}
+
+/// Lower and upper bounds to which elements are clamped.
+type ClipBounds<T> = (Option<T>, Option<T>);
+
+/// Returns the clip bounds in the request data converted to the element type.
+fn clip_bounds<T: Element>(
+    request_data: &models::RequestData,
+) -> Result<ClipBounds<T>, ActiveStorageError> {
+    let to_bound = |value: &Option<DValue>| value.clone().map(T::try_from_dvalue).transpose();
+    Ok((
+        to_bound(&request_data.clip_min)?,
+        to_bound(&request_data.clip_max)?,
+    ))
+}
+
+/// Returns an element clamped to the clip bounds.
+///
+/// NaN elements are not clamped.
+fn clip<T: Element>(x: T, (min, max): &ClipBounds<T>) -> T {
+    match (min, max) {
+        (Some(min), _) if x < *min => *min,
+        (_, Some(max)) if x > *max => *max,
+        _ => x,
+    }
+}
+
+/// Returns the sum of the non-missing elements of an array in an accumulator type, and a count of
+/// the non-missing elements.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `array`: The array to sum
+/// * `missing`: Optional missing data description
+/// * `bounds`: Bounds to which elements are clamped before summing
+/// * `convert`: Function to convert elements to the accumulator type
+fn sum_as<T: Element, A>(
+    request_data: &models::RequestData,
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    missing: &Option<Mask<T>>,
+    bounds: &ClipBounds<T>,
+    convert: fn(T) -> Option<A>,
+) -> (Bytes, usize)
+where
+    A: Copy + Zero + std::ops::Add<Output = A> + std::ops::Sub<Output = A> + AsBytes,
+{
+    let (sum, count) = fold_native(
+        request_data,
+        array,
+        missing,
+        (Summation::new(float_sum_strategy()), 0),
+        |(sum, count), x| {
+            let x = convert(clip(x, bounds)).expect("accumulate dtype can represent all values");
+            (sum.add(x), count + 1)
+        },
+    );
+    // Need to copy to provide ownership to caller.
+    (Bytes::copy_from_slice(sum.finish().as_bytes()), count)
+}
+
+/// Check that the non-missing elements of an array are finite.
+///
+/// Returns an [ActiveStorageError::NonFiniteValue] error naming the index of the first selected
+/// element in logical (row major) order that is NaN or infinite. As for [MinMaxLoc], the index is
+/// in the array given by the shape, rather than the selection, unless the elements are gathered
+/// using indices or a mask.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object for the request
+/// * `shape`: Shape of the array from which the elements are selected
+/// * `array`: Selected elements to check
+/// * `missing`: Elements excluded from the check
+/// * `operation`: Name of the operation, used in errors
+fn check_finite<T: Element>(
+    request_data: &models::RequestData,
+    shape: &[usize],
+    array: &ArrayView<T, ndarray::Dim<ndarray::IxDynImpl>>,
+    missing: &Option<Mask<T>>,
+    operation: &'static str,
+) -> Result<(), ActiveStorageError> {
+    let native = array::is_native_byte_order(request_data);
+    let filter = missing.as_ref().map(mask_filter);
+    let non_finite = array.indexed_iter().find(|(_, x)| {
+        let x = if native {
+            **x
+        } else {
+            array::reversed_byte_order(**x)
+        };
+        filter.as_ref().map_or(true, |filter| filter(&x)) && !x.to_f64().is_some_and(f64::is_finite)
+    });
+    match non_finite {
+        Some((index, _)) => Err(ActiveStorageError::NonFiniteValue {
+            operation,
+            index: array::unselected_index(shape, &request_data.selection, index.slice()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the pairwise sum of some contiguous elements.
+///
+/// Blocks of up to [PAIRWISE_BLOCK_SIZE] elements are summed using ndarray's native `sum` method,
+/// and the halves of longer runs of elements are summed recursively, like NumPy's `sum`.
+fn pairwise_sum<T: Element>(elements: &[T]) -> T::Accumulator {
+    if elements.len() <= PAIRWISE_BLOCK_SIZE {
+        return <T as Accumulate>::sum(&ArrayView::from(elements));
+    }
+    let (a, b) = elements.split_at(elements.len() / 2);
+    pairwise_sum(a) + pairwise_sum(b)
+}
+
+/// Returns the sum and count of the non-missing selected elements, clipped to any bounds, using a
+/// [Summation] with a strategy.
+fn float_sum_fold<T: Element>(
+    request_data: &models::RequestData,
+    sliced: &ArrayView<T, ndarray::IxDyn>,
+    missing: &Option<Mask<T>>,
+    bounds: &ClipBounds<T>,
+    strategy: FloatSumStrategy,
+) -> (T::Accumulator, usize) {
+    let (sum, count) = fold_native(
+        request_data,
+        sliced,
+        missing,
+        (Summation::<T::Accumulator>::new(strategy), 0),
+        |(sum, count), x| (sum.add(clip(x, bounds).into()), count + 1),
+    );
+    (sum.finish(), count)
+}
+
+/// Return the sum of selected elements in the array.
+///
+/// The sum is accumulated and returned in the accumulate dtype of the request if specified.
+/// If clip bounds are specified, non-missing elements are clamped to the bounds before summing.
+/// If `reject_nonfinite` is specified for floating point data, the sum fails if any non-missing
+/// element is NaN or infinite, naming the index of the first within the selection.
+pub struct Sum {}
+
+impl NumOperation for Sum {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        if request_data.reject_nonfinite == Some(true) && request_data.dtype.is_float() {
+            check_finite(request_data, array.shape(), &sliced, &missing, "sum")?;
+        }
+        let bounds = clip_bounds::<T>(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            Ok(match request_data.accumulate_dtype {
+                Some(models::DType::Int64) => {
+                    sum_as(request_data, sliced, &missing, &bounds, |x| x.to_i64())
+                }
+                Some(models::DType::Float64) => {
+                    sum_as(request_data, sliced, &missing, &bounds, |x| x.to_f64())
+                }
+                _ if request_data.dtype.sum_dtype().is_float() => {
+                    // The fold is only needed for missing data, clipping, byte swapping or
+                    // Kahan summation, or for pairwise summation of non-contiguous data.
+                    let plain = missing.is_none()
+                        && bounds == (None, None)
+                        && array::is_native_byte_order(request_data);
+                    let (sum, count) = match (float_sum_strategy(), sliced.as_slice_memory_order())
+                    {
+                        (FloatSumStrategy::Naive, _) if plain => {
+                            (<T as Accumulate>::sum(sliced), sliced.len())
+                        }
+                        (FloatSumStrategy::Pairwise, Some(elements)) if plain => {
+                            (pairwise_sum(elements), elements.len())
+                        }
+                        (strategy, _) => {
+                            float_sum_fold(request_data, sliced, &missing, &bounds, strategy)
+                        }
+                    };
+                    // Need to copy to provide ownership to caller.
+                    (Bytes::copy_from_slice(sum.as_bytes()), count)
+                }
+                _ => {
+                    let (sum, count) = if missing.is_some()
+                        || bounds != (None, None)
+                        || !array::is_native_byte_order(request_data)
+                    {
+                        // Use a fold to simultaneously sum and count the non-missing data.
+                        fold_native(
+                            request_data,
+                            sliced,
+                            &missing,
+                            (T::Accumulator::zero(), 0),
+                            |(a, count), b| (a + clip(b, &bounds).into(), count + 1),
+                        )
+                    } else {
+                        (<T as Accumulate>::sum(sliced), sliced.len())
+                    };
+                    // Need to copy to provide ownership to caller.
+                    (Bytes::copy_from_slice(sum.as_bytes()), count)
+                }
+            })
+        })?;
+        let dtype = request_data
+            .accumulate_dtype
+            .unwrap_or(request_data.dtype.sum_dtype());
+        Ok(models::Response::new(body, dtype, shape, count))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            request_data
+                .accumulate_dtype
+                .unwrap_or(request_data.dtype.sum_dtype()),
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+impl CombineScalar for Sum {
+    fn combine_scalar<T: Element>(a: T, b: T) -> T {
+        a + b
+    }
+}
+
+impl Combine for Sum {
+    fn combine(responses: Vec<models::Response>) -> Result<models::Response, ActiveStorageError> {
+        combine_scalars::<Self>(responses)
+    }
+}
+
+/// Return the sum of squares of selected elements in the array.
+///
+/// The result is always returned as `float64` to avoid overflow.
+pub struct SumOfSquares {}
+
+impl NumOperation for SumOfSquares {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let square = |x: T| {
+            let x = x
+                .to_f64()
+                .expect("all supported types are representable as f64");
+            x * x
+        };
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            // Use a fold to simultaneously sum and count the non-missing data.
+            let (sum, count) = fold_native(
+                request_data,
+                sliced,
+                &missing,
+                (0.0_f64, 0_usize),
+                |(a, count), b| (a + square(b), count + 1),
+            );
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(sum.as_bytes()), count))
+        })?;
+        Ok(models::Response::new(
+            body,
+            models::DType::Float64,
+            shape,
+            count,
+        ))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Float64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+/// Return the trimmed mean of selected elements in the array as `float64`.
+///
+/// The non-missing elements are sorted, and the `trim_fraction` of the request is discarded from
+/// each end before averaging the remainder, like SciPy's `trim_mean`. The number of elements
+/// discarded from each end is rounded down. NaN elements sort after all other values, as in
+/// NumPy, so NaN results only if NaN elements remain after trimming. All non-missing elements are
+/// collected in order to sort them, so memory use is proportional to the number of elements. An
+/// empty selection, or one in which no elements remain after trimming, fails with an
+/// [ActiveStorageError::EmptyArray] error.
+pub struct TrimmedMean {}
+
+impl NumOperation for TrimmedMean {
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let trim_fraction = request_data.trim_fraction.ok_or_else(|| {
+            ValidationError::new("Trimmed mean requires trim_fraction to be specified")
+        })?;
+        let array = array::build_array_unconverted::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let (body, shape, count) = reduce_axis(request_data, &sliced, |sliced| {
+            let mut values =
+                fold_native(request_data, sliced, &missing, vec![], |mut values, x| {
+                    values.push(
+                        x.to_f64()
+                            .expect("all supported types are representable as f64"),
+                    );
+                    values
+                });
+            values.sort_unstable_by(|a, b| {
+                a.partial_cmp(b)
+                    .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+            });
+            let cut = (trim_fraction * values.len() as f64) as usize;
+            let trimmed = &values[cut..values.len() - cut];
+            if trimmed.is_empty() {
+                return Err(ActiveStorageError::EmptyArray {
+                    operation: "trimmed_mean",
+                });
+            }
+            let result = trimmed.iter().sum::<f64>() / trimmed.len() as f64;
+            // Need to copy to provide ownership to caller.
+            Ok((Bytes::copy_from_slice(result.as_bytes()), values.len()))
+        })?;
+        Ok(models::Response::new(
+            body,
+            models::DType::Float64,
+            shape,
+            count,
+        ))
+    }
+
+    fn output(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((
+            models::DType::Float64,
+            operation::reduced_shape(request_data, shape)?,
+        ))
+    }
+}
+
+/// Return an array of weights converted to `f64`, with the selection applied.
+///
+/// # Arguments
+///
+/// * `request_data`: RequestData object describing the weights
+/// * `weights`: [`Vec<u8>`] containing the weights
+fn weights_array<W: Element>(
+    request_data: &models::RequestData,
+    mut weights: Vec<u8>,
+) -> Result<ndarray::ArrayD<f64>, ActiveStorageError> {
+    let array = array::build_array::<W>(request_data, &mut weights)?;
+    let slice_info = array::build_slice_info::<W>(&request_data.selection, array.shape());
+    Ok(array
+        .slice(slice_info)
+        .mapv(|w| w.to_f64().unwrap_or(f64::NAN)))
+}
+
+/// Return the weighted sum of selected elements in the array.
+///
+/// The weights are read from a second array with the same shape as the data. Elements that are
+/// missing, or whose weight is NaN, are excluded from both the weighted sum and the sum of
+/// weights, which is returned alongside the result. The result is always returned as `float64`.
+pub struct WeightedSum {}
+
+impl WeightedSum {
+    /// Execute the operation.
+    ///
+    /// This method dispatches to `execute_t` based on the `dtype` of the data, after converting
+    /// the weights to `f64`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_data`: RequestData object for the request
+    /// * `data`: [`Vec<u8>`] containing data to operate on
+    /// * `weights_request_data`: RequestData object describing the weights
+    /// * `weights`: [`Vec<u8>`] containing the weights
+    pub fn execute(
+        request_data: &models::RequestData,
+        data: Vec<u8>,
+        weights_request_data: &models::RequestData,
+        weights: Vec<u8>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        let weights = match weights_request_data.dtype {
+            models::DType::Int32 => weights_array::<i32>(weights_request_data, weights),
+            models::DType::Int64 => weights_array::<i64>(weights_request_data, weights),
+            models::DType::Uint32 => weights_array::<u32>(weights_request_data, weights),
+            models::DType::Uint64 => weights_array::<u64>(weights_request_data, weights),
+            models::DType::Float16 => weights_array::<half::f16>(weights_request_data, weights),
+            models::DType::Bfloat16 => weights_array::<half::bf16>(weights_request_data, weights),
+            models::DType::Float32 => weights_array::<f32>(weights_request_data, weights),
+            models::DType::Float64 => weights_array::<f64>(weights_request_data, weights),
+        }?;
+        match request_data.dtype {
+            models::DType::Int32 => Self::execute_t::<i32>(request_data, data, weights),
+            models::DType::Int64 => Self::execute_t::<i64>(request_data, data, weights),
+            models::DType::Uint32 => Self::execute_t::<u32>(request_data, data, weights),
+            models::DType::Uint64 => Self::execute_t::<u64>(request_data, data, weights),
+            models::DType::Float16 => Self::execute_t::<half::f16>(request_data, data, weights),
+            models::DType::Bfloat16 => Self::execute_t::<half::bf16>(request_data, data, weights),
+            models::DType::Float32 => Self::execute_t::<f32>(request_data, data, weights),
+            models::DType::Float64 => Self::execute_t::<f64>(request_data, data, weights),
+        }
+    }
+
+    /// Returns the data type and shape of the result of the operation, as for
+    /// [operation::Operation::output].
+    pub fn output(
+        _request_data: &models::RequestData,
+        _shape: Option<&[usize]>,
+    ) -> Option<(models::DType, Vec<usize>)> {
+        Some((models::DType::Float64, vec![]))
+    }
+
+    /// Returns the size in bytes of the response body of the operation, as for
+    /// [operation::Operation::response_bytes].
+    pub fn response_bytes(
+        request_data: &models::RequestData,
+        shape: Option<&[usize]>,
+    ) -> Option<usize> {
+        let (dtype, shape) = Self::output(request_data, shape)?;
+        Some(shape.iter().product::<usize>() * dtype.size_of())
+    }
+
+    fn execute_t<T: Element>(
+        request_data: &models::RequestData,
+        mut data: Vec<u8>,
+        weights: ndarray::ArrayD<f64>,
+    ) -> Result<models::Response, ActiveStorageError> {
+        check_no_axis(request_data, "weighted_sum")?;
+        let array = array::build_array::<T>(request_data, &mut data)?;
+        let slice_info = array::build_slice_info::<T>(&request_data.selection, array.shape());
+        let sliced = array.slice(slice_info);
+        if sliced.shape() != weights.shape() {
+            let mut error = ValidationError::new("Weights shape must match data shape");
+            error.add_param("data shape".into(), &sliced.shape());
+            error.add_param("weights shape".into(), &weights.shape());
+            return Err(error.into());
+        }
+        let missing = Mask::<T>::from_request_data(request_data)?;
+        let filter = missing.as_ref().map(mask_filter);
+        let (sum, weights_sum, count) = sliced
+            .iter()
+            .zip(weights.iter())
+            .filter(|(x, w)| !w.is_nan() && filter.as_ref().map_or(true, |filter| filter(x)))
+            .fold((0.0, 0.0, 0_usize), |(sum, weights_sum, count), (x, w)| {
+                let x = x.to_f64().expect("elements can be converted to f64");
+                (sum + x * w, weights_sum + w, count + 1)
+            });
+        let body = sum.as_bytes();
+        // Need to copy to provide ownership to caller.
+        let body = Bytes::copy_from_slice(body);
+        let mut response =
+            models::Response::new(body, models::DType::Float64, vec![], i64::try_from(count)?);
+        response.weights_sum = Some(weights_sum);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::operation::Operation;
-    use crate::test_utils;
-    use crate::types::DValue;
+    use crate::operation::Operation;
+    use crate::test_utils;
+    use crate::types::DValue;
+    use serde_json::json;
+
+    type ExecuteFn =
+        fn(&models::RequestData, Vec<u8>) -> Result<models::Response, ActiveStorageError>;
+
+    // Helper function that checks that the associative reductions produce the same response for
+    // data in non-native byte order as for the same values in native byte order.
+    fn test_non_native_byte_order<T: Element>(dtype: models::DType) {
+        let values: Vec<T> = (1..=8).map(|x| T::from_i32(x).unwrap()).collect();
+        let native = values.as_bytes().to_vec();
+        let non_native: Vec<u8> = values
+            .iter()
+            .flat_map(|x| x.as_bytes().iter().rev().copied())
+            .collect();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = dtype;
+        request_data.shape = Some(vec![2, 4]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(0, 4, 2),
+        ]);
+        let mut non_native_request_data = request_data.clone();
+        non_native_request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        for missing in [None, Some(Missing::MissingValue(3.into()))] {
+            for accumulate_dtype in [None, Some(models::DType::Float64)] {
+                request_data.missing.clone_from(&missing);
+                request_data.accumulate_dtype = accumulate_dtype;
+                non_native_request_data.missing.clone_from(&missing);
+                non_native_request_data.accumulate_dtype = accumulate_dtype;
+                let operations: [ExecuteFn; 5] = [
+                    Count::execute,
+                    Max::execute,
+                    Min::execute,
+                    Sum::execute,
+                    SumOfSquares::execute,
+                ];
+                for operation in operations {
+                    let expected = operation(&request_data, native.clone()).unwrap();
+                    let response = operation(&non_native_request_data, non_native.clone()).unwrap();
+                    assert_eq!(expected.body, response.body);
+                    assert_eq!(expected.dtype, response.dtype);
+                    assert_eq!(expected.count, response.count);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn non_native_byte_order_i32() {
+        test_non_native_byte_order::<i32>(models::DType::Int32)
+    }
+
+    #[test]
+    fn non_native_byte_order_i64() {
+        test_non_native_byte_order::<i64>(models::DType::Int64)
+    }
+
+    #[test]
+    fn non_native_byte_order_u32() {
+        test_non_native_byte_order::<u32>(models::DType::Uint32)
+    }
+
+    #[test]
+    fn non_native_byte_order_u64() {
+        test_non_native_byte_order::<u64>(models::DType::Uint64)
+    }
+
+    #[test]
+    fn non_native_byte_order_f16() {
+        test_non_native_byte_order::<half::f16>(models::DType::Float16)
+    }
+
+    #[test]
+    fn non_native_byte_order_bf16() {
+        test_non_native_byte_order::<half::bf16>(models::DType::Bfloat16)
+    }
+
+    #[test]
+    fn non_native_byte_order_f32() {
+        test_non_native_byte_order::<f32>(models::DType::Float32)
+    }
+
+    #[test]
+    fn non_native_byte_order_f64() {
+        test_non_native_byte_order::<f64>(models::DType::Float64)
+    }
+
+    fn approx_distinct(request_data: &models::RequestData, data: Vec<u8>) -> i64 {
+        let response = ApproxDistinct::execute(request_data, data).unwrap();
+        assert_eq!(models::DType::Int64, response.dtype);
+        i64::from_ne_bytes(response.body[..].try_into().unwrap())
+    }
+
+    #[test]
+    fn approx_distinct_i64_within_tolerance() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        for distinct in [1_i64, 10, 1000, 10_000, 100_000] {
+            // Each distinct value occurs several times, in a scattered order.
+            let values = (0..200_000_i64)
+                .map(|i| (i * 7919) % distinct)
+                .collect::<Vec<i64>>();
+            let approx = approx_distinct(&request_data, values.as_bytes().to_vec());
+            // Three times the standard error of the estimate.
+            let tolerance = (distinct as f64 * 3.0 * 1.04 / (HLL_REGISTERS as f64).sqrt()).max(0.5);
+            assert!(
+                ((approx - distinct) as f64).abs() <= tolerance,
+                "estimated {} distinct values, expected {}",
+                approx,
+                distinct
+            );
+        }
+    }
+
+    #[test]
+    fn approx_distinct_small_exact() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        let values = [5_u32, 3, 5, 9, 3, 3, 1];
+        let response = ApproxDistinct::execute(&request_data, values.as_bytes().into()).unwrap();
+        assert_eq!(4_i64.as_bytes(), response.body);
+        assert_eq!(7, response.count);
+    }
+
+    #[test]
+    fn approx_distinct_i32_missing_and_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.shape = Some(vec![2, 4]);
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::MissingValue(7.into()));
+        let integers = [1_i32, 1, 2, 7, 7, 7, 7, 4];
+        let response = ApproxDistinct::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!([2_i64, 1].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn approx_distinct_f32_nan_and_zero() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [0.0_f32, -0.0, f32::NAN, 1.5, f32::NAN, 1.5];
+        let response = ApproxDistinct::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(2_i64.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn approx_distinct_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(3.into()));
+        let values = [1_i64, 2, 3, 2].map(i64::swap_bytes);
+        let response = ApproxDistinct::execute(&request_data, values.as_bytes().into()).unwrap();
+        assert_eq!(2_i64.as_bytes(), response.body);
+        assert_eq!(3, response.count);
+    }
+
+    // Returns the exact percentile of some values, using linear interpolation like NumPy.
+    fn exact_percentile(values: &[f64], percentile: f64) -> f64 {
+        let mut values = values.to_vec();
+        values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = percentile / 100.0 * (values.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = (lower + 1).min(values.len() - 1);
+        values[lower] + (values[upper] - values[lower]) * (rank - lower as f64)
+    }
+
+    fn approx_percentile(request_data: &models::RequestData, data: Vec<u8>) -> f64 {
+        let response = ApproxPercentile::execute(request_data, data).unwrap();
+        assert_eq!(models::DType::Float64, response.dtype);
+        f64::from_ne_bytes(response.body[..].try_into().unwrap())
+    }
+
+    #[test]
+    fn histogram_bins_ordered() {
+        let values = [
+            f64::NEG_INFINITY,
+            -1e300,
+            -2.5,
+            -1e-300,
+            0.0,
+            1e-300,
+            1.0,
+            1.01,
+            3e10,
+            f64::INFINITY,
+        ];
+        for pair in values.windows(2) {
+            assert!(
+                Histogram::bin(pair[0]) < Histogram::bin(pair[1]),
+                "{:?}",
+                pair
+            );
+        }
+        assert_eq!(Histogram::bin(0.0), Histogram::bin(-0.0));
+        for value in values {
+            let midpoint = Histogram::midpoint(Histogram::bin(value));
+            assert!(
+                (midpoint - value).abs() <= value.abs() / 256.0 || midpoint == value,
+                "{} {}",
+                value,
+                midpoint
+            );
+        }
+    }
+
+    #[test]
+    fn approx_percentile_f64_within_tolerance() {
+        // A known distribution: the sum of two uniform pseudo-random values, which is triangular.
+        let mut state = 12345_u64;
+        let mut uniform = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1_u64 << 53) as f64
+        };
+        let values: Vec<f64> = (0..10_000)
+            .map(|_| 1000.0 * (uniform() + uniform()) - 700.0)
+            .collect();
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        for percentile in [0.0, 1.0, 25.0, 50.0, 90.0, 99.9, 100.0] {
+            request_data.percentile = Some(percentile);
+            let approx = approx_percentile(&request_data, values.as_bytes().to_vec());
+            let exact = exact_percentile(&values, percentile);
+            assert!(
+                (approx - exact).abs() <= exact.abs() / 256.0 + 1e-9,
+                "{} {} {}",
+                percentile,
+                approx,
+                exact
+            );
+        }
+        // The minimum and maximum are exact.
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        request_data.percentile = Some(0.0);
+        assert_eq!(
+            min,
+            approx_percentile(&request_data, values.as_bytes().to_vec())
+        );
+        request_data.percentile = Some(100.0);
+        assert_eq!(
+            max,
+            approx_percentile(&request_data, values.as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn approx_percentile_i32_missing_and_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue((-1).into()));
+        request_data.shape = Some(vec![2, 4]);
+        request_data.axis = Some(1);
+        request_data.percentile = Some(50.0);
+        let integers = [1_i32, -1, 3, 5, 1000, 2000, 3000, 4000];
+        let response =
+            ApproxPercentile::execute(&request_data, integers.as_bytes().into()).unwrap();
+        let results: Vec<f64> = response
+            .body
+            .chunks_exact(8)
+            .map(|bytes| f64::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert!((results[0] - 3.0).abs() <= 3.0 / 256.0, "{:?}", results);
+        assert!(
+            (results[1] - 2500.0).abs() <= 3000.0 / 256.0,
+            "{:?}",
+            results
+        );
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(7, response.count);
+    }
+
+    #[test]
+    fn approx_percentile_f32_nan_ignored() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.percentile = Some(100.0);
+        let floats = [0.5_f32, f32::NAN, 2.0];
+        let response = ApproxPercentile::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(2.0_f64.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn approx_percentile_requires_percentile() {
+        let request_data = test_utils::get_test_request_data();
+        match ApproxPercentile::execute(&request_data, [1_i32].as_bytes().into()) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => assert_eq!(
+                "Approx percentile requires percentile to be specified",
+                error.code
+            ),
+            _ => panic!("expected approx_percentile to fail"),
+        }
+    }
+
+    #[test]
+    fn approx_percentile_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        request_data.percentile = Some(10.0);
+        match ApproxPercentile::execute(&request_data, [1_i32].as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => {
+                assert_eq!("approx_percentile", operation)
+            }
+            _ => panic!("expected approx_percentile to fail"),
+        }
+    }
+
+    fn trimmed_mean(request_data: &models::RequestData, data: Vec<u8>) -> Vec<f64> {
+        let response = TrimmedMean::execute(request_data, data).unwrap();
+        assert_eq!(models::DType::Float64, response.dtype);
+        response
+            .body
+            .chunks_exact(8)
+            .map(|bytes| f64::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn trimmed_mean_i32() {
+        // scipy.stats.trim_mean([1, 2, 3, 4, 100, -50, 7, 8, 9, 10], 0.2) == 5.5
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.trim_fraction = Some(0.2);
+        let data = [1_i32, 2, 3, 4, 100, -50, 7, 8, 9, 10].as_bytes().to_vec();
+        assert_eq!(vec![5.5], trimmed_mean(&request_data, data.clone()));
+        // scipy.stats.trim_mean(np.arange(20), 0.1) == 9.5
+        let data = (0..20_i32).collect::<Vec<_>>().as_bytes().to_vec();
+        request_data.trim_fraction = Some(0.1);
+        assert_eq!(vec![9.5], trimmed_mean(&request_data, data));
+        // The number of elements discarded is rounded down: 0.4 * 3 elements discards 1.
+        request_data.trim_fraction = Some(0.4);
+        let data = [3_i32, 1, 2].as_bytes().to_vec();
+        assert_eq!(vec![2.0], trimmed_mean(&request_data, data));
+    }
+
+    #[test]
+    fn trimmed_mean_f64_untrimmed() {
+        // With no trimming, the trimmed mean is the mean.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.trim_fraction = Some(0.0);
+        let data = [1.5_f64, 2.5, 3.5, 40.0].as_bytes().to_vec();
+        let response = TrimmedMean::execute(&request_data, data).unwrap();
+        assert_eq!(11.875_f64.as_bytes(), response.body);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn trimmed_mean_f32_nan_trimmed() {
+        // NaN sorts last, so is discarded with the largest values.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.trim_fraction = Some(0.25);
+        let data = [f32::NAN, 2.0, -100.0, 4.0].as_bytes().to_vec();
+        assert_eq!(vec![3.0], trimmed_mean(&request_data, data));
+        request_data.trim_fraction = Some(0.0);
+        let data = [f32::NAN, 2.0, -100.0, 4.0].as_bytes().to_vec();
+        assert!(trimmed_mean(&request_data, data)[0].is_nan());
+    }
+
+    #[test]
+    fn trimmed_mean_i64_missing_and_axis() {
+        // scipy.stats.trim_mean([[0, 5, 1, 9], [4, 2, 8, 3], [7, 6, 11, 10]], 0.25, axis=1) is
+        // [3.0, 3.5, 8.5]. Excluding the missing 9 leaves 3 elements in the first row, of which
+        // none are discarded, giving [2.0, 3.5, 8.5].
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.shape = Some(vec![3, 4]);
+        request_data.axis = Some(1);
+        request_data.trim_fraction = Some(0.25);
+        let data = [0_i64, 5, 1, 9, 4, 2, 8, 3, 7, 6, 11, 10];
+        assert_eq!(
+            vec![3.0, 3.5, 8.5],
+            trimmed_mean(&request_data, data.as_bytes().to_vec())
+        );
+        request_data.missing = Some(Missing::MissingValue(9.into()));
+        let response = TrimmedMean::execute(&request_data, data.as_bytes().to_vec()).unwrap();
+        assert_eq!([2.0_f64, 3.5, 8.5].as_bytes(), response.body);
+        assert_eq!(vec![3], response.shape);
+        assert_eq!(11, response.count);
+    }
+
+    #[test]
+    fn trimmed_mean_nothing_remains() {
+        // Trimming half of an even number of elements from each end leaves nothing.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.trim_fraction = Some(0.5);
+        match TrimmedMean::execute(&request_data, [1_i32, 2].as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => {
+                assert_eq!("trimmed_mean", operation)
+            }
+            _ => panic!("expected trimmed_mean to fail"),
+        }
+        // An odd number of elements leaves the median.
+        let data = [5_i32, 1, 3].as_bytes().to_vec();
+        assert_eq!(vec![3.0], trimmed_mean(&request_data, data));
+    }
+
+    #[test]
+    fn trimmed_mean_requires_trim_fraction() {
+        let request_data = test_utils::get_test_request_data();
+        match TrimmedMean::execute(&request_data, [1_i32].as_bytes().into()) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => assert_eq!(
+                "Trimmed mean requires trim_fraction to be specified",
+                error.code
+            ),
+            _ => panic!("expected trimmed_mean to fail"),
+        }
+    }
+
+    #[test]
+    fn geometric_mean_f64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        let data = [0.5_f64, 2.0, 4.0, 32.0, 2.0].as_bytes().to_vec();
+        let response = GeometricMean::execute(&request_data, data).unwrap();
+        // (0.5 * 2 * 4 * 32 * 2)^(1/5) = 256^(1/5)
+        let result = f64::from_ne_bytes(response.body[..].try_into().unwrap());
+        assert!((result - 256_f64.powf(0.2)).abs() < 1e-12);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn geometric_mean_i32_missing_and_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::MissingValue(0.into()));
+        // The missing zero is excluded rather than failing.
+        let data = [1_i32, 10, 100, 0, 3, 27].as_bytes().to_vec();
+        let response = GeometricMean::execute(&request_data, data).unwrap();
+        let result: Vec<f64> = response
+            .body
+            .chunks_exact(8)
+            .map(|bytes| f64::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert!((result[0] - 10.0).abs() < 1e-12);
+        assert!((result[1] - 9.0).abs() < 1e-12);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn geometric_mean_non_positive() {
+        let request_data = test_utils::get_test_request_data();
+        for value in [0_i32, -2] {
+            let data = [4_i32, value, 9].as_bytes().to_vec();
+            match GeometricMean::execute(&request_data, data) {
+                Err(ActiveStorageError::NonPositiveValue { operation }) => {
+                    assert_eq!("geometric_mean", operation)
+                }
+                _ => panic!("expected geometric_mean to fail"),
+            }
+        }
+    }
+
+    #[test]
+    fn geometric_mean_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        match GeometricMean::execute(&request_data, [1_i32].as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => {
+                assert_eq!("geometric_mean", operation)
+            }
+            _ => panic!("expected geometric_mean to fail"),
+        }
+    }
+
+    #[test]
+    fn count_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Count::execute(&request_data, data).unwrap();
+        // A Vec<u8> of 8 elements == an i32 slice with 2 elements
+        // Count is always i64.
+        let expected: i64 = 2;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len()); // Assert that count value is 8 bytes (i.e. i64)
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(expected, response.count);
+    }
+
+    #[test]
+    fn count_u32_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.missing = Some(Missing::MissingValue(0x04030201.into()));
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Count::execute(&request_data, data).unwrap();
+        // A Vec<u8> of 8 elements == a u32 slice with 2 elements
+        // Count is always i64.
+        let expected: i64 = 1;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len()); // Assert that count value is 8 bytes (i.e. i64)
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(expected, response.count);
+    }
+
+    #[test]
+    fn count_missing_i32_1d_no_missing() {
+        let request_data = test_utils::get_test_request_data();
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = CountMissing::execute(&request_data, data).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn count_missing_i32_1d_missing_specifications() {
+        let data = [-1_i32, 0, 5, 10, 15, 20];
+        for (missing, expected) in [
+            (Missing::MissingValue((-1).into()), 1_i64),
+            (Missing::MissingValues(vec![(-1).into(), 20.into()]), 2),
+            (Missing::ValidMin(5.into()), 2),
+            (Missing::ValidMax(10.into()), 2),
+            (Missing::ValidRange(0.into(), 10.into()), 3),
+        ] {
+            let mut request_data = test_utils::get_test_request_data();
+            request_data.missing = Some(missing);
+            let response = CountMissing::execute(&request_data, data.as_bytes().into()).unwrap();
+            assert_eq!(expected.as_bytes(), response.body);
+            assert_eq!(models::DType::Int64, response.dtype);
+            // The count is of the non-missing elements.
+            assert_eq!(6 - expected, response.count);
+        }
+    }
+
+    #[test]
+    fn count_missing_i32_1d_fill_value() {
+        // Fill values are excluded but not counted as missing, even if also missing.
+        let data = [0_i32, 0, -1, 5, 10, -1, 0];
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.fill_value = Some(0.into());
+        let response = CountMissing::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+        request_data.missing = Some(Missing::ValidMin((-1).into()));
+        let response = CountMissing::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        request_data.missing = Some(Missing::MissingValues(vec![(-1).into(), 0.into()]));
+        let response = CountMissing::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(2_i64.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn fill_value_and_missing_value() {
+        // Elements equal to either the fill value or the missing value are excluded.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.fill_value = Some(DValue::from_f64(-999.0).unwrap());
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(1e20).unwrap()));
+        let data: Vec<u8> = [1.0_f32, -999.0, 2.0, 1e20, 4.0, -999.0]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        let response = Sum::execute(&request_data, data.clone()).unwrap();
+        assert_eq!(7.0_f32.as_bytes(), response.body);
+        assert_eq!(3, response.count);
+        let response = Count::execute(&request_data, data.clone()).unwrap();
+        assert_eq!(3_i64.as_bytes(), response.body);
+        let response = Min::execute(&request_data, data.clone()).unwrap();
+        assert_eq!(1.0_f32.as_bytes(), response.body);
+        let response = CountMissing::execute(&request_data, data.clone()).unwrap();
+        assert_eq!(1_i64.as_bytes(), response.body);
+        let response = Select::execute(&request_data, data).unwrap();
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn count_missing_f32_non_native_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        request_data.selection = Some(vec![models::Slice::new(1, 4, 1)]);
+        let data: Vec<u8> = [1.0_f32, 1.0, 2.0, 1.0, 1.0]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        let response = CountMissing::execute(&request_data, data).unwrap();
+        assert_eq!(2_i64.as_bytes(), response.body);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn count_missing_i64_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::ValidMin(0.into()));
+        let data = [-1_i64, -2, 3, 4, 5, 6];
+        let response = CountMissing::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([2_i64, 0].as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    /// Returns a request for a 4x6 int32 array with strides in both dimensions, selecting
+    /// `[0:4:2, 1:6:2]`.
+    fn count_strided_request_data() -> models::RequestData {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 6]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 4, 2),
+            models::Slice::new(1, 6, 2),
+        ]);
+        request_data
+    }
+
+    /// Returns the values 0 to 23 as int32 data.
+    fn count_strided_data() -> Vec<u8> {
+        (0..24_i32).collect::<Vec<_>>().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn count_i32_2d_strided() {
+        // np.arange(24).reshape(4, 6)[0:4:2, 1:6:2] is [[1, 3, 5], [13, 15, 17]].
+        let request_data = count_strided_request_data();
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(6_i64.as_bytes(), response.body);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_missing_values() {
+        let mut request_data = count_strided_request_data();
+        // 2 and 14 are not in the selection, so only 3 and 15 are missing.
+        request_data.missing = Some(Missing::MissingValues(vec![
+            2.into(),
+            3.into(),
+            14.into(),
+            15.into(),
+        ]));
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(4_i64.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+        let response = CountMissing::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(2_i64.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_valid_range() {
+        let mut request_data = count_strided_request_data();
+        request_data.missing = Some(Missing::ValidRange(3.into(), 13.into()));
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        // 3, 5 and 13 are valid.
+        assert_eq!(3_i64.as_bytes(), response.body);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_uneven() {
+        // Strides that do not divide the dimensions, and a stride larger than its dimension.
+        // np.arange(24).reshape(4, 6)[1:4:2, 0:6:4] is [[6, 10], [18, 22]].
+        let mut request_data = count_strided_request_data();
+        request_data.selection = Some(vec![
+            models::Slice::new(1, 4, 2),
+            models::Slice::new(0, 6, 4),
+        ]);
+        request_data.missing = Some(Missing::ValidMax(18.into()));
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(3_i64.as_bytes(), response.body);
+        request_data.selection = Some(vec![
+            models::Slice::new(3, 4, 5),
+            models::Slice::new(0, 6, 7),
+        ]);
+        request_data.missing = None;
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(1_i64.as_bytes(), response.body);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_fortran() {
+        // np.arange(24).reshape(4, 6, order="F")[0:4:2, 1:6:2] is [[4, 12, 20], [6, 14, 22]].
+        let mut request_data = count_strided_request_data();
+        request_data.order = Some(models::Order::F);
+        request_data.missing = Some(Missing::MissingValues(vec![1.into(), 6.into(), 20.into()]));
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!(4_i64.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+        request_data.axis = Some(1);
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!([2_i64, 2].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        request_data.axis = Some(0);
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!([1_i64, 2, 1].as_bytes(), response.body);
+        assert_eq!(vec![3], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_axis() {
+        let mut request_data = count_strided_request_data();
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::MissingValues(vec![3.into(), 5.into(), 15.into()]));
+        let response = Count::execute(&request_data, count_strided_data()).unwrap();
+        assert_eq!([1_i64, 2].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn count_i32_2d_strided_non_native_byte_order() {
+        let mut request_data = count_strided_request_data();
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(15.into()));
+        let data: Vec<i32> = (0..24_i32).map(|i| i.swap_bytes()).collect();
+        let response = Count::execute(&request_data, data.as_bytes().to_vec()).unwrap();
+        assert_eq!(5_i64.as_bytes(), response.body);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn valid_fraction_i32_all_valid() {
+        let request_data = test_utils::get_test_request_data();
+        let data = [1_i32, 2, 3, 4];
+        let response = ValidFraction::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(1.0_f64.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn valid_fraction_i32_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue((-1).into()));
+        let data = [-1_i32, -1, -1];
+        let response = ValidFraction::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(0.0_f64.as_bytes(), response.body);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn valid_fraction_f32_mixed_non_native() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::ValidRange(0.into(), 10.into()));
+        let data: Vec<u8> = [1.0_f32, -1.0, 5.0, 11.0]
+            .iter()
+            .flat_map(|value| array::reversed_byte_order(*value).to_ne_bytes())
+            .collect();
+        let response = ValidFraction::execute(&request_data, data).unwrap();
+        assert_eq!(0.5_f64.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn valid_fraction_u64_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        request_data.shape = Some(vec![2, 4]);
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::MissingValues(vec![0.into(), 7.into()]));
+        let data = [0_u64, 1, 2, 3, 7, 0, 7, 4];
+        let response = ValidFraction::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([0.75_f64, 0.25].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn first_i32_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue((-1).into()));
+        let data = [-1_i32, -1, 3, 4, -1];
+        let response = First::execute(&request_data, data.as_bytes().into()).unwrap();
+        // The leading missing elements are skipped.
+        assert_eq!(3_i32.as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn last_i32_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue((-1).into()));
+        let data = [-1_i32, -1, 3, 4, -1];
+        let response = Last::execute(&request_data, data.as_bytes().into()).unwrap();
+        // The trailing missing element is skipped.
+        assert_eq!(4_i32.as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn first_last_f32_2d_fortran_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.order = Some(models::Order::F);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        // Logical array is [[1, 3, 5], [2, 4, 6]] and the selection is [[3, 5], [4, 6]].
+        let data = [1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let first = First::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(3.0_f32.as_bytes(), first.body);
+        assert_eq!(4, first.count);
+        let last = Last::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(6.0_f32.as_bytes(), last.body);
+        assert_eq!(4, last.count);
+    }
+
+    #[test]
+    fn first_last_u64_1d_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let data = [1_u64.swap_bytes(), 2_u64.swap_bytes(), 3_u64.swap_bytes()];
+        let first = First::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(1_u64.as_bytes(), first.body);
+        let last = Last::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(3_u64.as_bytes(), last.body);
+    }
+
+    #[test]
+    fn first_last_i64_2d_non_native_byte_order_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.missing = Some(Missing::MissingValue(0.into()));
+        // Logical array is [[0, 0, 1], [2, 3, 0]].
+        let data = [0_i64, 0, 1, 2, 3, 0].map(i64::swap_bytes);
+        let first = First::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(1_i64.as_bytes(), first.body);
+        assert_eq!(3, first.count);
+        let last = Last::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(3_i64.as_bytes(), last.body);
+        assert_eq!(3, last.count);
+    }
+
+    #[test]
+    #[should_panic(expected = "EmptyArray")]
+    fn first_i32_1d_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::ValidMin(10.into()));
+        let data = [1_i32, 2, 3];
+        First::execute(&request_data, data.as_bytes().into()).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "EmptyArray")]
+    fn last_i32_1d_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::ValidMin(10.into()));
+        let data = [1_i32, 2, 3];
+        Last::execute(&request_data, data.as_bytes().into()).unwrap();
+    }
+
+    #[test]
+    fn cumsum_i32_1d() {
+        // np.cumsum(np.array([1, 2, 3, 4, 5], dtype=np.int32))
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        let data = [1_i32, 2, 3, 4, 5];
+        let response = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [1_i64, 3, 6, 10, 15];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![5], response.shape);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn cumsum_i32_1d_overflow() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        let data = [i32::MAX, i32::MAX];
+        let response = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [i64::from(i32::MAX), 2 * i64::from(i32::MAX)];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+    }
+
+    /// Returns request data for a 2x3 int32 array padded to a 3x4 chunk, and its data, with
+    /// padding elements of 1000.
+    fn padded_chunk() -> (models::RequestData, Vec<i32>) {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        request_data.stored_shape = Some(vec![3, 4]);
+        let data = vec![1, 2, 3, 1000, 4, 5, 6, 1000, 1000, 1000, 1000, 1000];
+        (request_data, data)
+    }
+
+    #[test]
+    fn sum_padded_chunk() {
+        let (request_data, data) = padded_chunk();
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(21_i32.as_bytes(), response.body);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn select_padded_chunk_with_selection() {
+        let (mut request_data, data) = padded_chunk();
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(-1, -4, -1),
+        ]);
+        let response = Select::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([3_i32, 2, 1, 6, 5, 4].as_bytes(), response.body);
+        assert_eq!(vec![2, 3], response.shape);
+    }
+
+    #[test]
+    fn max_padded_chunk_axis() {
+        let (mut request_data, data) = padded_chunk();
+        request_data.axis = Some(0);
+        let response = Max::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([4_i32, 5, 6].as_bytes(), response.body);
+        assert_eq!(vec![3], response.shape);
+    }
+
+    #[test]
+    fn diff_i32_1d() {
+        // np.diff(np.array([1, 2, 4, 7, 0], dtype=np.int32))
+        let request_data = test_utils::get_test_request_data();
+        let data = [1_i32, 2, 4, 7, 0];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [1_i32, 2, 3, -7];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(vec![4], response.shape);
+        assert_eq!(5, response.count);
+    }
+
+    #[test]
+    fn diff_i32_1d_overflow() {
+        // np.diff(np.array([-2**31, 2**31 - 1, -2**31], dtype=np.int32))
+        let request_data = test_utils::get_test_request_data();
+        let data = [i32::MIN, i32::MAX, i32::MIN];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [-1_i32, 1];
+        assert_eq!(expected.as_bytes(), response.body);
+    }
+
+    #[test]
+    fn diff_u64_1d_underflow() {
+        // np.diff(np.array([5, 3, 2**64 - 1, 0], dtype=np.uint64))
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        let data = [5_u64, 3, u64::MAX, 0];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [u64::MAX - 1, u64::MAX - 3, 1];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint64, response.dtype);
+    }
+
+    #[test]
+    fn diff_f64_1d_with_selection() {
+        // np.diff(np.array([0.5, 1.0, 4.0, 2.5, 8.0, -1.0])[5:0:-2])
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![6]);
+        request_data.selection = Some(vec![models::Slice::new(5, 0, -2)]);
+        let data = [0.5_f64, 1.0, 4.0, 2.5, 8.0, -1.0];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [3.5_f64, -1.5];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn diff_f32_1d_missing_value() {
+        // Missing elements are included in the differences, but not in the count.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.missing = Some(Missing::MissingValue((-9).into()));
+        let data = [1.0_f32, -9.0, 2.0];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [-10.0_f32, 11.0];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn diff_u32_2d() {
+        // np.diff(np.array([[1, 3, 6], [10, 15, 21]], dtype=np.uint32).ravel())
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        let data = [1_u32, 3, 6, 10, 15, 21];
+        let response = Diff::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [2_u32, 3, 4, 5, 6];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(vec![5], response.shape);
+    }
+
+    #[test]
+    fn diff_inverts_cumsum() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        let data = [4_i64, -3, 7, 0, 12];
+        let cumsum = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let response = Diff::execute(&request_data, cumsum.body.to_vec()).unwrap();
+        assert_eq!(data[1..].as_bytes(), response.body);
+    }
+
+    #[test]
+    fn diff_single_element() {
+        let request_data = test_utils::get_test_request_data();
+        let response = Diff::execute(&request_data, 1_i32.as_bytes().into()).unwrap();
+        assert!(response.body.is_empty());
+        assert_eq!(vec![0], response.shape);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn cumsum_u32_2d() {
+        // np.cumsum(np.arange(1, 7, dtype=np.uint32).reshape(2, 3)), with the selection shape.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        let data = [1_u32, 2, 3, 4, 5, 6];
+        let response = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [1_u64, 3, 6, 10, 15, 21];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint64, response.dtype);
+        assert_eq!(vec![2, 3], response.shape);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn cumsum_f32_2d_with_selection_missing_value() {
+        // a = np.arange(1, 7, dtype=np.float32).reshape(2, 3)[:, 1:]
+        // np.cumsum(np.where(a == 5, 0, a))
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        request_data.missing = Some(Missing::MissingValue(5.into()));
+        let data = [1_f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let response = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = [2_f32, 5.0, 5.0, 11.0];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![2, 2], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn cumsum_f16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        let data = [half::f16::from_f32(2048.0), half::f16::ONE, half::f16::ONE];
+        let response = CumSum::execute(&request_data, data.as_bytes().into()).unwrap();
+        // Accumulated in f32, so the small values are not lost.
+        let expected = [2048_f32, 2049.0, 2050.0];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+    }
+
+    #[test]
+    fn max_i64_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        // data:
+        // A Vec<u8> of 8 elements == a single i64 value
+        // where each element is 2 hexadecimal digits
+        // and the order is reversed on little-endian systems
+        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Max::execute(&request_data, data).unwrap();
+        let expected: i64 = 0x0807060504030201;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn max_i64_1d_missing_values() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.missing = Some(Missing::MissingValues(vec![0x0807060504030201_i64.into()]));
+        // data:
+        // A Vec<u8> of 16 elements == two i64 values
+        // where each element is 2 hexadecimal digits
+        // and the order is reversed on little-endian systems
+        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let response = Max::execute(&request_data, data).unwrap();
+        let expected: i64 = 0x100f0e0d0c0b0a09;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn max_f16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        let floats = [1.5, -2.0, 3.25, 0.5].map(half::f16::from_f32);
+        let data = floats.as_bytes();
+        let response = Max::execute(&request_data, data.into()).unwrap();
+        let expected = half::f16::from_f32(3.25);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.body.len());
+        assert_eq!(models::DType::Float16, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn max_bf16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        // bf16 has a 7 bit mantissa, so in [2, 4) values are rounded to multiples of 1/64.
+        let floats = [3.145, 3.15, -1e30, 2.0].map(half::bf16::from_f32);
+        let data = floats.as_bytes();
+        let response = Max::execute(&request_data, data.into()).unwrap();
+        let expected = half::bf16::from_f32(3.15625);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.body.len());
+        assert_eq!(models::DType::Bfloat16, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn max_bf16_1d_infinity() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        let floats = [half::bf16::ONE, half::bf16::INFINITY];
+        let data = floats.as_bytes();
+        let response = Max::execute(&request_data, data.into()).unwrap();
+        let expected = half::bf16::INFINITY;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Bfloat16, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn max_f32_1d_infinity() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [1.0, f32::INFINITY];
+        let data = floats.as_bytes();
+        let response = Max::execute(&request_data, data.into()).unwrap();
+        let expected = f32::INFINITY;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn max_f32_1d_infinity_first() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::INFINITY, 1.0];
+        let data = floats.as_bytes();
+        let response = Max::execute(&request_data, data.into()).unwrap();
+        let expected = f32::INFINITY;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn min_u64_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Min::execute(&request_data, data).unwrap();
+        let expected: u64 = 0x0807060504030201;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Uint64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn min_i64_1d_valid_min() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        // Minimum is one greater than smallest element.
+        request_data.missing = Some(Missing::ValidMin(0x0807060504030202_i64.into()));
+        // data:
+        // A Vec<u8> of 16 elements == two i64 values
+        // where each element is 2 hexadecimal digits
+        // and the order is reversed on little-endian systems
+        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let response = Min::execute(&request_data, data).unwrap();
+        let expected: i64 = 0x100f0e0d0c0b0a09;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(1, response.count);
+    }
+
+    #[test]
+    fn min_f32_1d_infinity() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [1.0, f32::INFINITY];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn min_f32_1d_infinity_first() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::INFINITY, 1.0];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn min_bf16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        // 1.001 is not representable in bf16 and rounds to 1.0.
+        let floats = [1.001, 1.0078125, 2.0].map(half::bf16::from_f32);
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = half::bf16::ONE;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.body.len());
+        assert_eq!(models::DType::Bfloat16, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn min_bf16_1d_neg_infinity() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        let floats = [half::bf16::ONE, half::bf16::NEG_INFINITY];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = half::bf16::NEG_INFINITY;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected undefined order error for min")]
+    fn min_bf16_1d_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        let floats = [half::bf16::ONE, half::bf16::NAN];
+        let data = floats.as_bytes();
+        Min::execute(&request_data, data.into()).unwrap();
+    }
+
+    #[test]
+    fn min_bf16_1d_nan_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(42.0).unwrap()));
+        let floats = [half::bf16::ONE, half::bf16::NAN];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = half::bf16::ONE;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected undefined order error for min")]
+    fn min_f32_1d_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [1.0, f32::NAN];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected undefined order error for min")]
+    fn min_f32_1d_nan_first() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::NAN, 1.0];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn min_f32_1d_nan_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(42.0).unwrap()));
+        let floats = [1.0, f32::NAN];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn min_f32_1d_nan_first_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(42.0).unwrap()));
+        let floats = [f32::NAN, 1.0];
+        let data = floats.as_bytes();
+        let response = Min::execute(&request_data, data.into()).unwrap();
+        // FIXME: Ignore NANs?
+        let expected = f32::NAN; //1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn mode_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        let integers = [3_i32, 1, 3, 2, 3, 1];
+        let data = integers.as_bytes();
+        let response = Mode::execute(&request_data, data.into()).unwrap();
+        let expected = 3_i32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn mode_i64_1d_tie() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        let integers = [5_i64, -2, 7, 5, -2, 7];
+        let data = integers.as_bytes();
+        let response = Mode::execute(&request_data, data.into()).unwrap();
+        // Ties are broken toward the smallest value.
+        let expected = -2_i64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn mode_u32_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.missing = Some(Missing::MissingValue(255.into()));
+        let integers = [255_u32, 4, 255, 9, 255, 9];
+        let data = integers.as_bytes();
+        let response = Mode::execute(&request_data, data.into()).unwrap();
+        let expected = 9_u32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn mode_u64_2d_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        let integers = [1_u64, 2, 8, 1, 8, 4];
+        let data = integers.as_bytes();
+        let response = Mode::execute(&request_data, data.into()).unwrap();
+        let expected = 8_u64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn mode_i32_1d_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        let integers = [1_i32, 1];
+        let data = integers.as_bytes();
+        let err = Mode::execute(&request_data, data.into()).unwrap_err();
+        assert_eq!(
+            "cannot perform mode on empty array or selection",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn mode_f32_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [1.0_f32, 1.0];
+        let data = floats.as_bytes();
+        let err = Mode::execute(&request_data, data.into()).unwrap_err();
+        assert_eq!("cannot perform mode on float32 data", err.to_string());
+    }
+
+    #[test]
+    fn nanmax_f32_1d_partial_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::NAN, 1.0, f32::NAN, 3.0, 2.0];
+        let data = floats.as_bytes();
+        let response = NanMax::execute(&request_data, data.into()).unwrap();
+        let expected = 3.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn nanmax_f64_1d_all_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        let floats = [f64::NAN, f64::NAN];
+        let data = floats.as_bytes();
+        let response = NanMax::execute(&request_data, data.into()).unwrap();
+        let max = f64::from_ne_bytes(response.body.as_ref().try_into().unwrap());
+        assert!(max.is_nan());
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn nanmax_f64_1d_all_nan_or_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.missing = Some(Missing::ValidMax(DValue::from_f64(10.0).unwrap()));
+        let floats = [f64::NAN, 20.0, f64::NAN];
+        let data = floats.as_bytes();
+        let response = NanMax::execute(&request_data, data.into()).unwrap();
+        let max = f64::from_ne_bytes(response.body.as_ref().try_into().unwrap());
+        assert!(max.is_nan());
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn nanmax_i32_1d_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        let integers = [1_i32, 1];
+        let data = integers.as_bytes();
+        let err = NanMax::execute(&request_data, data.into()).unwrap_err();
+        assert_eq!(
+            "cannot perform nanmax on empty array or selection",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn nanmin_f32_1d_partial_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::NAN, 1.0, f32::NAN, -3.0, 2.0];
+        let data = floats.as_bytes();
+        let response = NanMin::execute(&request_data, data.into()).unwrap();
+        let expected = -3.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn nanmin_f32_1d_nan_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(-3.0).unwrap()));
+        let floats = [f32::NAN, 1.0, -3.0, 2.0];
+        let data = floats.as_bytes();
+        let response = NanMin::execute(&request_data, data.into()).unwrap();
+        let expected = 1.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn nanmin_f16_1d_all_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        let floats = [half::f16::NAN; 3];
+        let data = floats.as_bytes();
+        let response = NanMin::execute(&request_data, data.into()).unwrap();
+        assert_eq!(2, response.body.len());
+        let min = half::f16::from_ne_bytes(response.body.as_ref().try_into().unwrap());
+        assert!(min.is_nan());
+        assert_eq!(models::DType::Float16, response.dtype);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn nanmin_u64_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        let integers = [7_u64, 3, 9];
+        let data = integers.as_bytes();
+        let response = NanMin::execute(&request_data, data.into()).unwrap();
+        let expected = 3_u64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn ptp_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        let integers = [3_i32, -7, 12, 0];
+        let response = Ptp::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!(19_u32.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn ptp_i32_1d_full_range() {
+        // The range of signed integers does not overflow.
+        let request_data = test_utils::get_test_request_data();
+        let integers = [i32::MAX, i32::MIN];
+        let response = Ptp::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!(u32::MAX.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint32, response.dtype);
+    }
+
+    #[test]
+    fn ptp_i64_1d_full_range() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        let integers = [i64::MIN, 0, i64::MAX];
+        let response = Ptp::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!(u64::MAX.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint64, response.dtype);
+    }
+
+    #[test]
+    fn ptp_u64_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        request_data.missing = Some(Missing::MissingValue(0.into()));
+        let integers = [7_u64, 0, 3, 9];
+        let response = Ptp::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!(6_u64.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn ptp_u32_1d_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let data: Vec<u8> = [5_u32, 1, 4]
+            .iter()
+            .flat_map(|x| array::reversed_byte_order(*x).to_ne_bytes())
+            .collect();
+        let response = Ptp::execute(&request_data, data).unwrap();
+        assert_eq!(4_u32.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint32, response.dtype);
+    }
+
+    #[test]
+    fn ptp_f32_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [1.5_f32, -2.25, 3.0];
+        let response = Ptp::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(5.25_f32.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn ptp_f16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        let floats = [0.5, 4.0, -1.0].map(half::f16::from_f32);
+        let response = Ptp::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(half::f16::from_f32(5.0).as_bytes(), response.body);
+        assert_eq!(models::DType::Float16, response.dtype);
+    }
+
+    #[test]
+    fn ptp_f64_2d_axis() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(1);
+        let floats = [1.0_f64, 4.0, 2.0, -1.0, -3.0, 10.0];
+        let response = Ptp::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!([3.0_f64, 13.0].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(6, response.count);
+    }
+
+    #[test]
+    fn ptp_all_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        let integers = [1_i32, 1];
+        match Ptp::execute(&request_data, integers.as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => assert_eq!("ptp", operation),
+            _ => panic!("expected ptp to fail with EmptyArray"),
+        }
+    }
+
+    #[test]
+    fn select_f32_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Select::execute(&request_data, data).unwrap();
+        let expected: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn select_f64_2d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![2, 1]);
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let response = Select::execute(&request_data, data).unwrap();
+        let expected: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(16, response.body.len());
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![2, 1], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn select_f32_2d_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.shape = Some(vec![2, 2]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 2, 1),
+        ]);
+        // 2x2 array, select second row of each column.
+        // [[0x04030201, 0x08070605], [0x12111009, 0x16151413]]
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let response = Select::execute(&request_data, data).unwrap();
+        // [[0x08070605], [0x16151413]]
+        let expected: [u8; 8] = [5, 6, 7, 8, 13, 14, 15, 16];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![2, 1], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn select_contiguous_zero_copy() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![3, 2]);
+        request_data.selection = Some(vec![
+            models::Slice::new(1, 3, 1),
+            models::Slice::new(0, 2, 1),
+        ]);
+        let data = [1_u32, 2, 3, 4, 5, 6].as_bytes().to_vec();
+        let ptr = data.as_ptr();
+        let response = Select::execute(&request_data, data).unwrap();
+        assert_eq!([3_u32, 4, 5, 6].as_bytes(), response.body);
+        // The selected rows are returned without copying.
+        assert_eq!(ptr.wrapping_add(8), response.body.as_ptr());
+        assert_eq!(vec![2, 2], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn select_output_order_c() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.order = Some(models::Order::F);
+        let data = [0_u32, 3, 1, 4, 2, 5].as_bytes().to_vec();
+        let response = Select::execute(&request_data, data).unwrap();
+        assert_eq!([0_u32, 1, 2, 3, 4, 5].as_bytes(), response.body);
+        assert_eq!(Some(models::Order::C), response.order);
+        assert_eq!(vec![2, 3], response.shape);
+    }
+
+    #[test]
+    fn select_output_order_f() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        request_data.output_order = Some(models::OutputOrder::F);
+        let data = [0_u32, 1, 2, 3, 4, 5].as_bytes().to_vec();
+        let response = Select::execute(&request_data, data).unwrap();
+        assert_eq!([1_u32, 4, 2, 5].as_bytes(), response.body);
+        assert_eq!(Some(models::Order::F), response.order);
+        assert_eq!(vec![2, 2], response.shape);
+    }
+
+    #[test]
+    fn select_output_order_k_zero_copy() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.order = Some(models::Order::F);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        request_data.output_order = Some(models::OutputOrder::K);
+        let data = [0_u32, 3, 1, 4, 2, 5].as_bytes().to_vec();
+        let ptr = data.as_ptr();
+        let response = Select::execute(&request_data, data).unwrap();
+        assert_eq!([1_u32, 4, 2, 5].as_bytes(), response.body);
+        // The selected columns are returned in F order without copying.
+        assert_eq!(ptr.wrapping_add(8), response.body.as_ptr());
+        assert_eq!(Some(models::Order::F), response.order);
+        assert_eq!(vec![2, 2], response.shape);
+    }
+
+    #[test]
+    fn select_output_dtype_f64_to_f32() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.output_dtype = Some(models::DType::Float32);
+        request_data.selection = Some(vec![models::Slice::new(0, 4, 1)]);
+        let floats = [1.5_f64, 0.1, f64::NAN, 1e300, 2.0];
+        let response = Select::execute(&request_data, floats.as_bytes().into()).unwrap();
+        // Values are rounded, and values out of range become infinite.
+        let expected = [1.5_f32, 0.1, f32::NAN, f32::INFINITY];
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(vec![4], response.shape);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn select_output_dtype_i64_to_i32() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int64;
+        request_data.output_dtype = Some(models::DType::Int32);
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let data: Vec<u8> = [-3_i64, i32::MAX as i64, 7]
+            .iter()
+            .flat_map(|x| array::reversed_byte_order(*x).to_ne_bytes())
+            .collect();
+        let response = Select::execute(&request_data, data).unwrap();
+        // The output is in native byte order, like other responses.
+        assert_eq!([-3_i32, i32::MAX, 7].as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn select_output_dtype_overflow() {
+        for (dtype, output_dtype, data) in [
+            (
+                models::DType::Int64,
+                models::DType::Int32,
+                [1_i64, i32::MAX as i64 + 1].as_bytes().to_vec(),
+            ),
+            (
+                models::DType::Int32,
+                models::DType::Uint64,
+                [1_i32, -1].as_bytes().to_vec(),
+            ),
+            (
+                models::DType::Uint64,
+                models::DType::Int64,
+                [u64::MAX, 1].as_bytes().to_vec(),
+            ),
+        ] {
+            let mut request_data = test_utils::get_test_request_data();
+            request_data.dtype = dtype;
+            request_data.output_dtype = Some(output_dtype);
+            match Select::execute(&request_data, data) {
+                Err(ActiveStorageError::CastOverflow { dtype }) => {
+                    assert_eq!(output_dtype.to_string().to_lowercase(), dtype)
+                }
+                _ => panic!("expected select to fail"),
+            }
+        }
+    }
+
+    #[test]
+    fn select_output_dtype_int_to_float() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint64;
+        request_data.output_dtype = Some(models::DType::Float64);
+        let integers = [0_u64, u64::MAX];
+        let response = Select::execute(&request_data, integers.as_bytes().into()).unwrap();
+        assert_eq!([0.0, u64::MAX as f64].as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+    }
+
+    // A large value followed by many values that are each too small to change it.
+    fn pathological_f32() -> Vec<f32> {
+        let mut values = vec![1.0_f32];
+        values.extend(std::iter::repeat(1e-8).take(1_000_000));
+        values
+    }
+
+    fn summation_f32(strategy: FloatSumStrategy, values: &[f32]) -> f32 {
+        values
+            .iter()
+            .fold(Summation::new(strategy), |sum, x| sum.add(*x))
+            .finish()
+    }
+
+    #[test]
+    fn summation_pathological() {
+        let values = pathological_f32();
+        assert_eq!(1.0, summation_f32(FloatSumStrategy::Naive, &values));
+        let kahan = summation_f32(FloatSumStrategy::Kahan, &values);
+        assert!((kahan - 1.01).abs() < 1e-6, "{}", kahan);
+        let pairwise = summation_f32(FloatSumStrategy::Pairwise, &values);
+        assert!((pairwise - 1.01).abs() < 1e-5, "{}", pairwise);
+    }
+
+    #[test]
+    fn pairwise_sum_contiguous() {
+        let values = pathological_f32();
+        let sum = pairwise_sum(&values);
+        assert!((sum - 1.01).abs() < 1e-5, "{}", sum);
+        for length in [0, 3, PAIRWISE_BLOCK_SIZE, 5 * PAIRWISE_BLOCK_SIZE + 7] {
+            let values: Vec<half::f16> = (0..length).map(|_| half::f16::ONE).collect();
+            assert_eq!(length as f32, pairwise_sum(&values), "{}", length);
+        }
+    }
+
+    fn stats(request_data: &models::RequestData, data: Vec<u8>) -> models::Stats {
+        let response = Stats::execute(request_data, data).unwrap();
+        assert_eq!(request_data.dtype, response.dtype);
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn stats_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        let integers = [3_i32, -7, 12, 0];
+        let expected = models::Stats {
+            min: (-7).into(),
+            max: 12.into(),
+            sum: 8.into(),
+            count: 4,
+        };
+        assert_eq!(expected, stats(&request_data, integers.as_bytes().into()));
+    }
+
+    #[test]
+    fn stats_f16_1d_sum_dtype() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        let floats = [0.5, 4.0, -1.25].map(half::f16::from_f32);
+        let expected = models::Stats {
+            min: (-1.25).into(),
+            max: 4.0.into(),
+            sum: 3.25.into(),
+            count: 3,
+        };
+        assert_eq!(expected, stats(&request_data, floats.as_bytes().into()));
+    }
+
+    #[test]
+    fn stats_empty_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.selection = Some(vec![models::Slice::new(1, 1, 1)]);
+        let integers = [1_i32, 2];
+        match Stats::execute(&request_data, integers.as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => assert_eq!("stats", operation),
+            _ => panic!("expected stats to fail"),
+        }
+    }
+
+    #[test]
+    fn stats_axis_unsupported() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 2]);
+        request_data.axis = Some(0);
+        let integers = [1_i32, 2, 3, 4];
+        match Stats::execute(&request_data, integers.as_bytes().into()) {
+            Err(ActiveStorageError::AxisUnsupported { operation }) => {
+                assert_eq!("stats", operation)
+            }
+            _ => panic!("expected stats to fail"),
+        }
+    }
+
+    fn minmax_loc(request_data: &models::RequestData, data: Vec<u8>) -> serde_json::Value {
+        let response = MinMaxLoc::execute(request_data, data).unwrap();
+        assert_eq!(request_data.dtype, response.dtype);
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn minmax_loc_i32_2d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 3]);
+        let integers = [3_i32, -7, 12, 0, 12, -7];
+        // Ties are resolved to the first occurrence in C order.
+        let expected = json!({
+            "min": {"value": -7, "index": [0, 1]},
+            "max": {"value": 12, "index": [0, 2]},
+        });
+        assert_eq!(
+            expected,
+            minmax_loc(&request_data, integers.as_bytes().into())
+        );
+    }
+
+    #[test]
+    fn minmax_loc_strided_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![4, 5]);
+        request_data.selection = Some(vec![
+            models::Slice::new(1, 4, 2),
+            models::Slice::new(4, 0, -2),
+        ]);
+        let integers: Vec<i32> = (0..20).map(|i| (i * 7) % 11).collect();
+        // The selection is rows 1 and 3, and columns 4 and 2, of:
+        // [[0, 7, 3, 10, 6], [2, 9, 5, 1, 8], [4, 0, 7, 3, 10], [6, 2, 9, 5, 1]]
+        let expected = json!({
+            "min": {"value": 1, "index": [3, 4]},
+            "max": {"value": 9, "index": [3, 2]},
+        });
+        assert_eq!(
+            expected,
+            minmax_loc(&request_data, integers.as_bytes().into())
+        );
+        // In selection order, the first of equal elements is in the last column selected.
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 4, 1),
+            models::Slice::new(4, 0, -2),
+        ]);
+        let integers = [0_i32; 20];
+        let expected = json!({
+            "min": {"value": 0, "index": [0, 4]},
+            "max": {"value": 0, "index": [0, 4]},
+        });
+        assert_eq!(
+            expected,
+            minmax_loc(&request_data, integers.as_bytes().into())
+        );
+    }
+
+    #[test]
+    fn minmax_loc_missing_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.missing = Some(Missing::MissingValue(100.into()));
+        let floats = [f64::NAN, 100.0, 2.5, -1.0, f64::NAN];
+        let response = MinMaxLoc::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(2, response.count);
+        let expected = json!({
+            "min": {"value": -1.0, "index": [3]},
+            "max": {"value": 2.5, "index": [2]},
+        });
+        assert_eq!(
+            expected,
+            serde_json::from_slice::<serde_json::Value>(&response.body).unwrap()
+        );
+    }
+
+    #[test]
+    fn minmax_loc_non_native_byte_order() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let integers = [5_u32, 300, 2].map(u32::swap_bytes);
+        let expected = json!({
+            "min": {"value": 2, "index": [2]},
+            "max": {"value": 300, "index": [1]},
+        });
+        assert_eq!(
+            expected,
+            minmax_loc(&request_data, integers.as_bytes().into())
+        );
+    }
+
+    #[test]
+    fn minmax_loc_all_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let floats = [f32::NAN, f32::NAN];
+        match MinMaxLoc::execute(&request_data, floats.as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => {
+                assert_eq!("minmax_loc", operation)
+            }
+            _ => panic!("expected minmax_loc to fail"),
+        }
+    }
+
+    // Returns a scalar response body of a data type as a JSON number.
+    fn scalar_json(body: &[u8], dtype: models::DType) -> serde_json::Value {
+        use zerocopy::FromBytes;
+        match dtype {
+            models::DType::Int32 => models::json_value(i32::read_from(body).unwrap(), dtype),
+            models::DType::Int64 => models::json_value(i64::read_from(body).unwrap(), dtype),
+            models::DType::Uint32 => models::json_value(u32::read_from(body).unwrap(), dtype),
+            models::DType::Uint64 => models::json_value(u64::read_from(body).unwrap(), dtype),
+            models::DType::Float16 => {
+                models::json_value(half::f16::read_from(body).unwrap(), dtype)
+            }
+            models::DType::Bfloat16 => {
+                models::json_value(half::bf16::read_from(body).unwrap(), dtype)
+            }
+            models::DType::Float32 => models::json_value(f32::read_from(body).unwrap(), dtype),
+            models::DType::Float64 => models::json_value(f64::read_from(body).unwrap(), dtype),
+        }
+    }
+
+    type Execute =
+        fn(&models::RequestData, Vec<u8>) -> Result<models::Response, ActiveStorageError>;
+
+    #[test]
+    fn stats_matches_individual_reducers() {
+        // Compare with the min, max, sum and count operations for each data type, with and
+        // without missing data, a selection and non-native byte order.
+        fn bytes<T: AsBytes>(values: impl Iterator<Item = T>) -> Vec<u8> {
+            values.collect::<Vec<T>>().as_bytes().to_vec()
+        }
+        let values = || (0..300_i64).map(|i| (i * 37) % 101);
+        for dtype in [
+            models::DType::Int32,
+            models::DType::Int64,
+            models::DType::Uint32,
+            models::DType::Uint64,
+            models::DType::Float16,
+            models::DType::Bfloat16,
+            models::DType::Float32,
+            models::DType::Float64,
+        ] {
+            for (missing, selection, byte_order) in [
+                (None, None, None),
+                (Some(Missing::ValidRange(5.into(), 60.into())), None, None),
+                (
+                    Some(Missing::MissingValue(7.into())),
+                    Some(vec![models::Slice::new(3, 290, 4)]),
+                    Some(crate::types::NON_NATIVE_BYTE_ORDER),
+                ),
+            ] {
+                let mut request_data = test_utils::get_test_request_data();
+                request_data.dtype = dtype;
+                request_data.missing = missing;
+                request_data.selection = selection;
+                request_data.byte_order = byte_order;
+                let mut data = match dtype {
+                    models::DType::Int32 => bytes(values().map(|x| x as i32)),
+                    models::DType::Int64 => bytes(values()),
+                    models::DType::Uint32 => bytes(values().map(|x| x as u32)),
+                    models::DType::Uint64 => bytes(values().map(|x| x as u64)),
+                    models::DType::Float16 => {
+                        bytes(values().map(|x| half::f16::from_f64(x as f64)))
+                    }
+                    models::DType::Bfloat16 => {
+                        bytes(values().map(|x| half::bf16::from_f64(x as f64)))
+                    }
+                    models::DType::Float32 => bytes(values().map(|x| x as f32)),
+                    models::DType::Float64 => bytes(values().map(|x| x as f64)),
+                };
+                if byte_order.is_some() {
+                    for element in data.chunks_exact_mut(dtype.size_of()) {
+                        element.reverse();
+                    }
+                }
+                let stats = stats(&request_data, data.clone());
+                let execute = |execute: Execute| {
+                    let response = execute(&request_data, data.clone()).unwrap();
+                    assert_eq!(stats.count, response.count, "{:?}", request_data);
+                    scalar_json(&response.body, response.dtype)
+                };
+                assert_eq!(execute(Min::execute), stats.min, "{:?}", request_data);
+                assert_eq!(execute(Max::execute), stats.max, "{:?}", request_data);
+                assert_eq!(execute(Sum::execute), stats.sum, "{:?}", request_data);
+                assert_eq!(
+                    execute(Count::execute),
+                    serde_json::Value::from(stats.count),
+                    "{:?}",
+                    request_data
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn summation_short() {
+        // Fewer values than a pairwise block, and a partial final block.
+        for length in [0, 3, PAIRWISE_BLOCK_SIZE, 5 * PAIRWISE_BLOCK_SIZE + 7] {
+            let values: Vec<i64> = (0..length as i64).collect();
+            let expected: i64 = values.iter().sum();
+            for strategy in [
+                FloatSumStrategy::Naive,
+                FloatSumStrategy::Kahan,
+                FloatSumStrategy::Pairwise,
+            ] {
+                let sum = values
+                    .iter()
+                    .fold(Summation::new(strategy), |sum, x| sum.add(*x))
+                    .finish();
+                assert_eq!(expected, sum, "{:?} {}", strategy, length);
+            }
+        }
+    }
+
+    #[test]
+    fn sum_f32_pathological() {
+        // The default pairwise summation does not lose the small values.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        let data = pathological_f32().as_bytes().to_vec();
+        let response = Sum::execute(&request_data, data).unwrap();
+        let sum = <f32 as zerocopy::FromBytes>::read_from(&response.body[..]).unwrap();
+        assert!((sum - 1.01).abs() < 1e-5, "{}", sum);
+        assert_eq!(1_000_001, response.count);
+    }
+
+    #[test]
+    fn sum_i32_1d_accumulate_int64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.accumulate_dtype = Some(models::DType::Int64);
+        let data = [i32::MAX - 1, i32::MAX - 2, 3];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = 2 * i64::from(i32::MAX);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_u32_1d_accumulate_int64_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.accumulate_dtype = Some(models::DType::Int64);
+        request_data.missing = Some(Missing::MissingValue(7.into()));
+        let data = [u32::MAX, 7, u32::MAX];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = 2 * i64::from(u32::MAX);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_f32_1d_accumulate_float64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.accumulate_dtype = Some(models::DType::Float64);
+        let data = [16777216_f32, 1.0, 1.0];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        // A float32 accumulator would lose the small values.
+        let expected = 16777218_f64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_i32_1d_clip() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.clip_min = Some((-2).into());
+        request_data.clip_max = Some(5.into());
+        let data = [-10_i32, -1, 3, 100];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        // -10 is clamped to -2 and 100 to 5.
+        let expected = 5_i32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(4, response.count);
+    }
+
+    #[test]
+    fn sum_f64_1d_clip_max_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.clip_max = Some(1.into());
+        request_data.missing = Some(Missing::MissingValue(100.into()));
+        let data = [0.5_f64, 100.0, 2.5, -3.0];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        // The missing value is excluded rather than clamped.
+        let expected = 0.5_f64 + 1.0 - 3.0;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_u32_1d_clip_min_accumulate_int64() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.accumulate_dtype = Some(models::DType::Int64);
+        request_data.clip_min = Some(u32::MAX.into());
+        let data = [0_u32, 1, u32::MAX];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = 3 * i64::from(u32::MAX);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_f16_1d_clip() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        request_data.clip_min = Some(0.into());
+        request_data.clip_max = Some(2.into());
+        let data = [-1.0_f32, 1.5, 4.0].map(half::f16::from_f32);
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        let expected = 3.5_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_u32_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let response = Sum::execute(&request_data, data).unwrap();
+        let expected: u32 = 0x04030201 + 0x08070605;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Uint32, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
 
     #[test]
-    fn count_i32_1d() {
-        let request_data = test_utils::get_test_request_data();
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Count::execute(&request_data, data).unwrap();
-        // A Vec<u8> of 8 elements == a u32 slice with 2 elements
-        // Count is always i64.
-        let expected: i64 = 2;
+    fn sum_i32_2d_indices() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.shape = Some(vec![3, 4]);
+        request_data.indices = Some(vec![11, 0, 5, 5, 7]);
+        let values: Vec<i32> = (0..12).map(|x| x * 10).collect();
+        let (request_data, data) =
+            gather_indices(request_data, values.as_bytes().to_vec()).unwrap();
+        assert_eq!(Some(vec![5]), request_data.shape);
+        let response = Sum::execute(&request_data, data).unwrap();
+        let expected: i32 = 110 + 50 + 50 + 70;
         assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len()); // Assert that count value is 8 bytes (i.e. i64)
-        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(models::DType::Int32, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(expected, response.count);
+        assert_eq!(5, response.count);
     }
 
     #[test]
-    fn count_u32_1d_missing_value() {
+    fn sum_i32_2d_indices_f_order_non_native_missing() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.order = Some(models::Order::F);
+        request_data.missing = Some(Missing::MissingValue(3.into()));
+        // Flat indices 1, 2 and 4 are elements [0, 1], [0, 2] and [1, 1], stored at offsets 2, 4
+        // and 3.
+        request_data.indices = Some(vec![1, 2, 4]);
+        let data: Vec<u8> = [0_i32, 1, 2, 3, 4, 5]
+            .iter()
+            .flat_map(|x| x.as_bytes().iter().rev().copied())
+            .collect();
+        let (request_data, data) = gather_indices(request_data, data).unwrap();
+        let response = Sum::execute(&request_data, data).unwrap();
+        assert_eq!(6_i32.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_u32_2d_mask_f_order() {
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Uint32;
-        request_data.missing = Some(Missing::MissingValue(0x04030201.into()));
+        request_data.shape = Some(vec![2, 2]);
+        request_data.order = Some(models::Order::F);
+        let data = [1_u32, 2, 3, 4].as_bytes().to_vec();
+        // The mask is in the same order as the data, so selects the stored elements 2 and 3.
+        let (request_data, data) = gather_mask(request_data, data, &[0, 1, 1, 0]).unwrap();
+        // The elements are gathered in C order.
+        assert_eq!([3_u32, 2].as_bytes(), data);
+        let response = Sum::execute(&request_data, data).unwrap();
+        assert_eq!(5_u32.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn gather_indices_out_of_bounds() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.indices = Some(vec![0, 2]);
+        let data = [1_i32, 2].as_bytes().to_vec();
+        match gather_indices(request_data, data).unwrap_err() {
+            ActiveStorageError::RequestDataValidationSingle(error) => assert_eq!(
+                "Indices must be less than the number of elements",
+                error.code
+            ),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn sum_u32_1d_valid_max() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.missing = Some(Missing::ValidMax((0x08070605 - 1).into()));
         let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Count::execute(&request_data, data).unwrap();
-        // A Vec<u8> of 8 elements == a u32 slice with 2 elements
-        // Count is always i64.
-        let expected: i64 = 1;
+        let response = Sum::execute(&request_data, data).unwrap();
+        let expected: u32 = 0x04030201;
         assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len()); // Assert that count value is 8 bytes (i.e. i64)
-        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Uint32, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(expected, response.count);
+        assert_eq!(1, response.count);
     }
 
     #[test]
-    fn max_i64_1d() {
+    fn sum_f16_1d() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Int64;
-        // data:
-        // A Vec<u8> of 8 elements == a single i64 value
-        // where each element is 2 hexadecimal digits
-        // and the order is reversed on little-endian systems
-        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Max::execute(&request_data, data).unwrap();
-        let expected: i64 = 0x0807060504030201;
+        request_data.dtype = models::DType::Float16;
+        let floats = [1.5, -2.0, 3.25, 0.5].map(half::f16::from_f32);
+        let data = floats.as_bytes();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
+        // Sums of float16 are promoted to float32.
+        let expected = 3.25_f32;
         assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(1, response.count);
+        assert_eq!(4, response.count);
     }
 
     #[test]
-    fn max_i64_1d_missing_values() {
+    fn sum_f16_1d_precision() {
+        // In float16, 2048 + 1 == 2048, so accumulating in float16 would give 2048.
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Int64;
-        request_data.missing = Some(Missing::MissingValues(vec![0x0807060504030201_i64.into()]));
-        // data:
-        // A Vec<u8> of 16 elements == two i64 values
-        // where each element is 2 hexadecimal digits
-        // and the order is reversed on little-endian systems
-        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        let response = Max::execute(&request_data, data).unwrap();
-        let expected: i64 = 0x100f0e0d0c0b0a09;
+        request_data.dtype = models::DType::Float16;
+        let floats = vec![half::f16::ONE; 4096];
+        let data = floats.as_bytes();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
+        let expected = 4096.0_f32;
         assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(4096, response.count);
+    }
+
+    #[test]
+    fn sum_f16_1d_missing_value_big_endian() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float16;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(-2.0).unwrap()));
+        let floats = [1.5, -2.0, 3.25, 0.5].map(half::f16::from_f32);
+        let data: Vec<u8> = floats
+            .iter()
+            .flat_map(|f| {
+                let mut bytes = f.to_ne_bytes();
+                bytes.reverse();
+                bytes
+            })
+            .collect();
+        let response = Sum::execute(&request_data, data).unwrap();
+        let expected = 5.25_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_bf16_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        let floats = vec![half::bf16::ONE; 512];
+        let data = floats.as_bytes();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
+        // In bf16, 256 + 1 == 256, so the sum must be accumulated in float32.
+        let expected = 512.0_f32;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(4, response.body.len());
+        assert_eq!(models::DType::Float32, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(1, response.count);
+        assert_eq!(512, response.count);
     }
 
     #[test]
-    fn max_f32_1d_infinity() {
+    fn sum_bf16_1d_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Bfloat16;
+        let floats = [half::bf16::NAN, half::bf16::ONE];
+        let data = floats.as_bytes();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
+        let sum = f32::from_ne_bytes(response.body.as_ref().try_into().unwrap());
+        assert!(sum.is_nan());
+        assert_eq!(models::DType::Float32, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_f32_1d_infinity() {
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Float32;
         let floats = [1.0, f32::INFINITY];
         let data = floats.as_bytes();
-        let response = Max::execute(&request_data, data.into()).unwrap();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
         let expected = f32::INFINITY;
         assert_eq!(expected.as_bytes(), response.body);
         assert_eq!(4, response.body.len());
         assert_eq!(models::DType::Float32, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_f64_1d_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        let floats = [f64::NAN, 1.0];
+        let data = floats.as_bytes();
+        let response = Sum::execute(&request_data, data.into()).unwrap();
+        let expected = f64::NAN;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_f32_2d_reject_nonfinite_infinity() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.shape = Some(vec![2, 3]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        request_data.reject_nonfinite = Some(true);
+        // The infinity at index [0, 0] of the array is not selected, and the index of the other
+        // is in the array rather than the selection.
+        let floats = [f32::INFINITY, 1.0, 2.0, 3.0, f32::NEG_INFINITY, 4.0];
+        match Sum::execute(&request_data, floats.as_bytes().into()) {
+            Err(ActiveStorageError::NonFiniteValue { operation, index }) => {
+                assert_eq!("sum", operation);
+                assert_eq!(vec![1, 1], index);
+            }
+            _ => panic!("expected sum to fail with NonFiniteValue"),
+        }
+    }
+
+    #[test]
+    fn sum_f64_1d_reject_nonfinite_nan() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.reject_nonfinite = Some(true);
+        let floats = [1.0, 2.0, f64::NAN, f64::NAN].map(|x: f64| x.to_bits().swap_bytes());
+        match Sum::execute(&request_data, floats.as_bytes().into()) {
+            Err(ActiveStorageError::NonFiniteValue { operation, index }) => {
+                assert_eq!("sum", operation);
+                assert_eq!(vec![2], index);
+            }
+            _ => panic!("expected sum to fail with NonFiniteValue"),
+        }
+    }
+
+    #[test]
+    fn sum_f64_1d_reject_nonfinite_missing() {
+        // Missing elements are not checked.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float64;
+        request_data.missing = Some(Missing::ValidMax(10.into()));
+        request_data.reject_nonfinite = Some(true);
+        let floats = [1.0, f64::INFINITY, 2.0];
+        let response = Sum::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(3.0_f64.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+        // Without the flag, the non-finite sum is returned.
+        request_data.missing = None;
+        request_data.reject_nonfinite = Some(false);
+        let response = Sum::execute(&request_data, floats.as_bytes().into()).unwrap();
+        assert_eq!(f64::INFINITY.as_bytes(), response.body);
+    }
+
+    #[test]
+    fn partial_cmp_behaviour() {
+        assert_eq!(
+            f64::INFINITY.partial_cmp(&1.0),
+            Some(std::cmp::Ordering::Greater)
+        );
+        assert_eq!(f64::NAN.partial_cmp(&1.0), None);
+        assert_eq!(
+            f64::INFINITY.partial_cmp(&f64::NEG_INFINITY),
+            Some(std::cmp::Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn sum_of_squares_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        // Squares of these values overflow i32.
+        let integers = [i32::MAX, -3, 4];
+        let data = integers.as_bytes();
+        let response = SumOfSquares::execute(&request_data, data.into()).unwrap();
+        let expected = (i32::MAX as f64) * (i32::MAX as f64) + 25.0;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_of_squares_i32_1d_missing_value() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.missing = Some(Missing::MissingValue((-3).into()));
+        let integers = [2_i32, -3, 4];
+        let data = integers.as_bytes();
+        let response = SumOfSquares::execute(&request_data, data.into()).unwrap();
+        let expected = 20.0_f64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn sum_of_squares_f32_1d() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        // The sum of squares of these values is larger than f32::MAX.
+        let floats = [1.5_f32, 2e20, -2e20];
+        let data = floats.as_bytes();
+        let response = SumOfSquares::execute(&request_data, data.into()).unwrap();
+        let expected = 1.5_f64 * 1.5 + 2.0 * (2e20_f32 as f64) * (2e20_f32 as f64);
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(8, response.body.len());
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(3, response.count);
+    }
+
+    #[test]
+    fn sum_of_squares_f32_2d_with_selection() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Float32;
+        request_data.shape = Some(vec![2, 2]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 2, 1),
+        ]);
+        let floats = [1.0_f32, 0.5, 3.0, 0.25];
+        let data = floats.as_bytes();
+        let response = SumOfSquares::execute(&request_data, data.into()).unwrap();
+        let expected = 0.3125_f64;
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(2, response.count);
+    }
+
+    /// Returns data for a C ordered array laid out in Fortran (column major) order.
+    fn to_fortran_order<T: Element>(data: &[T], shape: &[usize]) -> Vec<T> {
+        let array = ArrayView::from_shape(shape, data).unwrap();
+        array.t().iter().copied().collect()
+    }
+
+    /// Returns request data for the same logical array in C order and Fortran order.
+    fn c_and_f_order_request_data(
+        selection: Option<Vec<models::Slice>>,
+    ) -> (models::RequestData, models::RequestData) {
+        let build = |order| {
+            let mut request_data = test_utils::get_test_request_data();
+            request_data.dtype = models::DType::Int64;
+            request_data.shape = Some(vec![3, 4, 5]);
+            request_data.order = Some(order);
+            request_data.selection.clone_from(&selection);
+            request_data
+        };
+        (build(models::Order::C), build(models::Order::F))
+    }
+
+    /// Executes an operation on the same logical array in C order and Fortran order, checking
+    /// that the responses are identical. Returns the C order response.
+    fn execute_c_and_f_order<O: Operation>(
+        selection: Option<Vec<models::Slice>>,
+        missing: Option<Missing<DValue>>,
+    ) -> models::Response {
+        let (mut c_request_data, mut f_request_data) = c_and_f_order_request_data(selection);
+        c_request_data.missing.clone_from(&missing);
+        f_request_data.missing = missing;
+        let c_data = (0..60_i64)
+            .map(|i| (i * 37) % 61 - 30)
+            .collect::<Vec<i64>>();
+        let f_data = to_fortran_order(&c_data, &[3, 4, 5]);
+        let c_response = O::execute(&c_request_data, c_data.as_bytes().into()).unwrap();
+        let f_response = O::execute(&f_request_data, f_data.as_bytes().into()).unwrap();
+        assert_eq!(c_response.body, f_response.body);
+        assert_eq!(c_response.dtype, f_response.dtype);
+        assert_eq!(c_response.shape, f_response.shape);
+        assert_eq!(c_response.count, f_response.count);
+        c_response
+    }
+
+    fn strided_selection() -> Option<Vec<models::Slice>> {
+        Some(vec![
+            models::Slice::new(0, 3, 2),
+            models::Slice::new(3, 0, -2),
+            models::Slice::new(1, 5, 3),
+        ])
+    }
+
+    #[test]
+    fn fortran_order_layout() {
+        // Check the test helper against a hand-written 2x3 array.
+        let data = [1_i32, 2, 3, 4, 5, 6];
+        assert_eq!(vec![1, 4, 2, 5, 3, 6], to_fortran_order(&data, &[2, 3]));
+    }
+
+    #[test]
+    fn c_and_f_order_count() {
+        let response = execute_c_and_f_order::<Count>(strided_selection(), None);
+        assert_eq!(8, response.count);
+    }
+
+    #[test]
+    fn c_and_f_order_cumsum() {
+        let response = execute_c_and_f_order::<CumSum>(None, None);
+        let expected: Vec<i64> = (0..60_i64)
+            .map(|i| (i * 37) % 61 - 30)
+            .scan(0, |sum, x| {
+                *sum += x;
+                Some(*sum)
+            })
+            .collect();
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(vec![3, 4, 5], response.shape);
+        execute_c_and_f_order::<CumSum>(strided_selection(), None);
+    }
+
+    #[test]
+    fn c_and_f_order_diff() {
+        let response = execute_c_and_f_order::<Diff>(None, None);
+        let values: Vec<i64> = (0..60_i64).map(|i| (i * 37) % 61 - 30).collect();
+        let expected: Vec<i64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+        assert_eq!(expected.as_bytes(), response.body);
+        assert_eq!(vec![59], response.shape);
+        execute_c_and_f_order::<Diff>(strided_selection(), None);
+    }
+
+    #[test]
+    fn c_and_f_order_max() {
+        execute_c_and_f_order::<Max>(None, None);
+        execute_c_and_f_order::<Max>(strided_selection(), None);
+    }
+
+    #[test]
+    fn c_and_f_order_min() {
+        execute_c_and_f_order::<Min>(None, None);
+        execute_c_and_f_order::<Min>(strided_selection(), None);
+    }
+
+    #[test]
+    fn c_and_f_order_sum() {
+        let response = execute_c_and_f_order::<Sum>(None, None);
+        let expected: i64 = (0..60_i64).map(|i| (i * 37) % 61 - 30).sum();
+        assert_eq!(expected.as_bytes(), response.body);
+        execute_c_and_f_order::<Sum>(strided_selection(), None);
+        execute_c_and_f_order::<Sum>(
+            strided_selection(),
+            Some(Missing::ValidRange((-10).into(), 10.into())),
+        );
+    }
+
+    #[test]
+    fn c_and_f_order_select() {
+        let response = execute_c_and_f_order::<Select>(strided_selection(), None);
+        assert_eq!(vec![2, 2, 2], response.shape);
+        // Build the expected result using NumPy semantics: a[0:3:2, 3:0:-2, 1:5:3].
+        let data = (0..60_i64)
+            .map(|i| (i * 37) % 61 - 30)
+            .collect::<Vec<i64>>();
+        let mut expected = vec![];
+        for i in [0, 2] {
+            for j in [3, 1] {
+                for k in [1, 4] {
+                    expected.push(data[i * 20 + j * 5 + k]);
+                }
+            }
+        }
+        assert_eq!(expected.as_bytes(), response.body);
+    }
+
+    #[test]
+    fn c_and_f_order_select_byte_order() {
+        // Selecting elements in non-native byte order must also respect the layout.
+        let (mut c_request_data, mut f_request_data) =
+            c_and_f_order_request_data(strided_selection());
+        c_request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        f_request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        let c_data = (0..60_i64).map(|i| i.swap_bytes()).collect::<Vec<i64>>();
+        let f_data = to_fortran_order(&c_data, &[3, 4, 5]);
+        let c_response = Select::execute(&c_request_data, c_data.as_bytes().into()).unwrap();
+        let f_response = Select::execute(&f_request_data, f_data.as_bytes().into()).unwrap();
+        assert_eq!(c_response.body, f_response.body);
+        // The elements are returned verbatim in the byte order of the data.
+        let expected = [16_i64, 19, 6, 9, 56, 59, 46, 49].map(i64::swap_bytes);
+        assert_eq!(expected.as_bytes(), c_response.body);
+        assert_eq!(crate::types::NON_NATIVE_BYTE_ORDER, c_response.byte_order);
+        assert_eq!(crate::types::NON_NATIVE_BYTE_ORDER, f_response.byte_order);
+    }
+
+    #[test]
+    fn select_non_native_output_dtype() {
+        // Cast elements are returned in native byte order.
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Int32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(2.into()));
+        request_data.output_dtype = Some(models::DType::Int64);
+        let data = [1_i32, 2, 3].map(i32::swap_bytes);
+        let response = Select::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([1_i64, 2, 3].as_bytes(), response.body);
+        assert_eq!(NATIVE_BYTE_ORDER, response.byte_order);
+        assert_eq!(2, response.count);
+    }
+
+    #[test]
+    fn select_non_native_verbatim() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.dtype = models::DType::Uint32;
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.shape = Some(vec![4]);
+        request_data.selection = Some(vec![models::Slice::new(0, 4, 2)]);
+        request_data.missing = Some(Missing::MissingValue(3.into()));
+        let data = [1_u32, 2, 3, 4].map(u32::swap_bytes);
+        let response = Select::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([1_u32, 3].map(u32::swap_bytes).as_bytes(), response.body);
+        assert_eq!(crate::types::NON_NATIVE_BYTE_ORDER, response.byte_order);
+        // Missing data is compared in native byte order.
+        assert_eq!(1, response.count);
+        let json = response.to_json();
+        assert_eq!(serde_json::json!([1, 3]), json["result"]);
+    }
+
+    // Helper function that returns request data for weights of a data type.
+    fn weights_request_data(
+        request_data: &models::RequestData,
+        dtype: models::DType,
+    ) -> models::RequestData {
+        let mut request_data = request_data.clone();
+        request_data.weights_object = Some("weights".to_string());
+        request_data.weights_dtype = Some(dtype);
+        request_data.weights_request_data().unwrap()
+    }
+
+    #[test]
+    fn weighted_sum_i32_1d() {
+        let request_data = test_utils::get_test_request_data();
+        let weights_request_data = weights_request_data(&request_data, models::DType::Float64);
+        let data = [1_i32, 2, 3, 4];
+        let weights = [0.5_f64, 0.25, 0.125, 0.125];
+        let response = WeightedSum::execute(
+            &request_data,
+            data.as_bytes().into(),
+            &weights_request_data,
+            weights.as_bytes().into(),
+        )
+        .unwrap();
+        let expected = 0.5 + 0.5 + 0.375 + 0.5;
+        assert_eq!(f64::as_bytes(&expected), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![0; 0], response.shape);
+        assert_eq!(4, response.count);
+        assert_eq!(Some(1.0), response.weights_sum);
     }
 
     #[test]
-    fn max_f32_1d_infinity_first() {
+    fn weighted_sum_f32_2d_selection_missing() {
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Float32;
-        let floats = [f32::INFINITY, 1.0];
-        let data = floats.as_bytes();
-        let response = Max::execute(&request_data, data.into()).unwrap();
-        let expected = f32::INFINITY;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 3, 1),
+        ]);
+        request_data.missing = Some(Missing::MissingValue((-1).into()));
+        let weights_request_data = weights_request_data(&request_data, models::DType::Uint32);
+        let data = [10_f32, 1.0, -1.0, 20.0, 2.0, 3.0];
+        let weights = [7_u32, 1, 2, 8, 3, 4];
+        let response = WeightedSum::execute(
+            &request_data,
+            data.as_bytes().into(),
+            &weights_request_data,
+            weights.as_bytes().into(),
+        )
+        .unwrap();
+        // The selected elements are [[1, -1], [2, 3]], with -1 missing.
+        let expected = 1.0 * 1.0 + 2.0 * 3.0 + 3.0 * 4.0;
+        assert_eq!(f64::as_bytes(&expected), response.body);
+        assert_eq!(3, response.count);
+        assert_eq!(Some(8.0), response.weights_sum);
+    }
+
+    #[test]
+    fn weighted_sum_nan_weight() {
+        let request_data = test_utils::get_test_request_data();
+        let weights_request_data = weights_request_data(&request_data, models::DType::Float32);
+        let data = [1_i32, 2, 3];
+        let weights = [2.0_f32, f32::NAN, 3.0];
+        let response = WeightedSum::execute(
+            &request_data,
+            data.as_bytes().into(),
+            &weights_request_data,
+            weights.as_bytes().into(),
+        )
+        .unwrap();
+        let expected = 2.0 + 9.0;
+        assert_eq!(f64::as_bytes(&expected), response.body);
         assert_eq!(2, response.count);
+        assert_eq!(Some(5.0), response.weights_sum);
     }
 
     #[test]
-    fn min_u64_1d() {
-        let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Uint64;
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Min::execute(&request_data, data).unwrap();
-        let expected: u64 = 0x0807060504030201;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Uint64, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(1, response.count);
+    #[should_panic(expected = "Weights shape must match data shape")]
+    fn weighted_sum_shape_mismatch() {
+        let request_data = test_utils::get_test_request_data();
+        let weights_request_data = weights_request_data(&request_data, models::DType::Int32);
+        let data = [1_i32, 2, 3];
+        let weights = [1_i32, 2];
+        WeightedSum::execute(
+            &request_data,
+            data.as_bytes().into(),
+            &weights_request_data,
+            weights.as_bytes().into(),
+        )
+        .unwrap();
+    }
+
+    // Helper function that executes an operation on several chunks of data and combines the
+    // results.
+    fn execute_combine<O: Combine>(
+        request_data: &models::RequestData,
+        chunks: &[&[u8]],
+    ) -> models::Response {
+        let responses = chunks
+            .iter()
+            .map(|chunk| O::execute(request_data, chunk.to_vec()).unwrap())
+            .collect();
+        O::combine(responses).unwrap()
     }
 
     #[test]
-    fn min_i64_1d_valid_min() {
+    fn combine_count_i32() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Int64;
-        // Minimum is one greater than smallest element.
-        request_data.missing = Some(Missing::ValidMin(0x0807060504030202_i64.into()));
-        // data:
-        // A Vec<u8> of 16 elements == two i64 values
-        // where each element is 2 hexadecimal digits
-        // and the order is reversed on little-endian systems
-        // so [1, 2, 3] is 0x030201 as an i64 in hexadecimal
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        let response = Min::execute(&request_data, data).unwrap();
-        let expected: i64 = 0x100f0e0d0c0b0a09;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
+        request_data.missing = Some(Missing::MissingValue(0.into()));
+        let chunks = [[1_i32, 0, 3].as_bytes(), [4_i32, 5].as_bytes()];
+        let response = execute_combine::<Count>(&request_data, &chunks);
+        assert_eq!(&4_i64.to_ne_bytes()[..], response.body);
         assert_eq!(models::DType::Int64, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(1, response.count);
+        assert_eq!(4, response.count);
     }
 
     #[test]
-    fn min_f32_1d_infinity() {
+    fn combine_max_f32() {
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Float32;
-        let floats = [1.0, f32::INFINITY];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        let expected = 1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
+        let chunks = [[1.0_f32, 7.5].as_bytes(), [-2.0_f32, 3.0, 4.0].as_bytes()];
+        let response = execute_combine::<Max>(&request_data, &chunks);
+        assert_eq!(7.5_f32.as_bytes(), response.body);
         assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        assert_eq!(5, response.count);
     }
 
     #[test]
-    fn min_f32_1d_infinity_first() {
+    fn combine_min_u64() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        let floats = [f32::INFINITY, 1.0];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        let expected = 1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        request_data.dtype = models::DType::Uint64;
+        let chunks = [
+            [9_u64, 7].as_bytes(),
+            [8_u64, 3].as_bytes(),
+            [5_u64].as_bytes(),
+        ];
+        let response = execute_combine::<Min>(&request_data, &chunks);
+        assert_eq!(3_u64.as_bytes(), response.body);
+        assert_eq!(models::DType::Uint64, response.dtype);
+        assert_eq!(5, response.count);
     }
 
     #[test]
-    #[should_panic(expected = "unexpected undefined order error for min")]
-    fn min_f32_1d_nan() {
+    fn combine_sum_f16() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        let floats = [1.0, f32::NAN];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        let expected = 1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
+        request_data.dtype = models::DType::Float16;
+        let first = [half::f16::from_f32(1.5), half::f16::from_f32(2.0)];
+        let second = [half::f16::from_f32(4.0)];
+        let chunks = [first.as_bytes(), second.as_bytes()];
+        let response = execute_combine::<Sum>(&request_data, &chunks);
+        // Half precision floats are summed in f32.
+        assert_eq!(7.5_f32.as_bytes(), response.body);
         assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        assert_eq!(3, response.count);
     }
 
     #[test]
-    #[should_panic(expected = "unexpected undefined order error for min")]
-    fn min_f32_1d_nan_first() {
+    fn combine_invalid() {
+        let request_data = test_utils::get_test_request_data();
+        let response = || Max::execute(&request_data, [1_i32, 2].as_bytes().to_vec()).unwrap();
+        let mut other = Max::execute(&request_data, [3_i32].as_bytes().to_vec()).unwrap();
+        other.dtype = models::DType::Int64;
+        let not_scalar = models::Response::new(
+            Bytes::copy_from_slice([1_i32, 2].as_bytes()),
+            models::DType::Int32,
+            vec![2],
+            2,
+        );
+        let invalid = [
+            (vec![], "Batch responses must not be empty"),
+            (
+                vec![response(), other],
+                "Batch responses must have the same dtype",
+            ),
+            (
+                vec![response(), not_scalar],
+                "Batch responses must be scalars",
+            ),
+        ];
+        for (responses, code) in invalid {
+            match Max::combine(responses) {
+                Err(ActiveStorageError::RequestDataValidationSingle(error)) => {
+                    assert_eq!(code, error.code)
+                }
+                _ => panic!("expected combine to fail"),
+            }
+        }
+    }
+
+    /// Returns request data for a 2x4 i32 array with a selection containing no elements.
+    fn empty_selection_request_data() -> models::RequestData {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        let floats = [f32::NAN, 1.0];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        let expected = 1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
+        request_data.shape = Some(vec![2, 4]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(3, 3, 1),
+        ]);
+        request_data
+    }
+
+    const EMPTY_SELECTION_DATA: [i32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    #[test]
+    fn count_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let response =
+            Count::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
         assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        assert_eq!(0, response.count);
     }
 
     #[test]
-    fn min_f32_1d_nan_missing_value() {
-        let mut request_data = test_utils::get_test_request_data();
+    fn approx_distinct_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let response =
+            ApproxDistinct::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn count_missing_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let response =
+            CountMissing::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(0_i64.as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn sum_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let response = Sum::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(0_i32.as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(0, response.count);
+        let response =
+            SumOfSquares::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(0_f64.as_bytes(), response.body);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn sum_f32_empty_selection_non_native_missing() {
+        let mut request_data = empty_selection_request_data();
         request_data.dtype = models::DType::Float32;
-        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(42.0).unwrap()));
-        let floats = [1.0, f32::NAN];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        let expected = 1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.missing = Some(Missing::MissingValue(1.into()));
+        let data = [0_f32; 8];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!(0_f32.as_bytes(), response.body);
+        assert_eq!(0, response.count);
     }
 
     #[test]
-    fn min_f32_1d_nan_first_missing_value() {
+    fn select_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let response =
+            Select::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert!(response.body.is_empty());
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(vec![2, 0], response.shape);
+        assert_eq!(0, response.count);
+        let response =
+            CumSum::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert!(response.body.is_empty());
+        assert_eq!(vec![2, 0], response.shape);
+        assert_eq!(0, response.count);
+        let response =
+            Diff::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert!(response.body.is_empty());
+        assert_eq!(vec![0], response.shape);
+        assert_eq!(0, response.count);
+    }
+
+    #[test]
+    fn weighted_sum_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let weights_request_data = weights_request_data(&request_data, models::DType::Float64);
+        let weights = [1_f64; 8];
+        let response = WeightedSum::execute(
+            &request_data,
+            EMPTY_SELECTION_DATA.as_bytes().into(),
+            &weights_request_data,
+            weights.as_bytes().into(),
+        )
+        .unwrap();
+        assert_eq!(0_f64.as_bytes(), response.body);
+        assert_eq!(0, response.count);
+        assert_eq!(Some(0.0), response.weights_sum);
+    }
+
+    #[test]
+    fn reductions_empty_selection() {
+        let request_data = empty_selection_request_data();
+        let data = || -> Vec<u8> { EMPTY_SELECTION_DATA.as_bytes().into() };
+        let results = [
+            ("first", First::execute(&request_data, data())),
+            (
+                "geometric_mean",
+                GeometricMean::execute(&request_data, data()),
+            ),
+            ("last", Last::execute(&request_data, data())),
+            ("max", Max::execute(&request_data, data())),
+            ("min", Min::execute(&request_data, data())),
+            ("mode", Mode::execute(&request_data, data())),
+            ("nanmax", NanMax::execute(&request_data, data())),
+            ("nanmin", NanMin::execute(&request_data, data())),
+            ("ptp", Ptp::execute(&request_data, data())),
+            (
+                "trimmed_mean",
+                TrimmedMean::execute(
+                    &models::RequestData {
+                        trim_fraction: Some(0.1),
+                        ..request_data.clone()
+                    },
+                    data(),
+                ),
+            ),
+            (
+                "valid_fraction",
+                ValidFraction::execute(&request_data, data()),
+            ),
+        ];
+        for (name, result) in results {
+            match result {
+                Err(ActiveStorageError::EmptyArray { operation }) => assert_eq!(name, operation),
+                _ => panic!("expected {} to fail with EmptyArray", name),
+            }
+        }
+    }
+
+    #[test]
+    fn select_stride_larger_than_dimension() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        request_data.missing = Some(Missing::MissingValue(DValue::from_f64(42.0).unwrap()));
-        let floats = [f32::NAN, 1.0];
-        let data = floats.as_bytes();
-        let response = Min::execute(&request_data, data.into()).unwrap();
-        // FIXME: Ignore NANs?
-        let expected = f32::NAN; //1.0_f32;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
+        request_data.shape = Some(vec![2, 4]);
+        request_data.selection = Some(vec![
+            models::Slice::new(0, 2, 1),
+            models::Slice::new(1, 4, 10),
+        ]);
+        let response =
+            Select::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!([2_i32, 6].as_bytes(), response.body);
+        assert_eq!(vec![2, 1], response.shape);
+        assert_eq!(2, response.count);
+        let response = Max::execute(&request_data, EMPTY_SELECTION_DATA.as_bytes().into()).unwrap();
+        assert_eq!(6_i32.as_bytes(), response.body);
         assert_eq!(2, response.count);
     }
 
     #[test]
-    fn select_f32_1d() {
+    fn sum_i32_2d_axis_0() {
+        // np.sum(np.arange(6, dtype=np.int32).reshape(2, 3), axis=0)
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Select::execute(&request_data, data).unwrap();
-        let expected: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![2], response.shape);
-        assert_eq!(2, response.count);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(0);
+        let data = [0_i32, 1, 2, 3, 4, 5];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([3_i32, 5, 7].as_bytes(), response.body);
+        assert_eq!(models::DType::Int32, response.dtype);
+        assert_eq!(vec![3], response.shape);
+        assert_eq!(6, response.count);
     }
 
     #[test]
-    fn select_f64_2d() {
+    fn sum_i32_2d_axis_1() {
+        // np.sum(np.arange(6, dtype=np.int32).reshape(2, 3), axis=1)
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float64;
-        request_data.shape = Some(vec![2, 1]);
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        let response = Select::execute(&request_data, data).unwrap();
-        let expected: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(16, response.body.len());
-        assert_eq!(models::DType::Float64, response.dtype);
-        assert_eq!(vec![2, 1], response.shape);
-        assert_eq!(2, response.count);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(1);
+        let data = [0_i32, 1, 2, 3, 4, 5];
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([3_i32, 12].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(6, response.count);
     }
 
     #[test]
-    fn select_f32_2d_with_selection() {
+    fn sum_f64_2d_axis_missing_selection() {
+        // a = np.ma.masked_equal(np.arange(12.0).reshape(3, 4), 5.0)[:, 1:4:2]
+        // np.ma.sum(a, axis=0)
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float32;
-        request_data.shape = Some(vec![2, 2]);
+        request_data.dtype = models::DType::Float64;
+        request_data.shape = Some(vec![3, 4]);
         request_data.selection = Some(vec![
-            models::Slice::new(0, 2, 1),
-            models::Slice::new(1, 2, 1),
+            models::Slice::new(0, 3, 1),
+            models::Slice::new(1, 4, 2),
         ]);
-        // 2x2 array, select second row of each column.
-        // [[0x04030201, 0x08070605], [0x12111009, 0x16151413]]
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        let response = Select::execute(&request_data, data).unwrap();
-        // [[0x08070605], [0x16151413]]
-        let expected: [u8; 8] = [5, 6, 7, 8, 13, 14, 15, 16];
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![2, 1], response.shape);
-        assert_eq!(2, response.count);
+        request_data.missing = Some(Missing::MissingValue(5.into()));
+        request_data.axis = Some(0);
+        let data: Vec<f64> = (0..12).map(f64::from).collect();
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([10.0_f64, 21.0].as_bytes(), response.body);
+        assert_eq!(models::DType::Float64, response.dtype);
+        assert_eq!(vec![2], response.shape);
+        assert_eq!(5, response.count);
     }
 
     #[test]
-    fn sum_u32_1d() {
+    fn sum_u32_2d_axis_f_order_non_native() {
+        // np.sum(np.arange(6, dtype=np.uint32).reshape(2, 3, order="F"), axis=1)
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Uint32;
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Sum::execute(&request_data, data).unwrap();
-        let expected: u32 = 0x04030201 + 0x08070605;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Uint32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.order = Some(models::Order::F);
+        request_data.byte_order = Some(crate::types::NON_NATIVE_BYTE_ORDER);
+        request_data.axis = Some(1);
+        let data: Vec<u32> = (0..6_u32).map(u32::swap_bytes).collect();
+        let response = Sum::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([6_u32, 9].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
     }
 
     #[test]
-    fn sum_u32_1d_valid_max() {
+    fn reductions_3d_axis() {
+        // a = np.arange(24, dtype=np.int64).reshape(2, 3, 4)
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Uint32;
-        request_data.missing = Some(Missing::ValidMax((0x08070605 - 1).into()));
-        let data = vec![1, 2, 3, 4, 5, 6, 7, 8];
-        let response = Sum::execute(&request_data, data).unwrap();
-        let expected: u32 = 0x04030201;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Uint32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(1, response.count);
+        request_data.dtype = models::DType::Int64;
+        request_data.shape = Some(vec![2, 3, 4]);
+        request_data.axis = Some(1);
+        let data: Vec<i64> = (0..24).collect();
+        let data = || -> Vec<u8> { data.as_bytes().into() };
+        // np.max(a, axis=1)
+        let response = Max::execute(&request_data, data()).unwrap();
+        assert_eq!([8_i64, 9, 10, 11, 20, 21, 22, 23].as_bytes(), response.body);
+        assert_eq!(vec![2, 4], response.shape);
+        assert_eq!(24, response.count);
+        // np.min(a, axis=1)
+        let response = Min::execute(&request_data, data()).unwrap();
+        assert_eq!([0_i64, 1, 2, 3, 12, 13, 14, 15].as_bytes(), response.body);
+        // a[:, 0, :]
+        let response = First::execute(&request_data, data()).unwrap();
+        assert_eq!([0_i64, 1, 2, 3, 12, 13, 14, 15].as_bytes(), response.body);
+        // a[:, -1, :]
+        let response = Last::execute(&request_data, data()).unwrap();
+        assert_eq!([8_i64, 9, 10, 11, 20, 21, 22, 23].as_bytes(), response.body);
+        // np.count_nonzero(np.ones_like(a), axis=1)
+        let response = Count::execute(&request_data, data()).unwrap();
+        assert_eq!([3_i64; 8].as_bytes(), response.body);
+        assert_eq!(models::DType::Int64, response.dtype);
+        assert_eq!(vec![2, 4], response.shape);
+        // np.sum(a.astype(np.float64) ** 2, axis=1)[:2]
+        let response = SumOfSquares::execute(&request_data, data()).unwrap();
+        assert_eq!([80.0_f64, 107.0].as_bytes(), &response.body[..16]);
     }
 
     #[test]
-    fn sum_f32_1d_infinity() {
+    fn nan_reductions_axis() {
+        // np.nanmax(np.array([[1, np.nan], [np.nan, np.nan]], dtype=np.float32), axis=1)
         let mut request_data = test_utils::get_test_request_data();
         request_data.dtype = models::DType::Float32;
-        let floats = [1.0, f32::INFINITY];
-        let data = floats.as_bytes();
-        let response = Sum::execute(&request_data, data.into()).unwrap();
-        let expected = f32::INFINITY;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(4, response.body.len());
-        assert_eq!(models::DType::Float32, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        request_data.shape = Some(vec![2, 2]);
+        request_data.axis = Some(1);
+        let data = [1.0_f32, f32::NAN, f32::NAN, f32::NAN];
+        let response = NanMax::execute(&request_data, data.as_bytes().into()).unwrap();
+        let body: Vec<f32> = response
+            .body
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(1.0, body[0]);
+        assert!(body[1].is_nan());
+        assert_eq!(1, response.count);
     }
 
     #[test]
-    fn sum_f64_1d_nan() {
+    fn mode_axis() {
         let mut request_data = test_utils::get_test_request_data();
-        request_data.dtype = models::DType::Float64;
-        let floats = [f64::NAN, 1.0];
-        let data = floats.as_bytes();
-        let response = Sum::execute(&request_data, data.into()).unwrap();
-        let expected = f64::NAN;
-        assert_eq!(expected.as_bytes(), response.body);
-        assert_eq!(8, response.body.len());
-        assert_eq!(models::DType::Float64, response.dtype);
-        assert_eq!(vec![0; 0], response.shape);
-        assert_eq!(2, response.count);
+        request_data.shape = Some(vec![2, 3]);
+        request_data.axis = Some(1);
+        let data = [1_i32, 2, 2, 3, 3, 1];
+        let response = Mode::execute(&request_data, data.as_bytes().into()).unwrap();
+        assert_eq!([2_i32, 3].as_bytes(), response.body);
+        assert_eq!(vec![2], response.shape);
     }
 
     #[test]
-    fn partial_cmp_behaviour() {
-        assert_eq!(
-            f64::INFINITY.partial_cmp(&1.0),
-            Some(std::cmp::Ordering::Greater)
-        );
-        assert_eq!(f64::NAN.partial_cmp(&1.0), None);
-        assert_eq!(
-            f64::INFINITY.partial_cmp(&f64::NEG_INFINITY),
-            Some(std::cmp::Ordering::Greater)
-        );
+    fn max_axis_all_missing_lane() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.shape = Some(vec![2, 2]);
+        request_data.axis = Some(1);
+        request_data.missing = Some(Missing::MissingValue(0.into()));
+        let data = [1_i32, 2, 0, 0];
+        match Max::execute(&request_data, data.as_bytes().into()) {
+            Err(ActiveStorageError::EmptyArray { operation }) => assert_eq!("max", operation),
+            _ => panic!("expected max to fail with EmptyArray"),
+        }
+    }
+
+    #[test]
+    fn axis_unsupported() {
+        let mut request_data = test_utils::get_test_request_data();
+        request_data.axis = Some(0);
+        let data = || -> Vec<u8> { [1_i32, 2].as_bytes().into() };
+        let results = [
+            ("cumsum", CumSum::execute(&request_data, data())),
+            ("diff", Diff::execute(&request_data, data())),
+            ("select", Select::execute(&request_data, data())),
+        ];
+        for (name, result) in results {
+            match result {
+                Err(ActiveStorageError::AxisUnsupported { operation }) => {
+                    assert_eq!(name, operation)
+                }
+                _ => panic!("expected {} to fail with AxisUnsupported", name),
+            }
+        }
+        let weights_request_data = weights_request_data(&request_data, models::DType::Float64);
+        match WeightedSum::execute(
+            &request_data,
+            data(),
+            &weights_request_data,
+            [1.0_f64, 1.0].as_bytes().into(),
+        ) {
+            Err(ActiveStorageError::AxisUnsupported { operation }) => {
+                assert_eq!("weighted_sum", operation)
+            }
+            _ => panic!("expected weighted_sum to fail with AxisUnsupported"),
+        }
     }
 }
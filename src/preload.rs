@@ -0,0 +1,143 @@
+//! List of objects to preload into the download cache at startup
+
+use crate::models::RequestData;
+
+use serde::Deserialize;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use url::Url;
+
+/// An object to preload into the download cache.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PreloadEntry {
+    /// URL of the object store.
+    pub source: Url,
+
+    /// Bucket containing the object.
+    pub bucket: String,
+
+    /// Object name.
+    pub object: String,
+
+    /// Optional region of the object store.
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl PreloadEntry {
+    /// Returns request data for downloading the whole object.
+    pub fn request_data(&self) -> RequestData {
+        // The data type is required, but not used to download the object.
+        let mut request_data: RequestData = serde_json::from_value(serde_json::json!({
+            "source": self.source,
+            "bucket": self.bucket,
+            "object": self.object,
+            "dtype": "uint32",
+        }))
+        .expect("request data with only required fields is valid");
+        request_data.region.clone_from(&self.region);
+        request_data.path_style();
+        request_data
+    }
+}
+
+/// Reads a preload file, returning its entries.
+///
+/// Each line of the file is a JSON object with the `source`, `bucket` and `object` of an object
+/// to preload, and optionally its `region`. Blank lines and lines starting with `#` are ignored.
+///
+/// # Arguments
+///
+/// * `path`: Path to the preload file
+pub fn read_preload_file(path: &Path) -> io::Result<Vec<PreloadEntry>> {
+    parse(BufReader::new(std::fs::File::open(path)?))
+}
+
+/// Parses the entries of a preload file.
+fn parse(reader: impl BufRead) -> io::Result<Vec<PreloadEntry>> {
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = serde_json::from_str(line).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, error),
+            )
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entries() {
+        let file = r#"
+            # Hot objects
+            {"source": "http://localhost:9000", "bucket": "bar", "object": "baz"}
+
+            {"source": "https://s3.example.com", "bucket": "foo", "object": "a/b", "region": "eu-west-2"}
+        "#;
+        let entries = parse(file.as_bytes()).unwrap();
+        assert_eq!(
+            vec![
+                PreloadEntry {
+                    source: Url::parse("http://localhost:9000").unwrap(),
+                    bucket: "bar".to_string(),
+                    object: "baz".to_string(),
+                    region: None,
+                },
+                PreloadEntry {
+                    source: Url::parse("https://s3.example.com").unwrap(),
+                    bucket: "foo".to_string(),
+                    object: "a/b".to_string(),
+                    region: Some("eu-west-2".to_string()),
+                },
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn parse_invalid_entry() {
+        let file = concat!(
+            r#"{"source": "http://localhost:9000", "bucket": "bar", "object": "baz"}"#,
+            "\n",
+            r#"{"source": "http://localhost:9000", "bucket": "bar"}"#,
+        );
+        let error = parse(file.as_bytes()).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(error
+            .to_string()
+            .starts_with("line 2: missing field `object`"));
+    }
+
+    #[test]
+    fn read_missing_file() {
+        let error = read_preload_file(Path::new("/nonexistent/preload.jsonl")).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, error.kind());
+    }
+
+    #[test]
+    fn entry_request_data() {
+        let entry = PreloadEntry {
+            source: Url::parse("http://localhost:9000").unwrap(),
+            bucket: "bar".to_string(),
+            object: "baz".to_string(),
+            region: Some("eu-west-2".to_string()),
+        };
+        let request_data = entry.request_data();
+        assert_eq!(entry.source, request_data.source);
+        assert_eq!("bar", request_data.bucket);
+        assert_eq!("baz", request_data.object);
+        assert_eq!(Some("eu-west-2".to_string()), request_data.region);
+    }
+}
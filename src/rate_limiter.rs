@@ -0,0 +1,181 @@
+//! Per-source request rate limiting
+
+use crate::error::ActiveStorageError;
+
+use hashbrown::HashMap;
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Maximum number of token buckets kept before idle buckets are removed.
+const MAX_BUCKETS: usize = 4096;
+
+/// Time after which an unused bucket is full, and so may be removed without affecting the limit.
+const IDLE_TIME: Duration = Duration::from_secs(1);
+
+/// A token bucket for a single source.
+struct TokenBucket {
+    /// Number of tokens currently available.
+    tokens: f64,
+
+    /// Time at which the tokens were last replenished.
+    updated: Instant,
+}
+
+/// [crate::rate_limiter::RateLimiter] limits the rate of requests to each object store source.
+///
+/// A token bucket is kept for each source, keyed by the source's scheme, host and port. Each
+/// bucket holds up to one second's worth of tokens, and is replenished continuously at the
+/// configured rate.
+///
+/// A bucket that has not been used for a second is full, which is equivalent to having no bucket,
+/// so idle buckets are removed when the number of buckets reaches a maximum. If every bucket is
+/// in use, the least recently used bucket is removed instead.
+pub struct RateLimiter {
+    /// Maximum number of requests per second for each source.
+    rate: f64,
+
+    /// Token buckets, keyed by source origin.
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Returns a new RateLimiter object.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate`: Maximum number of requests per second for each source
+    pub fn new(rate: NonZeroU32) -> Self {
+        Self {
+            rate: rate.get().into(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token from the bucket for a source.
+    ///
+    /// Returns a [crate::error::ActiveStorageError::RateLimited] error if no tokens are
+    /// available.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: Object storage API URL
+    pub fn check(&self, source: &Url) -> Result<(), ActiveStorageError> {
+        let key = source.origin().ascii_serialization();
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            Self::remove_idle(&mut buckets, now);
+        }
+        let bucket = buckets.entry(key).or_insert(TokenBucket {
+            tokens: self.rate,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.rate;
+            Err(ActiveStorageError::RateLimited {
+                // Round up to a whole number of seconds, as required by Retry-After.
+                retry_after: wait.ceil().max(1.0) as u64,
+            })
+        }
+    }
+
+    /// Remove idle buckets, or the least recently used bucket if none are idle.
+    fn remove_idle(buckets: &mut HashMap<String, TokenBucket>, now: Instant) {
+        buckets.retain(|_, bucket| now.duration_since(bucket.updated) < IDLE_TIME);
+        if buckets.len() >= MAX_BUCKETS {
+            let oldest = buckets
+                .iter()
+                .min_by_key(|(_, bucket)| bucket.updated)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                buckets.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn burst() {
+        let rl = RateLimiter::new(NonZeroU32::new(3).unwrap());
+        let source = url("http://example.com");
+        for _ in 0..3 {
+            rl.check(&source).unwrap();
+        }
+        match rl.check(&source) {
+            Err(ActiveStorageError::RateLimited { retry_after }) => assert_eq!(1, retry_after),
+            _ => panic!("expected rate limited error"),
+        }
+    }
+
+    #[test]
+    fn independent_sources() {
+        let rl = RateLimiter::new(NonZeroU32::new(1).unwrap());
+        rl.check(&url("http://example.com")).unwrap();
+        assert!(rl.check(&url("http://example.com/foo")).is_err());
+        rl.check(&url("http://example.com:8080")).unwrap();
+        rl.check(&url("https://example.com")).unwrap();
+        rl.check(&url("http://example.org")).unwrap();
+    }
+
+    #[test]
+    fn replenish() {
+        let rl = RateLimiter::new(NonZeroU32::new(1).unwrap());
+        let source = url("http://example.com");
+        rl.check(&source).unwrap();
+        assert!(rl.check(&source).is_err());
+        // Wind back the clock rather than sleeping.
+        let mut buckets = rl.buckets.lock().unwrap();
+        let bucket = buckets.values_mut().next().unwrap();
+        bucket.updated -= std::time::Duration::from_secs(1);
+        drop(buckets);
+        rl.check(&source).unwrap();
+    }
+
+    #[test]
+    fn remove_idle() {
+        let rl = RateLimiter::new(NonZeroU32::new(1).unwrap());
+        for i in 0..MAX_BUCKETS {
+            rl.check(&url(&format!("http://{}.example.com", i)))
+                .unwrap();
+        }
+        // Make half of the buckets idle.
+        let mut buckets = rl.buckets.lock().unwrap();
+        for bucket in buckets.values_mut().take(MAX_BUCKETS / 2) {
+            bucket.updated -= IDLE_TIME;
+        }
+        drop(buckets);
+        rl.check(&url("http://example.org")).unwrap();
+        assert_eq!(MAX_BUCKETS / 2 + 1, rl.buckets.lock().unwrap().len());
+    }
+
+    #[test]
+    fn remove_least_recently_used() {
+        let rl = RateLimiter::new(NonZeroU32::new(1).unwrap());
+        let source = url("http://example.com");
+        rl.check(&source).unwrap();
+        for i in 1..MAX_BUCKETS {
+            rl.check(&url(&format!("http://{}.example.com", i)))
+                .unwrap();
+        }
+        // No buckets are idle, so the oldest is removed, and its limit is reset.
+        rl.check(&url("http://example.org")).unwrap();
+        assert_eq!(MAX_BUCKETS, rl.buckets.lock().unwrap().len());
+        rl.check(&source).unwrap();
+    }
+}
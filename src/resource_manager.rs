@@ -1,23 +1,264 @@
 //! Resource management
 
 use crate::error::ActiveStorageError;
+use crate::metrics;
 
-use tokio::sync::{Semaphore, SemaphorePermit};
+use prometheus::IntGauge;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Priority of a request when acquiring resources.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Priority {
+    /// Low priority, e.g. for batch requests that should not delay interactive requests.
+    Low,
+    /// High priority. This is the default.
+    #[default]
+    High,
+}
+
+tokio::task_local! {
+    /// Priority of the request being processed by the current task.
+    static PRIORITY: Priority;
+}
+
+/// Runs a future with a request priority, which is used for any resources acquired from a
+/// [crate::resource_manager::ResourceManager] by the future.
+pub async fn with_priority<F: Future>(priority: Priority, future: F) -> F::Output {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Returns the priority of the current task, or the default if not set.
+fn current_priority() -> Priority {
+    PRIORITY.try_with(|priority| *priority).unwrap_or_default()
+}
+
+/// A counting semaphore that grants permits to waiters in order of priority.
+///
+/// Waiters with the same priority are served in the order in which they started waiting, as for a
+/// Tokio Semaphore. A waiter is only granted permits once all waiters ahead of it have been, so
+/// that large requests are not starved by small ones.
+struct PrioritySemaphore {
+    /// State shared between acquirers and permits.
+    state: Mutex<SemaphoreState>,
+}
+
+/// State of a [PrioritySemaphore].
+struct SemaphoreState {
+    /// Number of permits available.
+    available: usize,
+
+    /// Sequence number of the next waiter.
+    next_sequence: u64,
+
+    /// Number of permits requested by each waiter, and a channel to notify it once they are
+    /// granted, ordered by decreasing priority and then by sequence number.
+    waiters: BTreeMap<(std::cmp::Reverse<Priority>, u64), (usize, oneshot::Sender<()>)>,
+}
+
+/// Permits acquired from a [PrioritySemaphore], which are released when dropped.
+struct PriorityPermit<'a> {
+    /// Semaphore from which the permits were acquired.
+    semaphore: &'a PrioritySemaphore,
+
+    /// Number of permits held.
+    n: usize,
+}
+
+impl Drop for PriorityPermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.n);
+    }
+}
+
+/// Removes a waiter from a [PrioritySemaphore] if the acquiring future is dropped before the
+/// permits are granted, e.g. due to a timeout, or releases them if granted.
+struct WaiterGuard<'a> {
+    /// Semaphore being waited on.
+    semaphore: &'a PrioritySemaphore,
+
+    /// Key of the waiter, or `None` once the permits have been received.
+    key: Option<(std::cmp::Reverse<Priority>, u64)>,
+
+    /// Number of permits requested.
+    n: usize,
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        let Some(key) = self.key else {
+            return;
+        };
+        let mut state = self.semaphore.state.lock().unwrap();
+        // If the waiter is no longer queued, the permits were granted but not received.
+        if state.waiters.remove(&key).is_none() {
+            state.available += self.n;
+        }
+        // Waiters behind this one may now be able to proceed.
+        state.notify_waiters();
+    }
+}
+
+impl SemaphoreState {
+    /// Grants permits to waiters in order while enough are available.
+    fn notify_waiters(&mut self) {
+        while let Some(entry) = self.waiters.first_entry() {
+            let (n, _) = entry.get();
+            if *n > self.available {
+                break;
+            }
+            let (n, sender) = entry.remove();
+            self.available -= n;
+            if sender.send(()).is_err() {
+                // The waiter has gone.
+                self.available += n;
+            }
+        }
+    }
+}
+
+impl PrioritySemaphore {
+    /// Returns a new PrioritySemaphore with a number of permits.
+    fn new(permits: usize) -> Self {
+        Self {
+            state: Mutex::new(SemaphoreState {
+                available: permits,
+                next_sequence: 0,
+                waiters: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Acquires `n` permits, waiting behind any waiters with the same or higher priority.
+    async fn acquire_many(&self, n: usize, priority: Priority) -> PriorityPermit<'_> {
+        let (receiver, mut guard) = {
+            let mut state = self.state.lock().unwrap();
+            let key = (std::cmp::Reverse(priority), state.next_sequence);
+            state.next_sequence += 1;
+            let (sender, receiver) = oneshot::channel();
+            state.waiters.insert(key, (n, sender));
+            state.notify_waiters();
+            let guard = WaiterGuard {
+                semaphore: self,
+                key: Some(key),
+                n,
+            };
+            (receiver, guard)
+        };
+        // The sender is only dropped once the permits have been granted.
+        let _ = receiver.await;
+        guard.key = None;
+        PriorityPermit { semaphore: self, n }
+    }
+
+    /// Acquires a permit if one is available and nobody is waiting, without waiting.
+    #[cfg(test)]
+    fn try_acquire(&self) -> Result<PriorityPermit<'_>, tokio::sync::TryAcquireError> {
+        let mut state = self.state.lock().unwrap();
+        if state.available == 0 || !state.waiters.is_empty() {
+            return Err(tokio::sync::TryAcquireError::NoPermits);
+        }
+        state.available -= 1;
+        Ok(PriorityPermit {
+            semaphore: self,
+            n: 1,
+        })
+    }
+
+    /// Releases `n` permits, granting them to any waiters.
+    fn release(&self, n: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.available += n;
+        state.notify_waiters();
+    }
+}
 
 /// [crate::resource_manager::ResourceManager] provides a simple way to allocate various resources
-/// to tasks. Resource management is performed using a Tokio Semaphore for each type of resource.
+/// to tasks. Resource management is performed using a semaphore for each type of resource, which
+/// grants permits in order of the priority of the requesting task (see [with_priority]), and then
+/// in the order in which they were requested.
 pub struct ResourceManager {
     /// Optional semaphore for S3 connections.
-    s3_connections: Option<Semaphore>,
+    s3_connections: Option<PrioritySemaphore>,
 
     /// Optional semaphore for memory (bytes).
-    memory: Option<Semaphore>,
+    memory: Option<PrioritySemaphore>,
 
     /// Optional total memory pool in bytes.
     total_memory: Option<usize>,
 
     /// Optional semaphore for tasks.
-    tasks: Option<Semaphore>,
+    tasks: Option<PrioritySemaphore>,
+
+    /// Optional maximum time to wait to acquire a resource.
+    acquire_timeout: Option<Duration>,
+
+    /// Gauge of S3 connection permits held.
+    s3_connections_gauge: IntGauge,
+
+    /// Gauge of memory permits held (bytes).
+    memory_gauge: IntGauge,
+
+    /// Gauge of task permits held.
+    tasks_gauge: IntGauge,
+}
+
+/// A permit for some resource acquired from a [crate::resource_manager::ResourceManager].
+///
+/// The resource is released when the permit is dropped. The associated gauge tracks the number of
+/// permits held.
+pub struct ResourcePermit<'a> {
+    /// Semaphore permit.
+    _permit: PriorityPermit<'a>,
+
+    /// Gauge of permits held.
+    gauge: &'a IntGauge,
+
+    /// Number of permits held.
+    n: i64,
+}
+
+impl<'a> ResourcePermit<'a> {
+    /// Returns a new ResourcePermit object, incrementing the gauge.
+    fn new(permit: PriorityPermit<'a>, gauge: &'a IntGauge, n: i64) -> Self {
+        gauge.add(n);
+        Self {
+            _permit: permit,
+            gauge,
+            n,
+        }
+    }
+}
+
+impl Drop for ResourcePermit<'_> {
+    /// Decrement the gauge when the permit is released.
+    fn drop(&mut self) {
+        self.gauge.sub(self.n);
+    }
+}
+
+/// Memory acquired from a [crate::resource_manager::ResourceManager] that is held independently
+/// of the request that acquired it, e.g. for data pinned by a session.
+///
+/// The memory is released when dropped.
+pub struct PinnedMemory {
+    /// Resource manager from which the memory was acquired.
+    resource_manager: Arc<ResourceManager>,
+
+    /// Number of bytes held.
+    bytes: usize,
+}
+
+impl Drop for PinnedMemory {
+    fn drop(&mut self) {
+        if let Some(memory) = &self.resource_manager.memory {
+            memory.release(self.bytes);
+        }
+        self.resource_manager.memory_gauge.sub(self.bytes as i64);
+    }
 }
 
 impl ResourceManager {
@@ -26,25 +267,31 @@ impl ResourceManager {
         s3_connection_limit: Option<usize>,
         memory_limit: Option<usize>,
         task_limit: Option<usize>,
+        acquire_timeout: Option<Duration>,
     ) -> Self {
         Self {
-            s3_connections: s3_connection_limit.map(Semaphore::new),
-            memory: memory_limit.map(Semaphore::new),
+            s3_connections: s3_connection_limit.map(PrioritySemaphore::new),
+            memory: memory_limit.map(PrioritySemaphore::new),
             total_memory: memory_limit,
-            tasks: task_limit.map(Semaphore::new),
+            tasks: task_limit.map(PrioritySemaphore::new),
+            acquire_timeout,
+            s3_connections_gauge: metrics::S3_CONNECTIONS_GAUGE.clone(),
+            memory_gauge: metrics::MEMORY_GAUGE.clone(),
+            tasks_gauge: metrics::TASKS_GAUGE.clone(),
         }
     }
 
     /// Acquire an S3 connection resource.
-    pub async fn s3_connection(&self) -> Result<Option<SemaphorePermit>, ActiveStorageError> {
-        optional_acquire(&self.s3_connections, 1).await
+    pub async fn s3_connection(&self) -> Result<Option<ResourcePermit<'_>>, ActiveStorageError> {
+        self.acquire(&self.s3_connections, &self.s3_connections_gauge, 1)
+            .await
     }
 
     /// Acquire memory resource.
     pub async fn memory(
         &self,
         bytes: usize,
-    ) -> Result<Option<SemaphorePermit>, ActiveStorageError> {
+    ) -> Result<Option<ResourcePermit<'_>>, ActiveStorageError> {
         if let Some(total_memory) = self.total_memory {
             if bytes > total_memory {
                 return Err(ActiveStorageError::InsufficientMemory {
@@ -53,26 +300,61 @@ impl ResourceManager {
                 });
             };
         };
-        optional_acquire(&self.memory, bytes).await
+        self.acquire(&self.memory, &self.memory_gauge, bytes).await
+    }
+
+    /// Converts a memory permit into [PinnedMemory], which holds the memory until it is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `permit`: Memory permit acquired from this resource manager
+    pub fn pin_memory(self: &Arc<Self>, permit: ResourcePermit<'_>) -> PinnedMemory {
+        let bytes = permit._permit.n;
+        // The permits and gauge are released when the pinned memory is dropped instead.
+        std::mem::forget(permit);
+        PinnedMemory {
+            resource_manager: self.clone(),
+            bytes,
+        }
     }
 
     /// Acquire a task resource.
-    pub async fn task(&self) -> Result<Option<SemaphorePermit>, ActiveStorageError> {
-        optional_acquire(&self.tasks, 1).await
+    pub async fn task(&self) -> Result<Option<ResourcePermit<'_>>, ActiveStorageError> {
+        self.acquire(&self.tasks, &self.tasks_gauge, 1).await
+    }
+
+    /// Acquire permits on an optional Semaphore, subject to the acquire timeout.
+    async fn acquire<'a>(
+        &self,
+        sem: &'a Option<PrioritySemaphore>,
+        gauge: &'a IntGauge,
+        n: usize,
+    ) -> Result<Option<ResourcePermit<'a>>, ActiveStorageError> {
+        if let Some(acquire_timeout) = self.acquire_timeout {
+            tokio::time::timeout(acquire_timeout, optional_acquire(sem, gauge, n))
+                .await
+                .map_err(|_| ActiveStorageError::ResourceAcquireTimeout {
+                    // Round up to a whole number of seconds, as required by Retry-After.
+                    retry_after: acquire_timeout.as_secs_f64().ceil().max(1.0) as u64,
+                })?
+        } else {
+            optional_acquire(sem, gauge, n).await
+        }
     }
 }
 
 /// Acquire permits on an optional Semaphore, if present.
-async fn optional_acquire(
-    sem: &Option<Semaphore>,
+///
+/// The gauge is updated while the permits are held.
+async fn optional_acquire<'a>(
+    sem: &'a Option<PrioritySemaphore>,
+    gauge: &'a IntGauge,
     n: usize,
-) -> Result<Option<SemaphorePermit>, ActiveStorageError> {
-    let n = n.try_into()?;
+) -> Result<Option<ResourcePermit<'a>>, ActiveStorageError> {
+    let gauge_n = n.try_into()?;
     if let Some(sem) = sem {
-        sem.acquire_many(n)
-            .await
-            .map(Some)
-            .map_err(|err| err.into())
+        let permit = sem.acquire_many(n, current_priority()).await;
+        Ok(Some(ResourcePermit::new(permit, gauge, gauge_n)))
     } else {
         Ok(None)
     }
@@ -86,7 +368,7 @@ mod tests {
 
     #[tokio::test]
     async fn no_resource_management() {
-        let rm = ResourceManager::new(None, None, None);
+        let rm = ResourceManager::new(None, None, None, None);
         assert!(rm.s3_connections.is_none());
         assert!(rm.memory.is_none());
         assert!(rm.tasks.is_none());
@@ -100,7 +382,7 @@ mod tests {
 
     #[tokio::test]
     async fn full_resource_management() {
-        let rm = ResourceManager::new(Some(1), Some(1), Some(1));
+        let rm = ResourceManager::new(Some(1), Some(1), Some(1), None);
         assert!(rm.s3_connections.is_some());
         assert!(rm.memory.is_some());
         assert!(rm.tasks.is_some());
@@ -124,4 +406,146 @@ mod tests {
             Some(TryAcquireError::NoPermits)
         );
     }
+
+    #[tokio::test]
+    async fn resource_gauges() {
+        let mut rm = ResourceManager::new(Some(2), Some(100), Some(2), None);
+        // Use unregistered gauges to avoid interference from other tests.
+        rm.s3_connections_gauge = IntGauge::new("s3_connections", "test").unwrap();
+        rm.memory_gauge = IntGauge::new("memory", "test").unwrap();
+        rm.tasks_gauge = IntGauge::new("tasks", "test").unwrap();
+        {
+            let _c = rm.s3_connection().await.unwrap();
+            let _m1 = rm.memory(10).await.unwrap();
+            let _m2 = rm.memory(32).await.unwrap();
+            let _t = rm.task().await.unwrap();
+            assert_eq!(1, rm.s3_connections_gauge.get());
+            assert_eq!(42, rm.memory_gauge.get());
+            assert_eq!(1, rm.tasks_gauge.get());
+            drop(_m1);
+            assert_eq!(32, rm.memory_gauge.get());
+        }
+        assert_eq!(0, rm.s3_connections_gauge.get());
+        assert_eq!(0, rm.memory_gauge.get());
+        assert_eq!(0, rm.tasks_gauge.get());
+    }
+
+    #[tokio::test]
+    async fn pin_memory() {
+        let mut rm = ResourceManager::new(None, Some(100), None, None);
+        rm.memory_gauge = IntGauge::new("memory", "test").unwrap();
+        let rm = Arc::new(rm);
+        let permit = rm.memory(60).await.unwrap().unwrap();
+        let pinned = rm.pin_memory(permit);
+        // The memory is held until the pinned memory is dropped.
+        assert_eq!(60, rm.memory_gauge.get());
+        assert_eq!(
+            40,
+            rm.memory.as_ref().unwrap().state.lock().unwrap().available
+        );
+        drop(pinned);
+        assert_eq!(0, rm.memory_gauge.get());
+        assert_eq!(
+            100,
+            rm.memory.as_ref().unwrap().state.lock().unwrap().available
+        );
+    }
+
+    #[tokio::test]
+    async fn resource_gauges_no_resource_management() {
+        let mut rm = ResourceManager::new(None, None, None, None);
+        rm.memory_gauge = IntGauge::new("memory", "test").unwrap();
+        let _m = rm.memory(10).await.unwrap();
+        assert_eq!(0, rm.memory_gauge.get());
+    }
+
+    #[tokio::test]
+    async fn priority_default() {
+        assert_eq!(Priority::High, current_priority());
+        with_priority(Priority::Low, async {
+            assert_eq!(Priority::Low, current_priority());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn high_priority_acquires_first() {
+        let rm = ResourceManager::new(Some(1), None, None, None);
+        let held = rm.s3_connection().await.unwrap();
+        let order = Mutex::new(Vec::new());
+        let acquire = |name: &'static str, priority: Priority| {
+            let (rm, order) = (&rm, &order);
+            with_priority(priority, async move {
+                let _permit = rm.s3_connection().await.unwrap();
+                order.lock().unwrap().push(name);
+                // Hold the permit briefly so that the next waiter must wait for its release.
+                tokio::task::yield_now().await;
+            })
+        };
+        let low1 = acquire("low1", Priority::Low);
+        let low2 = acquire("low2", Priority::Low);
+        let high = acquire("high", Priority::High);
+        let release = async {
+            // Let the low priority requests queue before the high priority one.
+            tokio::task::yield_now().await;
+            tokio::task::yield_now().await;
+            drop(held);
+        };
+        // Futures are polled in order, so low1 and low2 start waiting before high.
+        tokio::join!(low1, low2, high, release);
+        assert_eq!(vec!["high", "low1", "low2"], *order.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn large_request_not_starved() {
+        let rm = ResourceManager::new(None, Some(10), None, None);
+        let held = rm.memory(5).await.unwrap();
+        let order = Mutex::new(Vec::new());
+        let acquire = |name: &'static str, bytes: usize| {
+            let (rm, order) = (&rm, &order);
+            async move {
+                let _permit = rm.memory(bytes).await.unwrap();
+                order.lock().unwrap().push(name);
+            }
+        };
+        // The small request could proceed immediately, but waits behind the large one.
+        let large = acquire("large", 10);
+        let small = acquire("small", 1);
+        let release = async {
+            tokio::task::yield_now().await;
+            drop(held);
+        };
+        tokio::join!(large, small, release);
+        assert_eq!(vec!["large", "small"], *order.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiter_removed() {
+        let rm = ResourceManager::new(None, Some(10), None, Some(Duration::from_millis(10)));
+        let held = rm.memory(5).await.unwrap();
+        // A timed out waiter does not block later requests.
+        assert!(rm.memory(10).await.is_err());
+        assert!(rm.memory(5).await.unwrap().is_some());
+        drop(held);
+        assert!(rm.memory(10).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn acquire_timeout() {
+        let rm = ResourceManager::new(Some(1), Some(10), Some(1), Some(Duration::from_millis(10)));
+        let _m = rm.memory(10).await.unwrap();
+        match rm.memory(1).await {
+            Err(ActiveStorageError::ResourceAcquireTimeout { retry_after }) => {
+                assert_eq!(1, retry_after)
+            }
+            _ => panic!("expected resource acquire timeout"),
+        }
+        let _c = rm.s3_connection().await.unwrap();
+        assert!(rm.s3_connection().await.is_err());
+        let _t = rm.task().await.unwrap();
+        assert!(rm.task().await.is_err());
+        // Resources become available once released.
+        drop(_m);
+        assert!(rm.memory(1).await.unwrap().is_some());
+    }
 }
@@ -1,16 +1,33 @@
-//! A simplified S3 client that supports downloading objects.
+//! A simplified S3 client that supports downloading objects and retrieving their metadata.
 //! It attempts to hide the complexities of working with the AWS SDK for S3.
 
 use crate::error::ActiveStorageError;
-use crate::resource_manager::ResourceManager;
+use crate::models::ObjectInfo;
+use crate::resource_manager::{ResourceManager, ResourcePermit};
+use crate::source_filter::PublicResolver;
 
 use aws_credential_types::Credentials;
-use aws_sdk_s3::config::BehaviorVersion;
+use aws_sdk_s3::config::interceptors::BeforeTransmitInterceptorContextMut;
+use aws_sdk_s3::config::{BehaviorVersion, ConfigBag, Intercept, RuntimeComponents};
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::primitives::DateTimeFormat;
+use aws_sdk_s3::types::ChecksumMode;
 use aws_sdk_s3::Client;
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
 use aws_types::region::Region;
 use axum::body::Bytes;
 use hashbrown::HashMap;
-use tokio::sync::{RwLock, SemaphorePermit};
+use hyper::client::HttpConnector;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use std::io::{self, BufReader};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
 use tracing::Instrument;
 use url::Url;
 
@@ -33,65 +50,280 @@ impl S3Credentials {
     }
 }
 
+/// Key of an [crate::s3_client::S3ClientMap]: the S3 URL, region and credentials.
+type S3ClientKey = (Url, String, S3Credentials);
+
+/// An entry in an [crate::s3_client::S3ClientMap].
+struct S3ClientEntry {
+    /// The client object.
+    client: S3Client,
+    /// Value of the map's clock when the client was last used.
+    last_used: AtomicU64,
+}
+
 /// A map containing initialised S3Client objects.
 ///
 /// The [aws_sdk_s3::Client] object is relatively expensive to create, so we reuse them where
 /// possible. This type provides a map for storing the clients objects.
 ///
-/// The map's key is a 2-tuple of the S3 URL and credentials.
+/// The map's key is a 3-tuple of the S3 URL, region and credentials.
 /// The value is the corresponding client object.
+///
+/// The number of clients is limited, and the least recently used client is evicted when a new
+/// client would exceed the limit. Clients that have been evicted remain usable by requests that
+/// already hold them.
 pub struct S3ClientMap {
     /// A [hashbrown::HashMap] for storing the S3 clients. A read-write lock synchronises access to
     /// the map, optimised for reads.
-    map: RwLock<HashMap<(Url, S3Credentials), S3Client>>,
+    map: RwLock<HashMap<S3ClientKey, S3ClientEntry>>,
+    /// Maximum number of clients in the map.
+    capacity: NonZeroUsize,
+    /// Counter incremented each time a client is used, to order clients by recency of use without
+    /// requiring a write lock.
+    clock: AtomicU64,
+    /// HTTP client used by all S3 clients, or `None` to use the AWS SDK's default.
+    http_client: Option<SharedHttpClient>,
+    /// Whether S3 clients verify downloaded data against object checksums.
+    verify_checksums: bool,
+    /// Endpoint that S3 clients connect to instead of the source, if any.
+    endpoint_override: Option<Url>,
 }
 
-// FIXME: Currently clients are never removed from the map. If a large number of endpoints or
-// credentials are used this will cause the map to grow indefinitely with a large number of
-// clients. An ageing mechanism should be implemented
 impl S3ClientMap {
     /// Create and return an [crate::s3_client::S3ClientMap].
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: Maximum number of clients in the map
+    /// * `http_client`: HTTP client used by all S3 clients, or `None` to use the AWS SDK's default
+    /// * `verify_checksums`: Whether S3 clients verify downloaded data against object checksums
+    /// * `endpoint_override`: Optional endpoint that S3 clients connect to instead of the source
+    pub fn new(
+        capacity: NonZeroUsize,
+        http_client: Option<SharedHttpClient>,
+        verify_checksums: bool,
+        endpoint_override: Option<Url>,
+    ) -> Self {
         S3ClientMap {
             map: RwLock::new(HashMap::new()),
+            capacity,
+            clock: AtomicU64::new(0),
+            http_client,
+            verify_checksums,
+            endpoint_override,
         }
     }
 
+    /// Returns the next value of the map's clock.
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
     /// Get or create an [crate::s3_client::S3Client] object from the map.
     ///
     /// # Arguments
     ///
     /// * `url`: Object storage API URL
+    /// * `region`: Object storage region used to sign requests
     /// * `credentials`: Object storage account credentials
-    pub async fn get(&self, url: &Url, credentials: S3Credentials) -> S3Client {
-        let key = (url.clone(), credentials.clone());
+    pub async fn get(&self, url: &Url, region: &str, credentials: S3Credentials) -> S3Client {
+        let key = (url.clone(), region.to_string(), credentials.clone());
         // Common case: return an existing client from the map.
         {
             let map = self.map.read().await;
-            if let Some(client) = map.get(&key) {
-                return client.clone();
+            if let Some(entry) = map.get(&key) {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                return entry.client.clone();
             }
         }
         // Less common case: create a new client, insert it into the map and return it.
         let mut map = self.map.write().await;
         // Allow for a possible race here since we dropped the read lock.
-        if let Some(client) = map.get(&key) {
-            client.clone()
+        if let Some(entry) = map.get(&key) {
+            entry.last_used.store(self.tick(), Ordering::Relaxed);
+            entry.client.clone()
         } else {
-            tracing::info!("Creating new S3 client for {}", url);
-            let client = S3Client::new(url, credentials).await;
-            let (_, client) = map.insert_unique_unchecked(key, client);
-            client.clone()
+            if map.len() >= self.capacity.get() {
+                Self::evict_least_recently_used(&mut map);
+            }
+            tracing::info!("Creating new S3 client for {} in region {}", url, region);
+            let client = S3Client::new(url, region, credentials, self.http_client.as_ref())
+                .await
+                .verify_checksums(self.verify_checksums)
+                .endpoint_override(self.endpoint_override.as_ref());
+            let entry = S3ClientEntry {
+                client,
+                last_used: AtomicU64::new(self.tick()),
+            };
+            let (_, entry) = map.insert_unique_unchecked(key, entry);
+            entry.client.clone()
+        }
+    }
+
+    /// Remove the least recently used client from the map.
+    fn evict_least_recently_used(map: &mut HashMap<S3ClientKey, S3ClientEntry>) {
+        let oldest = map
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            tracing::info!("Evicting S3 client for {} in region {}", key.0, key.1);
+            map.remove(&key);
+        }
+    }
+}
+
+/// Returns an HTTP client for S3 clients with customised TLS certificate verification,
+/// connection pool settings or DNS resolution, or `None` if no customisation is required.
+///
+/// # Arguments
+///
+/// * `ca_bundle`: Optional path to a PEM file of CA certificates to trust in addition to the
+///   system's CA certificates
+/// * `insecure_skip_verify`: Whether to skip verification of server certificates
+/// * `max_idle_per_host`: Optional maximum number of idle connections to each host kept open
+///   for reuse
+/// * `idle_timeout`: Optional time after which idle connections are closed
+/// * `deny_private_sources`: Whether to refuse to connect to hosts that resolve to private IP
+///   addresses
+pub fn http_client(
+    ca_bundle: Option<&Path>,
+    insecure_skip_verify: bool,
+    max_idle_per_host: Option<usize>,
+    idle_timeout: Option<Duration>,
+    deny_private_sources: bool,
+) -> io::Result<Option<SharedHttpClient>> {
+    if ca_bundle.is_none()
+        && !insecure_skip_verify
+        && max_idle_per_host.is_none()
+        && idle_timeout.is_none()
+        && !deny_private_sources
+    {
+        return Ok(None);
+    }
+    let mut hyper_builder = hyper::Client::builder();
+    if let Some(max_idle_per_host) = max_idle_per_host {
+        hyper_builder.pool_max_idle_per_host(max_idle_per_host);
+    }
+    if let Some(idle_timeout) = idle_timeout {
+        hyper_builder.pool_idle_timeout(idle_timeout);
+    }
+    let builder = HyperClientBuilder::new().hyper_builder(hyper_builder);
+    if ca_bundle.is_none() && !insecure_skip_verify && !deny_private_sources {
+        return Ok(Some(builder.build_https()));
+    }
+    let mut roots = rustls::RootCertStore::empty();
+    let native_certs: Vec<Vec<u8>> = rustls_native_certs::load_native_certs()?
+        .into_iter()
+        .map(|cert| cert.0)
+        .collect();
+    roots.add_parsable_certificates(&native_certs);
+    if let Some(ca_bundle) = ca_bundle {
+        let mut reader = BufReader::new(std::fs::File::open(ca_bundle)?);
+        let (valid, _) = roots.add_parsable_certificates(&rustls_pemfile::certs(&mut reader)?);
+        if valid == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no valid CA certificates in {}", ca_bundle.display()),
+            ));
+        }
+    }
+    let mut config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    if insecure_skip_verify {
+        tracing::warn!(
+            "TLS certificate verification is DISABLED for S3 connections. Connections to object \
+            stores are vulnerable to interception. Do not use this in production."
+        );
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(InsecureServerCertVerifier));
+    }
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(config)
+        .https_or_http()
+        .enable_http1()
+        .enable_http2();
+    if deny_private_sources {
+        let mut http = HttpConnector::new_with_resolver(PublicResolver);
+        http.enforce_http(false);
+        Ok(Some(builder.build(connector.wrap_connector(http))))
+    } else {
+        Ok(Some(builder.build(connector.build())))
+    }
+}
+
+/// A server certificate verifier that accepts any certificate.
+struct InsecureServerCertVerifier;
+
+impl ServerCertVerifier for InsecureServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// An interceptor that sends signed requests to a different endpoint.
+///
+/// Requests are modified after signing, so the signature remains valid for the original host,
+/// which is sent in the `Host` header.
+#[derive(Debug)]
+struct EndpointOverride {
+    /// Endpoint to connect to. Only the scheme, host and port are used.
+    endpoint: Url,
+}
+
+impl Intercept for EndpointOverride {
+    fn name(&self) -> &'static str {
+        "EndpointOverride"
+    }
+
+    fn modify_before_transmit(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let request = context.request_mut();
+        let uri: http::Uri = request.uri().parse()?;
+        let authority = uri
+            .authority()
+            .ok_or("request URI has no host")?
+            .to_string();
+        let path_and_query = uri.path_and_query().map_or("/", |pq| pq.as_str());
+        let endpoint = &self.endpoint[..url::Position::BeforePath];
+        request.set_uri(format!("{}{}", endpoint, path_and_query))?;
+        if !request.headers().contains_key("host") {
+            request.headers_mut().insert("host", authority);
         }
+        Ok(())
     }
 }
 
+/// Object data downloaded from object storage.
+pub struct ObjectData {
+    /// Object data
+    pub data: Bytes,
+    /// Entity tag of the object, if reported
+    pub etag: Option<String>,
+}
+
 /// S3 client object.
 #[derive(Clone)]
 pub struct S3Client {
     /// Underlying AWS SDK S3 client object.
     client: Client,
+    /// Whether to verify downloaded data against object checksums.
+    verify_checksums: bool,
 }
 
 impl S3Client {
@@ -100,10 +332,20 @@ impl S3Client {
     /// # Arguments
     ///
     /// * `url`: Object storage API URL
+    /// * `region`: Object storage region used to sign requests
     /// * `credentials`: Object storage account credentials
-    pub async fn new(url: &Url, credentials: S3Credentials) -> Self {
-        let region = Region::new("us-east-1");
-        let builder = aws_sdk_s3::Config::builder().behavior_version(BehaviorVersion::latest());
+    /// * `http_client`: HTTP client to use, or `None` to use the AWS SDK's default
+    pub async fn new(
+        url: &Url,
+        region: &str,
+        credentials: S3Credentials,
+        http_client: Option<&SharedHttpClient>,
+    ) -> Self {
+        let region = Region::new(region.to_string());
+        let mut builder = aws_sdk_s3::Config::builder().behavior_version(BehaviorVersion::latest());
+        if let Some(http_client) = http_client {
+            builder = builder.http_client(http_client.clone());
+        }
         let builder = match credentials {
             S3Credentials::AccessKey {
                 access_key,
@@ -120,7 +362,44 @@ impl S3Client {
             .force_path_style(true)
             .build();
         let client = Client::from_conf(s3_config);
-        Self { client }
+        Self {
+            client,
+            verify_checksums: false,
+        }
+    }
+
+    /// Sets whether to verify downloaded data against object checksums, and returns the client.
+    ///
+    /// If enabled, the object's checksum is requested with each download and verified by the AWS
+    /// SDK as the data is read. Object stores only return checksums for objects stored with one,
+    /// and not for byte ranges, so other downloads are not verified.
+    pub fn verify_checksums(mut self, verify_checksums: bool) -> Self {
+        self.verify_checksums = verify_checksums;
+        self
+    }
+
+    /// Sets an endpoint to connect to instead of the source, and returns the client.
+    ///
+    /// Requests are still signed for the source, and sent with its host in the `Host` header, so
+    /// that the endpoint may be a transparent proxy or cache in front of the object store.
+    pub fn endpoint_override(mut self, endpoint: Option<&Url>) -> Self {
+        if let Some(endpoint) = endpoint {
+            let config = self
+                .client
+                .config()
+                .to_builder()
+                .interceptor(EndpointOverride {
+                    endpoint: endpoint.clone(),
+                })
+                .build();
+            self.client = Client::from_conf(config);
+        }
+        self
+    }
+
+    /// Returns the checksum mode for GetObject requests.
+    fn checksum_mode(&self) -> Option<ChecksumMode> {
+        self.verify_checksums.then_some(ChecksumMode::Enabled)
     }
 
     /// Downloads an object from object storage and returns the data as Bytes
@@ -130,55 +409,253 @@ impl S3Client {
     /// * `bucket`: Name of the bucket
     /// * `key`: Name of the object in the bucket
     /// * `range`: Optional byte range
+    /// * `part_number`: Optional part number of a multipart object to download, in which case
+    ///   `range` is not requested but must match the byte range of the part
     /// * `resource_manager`: ResourceManager object
-    /// * `mem_permits`: Optional SemaphorePermit for any memory resources reserved
+    /// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
     pub async fn download_object<'a>(
         self: &S3Client,
         bucket: &str,
         key: &str,
         range: Option<String>,
+        part_number: Option<i32>,
         resource_manager: &'a ResourceManager,
-        mem_permits: &mut Option<SemaphorePermit<'a>>,
+        mem_permits: &mut Option<ResourcePermit<'a>>,
     ) -> Result<Bytes, ActiveStorageError> {
-        let mut response = self
+        let (range, part_range) = split_part_range(range, part_number);
+        let response = self
             .client
             .get_object()
             .bucket(bucket)
             .key(key)
             .set_range(range)
+            .set_part_number(part_number)
+            .set_checksum_mode(self.checksum_mode())
+            .send()
+            .instrument(tracing::Span::current())
+            .await?;
+        check_part_range(part_number, part_range, &response)?;
+        read_object(response, resource_manager, mem_permits).await
+    }
+
+    /// Downloads an object from object storage unless its ETag matches `etag`
+    ///
+    /// Returns `None` if the object has not been modified, otherwise the data and the object's
+    /// ETag.
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket`: Name of the bucket
+    /// * `key`: Name of the object in the bucket
+    /// * `range`: Optional byte range
+    /// * `part_number`: Optional part number of a multipart object to download, as for
+    ///   [S3Client::download_object]
+    /// * `etag`: ETag of a previously downloaded copy of the object
+    /// * `resource_manager`: ResourceManager object
+    /// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_object_if_none_match<'a>(
+        self: &S3Client,
+        bucket: &str,
+        key: &str,
+        range: Option<String>,
+        part_number: Option<i32>,
+        etag: Option<&str>,
+        resource_manager: &'a ResourceManager,
+        mem_permits: &mut Option<ResourcePermit<'a>>,
+    ) -> Result<Option<ObjectData>, ActiveStorageError> {
+        let (range, part_range) = split_part_range(range, part_number);
+        let result = self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .set_range(range)
+            .set_part_number(part_number)
+            .set_checksum_mode(self.checksum_mode())
+            .set_if_none_match(etag.map(str::to_string))
+            .send()
+            .instrument(tracing::Span::current())
+            .await;
+        let response = match result {
+            // The SDK treats a 304 Not Modified response as an error.
+            Err(sdk_error)
+                if sdk_error
+                    .raw_response()
+                    .is_some_and(|response| response.status().as_u16() == 304) =>
+            {
+                return Ok(None)
+            }
+            result => result?,
+        };
+        check_part_range(part_number, part_range, &response)?;
+        let etag = response.e_tag().map(str::to_string);
+        let data = read_object(response, resource_manager, mem_permits).await?;
+        Ok(Some(ObjectData { data, etag }))
+    }
+
+    /// Retrieves an object's metadata from object storage without downloading its data
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket`: Name of the bucket
+    /// * `key`: Name of the object in the bucket
+    pub async fn head_object(
+        self: &S3Client,
+        bucket: &str,
+        key: &str,
+    ) -> Result<ObjectInfo, ActiveStorageError> {
+        let response = self
+            .client
+            .head_object()
+            .bucket(bucket)
+            .key(key)
             .send()
             .instrument(tracing::Span::current())
             .await?;
         // Fail if the content length header is missing.
-        let content_length: usize = response
+        let content_length = response
             .content_length()
             .ok_or(ActiveStorageError::S3ContentLengthMissing)?
             .try_into()?;
+        let last_modified = response
+            .last_modified()
+            .and_then(|time| time.fmt(DateTimeFormat::DateTime).ok());
+        Ok(ObjectInfo {
+            content_length,
+            last_modified,
+            etag: response.e_tag().map(str::to_string),
+        })
+    }
+}
 
-        // FIXME: how to account for compressed data?
-        if mem_permits.is_none() {
-            *mem_permits = resource_manager.memory(content_length).await?;
-        };
-        // The data returned by the S3 client does not have any alignment guarantees. In order to
-        // reinterpret the data as an array of numbers with a higher alignment than 1, we need to
-        // return the data in Bytes object in which the underlying data has a higher alignment.
-        // For now we're hard-coding an alignment of 8 bytes, although this should depend on the
-        // data type, and potentially whether there are any SIMD requirements.
-        // Create an 8-byte aligned Vec<u8>.
-        let mut buf = maligned::align_first::<u8, maligned::A8>(content_length);
-
-        // Iterate over the streaming response, copying data into the aligned Vec<u8>.
-        while let Some(bytes) = response
-            .body
-            .try_next()
-            .instrument(tracing::Span::current())
-            .await?
-        {
-            buf.extend_from_slice(&bytes)
+/// Reads the data of a GetObject response into memory and returns it as Bytes
+///
+/// # Arguments
+///
+/// * `response`: GetObject response
+/// * `resource_manager`: ResourceManager object
+/// * `mem_permits`: Optional ResourcePermit for any memory resources reserved
+async fn read_object<'a>(
+    mut response: GetObjectOutput,
+    resource_manager: &'a ResourceManager,
+    mem_permits: &mut Option<ResourcePermit<'a>>,
+) -> Result<Bytes, ActiveStorageError> {
+    // Fail if the content length header is missing.
+    let content_length: usize = response
+        .content_length()
+        .ok_or(ActiveStorageError::S3ContentLengthMissing)?
+        .try_into()?;
+
+    // Memory may already be reserved for the request, including for any decompressed data.
+    if mem_permits.is_none() {
+        *mem_permits = resource_manager.memory(content_length).await?;
+    };
+    // The data returned by the S3 client does not have any alignment guarantees. In order to
+    // reinterpret the data as an array of numbers with a higher alignment than 1, we need to
+    // return the data in Bytes object in which the underlying data has a higher alignment.
+    // For now we're hard-coding an alignment of 8 bytes, although this should depend on the
+    // data type, and potentially whether there are any SIMD requirements.
+    // Create an 8-byte aligned Vec<u8>.
+    let mut buf = maligned::align_first::<u8, maligned::A8>(content_length);
+
+    // Iterate over the streaming response, copying data into the aligned Vec<u8>. If checksum
+    // verification is enabled, the AWS SDK fails the final read on a mismatch.
+    while let Some(bytes) = response
+        .body
+        .try_next()
+        .instrument(tracing::Span::current())
+        .await
+        .map_err(|error| {
+            if is_checksum_mismatch(&error) {
+                ActiveStorageError::ChecksumMismatch
+            } else if buf.len() < content_length {
+                // The connection was most likely closed before the body was complete.
+                tracing::warn!(%error, "error receiving object from S3 storage");
+                ActiveStorageError::ShortRead {
+                    expected: content_length,
+                    actual: buf.len(),
+                }
+            } else {
+                error.into()
+            }
+        })?
+    {
+        buf.extend_from_slice(&bytes)
+    }
+    check_length(content_length, buf.len())?;
+    // Return as Bytes.
+    Ok(buf.into())
+}
+
+/// Check that the length of the data received for an object matches its Content-Length.
+///
+/// Returns a [crate::error::ActiveStorageError::ShortRead] error if fewer bytes were received,
+/// so that truncated data is never interpreted as an array.
+///
+/// # Arguments
+///
+/// * `expected`: Content-Length of the response
+/// * `actual`: Number of bytes received
+pub fn check_length(expected: usize, actual: usize) -> Result<(), ActiveStorageError> {
+    if actual < expected {
+        return Err(ActiveStorageError::ShortRead { expected, actual });
+    }
+    Ok(())
+}
+
+/// Returns whether an error reading an object's data was caused by a checksum mismatch.
+fn is_checksum_mismatch(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if error.is::<aws_smithy_checksums::body::validate::Error>() {
+            return true;
         }
-        // Return as Bytes.
-        Ok(buf.into())
+        source = error.source();
+    }
+    false
+}
+
+/// Return the path-style endpoint URL and bucket of a virtual-hosted-style source URL.
+///
+/// A source is virtual-hosted-style if its host has at least three labels and the second is `s3`
+/// or starts with `s3-` or `s3.`, e.g. `https://my-bucket.s3.eu-west-2.amazonaws.com`, in which
+/// case the first label is the bucket and the remaining labels are the endpoint. Returns `None`
+/// for other sources, including those with an IP address host.
+///
+/// # Arguments
+///
+/// * `source`: Object storage API URL
+pub fn virtual_hosted_bucket(source: &Url) -> Option<(Url, String)> {
+    let Some(url::Host::Domain(domain)) = source.host() else {
+        return None;
+    };
+    let (bucket, endpoint) = domain.split_once('.')?;
+    if bucket.is_empty() || !endpoint.contains('.') {
+        return None;
     }
+    let (service, _) = endpoint.split_once('.')?;
+    if service != "s3" && !service.starts_with("s3-") {
+        return None;
+    }
+    let mut endpoint_url = source.clone();
+    endpoint_url.set_host(Some(endpoint)).ok()?;
+    Some((endpoint_url, bucket.to_string()))
+}
+
+/// Returns whether a source is a presigned URL, which carries a Signature Version 4
+/// (`X-Amz-Signature`) or Signature Version 2 (`Signature`) signature in its query string.
+///
+/// Presigned URLs are fetched directly without signing the request, since S3 rejects requests
+/// that use more than one authentication mechanism.
+///
+/// # Arguments
+///
+/// * `source`: Object URL
+pub fn is_presigned_url(source: &Url) -> bool {
+    source
+        .query_pairs()
+        .any(|(name, _)| name == "X-Amz-Signature" || name == "Signature")
 }
 
 /// Return an optional byte range string based on the offset and size.
@@ -203,9 +680,64 @@ pub fn get_range(offset: Option<usize>, size: Option<usize>) -> Option<String> {
     }
 }
 
+/// Returns the byte range to request and the byte range that a part must match.
+///
+/// S3 does not allow a byte range to be requested together with a part number, so if a part
+/// number is specified, the range is instead checked against that of the downloaded part.
+fn split_part_range(
+    range: Option<String>,
+    part_number: Option<i32>,
+) -> (Option<String>, Option<String>) {
+    match part_number {
+        Some(_) => (None, range),
+        None => (range, None),
+    }
+}
+
+/// Check that the byte range of a downloaded part matches the expected range, if any.
+///
+/// Returns a [crate::error::ActiveStorageError::PartRangeMismatch] error if not.
+///
+/// # Arguments
+///
+/// * `part_number`: Optional part number of the download
+/// * `expected`: Optional expected byte range of the part, as a `Range` header value
+/// * `response`: Response to the GetObject request
+fn check_part_range(
+    part_number: Option<i32>,
+    expected: Option<String>,
+    response: &GetObjectOutput,
+) -> Result<(), ActiveStorageError> {
+    let (Some(part_number), Some(expected)) = (part_number, expected) else {
+        return Ok(());
+    };
+    let expected = expected.trim_start_matches("bytes=");
+    // The Content-Range of a part has the form `bytes <start>-<end>/<object size>`. An object that
+    // was not uploaded using multipart upload has a single part containing the whole object.
+    let part_range = match response.content_range() {
+        Some(content_range) => content_range
+            .trim_start_matches("bytes ")
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        None => format!("0-{}", response.content_length().unwrap_or(0) - 1),
+    };
+    if part_range == expected {
+        Ok(())
+    } else {
+        Err(ActiveStorageError::PartRangeMismatch {
+            part_number,
+            part_range,
+            range: expected.to_string(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils;
     use url::Url;
 
     fn make_access_key() -> S3Credentials {
@@ -216,30 +748,395 @@ mod tests {
         S3Credentials::access_key("user2", "password")
     }
 
+    fn capacity(capacity: usize) -> NonZeroUsize {
+        NonZeroUsize::new(capacity).unwrap()
+    }
+
     #[tokio::test]
     async fn s3_client_map() {
         let url = Url::parse("http://example.com").unwrap();
-        let map = S3ClientMap::new();
-        map.get(&url, make_access_key()).await;
-        map.get(&url, make_access_key()).await;
+        let map = S3ClientMap::new(capacity(8), None, false, None);
+        map.get(&url, "us-east-1", make_access_key()).await;
+        map.get(&url, "us-east-1", make_access_key()).await;
         assert_eq!(map.map.read().await.len(), 1);
-        map.get(&url, make_alt_access_key()).await;
+        map.get(&url, "us-east-1", make_alt_access_key()).await;
         assert_eq!(map.map.read().await.len(), 2);
-        map.get(&url, S3Credentials::None).await;
-        map.get(&url, S3Credentials::None).await;
+        map.get(&url, "us-east-1", S3Credentials::None).await;
+        map.get(&url, "us-east-1", S3Credentials::None).await;
         assert_eq!(map.map.read().await.len(), 3);
+        map.get(&url, "eu-west-2", make_access_key()).await;
+        assert_eq!(map.map.read().await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn s3_client_map_eviction() {
+        let map = S3ClientMap::new(capacity(2), None, false, None);
+        let a = Url::parse("http://a.example.com").unwrap();
+        let b = Url::parse("http://b.example.com").unwrap();
+        let c = Url::parse("http://c.example.com").unwrap();
+        map.get(&a, "us-east-1", S3Credentials::None).await;
+        map.get(&b, "us-east-1", S3Credentials::None).await;
+        // Use a again, so that b is the least recently used client.
+        map.get(&a, "us-east-1", S3Credentials::None).await;
+        map.get(&c, "us-east-1", S3Credentials::None).await;
+        {
+            let map = map.map.read().await;
+            assert_eq!(map.len(), 2);
+            let urls: Vec<&Url> = map.keys().map(|key| &key.0).collect();
+            assert!(urls.contains(&&a));
+            assert!(!urls.contains(&&b));
+            assert!(urls.contains(&&c));
+        }
+        // Evicted clients are recreated when used again.
+        map.get(&b, "us-east-1", S3Credentials::None).await;
+        let map = map.map.read().await;
+        assert_eq!(map.len(), 2);
+        let urls: Vec<&Url> = map.keys().map(|key| &key.0).collect();
+        assert!(!urls.contains(&&a));
+        assert!(urls.contains(&&b));
+        assert!(urls.contains(&&c));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn s3_client_map_concurrent() {
+        let map = Arc::new(S3ClientMap::new(capacity(4), None, false, None));
+        let tasks: Vec<_> = (0..64)
+            .map(|i| {
+                let map = map.clone();
+                tokio::spawn(async move {
+                    let url = Url::parse(&format!("http://{}.example.com", i % 16)).unwrap();
+                    map.get(&url, "us-east-1", S3Credentials::None).await;
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(map.map.read().await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn download_object_signed() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4]);
+        s3.set_credentials("user", "password");
+        let client = S3Client::new(&s3.url, "us-east-1", make_access_key(), None).await;
+        let rm = ResourceManager::new(None, None, None, None);
+        let data = client
+            .download_object("bar", "baz", None, None, &rm, &mut None)
+            .await
+            .unwrap();
+        assert_eq!(&[1, 2, 3, 4][..], data);
+        // The mock server checks the signature.
+        let client = S3Client::new(
+            &s3.url,
+            "us-east-1",
+            S3Credentials::access_key("user", "wrong"),
+            None,
+        )
+        .await;
+        assert!(client
+            .download_object("bar", "baz", None, None, &rm, &mut None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn download_object_pool_settings() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4]);
+        // A pool smaller than the number of concurrent requests still serves every request.
+        let http_client = http_client(None, false, Some(1), Some(Duration::from_secs(1)), false)
+            .unwrap()
+            .unwrap();
+        let client =
+            S3Client::new(&s3.url, "us-east-1", make_access_key(), Some(&http_client)).await;
+        let rm = ResourceManager::new(None, None, None, None);
+        let downloads = (0..8).map(|_| async {
+            client
+                .download_object("bar", "baz", None, None, &rm, &mut None)
+                .await
+        });
+        for data in futures::future::join_all(downloads).await {
+            assert_eq!(&[1, 2, 3, 4][..], data.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn download_object_deny_private() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4]);
+        let http_client = http_client(None, false, None, None, true).unwrap().unwrap();
+        let rm = ResourceManager::new(None, None, None, None);
+        // IP addresses are checked by the source filter rather than when connecting.
+        let client =
+            S3Client::new(&s3.url, "us-east-1", make_access_key(), Some(&http_client)).await;
+        let data = client
+            .download_object("bar", "baz", None, None, &rm, &mut None)
+            .await
+            .unwrap();
+        assert_eq!(&[1, 2, 3, 4][..], data);
+        // Hosts that resolve to private addresses are refused when connecting.
+        let mut url = s3.url.clone();
+        url.set_host(Some("localhost")).unwrap();
+        let client = S3Client::new(&url, "us-east-1", make_access_key(), Some(&http_client)).await;
+        assert!(client
+            .download_object("bar", "baz", None, None, &rm, &mut None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn download_object_endpoint_override() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![1, 2, 3, 4]);
+        s3.set_credentials("user", "password");
+        // The source host cannot be resolved, so the request must be sent to the endpoint, and
+        // the signature is only valid if it is for the source host sent in the Host header.
+        let source = Url::parse("http://s3.invalid:9000").unwrap();
+        let client = S3Client::new(&source, "us-east-1", make_access_key(), None)
+            .await
+            .endpoint_override(Some(&s3.url));
+        let rm = ResourceManager::new(None, None, None, None);
+        let data = client
+            .download_object(
+                "bar",
+                "baz",
+                get_range(Some(1), Some(2)),
+                None,
+                &rm,
+                &mut None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(&[2, 3][..], data);
+        let info = client.head_object("bar", "baz").await.unwrap();
+        assert_eq!(4, info.content_length);
+        // Without the override, the source host is used.
+        let client = S3Client::new(&source, "us-east-1", make_access_key(), None)
+            .await
+            .endpoint_override(None);
+        assert!(client
+            .download_object("bar", "baz", None, None, &rm, &mut None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn download_object_short_read() {
+        let s3 = test_utils::MockS3::start().await;
+        s3.put("bar", "baz", vec![0; 1024]);
+        s3.set_truncated("bar", "baz", 100);
+        let client = S3Client::new(&s3.url, "us-east-1", make_access_key(), None).await;
+        let rm = ResourceManager::new(None, None, None, None);
+        for range in [None, get_range(Some(8), Some(512))] {
+            let result = client
+                .download_object("bar", "baz", range.clone(), None, &rm, &mut None)
+                .await;
+            match result {
+                Err(ActiveStorageError::ShortRead { expected, actual }) => {
+                    assert_eq!(if range.is_some() { 512 } else { 1024 }, expected);
+                    assert_eq!(100, actual);
+                }
+                _ => panic!("expected short read error"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn download_object_part_number() {
+        let s3 = test_utils::MockS3::start().await;
+        let part_size = test_utils::MOCK_S3_MIN_PART_SIZE;
+        let (first, second) = (vec![1; part_size], vec![2; part_size]);
+        s3.put_multipart("bar", "baz", &[&first, &second, &[3; 4]]);
+        s3.put("bar", "single", vec![4; 8]);
+        let client = S3Client::new(&s3.url, "us-east-1", make_access_key(), None).await;
+        let rm = ResourceManager::new(None, None, None, None);
+        for range in [None, get_range(Some(part_size), Some(part_size))] {
+            let data = client
+                .download_object("bar", "baz", range, Some(2), &rm, &mut None)
+                .await
+                .unwrap();
+            assert_eq!(second, data);
+        }
+        let data = client
+            .download_object_if_none_match("bar", "baz", None, Some(3), None, &rm, &mut None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![3; 4], data.data);
+        // An object that was not uploaded using multipart upload has a single part.
+        let range = get_range(Some(0), Some(8));
+        let data = client
+            .download_object("bar", "single", range, Some(1), &rm, &mut None)
+            .await
+            .unwrap();
+        assert_eq!(vec![4; 8], data);
+    }
+
+    #[tokio::test]
+    async fn download_object_part_range_mismatch() {
+        let s3 = test_utils::MockS3::start().await;
+        let part_size = test_utils::MOCK_S3_MIN_PART_SIZE;
+        s3.put_multipart("bar", "baz", &[&vec![1; part_size], &[2; 8]]);
+        let client = S3Client::new(&s3.url, "us-east-1", make_access_key(), None).await;
+        let rm = ResourceManager::new(None, None, None, None);
+        let range = get_range(Some(part_size - 8), Some(16));
+        let result = client
+            .download_object("bar", "baz", range, Some(2), &rm, &mut None)
+            .await;
+        match result {
+            Err(ActiveStorageError::PartRangeMismatch {
+                part_number,
+                part_range,
+                range,
+            }) => {
+                assert_eq!(2, part_number);
+                assert_eq!(format!("{}-{}", part_size, part_size + 7), part_range);
+                assert_eq!(format!("{}-{}", part_size - 8, part_size + 7), range);
+            }
+            _ => panic!("expected part range mismatch error"),
+        }
+    }
+
+    #[test]
+    fn check_length_short() {
+        check_length(8, 8).unwrap();
+        match check_length(8, 5) {
+            Err(ActiveStorageError::ShortRead { expected, actual }) => {
+                assert_eq!((8, 5), (expected, actual))
+            }
+            _ => panic!("expected short read error"),
+        }
     }
 
     #[tokio::test]
     async fn new() {
         let url = Url::parse("http://example.com").unwrap();
-        S3Client::new(&url, make_access_key()).await;
+        S3Client::new(&url, "us-east-1", make_access_key(), None).await;
     }
 
     #[tokio::test]
     async fn new_no_auth() {
         let url = Url::parse("http://example.com").unwrap();
-        S3Client::new(&url, S3Credentials::None).await;
+        S3Client::new(&url, "us-east-1", S3Credentials::None, None).await;
+    }
+
+    #[test]
+    fn virtual_hosted_bucket_aws() {
+        for (source, endpoint) in [
+            (
+                "https://my-bucket.s3.amazonaws.com",
+                "https://s3.amazonaws.com/",
+            ),
+            (
+                "https://my-bucket.s3.eu-west-2.amazonaws.com",
+                "https://s3.eu-west-2.amazonaws.com/",
+            ),
+            (
+                "https://my-bucket.s3-eu-west-2.amazonaws.com:443/",
+                "https://s3-eu-west-2.amazonaws.com/",
+            ),
+            (
+                "http://my-bucket.s3.example.com:9000",
+                "http://s3.example.com:9000/",
+            ),
+        ] {
+            let (endpoint_url, bucket) =
+                virtual_hosted_bucket(&Url::parse(source).unwrap()).unwrap();
+            assert_eq!(endpoint, endpoint_url.as_str());
+            assert_eq!("my-bucket", bucket);
+        }
+    }
+
+    #[test]
+    fn virtual_hosted_bucket_path_style() {
+        for source in [
+            "https://s3.amazonaws.com",
+            "https://s3.eu-west-2.amazonaws.com",
+            "https://object-store.example.com",
+            "https://my-bucket.s3",
+            "https://my-bucket.s3beta.example.com",
+            "http://localhost:9000",
+            "http://127.0.0.1:9000",
+        ] {
+            assert_eq!(None, virtual_hosted_bucket(&Url::parse(source).unwrap()));
+        }
+    }
+
+    #[test]
+    fn is_presigned_url_signatures() {
+        for source in [
+            "https://s3.example.com/bar/baz?X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Signature=abc",
+            "https://bar.s3.amazonaws.com/baz?AWSAccessKeyId=foo&Expires=1&Signature=abc",
+        ] {
+            assert!(is_presigned_url(&Url::parse(source).unwrap()));
+        }
+        for source in [
+            "https://s3.example.com",
+            "https://s3.example.com/bar/baz?versionId=1",
+            "https://s3.example.com/bar/baz?x-amz-signature=abc",
+        ] {
+            assert!(!is_presigned_url(&Url::parse(source).unwrap()));
+        }
+    }
+
+    #[test]
+    fn tls_http_client_default() {
+        assert!(http_client(None, false, None, None, false)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn tls_http_client_ca_bundle() {
+        let ca_bundle = Path::new(crate::test_utils::TEST_CA_BUNDLE);
+        assert!(http_client(Some(ca_bundle), false, None, None, false)
+            .unwrap()
+            .is_some());
+        assert!(http_client(Some(ca_bundle), true, None, None, false)
+            .unwrap()
+            .is_some());
+        assert!(http_client(None, true, None, None, false)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn http_client_pool_settings() {
+        assert!(http_client(None, false, Some(4), None, false)
+            .unwrap()
+            .is_some());
+        let idle_timeout = Some(Duration::from_secs(30));
+        assert!(http_client(None, false, None, idle_timeout, false)
+            .unwrap()
+            .is_some());
+        let ca_bundle = Path::new(crate::test_utils::TEST_CA_BUNDLE);
+        assert!(
+            http_client(Some(ca_bundle), false, Some(4), idle_timeout, false)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn tls_http_client_invalid_ca_bundle() {
+        let missing = Path::new("/nonexistent/ca.pem");
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            http_client(Some(missing), false, None, None, false)
+                .err()
+                .unwrap()
+                .kind()
+        );
+        let not_pem = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            http_client(Some(not_pem), false, None, None, false)
+                .err()
+                .unwrap()
+                .kind()
+        );
     }
 
     #[test]
@@ -7,8 +7,26 @@ use std::{net::SocketAddr, process::exit, str::FromStr, time::Duration};
 use axum::ServiceExt;
 use axum_server::{tls_rustls::RustlsConfig, Handle};
 use expanduser::expanduser;
+use tokio::runtime::{self, Runtime};
 use tokio::signal;
 
+/// Returns a multi-threaded Tokio runtime sized according to the command line arguments
+///
+/// # Arguments
+///
+/// * `args`: Command line arguments
+pub fn runtime(args: &cli::CommandLineArgs) -> std::io::Result<Runtime> {
+    let mut builder = runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(worker_threads) = args.worker_threads {
+        builder.worker_threads(worker_threads.get());
+    }
+    if let Some(max_blocking_threads) = args.max_blocking_threads {
+        builder.max_blocking_threads(max_blocking_threads.get());
+    }
+    builder.build()
+}
+
 /// Serve the Reductionist service
 ///
 /// # Arguments
@@ -101,3 +119,31 @@ async fn shutdown_signal(handle: Handle, timeout: u64) {
     // Force shutdown if graceful shutdown takes longer than 10s
     handle.graceful_shutdown(Some(Duration::from_secs(timeout)));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn runtime_default() {
+        // The number of workers is left to Tokio, which depends on the host.
+        let runtime = runtime(&test_utils::get_test_args(&[])).unwrap();
+        assert!(runtime.metrics().num_workers() >= 1);
+        assert_eq!(4, runtime.block_on(async { 2 + 2 }));
+    }
+
+    #[test]
+    fn runtime_worker_threads() {
+        for worker_threads in [1, 3] {
+            let args = [
+                format!("--worker-threads={}", worker_threads),
+                "--max-blocking-threads=2".to_string(),
+            ];
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let runtime = runtime(&test_utils::get_test_args(&args)).unwrap();
+            assert_eq!(worker_threads, runtime.metrics().num_workers());
+            assert_eq!(4, runtime.block_on(async { 2 + 2 }));
+        }
+    }
+}
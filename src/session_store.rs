@@ -0,0 +1,299 @@
+//! Store of object data pinned by sessions
+
+use crate::resource_manager::PinnedMemory;
+
+use axum::body::Bytes;
+use hashbrown::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use url::Url;
+
+/// Object data pinned by a session.
+struct Session {
+    /// URL of the object store.
+    source: Url,
+
+    /// Bucket containing the object.
+    bucket: String,
+
+    /// Object name.
+    object: String,
+
+    /// Object data.
+    data: Bytes,
+
+    /// Memory held for the object data, if memory is limited.
+    _memory: Option<PinnedMemory>,
+
+    /// Time at which the session expires.
+    expires: Instant,
+}
+
+/// Contents of a [crate::session_store::SessionStore].
+#[derive(Default)]
+struct StoreState {
+    /// Sessions, by token.
+    sessions: HashMap<String, Session>,
+
+    /// Total size of pinned object data in bytes.
+    size: usize,
+}
+
+impl StoreState {
+    /// Remove a session, if present, returning whether it was present.
+    fn remove(&mut self, token: &str) -> bool {
+        match self.sessions.remove(token) {
+            Some(session) => {
+                self.size -= session.data.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all expired sessions.
+    fn remove_expired(&mut self, now: Instant) {
+        let expired: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.expires <= now)
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in expired {
+            self.remove(&token);
+        }
+    }
+}
+
+/// [crate::session_store::SessionStore] holds the data of objects pinned by sessions, so that
+/// several operations may be performed on the same object while downloading it only once.
+///
+/// Each session is identified by a random token, and expires a fixed time after it is created.
+/// Unlike the [crate::download_cache::DownloadCache], pinned data is not revalidated, so a
+/// session continues to use the data that was downloaded even if the object is modified, and the
+/// token alone authorises access to the data. The total size of pinned data is limited, and new
+/// sessions are rejected rather than evicting existing sessions if the limit would be exceeded.
+/// Any memory reserved for pinned data is held until the session is removed or expires.
+pub struct SessionStore {
+    /// Maximum total size of pinned object data in bytes.
+    max_size: usize,
+
+    /// Time for which a session is valid after it is created.
+    ttl: Duration,
+
+    /// Store contents.
+    state: Mutex<StoreState>,
+}
+
+impl SessionStore {
+    /// Returns a new SessionStore object.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size`: Maximum total size of pinned object data in bytes
+    /// * `ttl`: Time for which a session is valid after it is created
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self {
+            max_size,
+            ttl,
+            state: Mutex::new(StoreState::default()),
+        }
+    }
+
+    /// Returns the time for which a session is valid after it is created.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the number of bytes available for new sessions.
+    pub fn available(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.remove_expired(Instant::now());
+        self.max_size - state.size
+    }
+
+    /// Creates a session pinning an object's data and returns its token, or `None` if there is
+    /// insufficient space for the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: URL of the object store
+    /// * `bucket`: Bucket containing the object
+    /// * `object`: Object name
+    /// * `data`: Object data
+    /// * `memory`: Memory held for the object data, if memory is limited
+    pub fn create(
+        &self,
+        source: &Url,
+        bucket: &str,
+        object: &str,
+        data: Bytes,
+        memory: Option<PinnedMemory>,
+    ) -> Option<String> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.remove_expired(now);
+        if state.size + data.len() > self.max_size {
+            return None;
+        }
+        let token = format!("{:032x}", rand::random::<u128>());
+        state.size += data.len();
+        state.sessions.insert(
+            token.clone(),
+            Session {
+                source: source.clone(),
+                bucket: bucket.to_string(),
+                object: object.to_string(),
+                data,
+                _memory: memory,
+                expires: now + self.ttl,
+            },
+        );
+        Some(token)
+    }
+
+    /// Returns the data pinned by a session, if the session exists, has not expired, and is for
+    /// the object in the request.
+    ///
+    /// The data is shared with the session, so should be copied before it is modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: Session token
+    /// * `source`: URL of the object store
+    /// * `bucket`: Bucket containing the object
+    /// * `object`: Object name
+    pub fn get(&self, token: &str, source: &Url, bucket: &str, object: &str) -> Option<Bytes> {
+        let mut state = self.state.lock().unwrap();
+        state.remove_expired(Instant::now());
+        state
+            .sessions
+            .get(token)
+            .filter(|session| {
+                &session.source == source && session.bucket == bucket && session.object == object
+            })
+            .map(|session| session.data.clone())
+    }
+
+    /// Removes a session, returning whether it existed and had not expired.
+    pub fn remove(&self, token: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.remove_expired(Instant::now());
+        state.remove(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource_manager::ResourceManager;
+
+    use std::sync::Arc;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn source() -> Url {
+        Url::parse("http://example.com").unwrap()
+    }
+
+    fn create(store: &SessionStore, object: &str, data: &'static [u8]) -> Option<String> {
+        store.create(&source(), "bar", object, Bytes::from_static(data), None)
+    }
+
+    #[test]
+    fn create_get() {
+        let store = SessionStore::new(10, TTL);
+        let token = create(&store, "baz", &[1, 2, 3]).unwrap();
+        assert_eq!(32, token.len());
+        let data = store.get(&token, &source(), "bar", "baz").unwrap();
+        assert_eq!(&[1, 2, 3][..], data);
+        assert_eq!(7, store.available());
+    }
+
+    #[test]
+    fn get_unknown() {
+        let store = SessionStore::new(10, TTL);
+        assert!(store.get("foo", &source(), "bar", "baz").is_none());
+    }
+
+    #[test]
+    fn get_other_object() {
+        let store = SessionStore::new(10, TTL);
+        let token = create(&store, "baz", &[1, 2, 3]).unwrap();
+        // The token only gives access to the object it pinned.
+        assert!(store.get(&token, &source(), "bar", "qux").is_none());
+        assert!(store.get(&token, &source(), "foo", "baz").is_none());
+        let other_source = Url::parse("http://example.org").unwrap();
+        assert!(store.get(&token, &other_source, "bar", "baz").is_none());
+    }
+
+    #[test]
+    fn tokens_unique() {
+        let store = SessionStore::new(10, TTL);
+        let token1 = create(&store, "baz", &[1]).unwrap();
+        let token2 = create(&store, "baz", &[2]).unwrap();
+        assert_ne!(token1, token2);
+        assert_eq!(
+            &[1][..],
+            store.get(&token1, &source(), "bar", "baz").unwrap()
+        );
+        assert_eq!(
+            &[2][..],
+            store.get(&token2, &source(), "bar", "baz").unwrap()
+        );
+    }
+
+    #[test]
+    fn create_too_large() {
+        let store = SessionStore::new(10, TTL);
+        create(&store, "a", &[0; 6]).unwrap();
+        // Existing sessions are not evicted.
+        assert!(create(&store, "b", &[0; 6]).is_none());
+        assert_eq!(4, store.available());
+    }
+
+    #[test]
+    fn remove() {
+        let store = SessionStore::new(10, TTL);
+        let token = create(&store, "baz", &[1, 2, 3]).unwrap();
+        assert!(store.remove(&token));
+        assert!(store.get(&token, &source(), "bar", "baz").is_none());
+        assert!(!store.remove(&token));
+        assert_eq!(10, store.available());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expiry() {
+        let store = SessionStore::new(10, TTL);
+        let token = create(&store, "baz", &[0; 6]).unwrap();
+        tokio::time::advance(TTL - Duration::from_secs(1)).await;
+        assert!(store.get(&token, &source(), "bar", "baz").is_some());
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(store.get(&token, &source(), "bar", "baz").is_none());
+        assert!(!store.remove(&token));
+        // The space used by the expired session is released.
+        assert_eq!(10, store.available());
+        assert!(create(&store, "baz", &[0; 10]).is_some());
+    }
+
+    #[tokio::test]
+    async fn memory_released() {
+        let rm = Arc::new(ResourceManager::new(
+            None,
+            Some(10),
+            None,
+            Some(Duration::from_millis(10)),
+        ));
+        let store = SessionStore::new(10, TTL);
+        let permit = rm.memory(6).await.unwrap().unwrap();
+        let data = Bytes::from_static(&[0; 6]);
+        let token = store
+            .create(&source(), "bar", "baz", data, Some(rm.pin_memory(permit)))
+            .unwrap();
+        // The memory is held by the session until it is removed.
+        assert!(rm.memory(6).await.is_err());
+        assert!(store.remove(&token));
+        rm.memory(6).await.unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+//! Zarr v3 shard index handling
+//!
+//! A Zarr v3 shard, as written by the `sharding_indexed` codec, packs multiple inner chunks into
+//! a single object along with an index describing the location of each inner chunk.
+
+use crate::error::ActiveStorageError;
+use crate::models::{ByteRange, Shard};
+
+use validator::ValidationError;
+
+/// Size in bytes of each entry in a shard index
+const INDEX_ENTRY_SIZE: usize = 16;
+
+/// Size in bytes of the CRC32C checksum following a shard index
+const INDEX_CHECKSUM_SIZE: usize = 4;
+
+/// Value used for both the offset and size of an empty inner chunk
+const EMPTY_CHUNK: u64 = u64::MAX;
+
+/// Returns the size in bytes of a shard's index.
+///
+/// Returns a validation error if the size overflows.
+///
+/// # Arguments
+///
+/// * `shard`: Shard description from the request data
+pub fn index_size(shard: &Shard) -> Result<usize, ActiveStorageError> {
+    let checksum_size = if shard.index_checksum {
+        INDEX_CHECKSUM_SIZE
+    } else {
+        0
+    };
+    shard
+        .chunks
+        .checked_mul(INDEX_ENTRY_SIZE)
+        .and_then(|size| size.checked_add(checksum_size))
+        .ok_or_else(|| ValidationError::new("Shard index is too large").into())
+}
+
+/// Returns the byte range of the requested inner chunk, relative to the start of the shard.
+///
+/// The index checksum is not verified.
+///
+/// # Arguments
+///
+/// * `shard`: Shard description from the request data
+/// * `index`: Shard index data
+pub fn chunk_range(shard: &Shard, index: &[u8]) -> Result<ByteRange, ActiveStorageError> {
+    let expected_size = index_size(shard)?;
+    if index.len() != expected_size {
+        return Err(ActiveStorageError::ShardInvalid(format!(
+            "index size {} does not match expected size {}",
+            index.len(),
+            expected_size
+        )));
+    }
+    let entry = index
+        .chunks_exact(INDEX_ENTRY_SIZE)
+        .nth(shard.chunk)
+        .ok_or_else(|| {
+            ActiveStorageError::ShardInvalid(format!(
+                "inner chunk {} is not in the index",
+                shard.chunk
+            ))
+        })?;
+    let (offset, size) = entry.split_at(INDEX_ENTRY_SIZE / 2);
+    // Zarr v3 shard indices are always little endian.
+    let offset = u64::from_le_bytes(offset.try_into().unwrap());
+    let size = u64::from_le_bytes(size.try_into().unwrap());
+    if offset == EMPTY_CHUNK && size == EMPTY_CHUNK {
+        return Err(ActiveStorageError::ShardInvalid(format!(
+            "inner chunk {} is empty",
+            shard.chunk
+        )));
+    }
+    if size == 0 {
+        return Err(ActiveStorageError::ShardInvalid(format!(
+            "inner chunk {} has zero size",
+            shard.chunk
+        )));
+    }
+    Ok(ByteRange::new(offset.try_into()?, size.try_into()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_index(entries: &[(u64, u64)], checksum: bool) -> Vec<u8> {
+        let mut index: Vec<u8> = entries
+            .iter()
+            .flat_map(|(offset, size)| [offset.to_le_bytes(), size.to_le_bytes()].concat())
+            .collect();
+        if checksum {
+            index.extend_from_slice(&[0; INDEX_CHECKSUM_SIZE]);
+        }
+        index
+    }
+
+    #[test]
+    fn index_size_checksum() {
+        assert_eq!(68, index_size(&Shard::new(4, 0)).unwrap());
+    }
+
+    #[test]
+    fn index_size_no_checksum() {
+        let mut shard = Shard::new(4, 0);
+        shard.index_checksum = false;
+        assert_eq!(64, index_size(&shard).unwrap());
+    }
+
+    #[test]
+    fn chunk_range_valid() {
+        let index = make_index(&[(0, 16), (32, 8), (16, 16)], true);
+        assert_eq!(
+            ByteRange::new(32, 8),
+            chunk_range(&Shard::new(3, 1), &index).unwrap()
+        );
+        assert_eq!(
+            ByteRange::new(16, 16),
+            chunk_range(&Shard::new(3, 2), &index).unwrap()
+        );
+    }
+
+    #[test]
+    fn chunk_range_empty() {
+        let index = make_index(&[(0, 16), (EMPTY_CHUNK, EMPTY_CHUNK)], true);
+        assert_eq!(
+            "invalid Zarr shard: inner chunk 1 is empty",
+            chunk_range(&Shard::new(2, 1), &index)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn chunk_range_zero_size() {
+        let index = make_index(&[(0, 0)], true);
+        assert_eq!(
+            "invalid Zarr shard: inner chunk 0 has zero size",
+            chunk_range(&Shard::new(1, 0), &index)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn chunk_range_wrong_index_size() {
+        let index = make_index(&[(0, 16)], false);
+        assert_eq!(
+            "invalid Zarr shard: index size 16 does not match expected size 20",
+            chunk_range(&Shard::new(1, 0), &index)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn index_size_overflow() {
+        match index_size(&Shard::new(usize::MAX / 8, 0)) {
+            Err(ActiveStorageError::RequestDataValidationSingle(error)) => {
+                assert_eq!("Shard index is too large", error.code)
+            }
+            _ => panic!("expected index size to overflow"),
+        }
+    }
+
+    #[test]
+    fn chunk_range_not_in_index() {
+        let index = make_index(&[(0, 16)], true);
+        assert_eq!(
+            "invalid Zarr shard: inner chunk 1 is not in the index",
+            chunk_range(&Shard::new(1, 1), &index)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
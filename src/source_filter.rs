@@ -0,0 +1,300 @@
+//! Restriction of the object store sources that requests may access
+
+use crate::error::ActiveStorageError;
+
+use hyper::client::connect::dns::Name;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use url::{Host, Origin, Url};
+
+/// [crate::source_filter::SourceFilter] restricts the `source` URLs that the server will make
+/// requests to on behalf of clients.
+///
+/// Since the source is chosen by the client, without a restriction the server may be used to send
+/// requests to internal services that the client cannot reach directly, known as Server-Side
+/// Request Forgery (SSRF).
+///
+/// If a list of allowed sources is configured, only sources with the same scheme, host and port
+/// as one in the list are allowed. If private sources are denied, sources with a host that is or
+/// resolves to a loopback, private, shared (CGNAT), link-local or unspecified IP address are also
+/// rejected, unless the host is an IP address in the list of allowed sources.
+///
+/// Since a host may resolve to a different address when the request is sent, clients that send
+/// requests to sources should also resolve hosts using a [PublicResolver] when private sources are
+/// denied, and must not follow redirects.
+pub struct SourceFilter {
+    /// Origins of allowed sources. Empty to allow all sources.
+    allowed: Vec<Origin>,
+
+    /// Whether to reject sources with private IP addresses.
+    deny_private: bool,
+}
+
+impl SourceFilter {
+    /// Returns a new SourceFilter object.
+    ///
+    /// # Arguments
+    ///
+    /// * `allowed`: URLs of allowed sources. Empty to allow all sources
+    /// * `deny_private`: Whether to reject sources with private IP addresses
+    pub fn new(allowed: &[Url], deny_private: bool) -> Self {
+        Self {
+            allowed: allowed.iter().map(Url::origin).collect(),
+            deny_private,
+        }
+    }
+
+    /// Returns the host of a source to use as a metric label.
+    ///
+    /// Since the source is chosen by the client, only the hosts of sources in the list of allowed
+    /// sources are used, and other sources are labelled `other`, to bound the number of labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: Object storage API or object URL
+    pub fn metric_host<'a>(&self, source: &'a Url) -> &'a str {
+        match source.host_str() {
+            Some(host) if self.allowed.contains(&source.origin()) => host,
+            _ => "other",
+        }
+    }
+
+    /// Check whether requests may be sent to a source.
+    ///
+    /// Returns a [crate::error::ActiveStorageError::SourceNotAllowed] error if not.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: Object storage API or object URL
+    pub async fn check(&self, source: &Url) -> Result<(), ActiveStorageError> {
+        let allowed = if !self.allowed.is_empty() && !self.allowed.contains(&source.origin()) {
+            false
+        } else if !self.deny_private {
+            true
+        } else if !self.allowed.is_empty() && !matches!(source.host(), Some(Host::Domain(_))) {
+            // An IP address in the list of allowed sources is explicitly allowed.
+            true
+        } else {
+            !is_private_source(source).await
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(ActiveStorageError::SourceNotAllowed {
+                url: source.to_string(),
+            })
+        }
+    }
+}
+
+/// Returns whether a source's host is or resolves to a private IP address.
+///
+/// Hosts that cannot be resolved are not considered private, since no request can be sent to
+/// them.
+async fn is_private_source(source: &Url) -> bool {
+    let port = source.port_or_known_default().unwrap_or(0);
+    match source.host() {
+        Some(Host::Ipv4(ip)) => is_private_ip(ip.into()),
+        Some(Host::Ipv6(ip)) => is_private_ip(ip.into()),
+        Some(Host::Domain(domain)) => match tokio::net::lookup_host((domain, port)).await {
+            Ok(mut addrs) => addrs.any(|addr| is_private_ip(addr.ip())),
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+/// A DNS resolver that only returns public IP addresses.
+///
+/// Resolving the host again when a request is sent, rather than relying on an earlier
+/// [SourceFilter::check], prevents a host from resolving to a public address when checked and a
+/// private address when connected to, known as DNS rebinding. Resolution fails if the host only
+/// resolves to private addresses.
+#[derive(Clone, Debug, Default)]
+pub struct PublicResolver;
+
+impl PublicResolver {
+    /// Resolves a host and returns its public addresses.
+    async fn resolve(host: String) -> io::Result<Vec<SocketAddr>> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+            .await?
+            .filter(|addr| !is_private_ip(addr.ip()))
+            .collect();
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} does not resolve to a public IP address", host),
+            ));
+        }
+        Ok(addrs)
+    }
+}
+
+/// Resolver for the HTTP connector of S3 clients.
+impl hyper::service::Service<Name> for PublicResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        Box::pin(async move { Ok(Self::resolve(name.as_str().to_string()).await?.into_iter()) })
+    }
+}
+
+/// Resolver for the HTTP client.
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: reqwest::dns::Addrs = Box::new(Self::resolve(host).await?.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Returns whether an IP address is a loopback, private, shared (CGNAT), link-local or
+/// unspecified address.
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || is_shared_ipv4(&ip)
+                || ip.is_link_local()
+                || ip.is_unspecified()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_ip(ip.into()),
+            None => ip.is_loopback() || ip.is_unspecified() || is_private_ipv6(&ip),
+        },
+    }
+}
+
+/// Returns whether an IPv4 address is in the shared address space (100.64.0.0/10) used for
+/// carrier-grade NAT.
+fn is_shared_ipv4(ip: &std::net::Ipv4Addr) -> bool {
+    let [first, second, _, _] = ip.octets();
+    first == 100 && (second & 0xc0) == 64
+}
+
+/// Returns whether an IPv6 address is a unique local (fc00::/7) or link-local (fe80::/10)
+/// address.
+fn is_private_ipv6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[tokio::test]
+    async fn allow_all() {
+        let filter = SourceFilter::new(&[], false);
+        filter.check(&url("http://127.0.0.1:9000")).await.unwrap();
+        filter.check(&url("http://192.168.0.1")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn allowed_sources() {
+        let filter = SourceFilter::new(&[url("https://s3.example.com"), url("http://[::1]")], true);
+        filter.check(&url("https://s3.example.com")).await.unwrap();
+        // Paths are ignored, and explicitly allowed private sources are allowed.
+        filter
+            .check(&url("https://s3.example.com:443/foo"))
+            .await
+            .unwrap();
+        filter.check(&url("http://[::1]/bar/baz")).await.unwrap();
+        for source in [
+            "http://s3.example.com",
+            "https://s3.example.com:8443",
+            "https://other.example.com",
+            "http://127.0.0.1",
+        ] {
+            match filter.check(&url(source)).await {
+                Err(ActiveStorageError::SourceNotAllowed { url: error_url }) => {
+                    assert_eq!(url(source).to_string(), error_url)
+                }
+                _ => panic!("expected source {} to be rejected", source),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn allowed_sources_deny_private() {
+        // Allowed sources with a domain are still rejected if they resolve to a private address.
+        let allowed = [url("http://localhost:9000"), url("http://127.0.0.1:9000")];
+        let filter = SourceFilter::new(&allowed, false);
+        filter.check(&url("http://localhost:9000")).await.unwrap();
+        let filter = SourceFilter::new(&allowed, true);
+        filter.check(&url("http://127.0.0.1:9000")).await.unwrap();
+        assert!(filter.check(&url("http://localhost:9000")).await.is_err());
+    }
+
+    #[test]
+    fn metric_host() {
+        let filter = SourceFilter::new(&[url("https://s3.example.com")], false);
+        assert_eq!(
+            "s3.example.com",
+            filter.metric_host(&url("https://s3.example.com/foo"))
+        );
+        assert_eq!(
+            "other",
+            filter.metric_host(&url("https://other.example.com"))
+        );
+        let filter = SourceFilter::new(&[], false);
+        assert_eq!("other", filter.metric_host(&url("https://s3.example.com")));
+    }
+
+    #[tokio::test]
+    async fn deny_private() {
+        let filter = SourceFilter::new(&[], true);
+        filter.check(&url("http://8.8.8.8")).await.unwrap();
+        filter.check(&url("http://100.128.0.1")).await.unwrap();
+        filter.check(&url("https://[2001:db8::1]")).await.unwrap();
+        for source in [
+            "http://127.0.0.1:9000",
+            "http://10.0.0.1",
+            "http://172.16.0.1",
+            "http://192.168.0.1",
+            "http://169.254.169.254/latest/meta-data",
+            "http://0.0.0.0",
+            "http://100.64.0.1",
+            "http://100.127.255.255",
+            "http://[::1]",
+            "http://[::ffff:10.0.0.1]",
+            "http://[fd00::1]",
+            "http://[fe80::1]",
+            "http://localhost:9000",
+        ] {
+            assert!(
+                filter.check(&url(source)).await.is_err(),
+                "expected source {} to be rejected",
+                source
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn public_resolver() {
+        let addrs = PublicResolver::resolve("8.8.8.8".to_string())
+            .await
+            .unwrap();
+        assert_eq!(vec![SocketAddr::from(([8, 8, 8, 8], 0))], addrs);
+        for host in ["localhost", "127.0.0.1", "100.64.0.1"] {
+            let error = PublicResolver::resolve(host.to_string()).await.unwrap_err();
+            assert_eq!(io::ErrorKind::PermissionDenied, error.kind());
+        }
+    }
+}
@@ -1,24 +1,77 @@
+use crate::cli::CommandLineArgs;
 use crate::models::*;
 use crate::types::{ByteOrder, Missing};
 
+use axum::body::{Body, Bytes};
+use axum::http::{header, Method, Request, Response, StatusCode};
+use clap::Parser;
+use futures::StreamExt;
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use url::Url;
 
+/// Create a CommandLineArgs object with default values, plus any additional arguments.
+///
+/// The thread limit defaults to one CPU less than the host has, which would be zero on a single
+/// CPU host, so a limit of one is set unless overridden by `args`.
+pub(crate) fn get_test_args(args: &[&str]) -> CommandLineArgs {
+    let default_args = ["reductionist", "--thread-limit=1"];
+    CommandLineArgs::parse_from(default_args.iter().chain(args.iter()))
+}
+
 /// Create a RequestData object with only required fields set.
 pub(crate) fn get_test_request_data() -> RequestData {
     RequestData {
         source: Url::parse("http://example.com").unwrap(),
+        protocol: None,
+        presigned: None,
+        region: None,
         bucket: "bar".to_string(),
         object: "baz".to_string(),
+        session: None,
         dtype: DType::Int32,
         byte_order: None,
         offset: None,
+        skip_header_bytes: None,
         size: None,
+        units: None,
+        part_number: None,
+        ranges: None,
+        shard: None,
         shape: None,
+        stored_shape: None,
         order: None,
         selection: None,
+        indices: None,
+        hyperslab: None,
+        axis: None,
+        sparse_fetch: None,
         compression: None,
         filters: None,
         missing: None,
+        fill_value: None,
+        accumulate_dtype: None,
+        output_dtype: None,
+        output_order: None,
+        clip_min: None,
+        clip_max: None,
+        reject_nonfinite: None,
+        percentile: None,
+        trim_fraction: None,
+        weights_bucket: None,
+        weights_object: None,
+        weights_dtype: None,
+        mask_bucket: None,
+        mask_object: None,
+        expected_memory: None,
     }
 }
 
@@ -26,17 +79,677 @@ pub(crate) fn get_test_request_data() -> RequestData {
 pub(crate) fn get_test_request_data_optional() -> RequestData {
     RequestData {
         source: Url::parse("http://example.com").unwrap(),
+        protocol: Some(Protocol::S3),
+        presigned: None,
+        region: None,
         bucket: "bar".to_string(),
         object: "baz".to_string(),
+        session: None,
         dtype: DType::Int32,
         byte_order: Some(ByteOrder::Little),
         offset: Some(4),
+        skip_header_bytes: None,
         size: Some(8),
+        units: None,
+        part_number: None,
+        ranges: None,
+        shard: None,
         shape: Some(vec![2, 5]),
+        stored_shape: None,
         order: Some(Order::C),
         selection: Some(vec![Slice::new(1, 2, 3), Slice::new(4, 5, 6)]),
+        indices: None,
+        hyperslab: None,
+        axis: Some(0),
+        sparse_fetch: None,
         compression: Some(Compression::Gzip),
-        filters: Some(vec![Filter::Shuffle { element_size: 4 }]),
+        filters: Some(vec![Filter::Shuffle {
+            element_size: Some(4),
+        }]),
         missing: Some(Missing::MissingValue(42.into())),
+        fill_value: None,
+        accumulate_dtype: None,
+        output_dtype: None,
+        output_order: None,
+        clip_min: None,
+        clip_max: None,
+        reject_nonfinite: None,
+        percentile: None,
+        trim_fraction: None,
+        weights_bucket: None,
+        weights_object: None,
+        weights_dtype: None,
+        mask_bucket: None,
+        mask_object: None,
+        expected_memory: None,
+    }
+}
+
+/// Last-Modified time reported by a [MockS3] server for all objects.
+pub(crate) const MOCK_S3_LAST_MODIFIED: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+/// Return the ETag reported by a [MockS3] server for an object.
+pub(crate) fn mock_s3_etag(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Path to a PEM file containing the CA certificate that signed the certificate of a [MockS3]
+/// server started using [MockS3::start_https].
+pub(crate) const TEST_CA_BUNDLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tls/ca.pem");
+
+/// Returns the certificate chain of a [MockS3] server using HTTPS.
+fn test_tls_certs() -> Vec<rustls::Certificate> {
+    let pem = include_bytes!("../testdata/tls/cert.pem");
+    rustls_pemfile::certs(&mut &pem[..])
+        .unwrap()
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect()
+}
+
+/// Returns the private key of a [MockS3] server using HTTPS.
+fn test_tls_key() -> rustls::PrivateKey {
+    let pem = include_bytes!("../testdata/tls/key.pem");
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut &pem[..]).unwrap();
+    rustls::PrivateKey(keys.remove(0))
+}
+
+/// Minimum size of a part of a multipart object other than the last, as for S3.
+pub(crate) const MOCK_S3_MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Objects stored in a [MockS3] server, keyed by `/{bucket}/{key}` path.
+type MockObjects = Arc<Mutex<HashMap<String, Bytes>>>;
+
+/// CRC32C checksums of objects stored in a [MockS3] server, keyed by `/{bucket}/{key}` path.
+type MockChecksums = Arc<Mutex<HashMap<String, header::HeaderValue>>>;
+
+/// Error responses returned by a [MockS3] server, keyed by `/{bucket}/{key}` path.
+type MockErrors = Arc<Mutex<HashMap<String, (StatusCode, &'static str)>>>;
+
+/// Number of bytes of object data sent by a [MockS3] server before closing the connection,
+/// keyed by `/{bucket}/{key}` path.
+type MockTruncated = Arc<Mutex<HashMap<String, usize>>>;
+
+/// Sizes of the parts of multipart objects stored in a [MockS3] server, keyed by
+/// `/{bucket}/{key}` path.
+type MockParts = Arc<Mutex<HashMap<String, Vec<usize>>>>;
+
+/// Delay applied by a [MockS3] server before responding to each request.
+type MockDelay = Arc<Mutex<Duration>>;
+
+/// Number of `304 Not Modified` responses sent by a [MockS3] server.
+type MockNotModified = Arc<AtomicUsize>;
+
+/// Region that requests to a [MockS3] server must be signed for, if any.
+type MockRegion = Arc<Mutex<Option<String>>>;
+
+/// Access key and secret key that requests to a [MockS3] server must be signed with, if any.
+type MockCredentials = Arc<Mutex<Option<(String, String)>>>;
+
+/// A minimal in-process S3-compatible server for tests.
+///
+/// Supports path-style GET requests for objects, including single byte ranges, parts of multipart
+/// objects stored using [MockS3::put_multipart] and `If-None-Match` conditional requests, and HEAD
+/// requests for object metadata. Error responses may be configured for individual objects using
+/// [MockS3::set_error], and responses may be truncated using [MockS3::set_truncated].
+///
+/// Request signatures, including those of presigned URLs, are not checked unless required using
+/// [MockS3::set_credentials], but the region of the signature's credential scope may be checked
+/// using [MockS3::set_region]. As with S3, requests for presigned URLs must not also have an
+/// `Authorization` header.
+///
+/// Objects are stored with a CRC32C checksum, which is returned for whole object GET requests
+/// with checksum mode enabled.
+pub(crate) struct MockS3 {
+    /// URL of the server
+    pub url: Url,
+    /// Objects stored in the server
+    objects: MockObjects,
+    /// Checksums of objects stored in the server
+    checksums: MockChecksums,
+    /// Error responses returned by the server
+    errors: MockErrors,
+    /// Truncated responses returned by the server
+    truncated: MockTruncated,
+    /// Part sizes of multipart objects stored in the server
+    parts: MockParts,
+    /// Delay before responding to each request
+    delay: MockDelay,
+    /// Number of `304 Not Modified` responses sent
+    not_modified: MockNotModified,
+    /// Region that requests must be signed for
+    region: MockRegion,
+    /// Credentials that requests must be signed with
+    credentials: MockCredentials,
+}
+
+impl MockS3 {
+    /// Start a mock S3 server listening on a random local port.
+    pub(crate) async fn start() -> Self {
+        Self::start_inner(false).await
+    }
+
+    /// Start a mock S3 server using HTTPS, with a certificate for `127.0.0.1` signed by the CA
+    /// in [TEST_CA_BUNDLE].
+    pub(crate) async fn start_https() -> Self {
+        Self::start_inner(true).await
+    }
+
+    /// Start a mock S3 server using HTTP or HTTPS.
+    async fn start_inner(https: bool) -> Self {
+        let objects = MockObjects::default();
+        let checksums = MockChecksums::default();
+        let errors = MockErrors::default();
+        let truncated = MockTruncated::default();
+        let parts = MockParts::default();
+        let delay = MockDelay::default();
+        let not_modified = MockNotModified::default();
+        let region = MockRegion::default();
+        let credentials = MockCredentials::default();
+        let service_objects = objects.clone();
+        let service_checksums = checksums.clone();
+        let service_errors = errors.clone();
+        let service_truncated = truncated.clone();
+        let service_parts = parts.clone();
+        let service_delay = delay.clone();
+        let service_not_modified = not_modified.clone();
+        let service_region = region.clone();
+        let service_credentials = credentials.clone();
+        let service = service_fn(move |request| {
+            let objects = service_objects.clone();
+            let checksums = service_checksums.clone();
+            let errors = service_errors.clone();
+            let truncated = service_truncated.clone();
+            let parts = service_parts.clone();
+            let delay = *service_delay.lock().unwrap();
+            let not_modified = service_not_modified.clone();
+            let region = service_region.lock().unwrap().clone();
+            let credentials = service_credentials.lock().unwrap().clone();
+            async move {
+                tokio::time::sleep(delay).await;
+                Ok::<_, Infallible>(mock_s3_response(
+                    &objects,
+                    &checksums,
+                    &errors,
+                    &truncated,
+                    &parts,
+                    &not_modified,
+                    region.as_deref(),
+                    credentials.as_ref(),
+                    request,
+                ))
+            }
+        });
+        let incoming = AddrIncoming::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let addr = incoming.local_addr();
+        let url = if https {
+            let acceptor = hyper_rustls::TlsAcceptor::builder()
+                .with_single_cert(test_tls_certs(), test_tls_key())
+                .unwrap()
+                .with_all_versions_alpn()
+                .with_incoming(incoming);
+            let make_service = make_service_fn(move |_conn| {
+                let service = service.clone();
+                async move { Ok::<_, Infallible>(service) }
+            });
+            tokio::spawn(hyper::Server::builder(acceptor).serve(make_service));
+            format!("https://{}", addr)
+        } else {
+            let make_service = make_service_fn(move |_conn| {
+                let service = service.clone();
+                async move { Ok::<_, Infallible>(service) }
+            });
+            tokio::spawn(hyper::Server::builder(incoming).serve(make_service));
+            format!("http://{}", addr)
+        };
+        let url = Url::parse(&url).unwrap();
+        Self {
+            url,
+            objects,
+            checksums,
+            errors,
+            truncated,
+            parts,
+            delay,
+            not_modified,
+            region,
+            credentials,
+        }
+    }
+
+    /// Returns the number of `304 Not Modified` responses sent by the server.
+    pub(crate) fn not_modified_count(&self) -> usize {
+        self.not_modified.load(Ordering::SeqCst)
+    }
+
+    /// Set a delay to apply before responding to each request.
+    pub(crate) fn set_delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap() = delay;
+    }
+
+    /// Require requests to be signed for a region, as some S3-compatible stores do. Other requests
+    /// fail with `400 Bad Request`.
+    pub(crate) fn set_region(&self, region: &str) {
+        *self.region.lock().unwrap() = Some(region.to_string());
+    }
+
+    /// Require requests to have a valid signature for an access key and secret key, either in the
+    /// `Authorization` header or in the query string of a presigned URL. Other requests fail with
+    /// `403 Forbidden`.
+    pub(crate) fn set_credentials(&self, access_key: &str, secret_key: &str) {
+        *self.credentials.lock().unwrap() = Some((access_key.to_string(), secret_key.to_string()));
+    }
+
+    /// Return an S3 error response with the given status and error code for all requests for an
+    /// object.
+    pub(crate) fn set_error(
+        &self,
+        bucket: &str,
+        key: &str,
+        status: StatusCode,
+        code: &'static str,
+    ) {
+        self.errors
+            .lock()
+            .unwrap()
+            .insert(format!("/{}/{}", bucket, key), (status, code));
+    }
+
+    /// Close the connection after sending `size` bytes of the data of GET responses for an
+    /// object, as if the connection were reset. The full Content-Length is still sent.
+    pub(crate) fn set_truncated(&self, bucket: &str, key: &str, size: usize) {
+        self.truncated
+            .lock()
+            .unwrap()
+            .insert(format!("/{}/{}", bucket, key), size);
+    }
+
+    /// Store an object in the server.
+    pub(crate) fn put(&self, bucket: &str, key: &str, data: impl Into<Bytes>) {
+        let data = data.into();
+        let mut checksum = aws_smithy_checksums::ChecksumAlgorithm::Crc32c.into_impl();
+        checksum.update(&data);
+        let path = format!("/{}/{}", bucket, key);
+        self.checksums
+            .lock()
+            .unwrap()
+            .insert(path.clone(), checksum.header_value());
+        self.objects.lock().unwrap().insert(path, data);
+    }
+
+    /// Store an object in the server as if uploaded using multipart upload, with the given parts.
+    ///
+    /// As with S3 and MinIO, each part other than the last must be at least
+    /// [MOCK_S3_MIN_PART_SIZE] bytes.
+    pub(crate) fn put_multipart(&self, bucket: &str, key: &str, parts: &[&[u8]]) {
+        if let Some((_, parts)) = parts.split_last() {
+            assert!(parts.iter().all(|part| part.len() >= MOCK_S3_MIN_PART_SIZE));
+        }
+        self.put(bucket, key, parts.concat());
+        self.parts.lock().unwrap().insert(
+            format!("/{}/{}", bucket, key),
+            parts.iter().map(|part| part.len()).collect(),
+        );
+    }
+
+    /// Corrupt the data of a stored object by flipping the bits of its first byte, without
+    /// updating its checksum.
+    pub(crate) fn corrupt(&self, bucket: &str, key: &str) {
+        let mut objects = self.objects.lock().unwrap();
+        let data = objects.get_mut(&format!("/{}/{}", bucket, key)).unwrap();
+        let mut corrupted = data.to_vec();
+        corrupted[0] = !corrupted[0];
+        *data = corrupted.into();
+    }
+
+    /// Return a presigned GET URL for an object, signed using the AWS SDK.
+    pub(crate) async fn presign(&self, bucket: &str, key: &str) -> Url {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .credentials_provider(aws_sdk_s3::config::Credentials::from_keys(
+                "minioadmin",
+                "minioadmin",
+                None,
+            ))
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .endpoint_url(self.url.to_string())
+            .force_path_style(true)
+            .build();
+        let presigned = aws_sdk_s3::Client::from_conf(config)
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .presigned(
+                aws_sdk_s3::presigning::PresigningConfig::expires_in(Duration::from_secs(3600))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        Url::parse(presigned.uri()).unwrap()
+    }
+}
+
+/// Return an S3 XML error response.
+fn mock_s3_error(status: StatusCode, code: &str) -> Response<Body> {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code><Message>{}</Message></Error>",
+        code, code
+    );
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/xml")
+        .body(body.into())
+        .unwrap()
+}
+
+/// Returns whether a request to a [MockS3] server has a valid SigV4 signature for an access key
+/// and secret key, or `None` if the request is not signed.
+///
+/// The signature is recalculated from the request as received, including its `Host` header, so
+/// is only valid if the request was signed for the host that it was sent with.
+fn mock_s3_signature_valid(
+    request: &Request<Body>,
+    access_key: &str,
+    secret_key: &str,
+) -> Option<bool> {
+    use aws_sigv4::http_request::{PayloadChecksumKind, SignableBody};
+    let header = |name: &str| request.headers().get(name)?.to_str().ok();
+    // The header is AWS4-HMAC-SHA256 Credential=<scope>, SignedHeaders=<names>, Signature=<sig>.
+    let authorization = header("authorization")?;
+    let field = |name: &str| {
+        authorization
+            .split([' ', ','])
+            .find_map(|part| part.strip_prefix(name)?.strip_prefix('='))
+    };
+    let mut settings = mock_s3_signing_settings();
+    settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+    let signature = mock_s3_signature(
+        request,
+        request.uri().to_string(),
+        field("Credential")?,
+        field("SignedHeaders")?,
+        header("x-amz-date")?,
+        SignableBody::Precomputed(header("x-amz-content-sha256")?.to_string()),
+        settings,
+        access_key,
+        secret_key,
+    )?;
+    Some(Some(signature.as_str()) == field("Signature"))
+}
+
+/// Returns whether a request to a [MockS3] server for a presigned URL has a valid SigV4 signature
+/// in its query string for an access key and secret key, or `None` if the request is not
+/// presigned.
+///
+/// As for [mock_s3_signature_valid], the signature is recalculated from the request as received,
+/// so is not valid if the URL was modified after it was signed.
+fn mock_s3_presigned_signature_valid(
+    request: &Request<Body>,
+    access_key: &str,
+    secret_key: &str,
+) -> Option<bool> {
+    use aws_sigv4::http_request::{SignableBody, SignatureLocation};
+    let query = request.uri().query()?;
+    let param = |name: &str| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find_map(|(key, value)| (key == name).then_some(value))
+    };
+    // The signing parameters are added back to the query string when the request is signed.
+    let unsigned_query: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.starts_with("X-Amz-"))
+        .collect();
+    let mut uri = request.uri().path().to_string();
+    if !unsigned_query.is_empty() {
+        uri = format!("{}?{}", uri, unsigned_query.join("&"));
+    }
+    let mut settings = mock_s3_signing_settings();
+    settings.signature_location = SignatureLocation::QueryParams;
+    settings.expires_in = Some(Duration::from_secs(param("X-Amz-Expires")?.parse().ok()?));
+    let signature = mock_s3_signature(
+        request,
+        uri,
+        &param("X-Amz-Credential")?,
+        &param("X-Amz-SignedHeaders")?,
+        &param("X-Amz-Date")?,
+        SignableBody::UnsignedPayload,
+        settings,
+        access_key,
+        secret_key,
+    )?;
+    Some(param("X-Amz-Signature")? == signature)
+}
+
+/// Returns the signing settings used by the AWS SDK for S3.
+fn mock_s3_signing_settings() -> aws_sigv4::http_request::SigningSettings {
+    use aws_sigv4::http_request::{PercentEncodingMode, SigningSettings, UriPathNormalizationMode};
+    let mut settings = SigningSettings::default();
+    settings.percent_encoding_mode = PercentEncodingMode::Single;
+    settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+    settings
+}
+
+/// Returns the SigV4 signature of a request to a [MockS3] server, or `None` if the signing
+/// parameters are invalid or are not for the access key.
+///
+/// # Arguments
+///
+/// * `request`: Request as received, from which the signed headers are taken
+/// * `uri`: URI of the request, without any signature
+/// * `credential`: Credential scope, `<access key>/<date>/<region>/<service>/aws4_request`
+/// * `signed_headers`: Names of the signed headers, separated by `;`
+/// * `date`: Signing time in the form `20240102T030405Z`
+/// * `body`: Signed payload of the request
+/// * `settings`: Signing settings
+/// * `access_key`: Access key that the request must be signed with
+/// * `secret_key`: Secret key of the access key
+#[allow(clippy::too_many_arguments)]
+fn mock_s3_signature(
+    request: &Request<Body>,
+    uri: String,
+    credential: &str,
+    signed_headers: &str,
+    date: &str,
+    body: aws_sigv4::http_request::SignableBody,
+    settings: aws_sigv4::http_request::SigningSettings,
+    access_key: &str,
+    secret_key: &str,
+) -> Option<String> {
+    use aws_sigv4::http_request::{sign, SignableRequest};
+    let scope: Vec<&str> = credential.split('/').collect();
+    if scope.len() != 5 || scope[0] != access_key {
+        return None;
+    }
+    let headers = signed_headers
+        .split(';')
+        .map(|name| Some((name, request.headers().get(name)?.to_str().ok()?)))
+        .collect::<Option<Vec<_>>>()?;
+    let date = Some(date).filter(|date| date.len() == 16)?;
+    let date = format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &date[0..4],
+        &date[4..6],
+        &date[6..8],
+        &date[9..11],
+        &date[11..13],
+        &date[13..15]
+    );
+    let time =
+        aws_smithy_types::DateTime::from_str(&date, aws_smithy_types::date_time::Format::DateTime)
+            .ok()?;
+    let identity =
+        aws_credential_types::Credentials::from_keys(access_key, secret_key, None).into();
+    let params = aws_sigv4::sign::v4::SigningParams::builder()
+        .identity(&identity)
+        .region(scope[2])
+        .name(scope[3])
+        .time(time.try_into().ok()?)
+        .settings(settings)
+        .build()
+        .ok()?
+        .into();
+    let signable =
+        SignableRequest::new(request.method().as_str(), uri, headers.into_iter(), body).ok()?;
+    let output = sign(signable, &params).ok()?;
+    Some(output.signature().to_string())
+}
+
+/// Return a response for a single request to a [MockS3] server.
+#[allow(clippy::too_many_arguments)]
+fn mock_s3_response(
+    objects: &MockObjects,
+    checksums: &MockChecksums,
+    errors: &MockErrors,
+    truncated: &MockTruncated,
+    parts: &MockParts,
+    not_modified: &MockNotModified,
+    region: Option<&str>,
+    credentials: Option<&(String, String)>,
+    request: Request<Body>,
+) -> Response<Body> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return mock_s3_error(StatusCode::METHOD_NOT_ALLOWED, "MethodNotAllowed");
+    }
+    let presigned = request.uri().query().is_some_and(|query| {
+        query
+            .split('&')
+            .any(|param| param.starts_with("X-Amz-Signature="))
+    });
+    if presigned && request.headers().contains_key(header::AUTHORIZATION) {
+        return mock_s3_error(StatusCode::BAD_REQUEST, "InvalidArgument");
+    }
+    if let Some(region) = region {
+        // The credential scope is <access key>/<date>/<region>/<service>/aws4_request.
+        let signed_region = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.split_once("Credential="))
+            .and_then(|(_, credential)| credential.split('/').nth(2));
+        if signed_region != Some(region) {
+            return mock_s3_error(StatusCode::BAD_REQUEST, "AuthorizationHeaderMalformed");
+        }
+    }
+    if let Some((access_key, secret_key)) = credentials {
+        let valid = if presigned {
+            mock_s3_presigned_signature_valid(&request, access_key, secret_key)
+        } else {
+            mock_s3_signature_valid(&request, access_key, secret_key)
+        };
+        if !valid.unwrap_or(false) {
+            return mock_s3_error(StatusCode::FORBIDDEN, "SignatureDoesNotMatch");
+        }
+    }
+    if let Some((status, code)) = errors.lock().unwrap().get(request.uri().path()) {
+        return mock_s3_error(*status, code);
+    }
+    let Some(data) = objects.lock().unwrap().get(request.uri().path()).cloned() else {
+        return mock_s3_error(StatusCode::NOT_FOUND, "NoSuchKey");
+    };
+    if request.method() == Method::HEAD {
+        return Response::builder()
+            .header(header::CONTENT_LENGTH, data.len())
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .header(header::ETAG, mock_s3_etag(&data))
+            .header(header::LAST_MODIFIED, MOCK_S3_LAST_MODIFIED)
+            .body(Body::empty())
+            .unwrap();
+    }
+    let etag = mock_s3_etag(&data);
+    if request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|if_none_match| if_none_match == etag.as_str())
+    {
+        not_modified.fetch_add(1, Ordering::SeqCst);
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .body(Body::empty())
+            .unwrap();
+    }
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.split_once('-'))
+        .map(|(start, end)| {
+            let start: usize = start.parse().unwrap();
+            // Range-end is inclusive.
+            let end = end.parse::<usize>().map_or(data.len(), |end| end + 1);
+            (start, std::cmp::min(end, data.len()))
+        });
+    let part_number = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("partNumber="))
+            .map(|part_number| part_number.parse::<usize>().unwrap())
+    });
+    let object_size = data.len();
+    let range = match part_number {
+        Some(_) if range.is_some() => {
+            return mock_s3_error(StatusCode::BAD_REQUEST, "InvalidRequest");
+        }
+        Some(part_number) => {
+            // An object that was not uploaded using multipart upload has a single part.
+            let sizes = parts
+                .lock()
+                .unwrap()
+                .get(request.uri().path())
+                .cloned()
+                .unwrap_or_else(|| vec![data.len()]);
+            if part_number == 0 || part_number > sizes.len() {
+                return mock_s3_error(StatusCode::RANGE_NOT_SATISFIABLE, "InvalidPartNumber");
+            }
+            let start: usize = sizes[..part_number - 1].iter().sum();
+            Some((start, start + sizes[part_number - 1]))
+        }
+        None => range,
+    };
+    let (status, data) = match range {
+        Some((start, _)) if start >= data.len() => {
+            return mock_s3_error(StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange")
+        }
+        Some((start, end)) => (StatusCode::PARTIAL_CONTENT, data.slice(start..end)),
+        None => (StatusCode::OK, data),
+    };
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_LENGTH, data.len())
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::ETAG, etag);
+    if let Some((start, end)) = range {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end - 1, object_size),
+        );
+    }
+    if range.is_none()
+        && request
+            .headers()
+            .get("x-amz-checksum-mode")
+            .is_some_and(|mode| mode == "ENABLED")
+    {
+        if let Some(checksum) = checksums.lock().unwrap().get(request.uri().path()) {
+            response = response.header("x-amz-checksum-crc32c", checksum);
+        }
+    }
+    match truncated.lock().unwrap().get(request.uri().path()) {
+        Some(size) if *size < data.len() => {
+            // Give the client time to receive the response head and partial data before the
+            // connection is reset.
+            let partial = futures::stream::once(futures::future::ok(data.slice(..*size)));
+            let reset = futures::stream::once(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+            });
+            response
+                .body(Body::wrap_stream(partial.chain(reset)))
+                .unwrap()
+        }
+        _ => response.body(data.into()).unwrap(),
     }
 }
@@ -2,10 +2,27 @@
 
 use crate::cli::CommandLineArgs;
 
+use axum::http::{header::HeaderName, HeaderMap, Method, Request, StatusCode, Uri};
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
 use opentelemetry::runtime::Tokio;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
 use opentelemetry::sdk::trace::Tracer;
-use opentelemetry::trace::TraceError;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use opentelemetry::trace::{TraceContextExt, TraceError};
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::time::Duration;
+use tower_http::request_id::{MakeRequestId, MakeRequestUuid, RequestId};
+use tracing::Span;
+use tracing_appender::non_blocking::{NonBlocking, NonBlockingBuilder, WorkerGuard};
+use tracing_appender::rolling::{InitError, RollingFileAppender, Rotation};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// `x-request-id` header definition
+pub static HEADER_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+/// `traceparent` header definition (W3C trace context)
+pub static HEADER_TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
 
 /// Initialise and return a Jaeger tracer.
 fn init_tracer() -> Result<Tracer, TraceError> {
@@ -16,10 +33,28 @@ fn init_tracer() -> Result<Tracer, TraceError> {
         .install_batch(Tokio)
 }
 
+/// Returns the filter applied to tracing (logging).
+///
+/// The filter is given by the `--log-filter` directives if specified, or otherwise by the
+/// `RUST_LOG` environment variable, falling back to enable debug logging for this crate and
+/// tower_http if not set.
+///
+/// # Arguments
+///
+/// * `args`: Command line arguments.
+fn log_filter(args: &CommandLineArgs) -> EnvFilter {
+    match &args.log_filter {
+        // The directives are validated when the arguments are checked.
+        Some(directives) => EnvFilter::new(directives),
+        None => EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| "reductionist=debug,tower_http=debug".into()),
+    }
+}
+
 /// Initlialise tracing (logging)
 ///
-/// Applies a filter based on the `RUST_LOG` environment variable, falling back to enable debug
-/// logging for this crate and tower_http if not set.
+/// Applies the `--log-filter` directives if specified, or otherwise the `RUST_LOG` environment
+/// variable, falling back to debug logging for this crate and tower_http if neither is set.
 ///
 /// # Arguments
 ///
@@ -27,10 +62,7 @@ fn init_tracer() -> Result<Tracer, TraceError> {
 pub fn init_tracing(args: &CommandLineArgs) {
     let tracer = init_tracer().expect("Failed to initialize tracer");
     let subscriber = tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "reductionist=debug,tower_http=debug".into()),
-        )
+        .with(log_filter(args))
         .with(tracing_subscriber::fmt::layer());
     if args.enable_jaeger {
         subscriber
@@ -41,7 +73,294 @@ pub fn init_tracing(args: &CommandLineArgs) {
     }
 }
 
+/// How often the access log file is rotated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum AccessLogRotation {
+    /// Start a new file every minute.
+    Minutely,
+    /// Start a new file every hour.
+    Hourly,
+    /// Start a new file every day.
+    Daily,
+    /// Write to a single file.
+    #[default]
+    Never,
+}
+
+impl From<AccessLogRotation> for Rotation {
+    fn from(rotation: AccessLogRotation) -> Self {
+        match rotation {
+            AccessLogRotation::Minutely => Rotation::MINUTELY,
+            AccessLogRotation::Hourly => Rotation::HOURLY,
+            AccessLogRotation::Daily => Rotation::DAILY,
+            AccessLogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// Log of the requests handled by the server, written to a dedicated file separately from other
+/// tracing.
+///
+/// Lines are written to the file by a background thread, so recording a request does not block
+/// on file I/O. Pending lines are flushed when the access log is dropped.
+pub struct AccessLog {
+    /// Writer sending lines to the background thread.
+    writer: NonBlocking,
+    /// Guard that flushes pending lines when dropped.
+    _guard: WorkerGuard,
+}
+
+impl AccessLog {
+    /// Create and return an [AccessLog] writing to a file.
+    ///
+    /// If the file is rotated, the date and time of the period it covers are appended to its
+    /// name.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path to the access log file
+    /// * `rotation`: How often the file is rotated
+    /// * `max_files`: Optional maximum number of rotated files to keep, deleting the oldest
+    pub fn new(
+        path: &Path,
+        rotation: AccessLogRotation,
+        max_files: Option<NonZeroUsize>,
+    ) -> Result<Self, InitError> {
+        let directory = path
+            .parent()
+            .filter(|directory| !directory.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut builder = RollingFileAppender::builder().rotation(rotation.into());
+        if let Some(file_name) = path.file_name() {
+            builder = builder.filename_prefix(file_name.to_string_lossy());
+        }
+        if let Some(max_files) = max_files {
+            builder = builder.max_log_files(max_files.get());
+        }
+        // Since the log is used for auditing, wait rather than drop lines if the background
+        // thread falls far behind.
+        let (writer, guard) = NonBlockingBuilder::default()
+            .lossy(false)
+            .finish(builder.build(directory)?);
+        Ok(Self {
+            writer,
+            _guard: guard,
+        })
+    }
+
+    /// Record a request as a line of the access log.
+    ///
+    /// Each line is a JSON object with the time, method, URI, status, latency in milliseconds and
+    /// request ID of a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: Method of the request
+    /// * `uri`: URI of the request
+    /// * `status`: Status of the response
+    /// * `latency`: Time taken to produce the response
+    /// * `request_id`: Value of the `x-request-id` header of the request
+    pub fn record(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        status: StatusCode,
+        latency: Duration,
+        request_id: &str,
+    ) {
+        let time = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default();
+        let line = serde_json::json!({
+            "time": time,
+            "method": method.as_str(),
+            "uri": uri.to_string(),
+            "status": status.as_u16(),
+            "latency_ms": latency.as_secs_f64() * 1000.0,
+            "request_id": request_id,
+        });
+        // Write the whole line at once, so that it is sent to the background thread as a single
+        // message.
+        if let Err(error) = self
+            .writer
+            .clone()
+            .write_all(format!("{}\n", line).as_bytes())
+        {
+            tracing::error!("Failed to write to access log: {}", error);
+        }
+    }
+}
+
+/// Adapter for extracting OpenTelemetry context from HTTP request headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    /// Get a value for a key from the headers.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    /// Collect all the keys from the headers.
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Returns the trace ID from a W3C `traceparent` header value, if it is valid.
+///
+/// The header has the form `{version}-{trace-id}-{parent-id}-{trace-flags}`.
+fn traceparent_trace_id(traceparent: &str) -> Option<&str> {
+    let mut parts = traceparent.trim().split('-');
+    let (_version, trace_id, parent_id, _flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let is_hex = |s: &str| s.chars().all(|c| c.is_ascii_hexdigit());
+    let is_zero = |s: &str| s.chars().all(|c| c == '0');
+    if trace_id.len() == 32 && is_hex(trace_id) && !is_zero(trace_id) && parent_id.len() == 16 {
+        Some(trace_id)
+    } else {
+        None
+    }
+}
+
+/// A [tower_http::request_id::MakeRequestId] that derives a request ID from the trace context.
+///
+/// This is used when a request does not include an `x-request-id` header.
+/// If a valid W3C `traceparent` header is present, its trace ID is used as the request ID,
+/// otherwise a new UUID is generated.
+#[derive(Clone, Copy, Default)]
+pub struct MakeTraceContextRequestId;
+
+impl MakeRequestId for MakeTraceContextRequestId {
+    fn make_request_id<B>(&mut self, request: &Request<B>) -> Option<RequestId> {
+        request
+            .headers()
+            .get(&HEADER_TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(traceparent_trace_id)
+            .and_then(|trace_id| trace_id.parse().ok())
+            .map(RequestId::new)
+            .or_else(|| MakeRequestUuid.make_request_id(request))
+    }
+}
+
+/// Returns a [tracing::Span] for an HTTP request.
+///
+/// The span includes the request ID and any W3C `traceparent` header in its fields.
+/// If the request carries a W3C trace context, it is set as the parent of the span so that
+/// exported traces are linked to those of the caller.
+///
+/// # Arguments
+///
+/// * `request`: The HTTP request
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let header = |name: &HeaderName| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let span = tracing::debug_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id = %header(&HEADER_REQUEST_ID),
+        traceparent = %header(&HEADER_TRACEPARENT),
+    );
+    if request.headers().contains_key(&HEADER_TRACEPARENT) {
+        let context = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+        span.set_parent(context);
+    }
+    span
+}
+
+/// Returns the trace ID of the current span, or of the W3C `traceparent` header of a request if
+/// the span is not part of an OpenTelemetry trace, which is the case unless Jaeger is enabled.
+///
+/// # Arguments
+///
+/// * `request`: The HTTP request
+pub fn trace_id<B>(request: &Request<B>) -> Option<String> {
+    let context = Span::current().context();
+    let span_context = context.span().span_context().clone();
+    if span_context.is_valid() {
+        return Some(span_context.trace_id().to_string());
+    }
+    request
+        .headers()
+        .get(&HEADER_TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(traceparent_trace_id)
+        .map(str::to_string)
+}
+
 /// Shutdown tracing (logging)
 pub fn shutdown_tracing() {
     opentelemetry::global::shutdown_tracer_provider();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer appending to a shared buffer, so that the log can be inspected.
+    #[derive(Clone, Default)]
+    struct BufferWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for BufferWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn log_filter_directives() {
+        let args = test_utils::get_test_args(&["--log-filter=reductionist::s3_client=debug,info"]);
+        let writer = BufferWriter::default();
+        let make_writer = writer.clone();
+        let subscriber = tracing_subscriber::registry().with(log_filter(&args)).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || make_writer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "reductionist::s3_client", "downloading object");
+            tracing::debug!(target: "reductionist::app", "handling request");
+            tracing::info!(target: "reductionist::app", "request complete");
+        });
+        let log = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("downloading object"));
+        assert!(!log.contains("handling request"));
+        assert!(log.contains("request complete"));
+    }
+
+    #[test]
+    fn traceparent_trace_id_valid() {
+        assert_eq!(
+            Some("4bf92f3577b34da6a3ce929d0e0e4736"),
+            traceparent_trace_id("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+        );
+    }
+
+    #[test]
+    fn traceparent_trace_id_zero() {
+        assert_eq!(
+            None,
+            traceparent_trace_id("00-00000000000000000000000000000000-00f067aa0ba902b7-01")
+        );
+    }
+
+    #[test]
+    fn traceparent_trace_id_invalid() {
+        assert_eq!(None, traceparent_trace_id("foo"));
+        assert_eq!(None, traceparent_trace_id("00-xyz-00f067aa0ba902b7-01"));
+    }
+}
@@ -1,6 +1,6 @@
 //! Byte order (endianness)
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Native byte order of the host running Reductionist.
 #[cfg(target_endian = "big")]
@@ -17,7 +17,7 @@ pub const NON_NATIVE_BYTE_ORDER: ByteOrder = ByteOrder::Little;
 pub const NON_NATIVE_BYTE_ORDER: ByteOrder = ByteOrder::Big;
 
 /// Byte order / endianness.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, clap::ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum ByteOrder {
     /// Big Endian
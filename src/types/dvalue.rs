@@ -64,6 +64,30 @@ impl TryFromDValue for u64 {
     }
 }
 
+impl TryFromDValue for half::f16 {
+    fn try_from_dvalue(value: DValue) -> Result<Self, ActiveStorageError> {
+        // If the number is too large to be represented as an f16 this conversion returns infinity.
+        let float = half::f16::from_f64(as_f64(&value)?);
+        if float.is_finite() {
+            Ok(float)
+        } else {
+            Err(ActiveStorageError::IncompatibleMissing(value))
+        }
+    }
+}
+
+impl TryFromDValue for half::bf16 {
+    fn try_from_dvalue(value: DValue) -> Result<Self, ActiveStorageError> {
+        // If the number is too large to be represented as a bf16 this conversion returns infinity.
+        let float = half::bf16::from_f64(as_f64(&value)?);
+        if float.is_finite() {
+            Ok(float)
+        } else {
+            Err(ActiveStorageError::IncompatibleMissing(value))
+        }
+    }
+}
+
 impl TryFromDValue for f32 {
     fn try_from_dvalue(value: DValue) -> Result<Self, ActiveStorageError> {
         // If the number is too large to be represented as an f32 this cast returns infinity.
@@ -170,6 +194,31 @@ mod tests {
         u64::try_from_dvalue(DValue::from_f64(-1.0).unwrap()).unwrap();
     }
 
+    #[test]
+    fn test_try_from_dvalue_f16() {
+        let result = half::f16::try_from_dvalue(DValue::from_f64(42.0).unwrap()).unwrap();
+        assert_eq!(half::f16::from_f32(42.0), result);
+    }
+
+    #[test]
+    #[should_panic(expected = "IncompatibleMissing(Number(100000.0))")]
+    fn test_try_from_dvalue_f16_too_large() {
+        half::f16::try_from_dvalue(DValue::from_f64(100000.0).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_try_from_dvalue_bf16() {
+        let result = half::bf16::try_from_dvalue(DValue::from_f64(100000.0).unwrap()).unwrap();
+        // bf16 has a reduced mantissa, so the value is rounded.
+        assert_eq!(half::bf16::from_f32(99840.0), result);
+    }
+
+    #[test]
+    #[should_panic(expected = "IncompatibleMissing(Number(1e39))")]
+    fn test_try_from_dvalue_bf16_too_large() {
+        half::bf16::try_from_dvalue(DValue::from_f64(1e39).unwrap()).unwrap();
+    }
+
     #[test]
     fn test_try_from_dvalue_f32() {
         let result = f32::try_from_dvalue(DValue::from_f64(42.0).unwrap()).unwrap();
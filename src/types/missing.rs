@@ -25,7 +25,7 @@ use crate::types::DValue;
 ///    [DType].
 /// 2. T = a primitive numeric type (i32, u64, f32, etc.), used in numeric operations when we know
 ///    the DType of the values.
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Missing<T> {
     /// A single missing value
@@ -49,6 +49,8 @@ impl Missing<DValue> {
             DType::Int64 => Missing::<i64>::validate_dvalue(self),
             DType::Uint32 => Missing::<u32>::validate_dvalue(self),
             DType::Uint64 => Missing::<u64>::validate_dvalue(self),
+            DType::Float16 => Missing::<half::f16>::validate_dvalue(self),
+            DType::Bfloat16 => Missing::<half::bf16>::validate_dvalue(self),
             DType::Float32 => Missing::<f32>::validate_dvalue(self),
             DType::Float64 => Missing::<f64>::validate_dvalue(self),
         }
@@ -146,6 +148,14 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "Missing data valid range min must be less than max")]
+    fn test_validate_i64_range_min_gt_max() {
+        Missing::<DValue>::ValidRange(1.into(), (-1).into())
+            .validate(DType::Int64)
+            .unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "Missing data valid range min must be less than max")]
     fn test_validate_f32_range_min_eq_max() {
@@ -5,13 +5,16 @@ use crate::error::ActiveStorageError;
 use async_trait::async_trait;
 use axum::{
     extract::{rejection::JsonRejection, FromRequest, Json},
-    http::Request,
+    http::{Request, StatusCode},
 };
 use serde::de::DeserializeOwned;
 use validator::Validate;
 
 /// An axum extractor based on the Json extractor that also performs validation using the validator
 /// crate.
+///
+/// Bodies larger than the limit set using [axum::extract::DefaultBodyLimit] are rejected with a
+/// [crate::error::ActiveStorageError::RequestBodyTooLarge] error.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ValidatedJson<T>(pub T);
 
@@ -27,7 +30,12 @@ where
 
     /// Extract a `ValidatedJson` from a `Request`.
     async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
-        let Json(value) = Json::<T>::from_request(req, state).await?;
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => ActiveStorageError::RequestBodyTooLarge(rejection),
+                _ => rejection.into(),
+            })?;
         value.validate()?;
         Ok(ValidatedJson(value))
     }
@@ -40,7 +48,8 @@ mod tests {
     use super::*;
     use axum::{
         body::Body,
-        http::{self, Request, StatusCode},
+        extract::DefaultBodyLimit,
+        http::{self, Request},
         response::Response,
         routing::post,
         Router,
@@ -63,8 +72,14 @@ mod tests {
 
     // Build a router and make a oneshot request.
     async fn request(body: Body) -> Response {
+        request_with_limit(body, 1024).await
+    }
+
+    // Build a router with a body size limit and make a oneshot request.
+    async fn request_with_limit(body: Body, limit: usize) -> Response {
         Router::new()
             .route("/", post(test_handler))
+            .layer(DefaultBodyLimit::max(limit))
             .oneshot(
                 Request::builder()
                     .method(http::Method::POST)
@@ -150,4 +165,21 @@ mod tests {
         let re = Regex::new(r".*foo: Validation error: length.*").unwrap();
         assert!(re.is_match(&body[..]), "body: {}", body);
     }
+
+    #[tokio::test]
+    async fn body_too_large() {
+        let body = Body::from(format!(
+            r#"{{"foo": "abc", "pad": "{}"}}"#,
+            " ".repeat(1024)
+        ));
+        let response = request_with_limit(body, 1024).await;
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = body_string(response).await;
+        let re = Regex::new(r".*request_body_too_large.*").unwrap();
+        assert!(re.is_match(&body[..]), "body: {}", body);
+        let re = Regex::new(r".*request body exceeds the maximum size.*").unwrap();
+        assert!(re.is_match(&body[..]), "body: {}", body);
+    }
 }